@@ -0,0 +1,405 @@
+pub mod movement;
+
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use bevy_time::Time;
+use movement::{Dash, DashEvent, DoubleJumpEvent};
+use utils::cooldowns::{CooldownKey, CooldownPlugin, Cooldowns};
+use valence::{
+    abilities::{PlayerAbilitiesFlags, PlayerStartFlyingEvent},
+    entity::entity::Flags,
+    hand_swing::HandSwingEvent,
+    interact_item::InteractItemEvent,
+    inventory::{player_inventory::PlayerInventory, HeldItem},
+    prelude::*,
+};
+
+/// How an ability is cast.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AbilityTrigger {
+    /// Right click while holding an item.
+    RightClickItem(ItemKind),
+    /// Swing the hand while sneaking.
+    SneakSwing,
+    /// Press jump while in the air.
+    ///
+    /// This uses the flight toggle of the client, so players with such an ability are allowed to fly.
+    DoubleJump,
+}
+
+/// Everything a cast condition can check.
+#[derive(Clone, Copy, Debug)]
+pub struct CastContext {
+    pub caster: Entity,
+    pub position: DVec3,
+    pub look: Look,
+    pub layer: Entity,
+    /// The mana of the caster (if the caster has [`Mana`]).
+    pub mana: Option<f32>,
+}
+
+/// The parameters are: `context`.
+pub type CastCondition = Arc<dyn Fn(&CastContext) -> bool + Send + Sync>;
+/// The parameters are: `commands`, `context`.
+///
+/// Handlers can also be replaced by reading the [`AbilityCastEvent`].
+pub type CastHandler = Arc<dyn Fn(&mut Commands, &CastContext) + Send + Sync>;
+
+/// An ability that can be cast by entities that know it (see [`KnownAbilities`]).
+#[derive(Clone)]
+pub struct Ability {
+    pub trigger: AbilityTrigger,
+    pub cooldown: Duration,
+    /// The mana that is needed to cast the ability.
+    pub mana_cost: f32,
+    pub condition: Option<CastCondition>,
+    pub handler: Option<CastHandler>,
+}
+
+impl Ability {
+    pub fn new(trigger: AbilityTrigger) -> Self {
+        Self {
+            trigger,
+            cooldown: Duration::ZERO,
+            mana_cost: 0.0,
+            condition: None,
+            handler: None,
+        }
+    }
+
+    pub fn with_cooldown(mut self, cooldown: Duration) -> Self {
+        self.cooldown = cooldown;
+        self
+    }
+
+    pub fn with_mana_cost(mut self, mana_cost: f32) -> Self {
+        self.mana_cost = mana_cost;
+        self
+    }
+
+    pub fn with_condition(
+        mut self,
+        condition: impl Fn(&CastContext) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.condition = Some(Arc::new(condition));
+        self
+    }
+
+    pub fn with_handler(
+        mut self,
+        handler: impl Fn(&mut Commands, &CastContext) + Send + Sync + 'static,
+    ) -> Self {
+        self.handler = Some(Arc::new(handler));
+        self
+    }
+}
+
+/// All abilities by their name.
+#[derive(Resource, Default)]
+pub struct AbilityRegistry {
+    pub abilities: HashMap<String, Ability>,
+}
+
+impl AbilityRegistry {
+    pub fn register(&mut self, name: impl Into<String>, ability: Ability) -> &mut Self {
+        self.abilities.insert(name.into(), ability);
+        self
+    }
+}
+
+/// The names of the abilities an entity can cast.
+#[derive(Component, Clone, Debug, Default)]
+pub struct KnownAbilities(pub Vec<String>);
+
+/// A resource that is used to cast abilities.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Mana {
+    pub current: f32,
+    pub max: f32,
+    /// The mana that is regenerated every second.
+    pub regeneration: f32,
+}
+
+impl Mana {
+    pub fn new(max: f32, regeneration: f32) -> Self {
+        Self {
+            current: max,
+            max,
+            regeneration,
+        }
+    }
+}
+
+/// An event that is emitted after an ability was cast.
+#[derive(Event, Clone, Debug)]
+pub struct AbilityCastEvent {
+    pub caster: Entity,
+    pub ability: String,
+}
+
+/// An event that is emitted if an ability was triggered but could not be cast.
+#[derive(Event, Clone, Debug)]
+pub struct AbilityCastFailedEvent {
+    pub caster: Entity,
+    pub ability: String,
+    pub reason: CastFailReason,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CastFailReason {
+    /// The ability is on cooldown, this is the remaining time.
+    Cooldown(Duration),
+    NotEnoughMana,
+    /// The cast condition of the ability is not met.
+    Condition,
+}
+
+pub struct AbilityPlugin;
+
+impl Plugin for AbilityPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<CooldownPlugin>() {
+            app.add_plugins(CooldownPlugin);
+        }
+
+        app.init_resource::<AbilityRegistry>()
+            .add_event::<AbilityCastEvent>()
+            .add_event::<AbilityCastFailedEvent>()
+            .add_event::<AbilityTriggered>()
+            .add_event::<DashEvent>()
+            .add_event::<DoubleJumpEvent>()
+            .add_systems(
+                Update,
+                (
+                    allow_double_jump,
+                    (detect_triggers, cast_abilities).chain(),
+                    (movement::dash_triggers, movement::dash)
+                        .chain()
+                        .after(detect_triggers),
+                    movement::reset_double_jumps,
+                    movement::double_jump,
+                    regenerate_mana,
+                ),
+            )
+            .add_systems(PostUpdate, movement::remove_fall_exemption);
+    }
+}
+
+/// An ability trigger of an entity that still has to be checked.
+#[derive(Event)]
+pub(crate) struct AbilityTriggered {
+    pub(crate) caster: Entity,
+    pub(crate) trigger: AbilityTrigger,
+}
+
+#[allow(clippy::type_complexity)]
+fn detect_triggers(
+    mut interact_events: EventReader<InteractItemEvent>,
+    mut swing_events: EventReader<HandSwingEvent>,
+    mut flying_events: EventReader<PlayerStartFlyingEvent>,
+    mut casters: Query<
+        (
+            Option<&Inventory>,
+            Option<&HeldItem>,
+            Option<&Flags>,
+            Option<&mut PlayerAbilitiesFlags>,
+        ),
+        Or<(With<KnownAbilities>, With<Dash>)>,
+    >,
+    mut triggered_writer: EventWriter<AbilityTriggered>,
+) {
+    for event in interact_events.read() {
+        let Ok((Some(inventory), Some(held_item), _, _)) = casters.get(event.client) else {
+            continue;
+        };
+
+        let slot = match event.hand {
+            Hand::Main => held_item.slot(),
+            Hand::Off => PlayerInventory::SLOT_OFFHAND,
+        };
+
+        triggered_writer.send(AbilityTriggered {
+            caster: event.client,
+            trigger: AbilityTrigger::RightClickItem(inventory.slot(slot).item),
+        });
+    }
+
+    for event in swing_events.read() {
+        let Ok((_, _, Some(flags), _)) = casters.get(event.client) else {
+            continue;
+        };
+
+        if flags.sneaking() {
+            triggered_writer.send(AbilityTriggered {
+                caster: event.client,
+                trigger: AbilityTrigger::SneakSwing,
+            });
+        }
+    }
+
+    for event in flying_events.read() {
+        let Ok((_, _, _, Some(mut abilities))) = casters.get_mut(event.client) else {
+            continue;
+        };
+
+        // The flight toggle is only used to detect the jump.
+        abilities.set_flying(false);
+
+        triggered_writer.send(AbilityTriggered {
+            caster: event.client,
+            trigger: AbilityTrigger::DoubleJump,
+        });
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn cast_abilities(
+    mut commands: Commands,
+    mut events: EventReader<AbilityTriggered>,
+    registry: Res<AbilityRegistry>,
+    mut casters: Query<(
+        &KnownAbilities,
+        &Position,
+        &Look,
+        &EntityLayerId,
+        Option<&mut Mana>,
+        Option<&mut Cooldowns>,
+    )>,
+    mut cast_writer: EventWriter<AbilityCastEvent>,
+    mut failed_writer: EventWriter<AbilityCastFailedEvent>,
+) {
+    for event in events.read() {
+        let Ok((known, position, look, layer, mut mana, mut cooldowns)) =
+            casters.get_mut(event.caster)
+        else {
+            continue;
+        };
+
+        for name in known.0.iter() {
+            let Some(ability) = registry.abilities.get(name) else {
+                continue;
+            };
+
+            if ability.trigger != event.trigger {
+                continue;
+            }
+
+            let fail = |reason| AbilityCastFailedEvent {
+                caster: event.caster,
+                ability: name.clone(),
+                reason,
+            };
+
+            let remaining = cooldowns
+                .as_ref()
+                .map(|cooldowns| cooldowns.remaining(name.as_str()))
+                .unwrap_or_default();
+
+            if !remaining.is_zero() {
+                failed_writer.send(fail(CastFailReason::Cooldown(remaining)));
+                continue;
+            }
+
+            if ability.mana_cost > 0.0
+                && !mana
+                    .as_ref()
+                    .is_some_and(|mana| mana.current >= ability.mana_cost)
+            {
+                failed_writer.send(fail(CastFailReason::NotEnoughMana));
+                continue;
+            }
+
+            let context = CastContext {
+                caster: event.caster,
+                position: position.0,
+                look: *look,
+                layer: layer.0,
+                mana: mana.as_ref().map(|mana| mana.current),
+            };
+
+            if ability
+                .condition
+                .as_ref()
+                .is_some_and(|condition| !condition(&context))
+            {
+                failed_writer.send(fail(CastFailReason::Condition));
+                continue;
+            }
+
+            if let Some(mana) = mana.as_mut() {
+                mana.current -= ability.mana_cost;
+            }
+
+            if !ability.cooldown.is_zero() {
+                let key = CooldownKey::Custom(name.clone());
+
+                match cooldowns.as_mut() {
+                    Some(cooldowns) => start_cooldown(cooldowns, key, ability),
+                    None => {
+                        let mut new_cooldowns = Cooldowns::default();
+                        start_cooldown(&mut new_cooldowns, key, ability);
+                        commands.entity(event.caster).insert(new_cooldowns);
+                    }
+                }
+            }
+
+            if let Some(handler) = &ability.handler {
+                handler(&mut commands, &context);
+            }
+
+            cast_writer.send(AbilityCastEvent {
+                caster: event.caster,
+                ability: name.clone(),
+            });
+        }
+    }
+}
+
+/// Start the cooldown of an ability, the cooldown of abilities cast with an item is shown on the item.
+fn start_cooldown(cooldowns: &mut Cooldowns, key: CooldownKey, ability: &Ability) {
+    match ability.trigger {
+        AbilityTrigger::RightClickItem(item) => {
+            cooldowns.start_shown_on(key, ability.cooldown, item)
+        }
+        _ => cooldowns.start(key, ability.cooldown),
+    }
+}
+
+/// Players with a double jump ability (or dash) have to be allowed to fly, so the client sends the flight toggle.
+#[allow(clippy::type_complexity)]
+fn allow_double_jump(
+    mut query: Query<
+        (
+            Option<&KnownAbilities>,
+            Option<&Dash>,
+            &mut PlayerAbilitiesFlags,
+        ),
+        Or<(Changed<KnownAbilities>, Changed<Dash>)>,
+    >,
+    registry: Res<AbilityRegistry>,
+) {
+    for (known, dash, mut flags) in query.iter_mut() {
+        let double_jump = known.is_some_and(|known| {
+            known.0.iter().any(|name| {
+                registry
+                    .abilities
+                    .get(name)
+                    .is_some_and(|ability| ability.trigger == AbilityTrigger::DoubleJump)
+            })
+        }) || dash.is_some_and(|dash| dash.trigger == AbilityTrigger::DoubleJump);
+
+        if double_jump && !flags.allow_flying() {
+            flags.set_allow_flying(true);
+        }
+    }
+}
+
+fn regenerate_mana(time: Res<Time>, mut query: Query<&mut Mana>) {
+    let dt = time.delta_seconds();
+
+    for mut mana in query.iter_mut() {
+        if mana.current < mana.max {
+            mana.current = (mana.current + mana.regeneration * dt).min(mana.max);
+        }
+    }
+}
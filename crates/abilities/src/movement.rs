@@ -0,0 +1,293 @@
+//! Built-in movement abilities (double jump and dash).
+//!
+//! These are configured with components on the entity and do not have to be registered in the [`AbilityRegistry`](crate::AbilityRegistry),
+//! the systems are added by the [`AbilityPlugin`](crate::AbilityPlugin).
+
+use std::time::Duration;
+
+use fall_damage::{FallingState, NoFallDamage};
+use physics::projectile::look_direction;
+use utils::cooldowns::{CooldownKey, Cooldowns};
+use valence::{
+    abilities::{PlayerAbilitiesFlags, PlayerStartFlyingEvent},
+    entity::Velocity,
+    movement::MovementEvent,
+    prelude::*,
+};
+
+use crate::{AbilityTrigger, AbilityTriggered};
+
+/// The key of the dash cooldown in [`Cooldowns`].
+pub const DASH_COOLDOWN: &str = "dash";
+
+/// Allows a player to jump again while in the air.
+///
+/// The jump is detected with the flight toggle of the client, so players with this component are allowed to fly
+/// until they used all their jumps. The jumps are reset when the player lands on the ground.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct DoubleJump {
+    /// The upwards velocity of a jump (in blocks per second).
+    pub vertical: f32,
+    /// The velocity in the horizontal look direction of the player (in blocks per second).
+    pub forward: f32,
+    /// How many times the player can jump while in the air.
+    pub max_jumps: u32,
+    /// Whether the player takes no fall damage after a double jump.
+    pub prevent_fall_damage: bool,
+    jumps: u32,
+}
+
+impl DoubleJump {
+    pub fn new(vertical: f32, forward: f32) -> Self {
+        Self {
+            vertical,
+            forward,
+            max_jumps: 1,
+            prevent_fall_damage: true,
+            jumps: 0,
+        }
+    }
+
+    pub fn with_max_jumps(mut self, max_jumps: u32) -> Self {
+        self.max_jumps = max_jumps;
+        self
+    }
+
+    pub fn with_fall_damage(mut self) -> Self {
+        self.prevent_fall_damage = false;
+        self
+    }
+
+    /// The jumps that are left until the player lands.
+    pub fn remaining_jumps(&self) -> u32 {
+        self.max_jumps.saturating_sub(self.jumps)
+    }
+}
+
+impl Default for DoubleJump {
+    fn default() -> Self {
+        // TODO: set based on tick rate
+        Self::new(0.6 * 20.0, 0.4 * 20.0)
+    }
+}
+
+/// Allows an entity to dash in its look direction.
+///
+/// Players dash with the [`Dash::trigger`], other entities (or players) can be made to dash with the [`DashEvent`].
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Dash {
+    /// The velocity in the look direction (in blocks per second).
+    pub speed: f32,
+    /// The upwards velocity that is added to the dash (in blocks per second).
+    pub vertical: f32,
+    pub cooldown: Duration,
+    pub trigger: AbilityTrigger,
+}
+
+impl Dash {
+    pub fn new(speed: f32, trigger: AbilityTrigger) -> Self {
+        Self {
+            speed,
+            vertical: 0.0,
+            cooldown: Duration::ZERO,
+            trigger,
+        }
+    }
+
+    pub fn with_vertical(mut self, vertical: f32) -> Self {
+        self.vertical = vertical;
+        self
+    }
+
+    pub fn with_cooldown(mut self, cooldown: Duration) -> Self {
+        self.cooldown = cooldown;
+        self
+    }
+}
+
+/// Send this event to make an entity with the [`Dash`] component dash.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct DashEvent {
+    pub entity: Entity,
+}
+
+/// An event that is emitted after an entity double jumped.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct DoubleJumpEvent {
+    pub entity: Entity,
+}
+
+/// Marks entities that are exempt from fall damage because of a double jump.
+#[derive(Component)]
+struct DoubleJumped;
+
+/// Reset the jumps of players that are on the ground.
+pub(crate) fn reset_double_jumps(
+    mut events: EventReader<MovementEvent>,
+    mut query: Query<(&mut DoubleJump, &mut PlayerAbilitiesFlags)>,
+) {
+    for event in events.read() {
+        if !event.on_ground {
+            continue;
+        }
+
+        let Ok((mut double_jump, mut flags)) = query.get_mut(event.client) else {
+            continue;
+        };
+
+        if double_jump.jumps != 0 {
+            double_jump.jumps = 0;
+        }
+
+        if !flags.allow_flying() && double_jump.max_jumps > 0 {
+            flags.set_allow_flying(true);
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+pub(crate) fn double_jump(
+    mut commands: Commands,
+    mut events: EventReader<PlayerStartFlyingEvent>,
+    mut query: Query<(
+        &mut DoubleJump,
+        &mut PlayerAbilitiesFlags,
+        &mut Client,
+        &Look,
+        &Position,
+        Option<&mut FallingState>,
+    )>,
+    mut event_writer: EventWriter<DoubleJumpEvent>,
+) {
+    for event in events.read() {
+        let Ok((mut double_jump, mut flags, mut client, look, position, falling_state)) =
+            query.get_mut(event.client)
+        else {
+            continue;
+        };
+
+        // The flight toggle is only used to detect the jump.
+        flags.set_flying(false);
+
+        if double_jump.remaining_jumps() == 0 {
+            continue;
+        }
+
+        double_jump.jumps += 1;
+
+        if double_jump.remaining_jumps() == 0 {
+            // Otherwise the next jump press would toggle the flight again.
+            flags.set_allow_flying(false);
+        }
+
+        let mut direction = look_direction(look);
+        direction.y = 0.0;
+
+        let velocity =
+            direction.normalize_or_zero() * double_jump.forward + Vec3::Y * double_jump.vertical;
+        client.set_velocity(velocity);
+
+        if double_jump.prevent_fall_damage {
+            commands
+                .entity(event.client)
+                .insert((DoubleJumped, NoFallDamage));
+        } else if let Some(mut falling_state) = falling_state {
+            // Only the fall after the last jump counts.
+            falling_state.reset(position.0);
+        }
+
+        event_writer.send(DoubleJumpEvent {
+            entity: event.client,
+        });
+    }
+}
+
+pub(crate) fn dash_triggers(
+    mut events: EventReader<AbilityTriggered>,
+    query: Query<&Dash>,
+    mut dash_writer: EventWriter<DashEvent>,
+) {
+    for event in events.read() {
+        if query
+            .get(event.caster)
+            .is_ok_and(|dash| dash.trigger == event.trigger)
+        {
+            dash_writer.send(DashEvent {
+                entity: event.caster,
+            });
+        }
+    }
+}
+
+pub(crate) fn dash(
+    mut commands: Commands,
+    mut events: EventReader<DashEvent>,
+    mut query: Query<(
+        &Dash,
+        &Look,
+        Option<&mut Cooldowns>,
+        Option<&mut Client>,
+        Option<&mut Velocity>,
+    )>,
+) {
+    for event in events.read() {
+        let Ok((dash, look, cooldowns, client, velocity)) = query.get_mut(event.entity) else {
+            continue;
+        };
+
+        if !dash.cooldown.is_zero() {
+            let key = CooldownKey::from(DASH_COOLDOWN);
+
+            match cooldowns {
+                Some(mut cooldowns) => {
+                    if !cooldowns.is_ready(key.clone()) {
+                        continue;
+                    }
+
+                    start_dash_cooldown(&mut cooldowns, key, dash);
+                }
+                None => {
+                    let mut new_cooldowns = Cooldowns::default();
+                    start_dash_cooldown(&mut new_cooldowns, key, dash);
+                    commands.entity(event.entity).insert(new_cooldowns);
+                }
+            }
+        }
+
+        let dash_velocity = look_direction(look) * dash.speed + Vec3::Y * dash.vertical;
+
+        if !dash_velocity.is_finite() {
+            continue;
+        }
+
+        if let Some(mut client) = client {
+            client.set_velocity(dash_velocity);
+        } else if let Some(mut velocity) = velocity {
+            // Non client entities are moved by the physics crate.
+            velocity.0 = dash_velocity;
+        }
+    }
+}
+
+fn start_dash_cooldown(cooldowns: &mut Cooldowns, key: CooldownKey, dash: &Dash) {
+    match dash.trigger {
+        AbilityTrigger::RightClickItem(item) => cooldowns.start_shown_on(key, dash.cooldown, item),
+        _ => cooldowns.start(key, dash.cooldown),
+    }
+}
+
+/// Remove the fall damage exemption of a double jump after the entity landed.
+///
+/// Fall damage is applied in `Update`, so this has to run afterwards.
+pub(crate) fn remove_fall_exemption(
+    mut commands: Commands,
+    query: Query<(Entity, Option<&FallingState>), With<DoubleJumped>>,
+) {
+    for (entity, falling_state) in query.iter() {
+        if !falling_state.is_some_and(|state| state.in_air) {
+            commands
+                .entity(entity)
+                .remove::<(DoubleJumped, NoFallDamage)>();
+        }
+    }
+}
@@ -0,0 +1,326 @@
+//! Heuristics that detect common combat cheats (kill aura, reach and auto clickers).
+//!
+//! Nothing is punished by this crate, every check emits a [`SuspicionEvent`] with a score,
+//! so the server can decide what to do with it (e.g. notify staff or kick the player once the score is too high).
+
+use std::{
+    collections::{HashMap, VecDeque},
+    time::{Duration, Instant},
+};
+
+use bevy_time::Time;
+use combat::AttackEvent;
+use serde::{Deserialize, Serialize};
+use valence::{hand_swing::HandSwingEvent, movement::MovementEvent, prelude::*};
+
+/// The number of clicks that are kept to check the click consistency.
+const CLICK_HISTORY: usize = 20;
+
+/// The kind of cheat a check is looking for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum CheckKind {
+    /// Too many clicks per second or clicks with an inhuman consistency.
+    AutoClicker,
+    /// Hits that are too far away.
+    Reach,
+    /// Hits on entities the player is not looking at.
+    KillAura,
+    /// Rotations that are not possible with a real client (e.g. a pitch above 90 degrees).
+    Rotation,
+}
+
+/// The thresholds of the checks.
+#[derive(Resource, Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AntiCheatConfig {
+    /// More clicks per second than this are suspicious.
+    pub max_cps: u32,
+    /// If the standard deviation of the time between clicks is below this (in milliseconds), the clicks are suspicious.
+    pub min_click_deviation: f64,
+    /// Hits further away than this (in blocks, from the eyes to the hitbox) are suspicious.
+    pub max_reach: f64,
+    /// The number of hits that are used for the average reach.
+    pub reach_samples: usize,
+    /// If the average reach of the last hits is above this, the player is suspicious.
+    pub max_average_reach: f64,
+    /// Hits with a larger angle (in degrees) between the look direction and the victim are suspicious.
+    pub max_hit_angle: f32,
+    /// The suspicion of a check is reduced by this value every second.
+    pub decay_per_second: f32,
+}
+
+impl Default for AntiCheatConfig {
+    fn default() -> Self {
+        Self {
+            max_cps: 20,
+            min_click_deviation: 8.0,
+            max_reach: 3.4,
+            reach_samples: 10,
+            max_average_reach: 3.1,
+            max_hit_angle: 60.0,
+            decay_per_second: 0.5,
+        }
+    }
+}
+
+/// An event that is emitted when a check detected suspicious behaviour.
+#[derive(Event, Clone, Debug)]
+pub struct SuspicionEvent {
+    pub player: Entity,
+    pub check: CheckKind,
+    /// The score that was added by this detection.
+    pub score: f32,
+    /// The total (decaying) score of the check for this player.
+    pub total: f32,
+    /// A description of the detection for staff.
+    pub details: String,
+}
+
+/// The data of the checks, this is added to every client.
+#[derive(Component, Debug)]
+pub struct AntiCheatState {
+    clicks: VecDeque<Instant>,
+    reaches: VecDeque<f64>,
+    suspicion: HashMap<CheckKind, f32>,
+}
+
+impl AntiCheatState {
+    /// The current suspicion score of the check.
+    pub fn suspicion(&self, check: CheckKind) -> f32 {
+        self.suspicion.get(&check).copied().unwrap_or(0.0)
+    }
+
+    /// Reset the suspicion of all checks (e.g. after a staff member looked at the player).
+    pub fn reset(&mut self) {
+        self.suspicion.clear();
+    }
+
+    /// The clicks of the player in the last second.
+    pub fn cps(&self) -> usize {
+        self.clicks
+            .iter()
+            .filter(|click| click.elapsed() < Duration::from_secs(1))
+            .count()
+    }
+
+    fn flag(
+        &mut self,
+        player: Entity,
+        check: CheckKind,
+        score: f32,
+        details: String,
+        writer: &mut EventWriter<SuspicionEvent>,
+    ) {
+        let total = self.suspicion.entry(check).or_default();
+        *total += score;
+
+        writer.send(SuspicionEvent {
+            player,
+            check,
+            score,
+            total: *total,
+            details,
+        });
+    }
+}
+
+impl Default for AntiCheatState {
+    fn default() -> Self {
+        Self {
+            clicks: VecDeque::with_capacity(CLICK_HISTORY),
+            reaches: VecDeque::new(),
+            suspicion: HashMap::new(),
+        }
+    }
+}
+
+pub struct AntiCheatPlugin;
+
+impl Plugin for AntiCheatPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SuspicionEvent>()
+            .init_resource::<AntiCheatConfig>()
+            .add_systems(
+                Update,
+                (
+                    init_clients,
+                    check_clicks,
+                    check_attacks,
+                    check_rotations,
+                    decay_suspicion,
+                ),
+            );
+    }
+}
+
+fn init_clients(mut commands: Commands, clients: Query<Entity, Added<Client>>) {
+    for entity in clients.iter() {
+        commands.entity(entity).insert(AntiCheatState::default());
+    }
+}
+
+/// The standard deviation of the time between the clicks in milliseconds.
+fn click_deviation(clicks: &VecDeque<Instant>) -> f64 {
+    let intervals = clicks
+        .iter()
+        .zip(clicks.iter().skip(1))
+        .map(|(a, b)| b.duration_since(*a).as_secs_f64() * 1000.0)
+        .collect::<Vec<_>>();
+
+    let mean = intervals.iter().sum::<f64>() / intervals.len() as f64;
+    let variance = intervals
+        .iter()
+        .map(|interval| (interval - mean).powi(2))
+        .sum::<f64>()
+        / intervals.len() as f64;
+
+    variance.sqrt()
+}
+
+fn check_clicks(
+    config: Res<AntiCheatConfig>,
+    mut events: EventReader<HandSwingEvent>,
+    mut players: Query<&mut AntiCheatState>,
+    mut suspicion_writer: EventWriter<SuspicionEvent>,
+) {
+    for event in events.read() {
+        let Ok(mut state) = players.get_mut(event.client) else {
+            continue;
+        };
+
+        if state.clicks.len() >= CLICK_HISTORY {
+            state.clicks.pop_front();
+        }
+
+        state.clicks.push_back(Instant::now());
+
+        let cps = state.cps();
+
+        if cps > config.max_cps as usize {
+            state.flag(
+                event.client,
+                CheckKind::AutoClicker,
+                (cps - config.max_cps as usize) as f32 * 0.1,
+                format!("{cps} clicks per second"),
+                &mut suspicion_writer,
+            );
+        }
+
+        // Only check the consistency of fast clicking.
+        if state.clicks.len() == CLICK_HISTORY && cps >= 8 {
+            let deviation = click_deviation(&state.clicks);
+
+            if deviation < config.min_click_deviation {
+                state.flag(
+                    event.client,
+                    CheckKind::AutoClicker,
+                    0.5,
+                    format!("click deviation of {deviation:.1}ms at {cps} clicks per second"),
+                    &mut suspicion_writer,
+                );
+            }
+        }
+    }
+}
+
+fn check_attacks(
+    config: Res<AntiCheatConfig>,
+    mut events: EventReader<AttackEvent>,
+    mut players: Query<&mut AntiCheatState>,
+    mut suspicion_writer: EventWriter<SuspicionEvent>,
+) {
+    for event in events.read() {
+        let Ok(mut state) = players.get_mut(event.attacker) else {
+            continue;
+        };
+
+        // Weapons with a longer reach (e.g. spears) should not be flagged.
+        let reach = event.normalized_reach();
+
+        if reach > config.max_reach {
+            state.flag(
+                event.attacker,
+                CheckKind::Reach,
+                (reach - config.max_reach) as f32,
+                format!("hit from {:.2} blocks", reach),
+                &mut suspicion_writer,
+            );
+        }
+
+        if state.reaches.len() >= config.reach_samples {
+            state.reaches.pop_front();
+        }
+
+        state.reaches.push_back(reach);
+
+        // A consistently high reach is suspicious even if every single hit is within the limit.
+        if state.reaches.len() >= config.reach_samples {
+            let average = state.reaches.iter().sum::<f64>() / state.reaches.len() as f64;
+
+            if average > config.max_average_reach {
+                state.flag(
+                    event.attacker,
+                    CheckKind::Reach,
+                    0.2,
+                    format!(
+                        "average reach of {average:.2} blocks over {} hits",
+                        state.reaches.len()
+                    ),
+                    &mut suspicion_writer,
+                );
+            }
+        }
+
+        if event.angle > config.max_hit_angle {
+            state.flag(
+                event.attacker,
+                CheckKind::KillAura,
+                (event.angle - config.max_hit_angle) / 30.0,
+                format!("hit at an angle of {:.0} degrees", event.angle),
+                &mut suspicion_writer,
+            );
+        }
+    }
+}
+
+fn check_rotations(
+    mut events: EventReader<MovementEvent>,
+    mut players: Query<&mut AntiCheatState>,
+    mut suspicion_writer: EventWriter<SuspicionEvent>,
+) {
+    for event in events.read() {
+        // A NaN pitch is not `<= 90.0` either.
+        if event.look.pitch.abs() <= 90.0 {
+            continue;
+        }
+
+        if let Ok(mut state) = players.get_mut(event.client) {
+            state.flag(
+                event.client,
+                CheckKind::Rotation,
+                1.0,
+                format!("impossible pitch of {:.1} degrees", event.look.pitch),
+                &mut suspicion_writer,
+            );
+        }
+    }
+}
+
+fn decay_suspicion(
+    time: Res<Time>,
+    config: Res<AntiCheatConfig>,
+    mut players: Query<&mut AntiCheatState>,
+) {
+    let decay = config.decay_per_second * time.delta_seconds();
+
+    for mut state in players.iter_mut() {
+        if state.suspicion.is_empty() {
+            continue;
+        }
+
+        state.suspicion.retain(|_, score| {
+            *score -= decay;
+            *score > 0.0
+        });
+    }
+}
@@ -0,0 +1,148 @@
+use valence::prelude::*;
+
+/// A command that will be executed on the [`World`] at the start of the next tick.
+type AsyncCommand = Box<dyn FnOnce(&mut World) + Send>;
+
+/// The maximum amount of commands from async tasks that will be applied per tick.
+const DEFAULT_MAX_COMMANDS_PER_TICK: usize = 1024;
+
+/// A resource that connects the ECS with async tasks.
+///
+/// Async tasks do not get access to the [`World`] directly, instead they get an [`AsyncBridgeHandle`]
+/// which can be used to subscribe to mirrored events and to enqueue commands that will be applied
+/// at the start of the next tick.
+#[derive(Resource)]
+pub struct AsyncBridge {
+    command_sender: flume::Sender<AsyncCommand>,
+    command_receiver: flume::Receiver<AsyncCommand>,
+    /// The maximum amount of commands that will be applied per tick,
+    /// the remaining commands will be applied in the following ticks.
+    pub max_commands_per_tick: usize,
+}
+
+impl Default for AsyncBridge {
+    fn default() -> Self {
+        let (command_sender, command_receiver) = flume::unbounded();
+
+        Self {
+            command_sender,
+            command_receiver,
+            max_commands_per_tick: DEFAULT_MAX_COMMANDS_PER_TICK,
+        }
+    }
+}
+
+impl AsyncBridge {
+    /// Create a new handle that can be moved into async tasks.
+    pub fn handle(&self) -> AsyncBridgeHandle {
+        AsyncBridgeHandle {
+            command_sender: self.command_sender.clone(),
+        }
+    }
+}
+
+/// A handle to the ECS that can be used from async tasks (or other threads).
+#[derive(Clone)]
+pub struct AsyncBridgeHandle {
+    command_sender: flume::Sender<AsyncCommand>,
+}
+
+impl AsyncBridgeHandle {
+    /// Enqueue a command that will be executed on the [`World`] at the start of the next tick.
+    ///
+    /// Returns `false` if the app was already shut down.
+    pub fn run(&self, command: impl FnOnce(&mut World) + Send + 'static) -> bool {
+        self.command_sender.send(Box::new(command)).is_ok()
+    }
+
+    /// Send a bevy event into the ECS.
+    ///
+    /// Returns `false` if the app was already shut down.
+    pub fn send_event<E: Event>(&self, event: E) -> bool {
+        self.run(move |world| {
+            world.send_event(event);
+        })
+    }
+
+    /// Subscribe to an event that was registered with [`AsyncBridgeAppExt::mirror_event`].
+    ///
+    /// The subscription will be active from the next tick on,
+    /// dropping the receiver will end the subscription.
+    pub fn subscribe<E: Event + Clone>(&self) -> flume::Receiver<E> {
+        let (sender, receiver) = flume::unbounded();
+
+        self.run(move |world| {
+            if let Some(mut mirrored) = world.get_resource_mut::<MirroredEvents<E>>() {
+                mirrored.subscribers.push(sender);
+            }
+        });
+
+        receiver
+    }
+}
+
+/// Stores the subscribers of a mirrored event.
+#[derive(Resource)]
+struct MirroredEvents<E: Event + Clone> {
+    subscribers: Vec<flume::Sender<E>>,
+}
+
+impl<E: Event + Clone> Default for MirroredEvents<E> {
+    fn default() -> Self {
+        Self {
+            subscribers: Vec::new(),
+        }
+    }
+}
+
+pub trait AsyncBridgeAppExt {
+    /// Mirror all events of type `E` into the channels created with [`AsyncBridgeHandle::subscribe`].
+    fn mirror_event<E: Event + Clone>(&mut self) -> &mut Self;
+}
+
+impl AsyncBridgeAppExt for App {
+    fn mirror_event<E: Event + Clone>(&mut self) -> &mut Self {
+        self.init_resource::<MirroredEvents<E>>()
+            .add_systems(Last, mirror_events::<E>)
+    }
+}
+
+pub struct AsyncBridgePlugin;
+
+impl Plugin for AsyncBridgePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<AsyncBridge>()
+            .add_systems(First, apply_async_commands);
+    }
+}
+
+fn apply_async_commands(world: &mut World) {
+    let (receiver, max_commands) = {
+        let bridge = world.resource::<AsyncBridge>();
+        (
+            bridge.command_receiver.clone(),
+            bridge.max_commands_per_tick,
+        )
+    };
+
+    for command in receiver.try_iter().take(max_commands) {
+        command(world);
+    }
+}
+
+fn mirror_events<E: Event + Clone>(
+    mut mirrored: ResMut<MirroredEvents<E>>,
+    mut events: EventReader<E>,
+) {
+    if mirrored.subscribers.is_empty() {
+        events.clear();
+        return;
+    }
+
+    for event in events.read() {
+        // Subscribers that dropped their receiver will be removed.
+        mirrored
+            .subscribers
+            .retain(|subscriber| subscriber.send(event.clone()).is_ok());
+    }
+}
@@ -0,0 +1,95 @@
+//! Show the health of an entity (e.g. a boss) in a boss bar.
+
+use valence::{
+    ecs::entity::EntityHashMap,
+    prelude::*,
+    protocol::packets::play::boss_bar_s2c::{BossBarColor, BossBarDivision},
+};
+
+use crate::{BossBarId, BossBars};
+
+/// Shows the [`Health`](valence::entity::living::Health) of an entity in a boss bar,
+/// the boss bar is shown to every player that can see the layer of the entity.
+#[derive(Component, Clone, Debug)]
+pub struct HealthBossBar {
+    pub title: Text,
+    pub color: BossBarColor,
+    /// The health of the entity when it is fully healed.
+    pub max_health: f32,
+}
+
+impl HealthBossBar {
+    pub fn new(title: Text, color: BossBarColor, max_health: f32) -> Self {
+        Self {
+            title,
+            color,
+            max_health,
+        }
+    }
+}
+
+/// The boss bars of the entities with a [`HealthBossBar`].
+#[derive(Resource, Default)]
+pub(crate) struct HealthBossBars(EntityHashMap<BossBarId>);
+
+pub(crate) fn spawn_health_boss_bars(
+    mut boss_bars: ResMut<BossBars>,
+    mut health_boss_bars: ResMut<HealthBossBars>,
+    query: Query<(Entity, &HealthBossBar), Added<HealthBossBar>>,
+) {
+    for (entity, health_bar) in query.iter() {
+        let id = boss_bars.create(
+            health_bar.title.clone(),
+            health_bar.color,
+            BossBarDivision::NoDivision,
+        );
+
+        if let Some(bar) = boss_bars.get_mut(id) {
+            // The progress is updated in `update_bound_bars`.
+            bar.bind_to_health(entity, health_bar.max_health);
+        }
+
+        if let Some(old) = health_boss_bars.0.insert(entity, id) {
+            boss_bars.remove(old);
+        }
+    }
+}
+
+/// Boss bars are shown to the clients that can see the layer of the entity.
+pub(crate) fn update_health_boss_bar_viewers(
+    health_boss_bars: Res<HealthBossBars>,
+    mut boss_bars: ResMut<BossBars>,
+    owners: Query<&EntityLayerId, With<HealthBossBar>>,
+    clients: Query<(Entity, &VisibleEntityLayers), With<Client>>,
+) {
+    for (owner, id) in health_boss_bars.0.iter() {
+        let (Ok(layer), Some(bar)) = (owners.get(*owner), boss_bars.get_mut(*id)) else {
+            continue;
+        };
+
+        for (client, visible_layers) in clients.iter() {
+            if visible_layers.0.contains(&layer.0) {
+                bar.add_viewer(client);
+            } else {
+                bar.remove_viewer(client);
+            }
+        }
+    }
+}
+
+/// Remove the boss bars of entities that were despawned (or had their [`HealthBossBar`] removed).
+pub(crate) fn remove_health_boss_bars(
+    mut boss_bars: ResMut<BossBars>,
+    mut health_boss_bars: ResMut<HealthBossBars>,
+    owners: Query<(), With<HealthBossBar>>,
+) {
+    health_boss_bars.0.retain(|owner, id| {
+        let keep = owners.contains(*owner);
+
+        if !keep {
+            boss_bars.remove(*id);
+        }
+
+        keep
+    });
+}
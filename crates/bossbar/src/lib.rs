@@ -0,0 +1,292 @@
+mod health;
+
+use std::{
+    borrow::Cow,
+    collections::{BTreeSet, HashMap},
+};
+
+pub use health::HealthBossBar;
+use health::{
+    remove_health_boss_bars, spawn_health_boss_bars, update_health_boss_bar_viewers, HealthBossBars,
+};
+use valence::{
+    entity::living::Health,
+    prelude::*,
+    protocol::{
+        packets::play::{
+            boss_bar_s2c::{BossBarAction, BossBarColor, BossBarDivision, BossBarFlags},
+            BossBarS2c,
+        },
+        WritePacket,
+    },
+    text::IntoText,
+    uuid::Uuid,
+};
+
+/// The id of a boss bar that was created with [`BossBars::create`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct BossBarId(Uuid);
+
+/// A boss bar that is shown to a set of players.
+///
+/// Changes are sent to the viewers at the end of the tick, only the parts that changed are resent.
+pub struct BossBar {
+    title: Text,
+    progress: f32,
+    color: BossBarColor,
+    division: BossBarDivision,
+    flags: BossBarFlags,
+    viewers: BTreeSet<Entity>,
+    /// The viewers that already received the boss bar.
+    sent_to: BTreeSet<Entity>,
+    /// Show the health of this entity.
+    bound_to: Option<HealthBinding>,
+    changes: BossBarChanges,
+}
+
+#[derive(Default)]
+struct BossBarChanges {
+    title: bool,
+    progress: bool,
+    style: bool,
+    flags: bool,
+}
+
+#[derive(Clone, Copy)]
+struct HealthBinding {
+    entity: Entity,
+    max_health: f32,
+}
+
+impl BossBar {
+    pub fn title(&self) -> &Text {
+        &self.title
+    }
+
+    pub fn set_title(&mut self, title: impl IntoText<'static>) {
+        self.title = title.into_cow_text().into_owned();
+        self.changes.title = true;
+    }
+
+    /// The progress of the bar (0.0 - 1.0).
+    pub fn progress(&self) -> f32 {
+        self.progress
+    }
+
+    pub fn set_progress(&mut self, progress: f32) {
+        let progress = progress.clamp(0.0, 1.0);
+
+        if self.progress != progress {
+            self.progress = progress;
+            self.changes.progress = true;
+        }
+    }
+
+    pub fn set_style(&mut self, color: BossBarColor, division: BossBarDivision) {
+        self.color = color;
+        self.division = division;
+        self.changes.style = true;
+    }
+
+    pub fn set_flags(&mut self, flags: BossBarFlags) {
+        self.flags = flags;
+        self.changes.flags = true;
+    }
+
+    pub fn viewers(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.viewers.iter().copied()
+    }
+
+    pub fn add_viewer(&mut self, client: Entity) {
+        self.viewers.insert(client);
+    }
+
+    pub fn remove_viewer(&mut self, client: Entity) {
+        self.viewers.remove(&client);
+    }
+
+    /// Update the progress of the bar with the [`Health`] of an entity (e.g. an arena boss).
+    pub fn bind_to_health(&mut self, entity: Entity, max_health: f32) {
+        self.bound_to = Some(HealthBinding { entity, max_health });
+    }
+
+    pub fn unbind(&mut self) {
+        self.bound_to = None;
+    }
+}
+
+/// All boss bars that are managed by the [`BossBarPlugin`].
+#[derive(Resource, Default)]
+pub struct BossBars {
+    bars: HashMap<BossBarId, BossBar>,
+    /// Removed boss bars and the clients that still show them.
+    removed: Vec<(BossBarId, BTreeSet<Entity>)>,
+}
+
+impl BossBars {
+    /// Create a new boss bar without any viewers.
+    pub fn create(
+        &mut self,
+        title: impl IntoText<'static>,
+        color: BossBarColor,
+        division: BossBarDivision,
+    ) -> BossBarId {
+        let id = BossBarId(Uuid::from_u128(rand::random()));
+
+        self.bars.insert(
+            id,
+            BossBar {
+                title: title.into_cow_text().into_owned(),
+                progress: 1.0,
+                color,
+                division,
+                flags: BossBarFlags::new(),
+                viewers: BTreeSet::new(),
+                sent_to: BTreeSet::new(),
+                bound_to: None,
+                changes: BossBarChanges::default(),
+            },
+        );
+
+        id
+    }
+
+    /// Remove the boss bar, it will be hidden from all viewers.
+    pub fn remove(&mut self, id: BossBarId) {
+        if let Some(bar) = self.bars.remove(&id) {
+            self.removed.push((id, bar.sent_to));
+        }
+    }
+
+    pub fn get(&self, id: BossBarId) -> Option<&BossBar> {
+        self.bars.get(&id)
+    }
+
+    pub fn get_mut(&mut self, id: BossBarId) -> Option<&mut BossBar> {
+        self.bars.get_mut(&id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (BossBarId, &BossBar)> {
+        self.bars.iter().map(|(id, bar)| (*id, bar))
+    }
+}
+
+pub struct BossBarPlugin;
+
+impl Plugin for BossBarPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BossBars>()
+            .init_resource::<HealthBossBars>()
+            .add_systems(
+                PostUpdate,
+                (
+                    spawn_health_boss_bars,
+                    update_health_boss_bar_viewers,
+                    remove_health_boss_bars,
+                    update_bound_bars,
+                    sync_boss_bars,
+                )
+                    .chain(),
+            );
+    }
+}
+
+fn update_bound_bars(mut boss_bars: ResMut<BossBars>, health: Query<&Health>) {
+    for bar in boss_bars.bars.values_mut() {
+        let Some(binding) = bar.bound_to else {
+            continue;
+        };
+
+        // The bar is empty if the entity was despawned.
+        let health = health.get(binding.entity).map_or(0.0, |health| health.0);
+
+        if binding.max_health > 0.0 {
+            bar.set_progress(health / binding.max_health);
+        } else {
+            bar.set_progress(0.0);
+        }
+    }
+}
+
+fn sync_boss_bars(mut boss_bars: ResMut<BossBars>, mut clients: Query<&mut Client>) {
+    let boss_bars = &mut *boss_bars;
+
+    for (id, viewers) in boss_bars.removed.drain(..) {
+        for viewer in viewers {
+            if let Ok(mut client) = clients.get_mut(viewer) {
+                client.write_packet(&BossBarS2c {
+                    id: id.0,
+                    action: BossBarAction::Remove,
+                });
+            }
+        }
+    }
+
+    for (id, bar) in boss_bars.bars.iter_mut() {
+        // Clients that disconnected.
+        bar.viewers.retain(|viewer| clients.contains(*viewer));
+        bar.sent_to.retain(|viewer| clients.contains(*viewer));
+
+        let changes = std::mem::take(&mut bar.changes);
+
+        for viewer in bar.sent_to.difference(&bar.viewers) {
+            if let Ok(mut client) = clients.get_mut(*viewer) {
+                client.write_packet(&BossBarS2c {
+                    id: id.0,
+                    action: BossBarAction::Remove,
+                });
+            }
+        }
+
+        for viewer in bar.sent_to.intersection(&bar.viewers) {
+            let Ok(mut client) = clients.get_mut(*viewer) else {
+                continue;
+            };
+
+            if changes.title {
+                client.write_packet(&BossBarS2c {
+                    id: id.0,
+                    action: BossBarAction::UpdateTitle(Cow::Borrowed(&bar.title)),
+                });
+            }
+
+            if changes.progress {
+                client.write_packet(&BossBarS2c {
+                    id: id.0,
+                    action: BossBarAction::UpdateHealth(bar.progress),
+                });
+            }
+
+            if changes.style {
+                client.write_packet(&BossBarS2c {
+                    id: id.0,
+                    action: BossBarAction::UpdateStyle(bar.color, bar.division),
+                });
+            }
+
+            if changes.flags {
+                client.write_packet(&BossBarS2c {
+                    id: id.0,
+                    action: BossBarAction::UpdateFlags(bar.flags),
+                });
+            }
+        }
+
+        for viewer in bar.viewers.difference(&bar.sent_to) {
+            if let Ok(mut client) = clients.get_mut(*viewer) {
+                client.write_packet(&BossBarS2c {
+                    id: id.0,
+                    action: BossBarAction::Add {
+                        title: Cow::Borrowed(&bar.title),
+                        health: bar.progress,
+                        color: bar.color,
+                        division: bar.division,
+                        flags: bar.flags,
+                    },
+                });
+            }
+        }
+
+        bar.sent_to.clone_from(&bar.viewers);
+    }
+}
@@ -0,0 +1,67 @@
+use regions::{RegionFlag, RegionFlagCheck};
+use valence::{
+    digging::{DiggingEvent, DiggingState},
+    math::DVec3,
+    prelude::*,
+};
+
+use crate::{resend_block, BlockJournal, BuildState};
+
+/// Players in creative mode break blocks as soon as they start digging.
+///
+/// Breaking in the other game modes is not handled here.
+pub(crate) fn creative_break_system(
+    mut clients: Query<(&BuildState, &GameMode, &UniqueId, &mut Client)>,
+    mut layers: Query<(Entity, &mut ChunkLayer)>,
+    mut events: EventReader<DiggingEvent>,
+    mut region_check: RegionFlagCheck,
+    mut journal: Option<ResMut<BlockJournal>>,
+) {
+    for event in events.read() {
+        if event.state != DiggingState::Start {
+            continue;
+        }
+
+        let Ok((build_state, game_mode, uuid, mut client)) = clients.get_mut(event.client) else {
+            continue;
+        };
+
+        if *game_mode != GameMode::Creative || !build_state.build_config.creative_instant_break {
+            continue;
+        }
+
+        let (layer_entity, mut layer) = layers.single_mut();
+
+        let Some(old) = layer.block(event.position).map(|block| block.state) else {
+            continue;
+        };
+
+        let center = DVec3::new(
+            event.position.x as f64 + 0.5,
+            event.position.y as f64 + 0.5,
+            event.position.z as f64 + 0.5,
+        );
+
+        // Denied breaks in regions are already sent back by the regions plugin.
+        if !region_check.check(event.client, layer_entity, center, RegionFlag::Build) {
+            continue;
+        }
+
+        if !build_state.build_config.can_build_at(event.position) {
+            resend_block(&mut client, &layer, event.position);
+            continue;
+        }
+
+        layer.set_block(event.position, BlockState::AIR);
+
+        if let Some(journal) = journal.as_mut() {
+            journal.record(
+                Some(uuid.0),
+                layer_entity,
+                event.position,
+                old,
+                BlockState::AIR,
+            );
+        }
+    }
+}
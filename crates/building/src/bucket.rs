@@ -0,0 +1,322 @@
+use valence::{
+    block::{BlockKind, PropName, PropValue},
+    ecs::query::QueryData,
+    interact_item::InteractItemEvent,
+    inventory::HeldItem,
+    math::DVec3,
+    prelude::*,
+    protocol::{sound::SoundCategory, Sound},
+};
+
+use crate::{BlockJournal, BuildState};
+use regions::{RegionFlag, RegionFlagCheck};
+use utils::{
+    inventory::InventoryExt,
+    sound::{SoundBuilder, Sounds},
+};
+
+/// The maximum distance a player can place or pick up fluids from.
+const BUCKET_REACH: f64 = 5.0;
+/// The height of the eyes of a player.
+const EYE_HEIGHT: f64 = 1.62;
+
+/// An event that will be fired if a player placed a fluid with a bucket.
+#[derive(Event, Debug)]
+pub struct FluidPlacedEvent {
+    pub client: Entity,
+    pub position: BlockPos,
+    /// Either [`BlockKind::Water`] or [`BlockKind::Lava`].
+    pub fluid: BlockKind,
+}
+
+/// An event that will be fired if a player picked up a fluid with an empty bucket.
+#[derive(Event, Debug)]
+pub struct FluidPickedUpEvent {
+    pub client: Entity,
+    pub position: BlockPos,
+    /// Either [`BlockKind::Water`] or [`BlockKind::Lava`].
+    pub fluid: BlockKind,
+}
+
+#[derive(QueryData)]
+#[query_data(mutable)]
+pub(crate) struct BucketQuery {
+    entity: Entity,
+    build_state: &'static mut BuildState,
+    inventory: &'static mut Inventory,
+    held_item: &'static HeldItem,
+    position: &'static Position,
+    look: &'static Look,
+    game_mode: &'static GameMode,
+    uuid: &'static UniqueId,
+}
+
+/// Buckets are used with the use item packet (the client does not raycast fluids),
+/// so the targeted block is found on the server.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn bucket_system(
+    mut clients: Query<BucketQuery>,
+    mut layers: Query<(Entity, &mut ChunkLayer)>,
+    mut events: EventReader<InteractItemEvent>,
+    mut region_check: RegionFlagCheck,
+    mut journal: Option<ResMut<BlockJournal>>,
+    mut placed_writer: EventWriter<FluidPlacedEvent>,
+    mut picked_up_writer: EventWriter<FluidPickedUpEvent>,
+    mut sounds: Sounds,
+) {
+    for event in events.read() {
+        if event.hand != Hand::Main {
+            continue;
+        }
+
+        let Ok(mut bucket_query) = clients.get_mut(event.client) else {
+            continue;
+        };
+
+        let slot = bucket_query.held_item.slot();
+        let item = bucket_query.inventory.slot(slot).item;
+
+        let fluid = match item {
+            ItemKind::Bucket => None,
+            ItemKind::WaterBucket => Some(BlockKind::Water),
+            ItemKind::LavaBucket => Some(BlockKind::Lava),
+            _ => continue,
+        };
+
+        if !bucket_query.build_state.can_use_item(item)
+            || !bucket_query
+                .build_state
+                .build_config
+                .can_build_in(*bucket_query.game_mode)
+        {
+            continue;
+        }
+
+        let (layer_entity, mut layer) = layers.single_mut();
+
+        let origin = bucket_query.position.0 + DVec3::new(0.0, EYE_HEIGHT, 0.0);
+        let direction = bucket_query.look.vec().as_dvec3();
+
+        let (position, old_state, new_state, fluid, picked_up) = match fluid {
+            // Pick up a fluid source.
+            None => {
+                let Some((position, state)) = raycast(&layer, origin, direction, |state| {
+                    fluid_source(state).is_some()
+                }) else {
+                    continue;
+                };
+
+                let Some(fluid) = fluid_source(state) else {
+                    continue;
+                };
+
+                let new_state = if state.get(PropName::Waterlogged).is_some() {
+                    state.set(PropName::Waterlogged, PropValue::False)
+                } else {
+                    BlockState::AIR
+                };
+
+                (position, state, new_state, fluid, true)
+            }
+            // Place a fluid source.
+            Some(fluid) => {
+                let Some((hit_pos, hit_state)) = raycast(&layer, origin, direction, |state| {
+                    !state.is_air() && !state.is_liquid()
+                }) else {
+                    continue;
+                };
+
+                if fluid == BlockKind::Water
+                    && hit_state.get(PropName::Waterlogged) == Some(PropValue::False)
+                {
+                    let new_state = hit_state.set(PropName::Waterlogged, PropValue::True);
+                    (hit_pos, hit_state, new_state, fluid, false)
+                } else {
+                    let position = if hit_state.is_replaceable() {
+                        hit_pos
+                    } else {
+                        hit_pos.get_in_direction(hit_face(hit_pos, origin, direction))
+                    };
+
+                    let Some(state) = layer.block(position).map(|block| block.state) else {
+                        continue;
+                    };
+
+                    if !state.is_replaceable() {
+                        continue;
+                    }
+
+                    (position, state, fluid.to_state(), fluid, false)
+                }
+            }
+        };
+
+        let center = DVec3::new(
+            position.x as f64 + 0.5,
+            position.y as f64 + 0.5,
+            position.z as f64 + 0.5,
+        );
+
+        if !bucket_query.build_state.build_config.can_build_at(position)
+            || !region_check.check(bucket_query.entity, layer_entity, center, RegionFlag::Build)
+        {
+            continue;
+        }
+
+        let consumes_bucket = *bucket_query.game_mode != GameMode::Creative
+            || bucket_query
+                .build_state
+                .build_config
+                .creative_consumes_items;
+
+        // The new bucket would be lost if there is no room for it.
+        if consumes_bucket && !can_swap_bucket(&bucket_query.inventory, slot) {
+            continue;
+        }
+
+        layer.set_block(position, new_state);
+        bucket_query.build_state.use_item(item);
+
+        let (bucket, sound) = match (picked_up, fluid) {
+            (true, BlockKind::Water) => (ItemKind::WaterBucket, Sound::ItemBucketFill),
+            (true, _) => (ItemKind::LavaBucket, Sound::ItemBucketFillLava),
+            (false, BlockKind::Water) => (ItemKind::Bucket, Sound::ItemBucketEmpty),
+            (false, _) => (ItemKind::Bucket, Sound::ItemBucketEmptyLava),
+        };
+
+        // The client of the player already played the sound.
+        SoundBuilder::new(sound, center)
+            .category(SoundCategory::Block)
+            .exclude(bucket_query.entity)
+            .play(&mut sounds, layer_entity);
+
+        if consumes_bucket {
+            swap_bucket(&mut bucket_query.inventory, slot, bucket);
+        }
+
+        if picked_up {
+            picked_up_writer.send(FluidPickedUpEvent {
+                client: event.client,
+                position,
+                fluid,
+            });
+        } else {
+            placed_writer.send(FluidPlacedEvent {
+                client: event.client,
+                position,
+                fluid,
+            });
+        }
+
+        if let Some(journal) = journal.as_mut() {
+            journal.record(
+                Some(bucket_query.uuid.0),
+                layer_entity,
+                position,
+                old_state,
+                new_state,
+            );
+        }
+    }
+}
+
+/// The fluid of the block, if the block is a fluid source (or waterlogged).
+fn fluid_source(state: BlockState) -> Option<BlockKind> {
+    match state.to_kind() {
+        kind @ (BlockKind::Water | BlockKind::Lava)
+            if state.get(PropName::Level) == Some(PropValue::_0) =>
+        {
+            Some(kind)
+        }
+        _ if state.get(PropName::Waterlogged) == Some(PropValue::True) => Some(BlockKind::Water),
+        _ => None,
+    }
+}
+
+/// If the slot can be swapped with another bucket, a stack of buckets needs an empty slot for the new bucket.
+fn can_swap_bucket(inventory: &Inventory, slot: u16) -> bool {
+    inventory.slot(slot).count <= 1 || inventory.first_empty_pickup_slot().is_some()
+}
+
+/// Replace one bucket in the slot with another bucket, see [`can_swap_bucket`].
+fn swap_bucket(inventory: &mut Inventory, slot: u16, bucket: ItemKind) {
+    let stack = inventory.slot(slot);
+
+    if stack.count <= 1 {
+        inventory.set_slot(slot, ItemStack::new(bucket, 1, None));
+        return;
+    }
+
+    let amount = stack.count - 1;
+    inventory.set_slot_amount(slot, amount);
+
+    inventory.add_item(&ItemStack::new(bucket, 1, None), None);
+}
+
+/// Walk through the blocks along the ray and return the first block that matches the predicate.
+fn raycast(
+    layer: &ChunkLayer,
+    origin: DVec3,
+    direction: DVec3,
+    predicate: impl Fn(BlockState) -> bool,
+) -> Option<(BlockPos, BlockState)> {
+    let mut distance = 0.0;
+
+    while distance <= BUCKET_REACH {
+        let point = origin + direction * distance;
+        let position = BlockPos::new(
+            point.x.floor() as i32,
+            point.y.floor() as i32,
+            point.z.floor() as i32,
+        );
+
+        if let Some(state) = layer.block(position).map(|block| block.state) {
+            if predicate(state) {
+                return Some((position, state));
+            }
+        }
+
+        distance += 0.05;
+    }
+
+    None
+}
+
+/// The face of the block that was hit by the ray.
+fn hit_face(position: BlockPos, origin: DVec3, direction: DVec3) -> Direction {
+    let min = DVec3::new(position.x as f64, position.y as f64, position.z as f64);
+    let max = min + DVec3::ONE;
+
+    // The face with the latest entry time of the slab method is the face that was hit.
+    let entry = |origin: f64, direction: f64, min: f64, max: f64| {
+        if direction == 0.0 {
+            f64::NEG_INFINITY
+        } else if direction > 0.0 {
+            (min - origin) / direction
+        } else {
+            (max - origin) / direction
+        }
+    };
+
+    let tx = entry(origin.x, direction.x, min.x, max.x);
+    let ty = entry(origin.y, direction.y, min.y, max.y);
+    let tz = entry(origin.z, direction.z, min.z, max.z);
+
+    if tx >= ty && tx >= tz {
+        if direction.x > 0.0 {
+            Direction::West
+        } else {
+            Direction::East
+        }
+    } else if ty >= tz {
+        if direction.y > 0.0 {
+            Direction::Down
+        } else {
+            Direction::Up
+        }
+    } else if direction.z > 0.0 {
+        Direction::North
+    } else {
+        Direction::South
+    }
+}
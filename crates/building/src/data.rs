@@ -0,0 +1,88 @@
+//! A serializable version of the [`PlayerBuildConfig`], so it can be loaded from files.
+//!
+//! Placement handlers are referenced by their name in a [`BuildFormulas`] registry.
+
+use std::{
+    collections::{HashMap, HashSet},
+    time::Duration,
+};
+
+use regions::RegionShape;
+use serde::{Deserialize, Serialize};
+use utils::formula::{FormulaRegistry, UnknownFormula};
+use valence::ItemKind;
+
+use crate::{placement_handler::on_try_place_default, PlaceHandler, PlayerBuildConfig};
+
+/// The placement handlers that can be referenced by a [`PlayerBuildConfigData`].
+///
+/// The default handler is registered as `"default"`.
+pub struct BuildFormulas {
+    pub on_try_place: FormulaRegistry<PlaceHandler>,
+}
+
+impl Default for BuildFormulas {
+    fn default() -> Self {
+        Self {
+            on_try_place: FormulaRegistry::new().with("default", on_try_place_default as _),
+        }
+    }
+}
+
+/// The serializable version of the [`PlayerBuildConfig`].
+///
+/// See [`PlayerBuildConfig`] for the documentation of the fields.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PlayerBuildConfigData {
+    pub place_cooldown: Duration,
+    /// The name of a handler in [`BuildFormulas::on_try_place`].
+    pub on_try_place: String,
+    pub min_build_y: Option<i32>,
+    pub max_build_y: Option<i32>,
+    pub build_area: Option<RegionShape>,
+    #[serde(with = "utils::serialization::item_kind_map")]
+    pub item_cooldowns: HashMap<ItemKind, Duration>,
+    #[serde(with = "utils::serialization::item_kind_set")]
+    pub blocked_items: HashSet<ItemKind>,
+    pub adventure_can_build: bool,
+    pub creative_consumes_items: bool,
+    pub creative_instant_break: bool,
+}
+
+impl Default for PlayerBuildConfigData {
+    fn default() -> Self {
+        let config = PlayerBuildConfig::default();
+
+        Self {
+            place_cooldown: config.place_cooldown,
+            on_try_place: "default".to_string(),
+            min_build_y: config.min_build_y,
+            max_build_y: config.max_build_y,
+            build_area: config.build_area,
+            item_cooldowns: config.item_cooldowns,
+            blocked_items: config.blocked_items,
+            adventure_can_build: config.adventure_can_build,
+            creative_consumes_items: config.creative_consumes_items,
+            creative_instant_break: config.creative_instant_break,
+        }
+    }
+}
+
+impl PlayerBuildConfigData {
+    /// Create the config, fails if the placement handler is not registered in the [`BuildFormulas`].
+    pub fn to_config(&self, formulas: &BuildFormulas) -> Result<PlayerBuildConfig, UnknownFormula> {
+        Ok(PlayerBuildConfig {
+            place_cooldown: self.place_cooldown,
+            on_try_place: formulas.on_try_place.resolve(&self.on_try_place).copied()?,
+            min_build_y: self.min_build_y,
+            max_build_y: self.max_build_y,
+            build_area: self.build_area.clone(),
+            item_cooldowns: self.item_cooldowns.clone(),
+            blocked_items: self.blocked_items.clone(),
+            adventure_can_build: self.adventure_can_build,
+            creative_consumes_items: self.creative_consumes_items,
+            creative_instant_break: self.creative_instant_break,
+        })
+    }
+}
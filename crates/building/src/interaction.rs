@@ -0,0 +1,114 @@
+use valence::{
+    block::{BlockKind, PropName, PropValue},
+    prelude::*,
+};
+
+/// An event that will be fired if a player right clicks a block that can be interacted with
+/// (e.g. chests or crafting tables), but is not handled by the building crate itself.
+///
+/// Doors, trapdoors and fence gates are opened and closed by the building crate.
+#[derive(Event, Debug)]
+pub struct InteractableBlockEvent {
+    pub client: Entity,
+    pub hand: Hand,
+    pub position: BlockPos,
+    pub block: BlockState,
+    pub face: Direction,
+    /// The position of the cursor on the clicked block.
+    pub cursor_pos: Vec3,
+}
+
+/// Check if a player can interact with the block by right clicking it.
+pub fn is_interactable(kind: BlockKind) -> bool {
+    if is_toggleable(kind) {
+        return true;
+    }
+
+    let name = kind.to_str();
+
+    name.ends_with("_button")
+        || name.ends_with("_bed")
+        || name.ends_with("shulker_box")
+        || name.ends_with("_sign")
+        || name.ends_with("anvil")
+        || matches!(
+            kind,
+            BlockKind::Chest
+                | BlockKind::TrappedChest
+                | BlockKind::EnderChest
+                | BlockKind::Barrel
+                | BlockKind::CraftingTable
+                | BlockKind::Furnace
+                | BlockKind::BlastFurnace
+                | BlockKind::Smoker
+                | BlockKind::Dispenser
+                | BlockKind::Dropper
+                | BlockKind::Hopper
+                | BlockKind::BrewingStand
+                | BlockKind::EnchantingTable
+                | BlockKind::Beacon
+                | BlockKind::Lever
+                | BlockKind::Repeater
+                | BlockKind::Comparator
+                | BlockKind::NoteBlock
+                | BlockKind::Jukebox
+                | BlockKind::Loom
+                | BlockKind::CartographyTable
+                | BlockKind::Grindstone
+                | BlockKind::Stonecutter
+                | BlockKind::SmithingTable
+                | BlockKind::Lectern
+                | BlockKind::Bell
+                | BlockKind::CommandBlock
+                | BlockKind::ChainCommandBlock
+                | BlockKind::RepeatingCommandBlock
+        )
+}
+
+/// Doors, trapdoors and fence gates that can be opened by hand.
+fn is_toggleable(kind: BlockKind) -> bool {
+    kind != BlockKind::IronDoor
+        && kind != BlockKind::IronTrapdoor
+        && kind.to_state().get(PropName::Open).is_some()
+        && kind.to_state().get(PropName::Powered).is_some()
+        && (kind.to_state().get(PropName::Hinge).is_some()
+            || kind.to_state().get(PropName::Half).is_some()
+            || kind.to_state().get(PropName::InWall).is_some())
+}
+
+/// Opens or closes a door, trapdoor or fence gate.
+///
+/// Returns `false` if the block can not be toggled.
+pub(crate) fn toggle_block(layer: &mut ChunkLayer, position: BlockPos) -> bool {
+    let Some(state) = layer.block(position).map(|block| block.state) else {
+        return false;
+    };
+
+    if !is_toggleable(state.to_kind()) {
+        return false;
+    }
+
+    let open = if state.get(PropName::Open) == Some(PropValue::True) {
+        PropValue::False
+    } else {
+        PropValue::True
+    };
+
+    layer.set_block(position, state.set(PropName::Open, open));
+
+    // The other half of a door has to be updated as well.
+    if state.get(PropName::Hinge).is_some() {
+        let other = match state.get(PropName::Half) {
+            Some(PropValue::Lower) => position.get_in_direction(Direction::Up),
+            _ => position.get_in_direction(Direction::Down),
+        };
+
+        if let Some(other_state) = layer.block(other).map(|block| block.state) {
+            if other_state.to_kind() == state.to_kind() {
+                layer.set_block(other, other_state.set(PropName::Open, open));
+            }
+        }
+    }
+
+    true
+}
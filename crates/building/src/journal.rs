@@ -0,0 +1,185 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, SystemTime},
+};
+
+use regions::RegionShape;
+use valence::{prelude::*, uuid::Uuid};
+
+/// A single block change.
+#[derive(Debug, Clone)]
+pub struct JournalEntry {
+    /// The player that changed the block, `None` if the change was not caused by a player.
+    pub player: Option<Uuid>,
+    pub time: SystemTime,
+    pub layer: Entity,
+    pub position: BlockPos,
+    /// The block that was replaced.
+    pub old: BlockState,
+    pub new: BlockState,
+}
+
+/// A storage backend for the [`BlockJournal`].
+pub trait JournalStorage: Send + Sync + 'static {
+    fn push(&mut self, entry: JournalEntry);
+    /// Remove all entries that match the filter and return them (newest first).
+    fn take(&mut self, filter: &dyn Fn(&JournalEntry) -> bool) -> Vec<JournalEntry>;
+    /// All entries, oldest first.
+    fn entries(&self) -> Box<dyn Iterator<Item = &JournalEntry> + '_>;
+}
+
+/// Keeps the latest block changes in memory, the oldest entries are dropped once
+/// the capacity is reached.
+pub struct RingBufferStorage {
+    entries: VecDeque<JournalEntry>,
+    capacity: usize,
+}
+
+impl RingBufferStorage {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+}
+
+impl JournalStorage for RingBufferStorage {
+    fn push(&mut self, entry: JournalEntry) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        if self.entries.len() >= self.capacity {
+            self.entries.pop_front();
+        }
+
+        self.entries.push_back(entry);
+    }
+
+    fn take(&mut self, filter: &dyn Fn(&JournalEntry) -> bool) -> Vec<JournalEntry> {
+        let mut taken = Vec::new();
+        self.entries.retain(|entry| {
+            if filter(entry) {
+                taken.push(entry.clone());
+                false
+            } else {
+                true
+            }
+        });
+
+        taken.reverse();
+        taken
+    }
+
+    fn entries(&self) -> Box<dyn Iterator<Item = &JournalEntry> + '_> {
+        Box::new(self.entries.iter())
+    }
+}
+
+/// Records block placements and breaks so they can be rolled back later.
+///
+/// The journal is opt-in, placements of the [`BuildPlugin`](crate::BuildPlugin) are only
+/// recorded if this resource exists. Systems that break blocks should call [`BlockJournal::record`].
+#[derive(Resource)]
+pub struct BlockJournal {
+    storage: Box<dyn JournalStorage>,
+}
+
+impl Default for BlockJournal {
+    fn default() -> Self {
+        Self::new(RingBufferStorage::new(100_000))
+    }
+}
+
+impl BlockJournal {
+    pub fn new(storage: impl JournalStorage) -> Self {
+        Self {
+            storage: Box::new(storage),
+        }
+    }
+
+    /// Record a block change.
+    pub fn record(
+        &mut self,
+        player: Option<Uuid>,
+        layer: Entity,
+        position: BlockPos,
+        old: BlockState,
+        new: BlockState,
+    ) {
+        if old == new {
+            return;
+        }
+
+        self.storage.push(JournalEntry {
+            player,
+            time: SystemTime::now(),
+            layer,
+            position,
+            old,
+            new,
+        });
+    }
+
+    /// All recorded entries, oldest first.
+    pub fn entries(&self) -> impl Iterator<Item = &JournalEntry> + '_ {
+        self.storage.entries()
+    }
+
+    /// Roll back all changes of the layer that match the filter and happened within the time window.
+    ///
+    /// The changes are reverted newest first, so every block ends up in the state before
+    /// the oldest rolled back change. Returns the amount of reverted changes.
+    pub fn rollback_where(
+        &mut self,
+        layer: Entity,
+        chunk_layer: &mut ChunkLayer,
+        window: Duration,
+        filter: impl Fn(&JournalEntry) -> bool,
+    ) -> usize {
+        let since = SystemTime::now()
+            .checked_sub(window)
+            .unwrap_or(SystemTime::UNIX_EPOCH);
+
+        let entries = self
+            .storage
+            .take(&|entry| entry.layer == layer && entry.time >= since && filter(entry));
+
+        for entry in entries.iter() {
+            chunk_layer.set_block(entry.position, entry.old);
+        }
+
+        entries.len()
+    }
+
+    /// Roll back all changes a player made within the time window.
+    pub fn rollback_player(
+        &mut self,
+        layer: Entity,
+        chunk_layer: &mut ChunkLayer,
+        player: Uuid,
+        window: Duration,
+    ) -> usize {
+        self.rollback_where(layer, chunk_layer, window, |entry| {
+            entry.player == Some(player)
+        })
+    }
+
+    /// Roll back all changes inside a region within the time window.
+    pub fn rollback_region(
+        &mut self,
+        layer: Entity,
+        chunk_layer: &mut ChunkLayer,
+        shape: &RegionShape,
+        window: Duration,
+    ) -> usize {
+        self.rollback_where(layer, chunk_layer, window, |entry| {
+            shape.contains(DVec3::new(
+                entry.position.x as f64 + 0.5,
+                entry.position.y as f64 + 0.5,
+                entry.position.z as f64 + 0.5,
+            ))
+        })
+    }
+}
@@ -1,10 +1,32 @@
+mod breaking;
+mod bucket;
+pub mod data;
+mod interaction;
+mod journal;
 mod placement_handler;
 
+pub use bucket::{FluidPickedUpEvent, FluidPlacedEvent};
 use bvh::bvh_resource::BvhResource;
+pub use interaction::{is_interactable, InteractableBlockEvent};
+pub use journal::{BlockJournal, JournalEntry, JournalStorage, RingBufferStorage};
 use placement_handler::on_try_place_default;
-use std::time::{Duration, Instant};
+use regions::{RegionFlag, RegionFlagCheck, RegionShape};
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    time::{Duration, Instant},
+};
+use utils::rate_limit::{RateLimitKind, RateLimitPlugin, RateLimits};
 use valence::{
-    ecs::query::QueryData, interact_block::InteractBlockEvent, inventory::HeldItem, prelude::*,
+    ecs::query::QueryData,
+    entity::entity::Flags,
+    interact_block::InteractBlockEvent,
+    inventory::{ClientInventoryState, HeldItem},
+    prelude::*,
+    protocol::{
+        packets::play::{BlockUpdateS2c, ScreenHandlerSlotUpdateS2c},
+        WritePacket,
+    },
 };
 
 /// Attached to every player that is able to build.
@@ -12,6 +34,8 @@ use valence::{
 pub struct BuildState {
     /// Last time the player placed a block.
     pub last_place: Instant,
+    /// Last time the player used items that have a cooldown in [`PlayerBuildConfig::item_cooldowns`].
+    pub last_item_use: HashMap<ItemKind, Instant>,
     /// The build config for the player.
     pub build_config: PlayerBuildConfig,
 }
@@ -20,29 +44,105 @@ impl Default for BuildState {
     fn default() -> Self {
         Self {
             last_place: Instant::now(),
+            last_item_use: HashMap::new(),
             build_config: PlayerBuildConfig::default(),
         }
     }
 }
 
+impl BuildState {
+    /// Check if the item is not blocked and not on cooldown.
+    pub fn can_use_item(&self, item: ItemKind) -> bool {
+        if self.build_config.blocked_items.contains(&item) {
+            return false;
+        }
+
+        match (
+            self.build_config.item_cooldowns.get(&item),
+            self.last_item_use.get(&item),
+        ) {
+            (Some(cooldown), Some(last_use)) => last_use.elapsed() >= *cooldown,
+            _ => true,
+        }
+    }
+
+    /// Start the cooldown of the item (if it has one).
+    pub fn use_item(&mut self, item: ItemKind) {
+        if self.build_config.item_cooldowns.contains_key(&item) {
+            self.last_item_use.insert(item, Instant::now());
+        }
+    }
+}
+
+/// A callback that handles the placement of blocks, see [`PlayerBuildConfig::on_try_place`].
+pub type PlaceHandler = fn(
+    Entity,
+    BlockPos,
+    &ChunkLayer,
+    &Inventory,
+    &HeldItem,
+    Direction,
+    Vec3,
+    &Look,
+    &BvhResource,
+) -> Vec<(BlockPos, BlockState)>;
+
 /// Configuration
+#[derive(Clone)]
 pub struct PlayerBuildConfig {
     /// A Cooldown for placing blocks.
     pub place_cooldown: Duration,
     /// A callback when the player tries to place a block.
-    /// This function handles the actual placement of blocks.
+    /// This function decides which blocks are placed.
     ///
-    /// The parameters are: `player_entity`, `clicked_pos` (position of the block the player clicked on), `chunk_layer`, `player_inventory`, `held_item`, `direction`.
-    /// Returns `true` if the placement was successful.
-    pub on_try_place: fn(
-        Entity,
-        BlockPos,
-        &mut ChunkLayer,
-        &mut Inventory,
-        &HeldItem,
-        Direction,
-        &BvhResource,
-    ) -> bool,
+    /// The parameters are: `player_entity`, `clicked_pos` (position of the block the player clicked on), `chunk_layer`, `player_inventory`, `held_item`, `direction`, `cursor_pos` (position of the cursor on the clicked block), `player_look`.
+    /// Returns the blocks that will be placed, the placement is rejected if it is empty
+    /// or if any of the positions is outside the build limits of the player.
+    pub on_try_place: PlaceHandler,
+    /// The lowest y level the player can place blocks at.
+    pub min_build_y: Option<i32>,
+    /// The highest y level the player can place blocks at.
+    pub max_build_y: Option<i32>,
+    /// If set, the player can only place blocks inside this area.
+    pub build_area: Option<RegionShape>,
+    /// Cooldowns for specific items, these are applied in addition to the `place_cooldown`.
+    pub item_cooldowns: HashMap<ItemKind, Duration>,
+    /// Items that can not be placed (or used, in case of buckets).
+    pub blocked_items: HashSet<ItemKind>,
+    /// Players in [`GameMode::Adventure`] can place blocks and use buckets.
+    pub adventure_can_build: bool,
+    /// Players in [`GameMode::Creative`] use up the items they place.
+    pub creative_consumes_items: bool,
+    /// Players in [`GameMode::Creative`] break blocks instantly.
+    pub creative_instant_break: bool,
+}
+
+impl PlayerBuildConfig {
+    /// Check if the position is within the build limits of the player.
+    pub fn can_build_at(&self, pos: BlockPos) -> bool {
+        if self.min_build_y.is_some_and(|min_y| pos.y < min_y)
+            || self.max_build_y.is_some_and(|max_y| pos.y > max_y)
+        {
+            return false;
+        }
+
+        self.build_area.as_ref().map_or(true, |area| {
+            area.contains(DVec3::new(
+                pos.x as f64 + 0.5,
+                pos.y as f64 + 0.5,
+                pos.z as f64 + 0.5,
+            ))
+        })
+    }
+
+    /// Check if the player can place blocks in the game mode.
+    pub fn can_build_in(&self, game_mode: GameMode) -> bool {
+        match game_mode {
+            GameMode::Survival | GameMode::Creative => true,
+            GameMode::Adventure => self.adventure_can_build,
+            GameMode::Spectator => false,
+        }
+    }
 }
 
 impl Default for PlayerBuildConfig {
@@ -50,6 +150,14 @@ impl Default for PlayerBuildConfig {
         Self {
             place_cooldown: Duration::ZERO,
             on_try_place: on_try_place_default,
+            min_build_y: None,
+            max_build_y: None,
+            build_area: None,
+            item_cooldowns: HashMap::new(),
+            blocked_items: HashSet::new(),
+            adventure_can_build: false,
+            creative_consumes_items: false,
+            creative_instant_break: true,
         }
     }
 }
@@ -58,7 +166,22 @@ pub struct BuildPlugin;
 
 impl Plugin for BuildPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(FixedPreUpdate, build_system);
+        app.add_event::<InteractableBlockEvent>()
+            .add_event::<FluidPlacedEvent>()
+            .add_event::<FluidPickedUpEvent>();
+
+        if !app.is_plugin_added::<RateLimitPlugin>() {
+            app.add_plugins(RateLimitPlugin);
+        }
+
+        app.add_systems(
+            FixedPreUpdate,
+            (
+                build_system,
+                bucket::bucket_system,
+                breaking::creative_break_system,
+            ),
+        );
     }
 }
 
@@ -69,41 +192,188 @@ struct BuildQuery {
     build_state: &'static mut BuildState,
     inventory: &'static mut Inventory,
     held_item: &'static HeldItem,
+    look: &'static Look,
+    flags: &'static Flags,
+    uuid: &'static UniqueId,
+    inventory_state: &'static ClientInventoryState,
+    client: &'static mut Client,
+    game_mode: &'static GameMode,
 }
 
+#[allow(clippy::too_many_arguments)]
 fn build_system(
     mut clients: Query<BuildQuery>,
     bvh: Res<BvhResource>,
-    mut layers: Query<&mut ChunkLayer>,
+    mut layers: Query<(Entity, &mut ChunkLayer)>,
     mut events: EventReader<InteractBlockEvent>,
+    mut region_check: RegionFlagCheck,
+    mut interactable_writer: EventWriter<InteractableBlockEvent>,
+    mut journal: Option<ResMut<BlockJournal>>,
+    mut rate_limits: RateLimits,
 ) {
     for event in events.read() {
         let Ok(mut build_query) = clients.get_mut(event.client) else {
             continue;
         };
 
-        if build_query.build_state.last_place.elapsed()
-            < build_query.build_state.build_config.place_cooldown
+        if event.hand != Hand::Main {
+            continue;
+        }
+
+        let (layer_entity, mut layer) = layers.single_mut();
+
+        let clicked_state = layer
+            .block(event.position)
+            .map(|block| block.state)
+            .unwrap_or(BlockState::AIR);
+
+        let holding_item = !build_query
+            .inventory
+            .slot(build_query.held_item.slot())
+            .is_empty();
+
+        // Sneaking players place blocks against interactable blocks instead of using them.
+        if is_interactable(clicked_state.to_kind())
+            && !(build_query.flags.sneaking() && holding_item)
         {
+            if !interaction::toggle_block(&mut layer, event.position) {
+                interactable_writer.send(InteractableBlockEvent {
+                    client: event.client,
+                    hand: event.hand,
+                    position: event.position,
+                    block: clicked_state,
+                    face: event.face,
+                    cursor_pos: event.cursor_pos,
+                });
+            }
+
+            // The client might have predicted a placement.
+            resend_block(
+                &mut build_query.client,
+                &layer,
+                event.position.get_in_direction(event.face),
+            );
             continue;
         }
 
-        if event.hand != Hand::Main {
+        // Replaceable blocks (like grass) are replaced directly.
+        let place_pos = if clicked_state.is_replaceable() {
+            event.position
+        } else {
+            event.position.get_in_direction(event.face)
+        };
+
+        let item = build_query
+            .inventory
+            .slot(build_query.held_item.slot())
+            .item;
+
+        if !build_query
+            .build_state
+            .build_config
+            .can_build_in(*build_query.game_mode)
+        {
+            reject_placement(&mut build_query, &layer, place_pos);
             continue;
         }
 
-        let mut layer = layers.single_mut();
+        if build_query.build_state.last_place.elapsed()
+            < build_query.build_state.build_config.place_cooldown
+            || !build_query.build_state.can_use_item(item)
+            || !rate_limits.check(event.client, RateLimitKind::BlockPlace)
+        {
+            reject_placement(&mut build_query, &layer, place_pos);
+            continue;
+        }
 
-        if (build_query.build_state.build_config.on_try_place)(
+        let placements = (build_query.build_state.build_config.on_try_place)(
             build_query.entity,
             event.position,
-            &mut layer,
-            &mut build_query.inventory,
+            &layer,
+            &build_query.inventory,
             build_query.held_item,
             event.face,
+            event.cursor_pos,
+            build_query.look,
             &bvh,
-        ) {
-            build_query.build_state.last_place = Instant::now();
+        );
+
+        // Every block of the placement (e.g. the upper half of a door) has to be inside the build limits.
+        let can_place = !placements.is_empty()
+            && placements.iter().all(|(pos, _)| {
+                let center = DVec3::new(pos.x as f64 + 0.5, pos.y as f64 + 0.5, pos.z as f64 + 0.5);
+
+                build_query.build_state.build_config.can_build_at(*pos)
+                    && region_check.check(
+                        build_query.entity,
+                        layer_entity,
+                        center,
+                        RegionFlag::Build,
+                    )
+            });
+
+        if !can_place {
+            reject_placement(&mut build_query, &layer, place_pos);
+            continue;
         }
+
+        build_query.build_state.last_place = Instant::now();
+        build_query.build_state.use_item(item);
+
+        if *build_query.game_mode != GameMode::Creative
+            || build_query.build_state.build_config.creative_consumes_items
+        {
+            consume_item(&mut build_query.inventory, build_query.held_item);
+        }
+
+        for (pos, state) in placements {
+            let old = layer.set_block(pos, state);
+
+            if let (Some(journal), Some(old)) = (journal.as_mut(), old) {
+                journal.record(
+                    Some(build_query.uuid.0),
+                    layer_entity,
+                    pos,
+                    old.state,
+                    state,
+                );
+            }
+        }
+    }
+}
+
+fn consume_item(inventory: &mut Inventory, held_item: &HeldItem) {
+    let slot = held_item.slot();
+    let count = inventory.slot(slot).count;
+
+    if count > 1 {
+        inventory.set_slot_amount(slot, count - 1);
+    } else {
+        inventory.set_slot(slot, ItemStack::EMPTY);
+    }
+}
+
+/// The client already predicted the placement, so the real block and the held item are sent again.
+fn reject_placement(build_query: &mut BuildQueryItem, layer: &ChunkLayer, position: BlockPos) {
+    resend_block(&mut build_query.client, layer, position);
+
+    let slot = build_query.held_item.slot();
+    build_query
+        .client
+        .write_packet(&ScreenHandlerSlotUpdateS2c {
+            window_id: 0,
+            state_id: build_query.inventory_state.state_id().0.into(),
+            slot_idx: slot as i16,
+            slot_data: Cow::Borrowed(build_query.inventory.slot(slot)),
+        });
+}
+
+/// Send the real block at the position to the client, to undo client side predictions.
+fn resend_block(client: &mut Client, layer: &ChunkLayer, position: BlockPos) {
+    if let Some(block) = layer.block(position) {
+        client.write_packet(&BlockUpdateS2c {
+            position,
+            block_id: block.state,
+        });
     }
 }
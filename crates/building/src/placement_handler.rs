@@ -1,82 +1,360 @@
 use bvh::bvh_resource::{BvhResource, ENTITY_BLOCK_BVH_IDX};
 use valence::{
     block::{BlockKind, PropName, PropValue},
+    entity::Look,
     inventory::HeldItem,
-    math::{Aabb, DVec3},
+    math::{Aabb, DVec3, Vec3},
     prelude::{Entity, Inventory},
-    BlockPos, BlockState, ChunkLayer, Direction, ItemStack,
+    BlockPos, BlockState, ChunkLayer, Direction,
 };
 
 /// A default implementation for the block placement handler.
 /// That mimics vanilla Minecraft behavior.
+#[allow(clippy::too_many_arguments)]
 pub fn on_try_place_default(
     _player_entity: Entity,
     clicked_pos: BlockPos,
-    chunk_layer: &mut ChunkLayer,
-    player_inventory: &mut Inventory,
+    chunk_layer: &ChunkLayer,
+    player_inventory: &Inventory,
     held_item: &HeldItem,
     direction: Direction,
+    cursor_pos: Vec3,
+    look: &Look,
     bvh: &BvhResource,
-) -> bool {
-    let slot_id = held_item.slot();
-    let stack = player_inventory.slot(slot_id);
+) -> Vec<(BlockPos, BlockState)> {
+    let stack = player_inventory.slot(held_item.slot());
 
     if stack.count == 0 {
         // The player is not holding any items.
-        return false;
+        return vec![];
     }
 
     let Some(block_kind) = BlockKind::from_item_kind(stack.item) else {
         // The item the player is holding cannot be placed as a block.
-        return false;
+        return vec![];
     };
 
-    let block_state = BlockState::from_kind(block_kind);
-    let block_hitboxes = block_state.collision_shapes();
+    let clicked_state = chunk_layer
+        .block(clicked_pos)
+        .map(|block| block.state)
+        .unwrap_or(BlockState::AIR);
 
-    let real_pos = clicked_pos.get_in_direction(direction);
+    // Clicking on a slab with the same slab can turn it into a double slab.
+    if let Some(merged) = merge_slab(clicked_state, block_kind, direction, false) {
+        if !collides(merged, clicked_pos, bvh) {
+            return vec![(clicked_pos, merged)];
+        }
+    }
 
-    for mut block_hitbox in block_hitboxes {
-        let tolerance = DVec3::new(0.0, 0.01, 0.0);
-        block_hitbox = Aabb::new(
-            block_hitbox.min()
-                + DVec3::new(real_pos.x as f64, real_pos.y as f64, real_pos.z as f64)
-                + tolerance,
-            block_hitbox.max()
-                + DVec3::new(real_pos.x as f64, real_pos.y as f64, real_pos.z as f64)
-                - tolerance,
-        );
+    // Replaceable blocks (like grass or snow layers) are replaced instead of placing next to them.
+    let real_pos = if clicked_state.is_replaceable() {
+        clicked_pos
+    } else {
+        clicked_pos.get_in_direction(direction)
+    };
 
-        if bvh[ENTITY_BLOCK_BVH_IDX]
-            .get_in_range(block_hitbox)
-            .next()
-            .is_some()
-        {
-            // TODO: this ignores the `BlockCollisionConfig` as defined in physics.
-            // The block would intersect with another entity.
-            return false;
+    let real_state = chunk_layer
+        .block(real_pos)
+        .map(|block| block.state)
+        .unwrap_or(BlockState::AIR);
+
+    // The block might also be merged with a slab that is already at the placement position.
+    if real_pos != clicked_pos {
+        if let Some(merged) = merge_slab(real_state, block_kind, direction, true) {
+            if !collides(merged, real_pos, bvh) {
+                return vec![(real_pos, merged)];
+            }
         }
     }
 
-    // The block can be placed.
+    if !real_state.is_replaceable() {
+        return vec![];
+    }
 
-    if stack.count > 1 {
-        let amount = stack.count - 1;
-        player_inventory.set_slot_amount(slot_id, amount);
+    // The position of the cursor relative to the block that will be placed.
+    let hit = DVec3::new(
+        clicked_pos.x as f64 + cursor_pos.x as f64 - real_pos.x as f64,
+        clicked_pos.y as f64 + cursor_pos.y as f64 - real_pos.y as f64,
+        clicked_pos.z as f64 + cursor_pos.z as f64 - real_pos.z as f64,
+    );
+
+    let Some(state) = derive_state(block_kind, direction, hit, look) else {
+        return vec![];
+    };
+
+    let state = if real_state.to_kind() == BlockKind::Water
+        && real_state.get(PropName::Level) == Some(PropValue::_0)
+    {
+        state.set(PropName::Waterlogged, PropValue::True)
     } else {
-        player_inventory.set_slot(slot_id, ItemStack::EMPTY);
+        state
+    };
+
+    let mut placements = vec![(real_pos, state)];
+
+    if state.get(PropName::Hinge).is_some() {
+        // Doors also occupy the block above.
+        placements.push((
+            real_pos.get_in_direction(Direction::Up),
+            state.set(PropName::Half, PropValue::Upper),
+        ));
+    } else if state.get(PropName::Part).is_some() {
+        // Beds have their head in the direction the player is looking.
+        let facing = horizontal_facing(look);
+        placements.push((
+            real_pos.get_in_direction(facing),
+            state.set(PropName::Part, PropValue::Head),
+        ));
+    }
+
+    // The other parts of multi block placements need space as well.
+    let has_space = placements.iter().skip(1).all(|(pos, _)| {
+        chunk_layer
+            .block(*pos)
+            .map_or(false, |block| block.state.is_replaceable())
+    });
+
+    if !has_space {
+        return vec![];
     }
 
-    let state = block_kind.to_state().set(
+    if placements
+        .iter()
+        .any(|&(pos, state)| collides(state, pos, bvh))
+    {
+        // TODO: this ignores the `BlockCollisionConfig` as defined in physics.
+        // The block would intersect with another entity.
+        return vec![];
+    }
+
+    // The block can be placed.
+    placements
+}
+
+/// Check if the block would intersect with an entity.
+fn collides(state: BlockState, pos: BlockPos, bvh: &BvhResource) -> bool {
+    let offset = DVec3::new(pos.x as f64, pos.y as f64, pos.z as f64);
+    let tolerance = DVec3::new(0.0, 0.01, 0.0);
+
+    state.collision_shapes().any(|hitbox| {
+        let hitbox = Aabb::new(
+            hitbox.min() + offset + tolerance,
+            hitbox.max() + offset - tolerance,
+        );
+
+        bvh[ENTITY_BLOCK_BVH_IDX]
+            .get_in_range(hitbox)
+            .next()
+            .is_some()
+    })
+}
+
+/// Returns the double slab if a slab of `kind` can be merged into `existing`.
+///
+/// If `inside` is `true`, the new slab is placed into the block of the existing slab
+/// (clicked on a neighbouring block), otherwise the existing slab itself was clicked.
+fn merge_slab(
+    existing: BlockState,
+    kind: BlockKind,
+    direction: Direction,
+    inside: bool,
+) -> Option<BlockState> {
+    if existing.to_kind() != kind {
+        return None;
+    }
+
+    let merged = existing.set(PropName::Type, PropValue::Double);
+    if merged.get(PropName::Type) != Some(PropValue::Double) || merged == existing {
+        return None;
+    }
+
+    let fits = match existing.get(PropName::Type) {
+        Some(PropValue::Bottom) => inside || direction == Direction::Up,
+        Some(PropValue::Top) => inside || direction == Direction::Down,
+        _ => false,
+    };
+
+    // Merged slabs can not be waterlogged.
+    fits.then(|| merged.set(PropName::Waterlogged, PropValue::False))
+}
+
+/// Derive the state of the placed block from the clicked face, the cursor position and the player look.
+///
+/// Returns `None` if the block can not be placed on this face (e.g. torches on a ceiling).
+fn derive_state(kind: BlockKind, face: Direction, hit: DVec3, look: &Look) -> Option<BlockState> {
+    let kind = match (wall_variant(kind), face) {
+        (
+            Some(wall_kind),
+            Direction::North | Direction::South | Direction::West | Direction::East,
+        ) => wall_kind,
+        (Some(_), Direction::Down) => return None,
+        _ => kind,
+    };
+
+    let mut state = kind.to_state();
+    let look_facing = horizontal_facing(look);
+    let upper_half = face == Direction::Down || (face != Direction::Up && hit.y > 0.5);
+
+    state = state.set(
         PropName::Axis,
-        match direction {
+        match face {
             Direction::Down | Direction::Up => PropValue::Y,
             Direction::North | Direction::South => PropValue::Z,
             Direction::West | Direction::East => PropValue::X,
         },
     );
 
-    chunk_layer.set_block(real_pos, state);
+    if state.get(PropName::Facing).is_some() {
+        let facing = if is_wall_mounted(kind) {
+            // Wall mounted blocks face away from the block they are attached to.
+            face
+        } else if state.get(PropName::Hinge).is_some()
+            || state.get(PropName::Part).is_some()
+            || state.get(PropName::InWall).is_some()
+            || state.get(PropName::Shape).is_some()
+        {
+            // Doors, beds, fence gates and stairs face in the direction the player is looking.
+            look_facing
+        } else if state.get(PropName::Open).is_some() && state.get(PropName::Half).is_some() {
+            // Trapdoors are attached to the clicked block if placed on a side.
+            match face {
+                Direction::Down | Direction::Up => opposite(look_facing),
+                _ => face,
+            }
+        } else if state
+            .set(PropName::Facing, PropValue::Up)
+            .get(PropName::Facing)
+            == Some(PropValue::Up)
+        {
+            // Blocks like pistons and dispensers can face in every direction.
+            opposite(facing_6(look))
+        } else {
+            // Most other blocks (e.g. chests and furnaces) face the player.
+            opposite(look_facing)
+        };
+
+        state = state.set(PropName::Facing, direction_value(facing));
+    }
+
+    if state.get(PropName::Hinge).is_some() {
+        state = state
+            .set(PropName::Half, PropValue::Lower)
+            .set(PropName::Hinge, door_hinge(look_facing, hit));
+    } else if state.get(PropName::Half).is_some() {
+        // Stairs and trapdoors.
+        state = state.set(
+            PropName::Half,
+            if upper_half {
+                PropValue::Top
+            } else {
+                PropValue::Bottom
+            },
+        );
+    }
+
+    if state.get(PropName::Part).is_some() {
+        state = state.set(PropName::Part, PropValue::Foot);
+    }
+
+    if state
+        .set(PropName::Type, PropValue::Double)
+        .get(PropName::Type)
+        == Some(PropValue::Double)
+    {
+        state = state.set(
+            PropName::Type,
+            if upper_half {
+                PropValue::Top
+            } else {
+                PropValue::Bottom
+            },
+        );
+    }
+
+    Some(state)
+}
+
+/// The variant of the block that is attached to a wall.
+fn wall_variant(kind: BlockKind) -> Option<BlockKind> {
+    match kind {
+        BlockKind::Torch => Some(BlockKind::WallTorch),
+        BlockKind::SoulTorch => Some(BlockKind::SoulWallTorch),
+        BlockKind::RedstoneTorch => Some(BlockKind::RedstoneWallTorch),
+        _ => None,
+    }
+}
 
-    true
+fn is_wall_mounted(kind: BlockKind) -> bool {
+    matches!(
+        kind,
+        BlockKind::WallTorch
+            | BlockKind::SoulWallTorch
+            | BlockKind::RedstoneWallTorch
+            | BlockKind::Ladder
+    )
+}
+
+/// Vanilla hinge placement without looking at neighbouring doors.
+fn door_hinge(facing: Direction, hit: DVec3) -> PropValue {
+    let (step_x, step_z) = match facing {
+        Direction::North => (0, -1),
+        Direction::South => (0, 1),
+        Direction::West => (-1, 0),
+        Direction::East => (1, 0),
+        _ => (0, 0),
+    };
+
+    let left = (step_x >= 0 || hit.z >= 0.5)
+        && (step_x <= 0 || hit.z <= 0.5)
+        && (step_z >= 0 || hit.x <= 0.5)
+        && (step_z <= 0 || hit.x >= 0.5);
+
+    if left {
+        PropValue::Left
+    } else {
+        PropValue::Right
+    }
+}
+
+/// The horizontal direction the player is looking at.
+fn horizontal_facing(look: &Look) -> Direction {
+    match ((look.yaw / 90.0 + 0.5).floor() as i32).rem_euclid(4) {
+        0 => Direction::South,
+        1 => Direction::West,
+        2 => Direction::North,
+        _ => Direction::East,
+    }
+}
+
+/// The direction the player is looking at, including up and down.
+fn facing_6(look: &Look) -> Direction {
+    if look.pitch > 45.0 {
+        Direction::Down
+    } else if look.pitch < -45.0 {
+        Direction::Up
+    } else {
+        horizontal_facing(look)
+    }
+}
+
+fn opposite(direction: Direction) -> Direction {
+    match direction {
+        Direction::Down => Direction::Up,
+        Direction::Up => Direction::Down,
+        Direction::North => Direction::South,
+        Direction::South => Direction::North,
+        Direction::West => Direction::East,
+        Direction::East => Direction::West,
+    }
+}
+
+fn direction_value(direction: Direction) -> PropValue {
+    match direction {
+        Direction::Down => PropValue::Down,
+        Direction::Up => PropValue::Up,
+        Direction::North => PropValue::North,
+        Direction::South => PropValue::South,
+        Direction::West => PropValue::West,
+        Direction::East => PropValue::East,
+    }
 }
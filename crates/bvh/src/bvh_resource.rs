@@ -1,5 +1,10 @@
-use std::collections::HashMap;
-use valence::{math::Aabb, prelude::*};
+use std::{collections::HashMap, time::Duration};
+
+use valence::{
+    ecs::entity::EntityHashMap,
+    math::{Aabb, DVec3},
+    prelude::*,
+};
 
 /// Use the BVH with key `0` for entity-entity collisions.
 pub const ENTITY_ENTITY_BVH_IDX: u64 = 0;
@@ -13,6 +18,19 @@ pub struct EntityBvhEntry {
     pub entity: Entity,
     /// The hitbox used for collision detection.
     pub hitbox: Aabb,
+    /// The collision groups the entity is part of (bitflags).
+    pub layer: u32,
+    /// The collision groups the entity can collide with (bitflags).
+    pub mask: u32,
+}
+
+impl EntityBvhEntry {
+    /// If two entries can collide with each other.
+    ///
+    /// Both entries have to be in a group the other one can collide with.
+    pub fn can_collide(&self, layer: u32, mask: u32) -> bool {
+        self.layer & mask != 0 && self.mask & layer != 0
+    }
 }
 
 // TODO: make this not a resource so it can be per layer
@@ -41,29 +59,201 @@ impl BvhResource {
     pub fn with_bvhs(num: usize) -> Self {
         let mut bvhs = HashMap::with_capacity(num);
         for i in 0..num {
-            bvhs.insert(i as u64, Bvh(crate::Bvh::default()));
+            bvhs.insert(i as u64, Bvh::default());
         }
 
         Self { bvhs }
     }
 }
 
+/// Statistics about the updates of a [`Bvh`], useful for profiling.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BvhStats {
+    /// How often the BVH was rebuilt from scratch.
+    pub rebuilds: u64,
+    /// How often the BVH was refitted.
+    pub refits: u64,
+    /// How often an update was skipped, because no entity moved.
+    pub skipped: u64,
+    /// The time the last update took.
+    pub last_update: Duration,
+    /// The cost of the tree after the last update divided by the cost after the last rebuild.
+    pub cost_ratio: f64,
+}
+
 /// A BVH for entities that are able to collide with each other.
-pub struct Bvh(crate::Bvh<EntityBvhEntry>);
+pub struct Bvh {
+    bvh: crate::Bvh<EntityBvhEntry>,
+    /// The index of every entity in the elements of the BVH.
+    indices: EntityHashMap<usize>,
+    /// If the BVH was built since it was created or cleared.
+    built: bool,
+    /// The cost of the tree right after it was built.
+    build_cost: f64,
+    /// If the cost of the tree grows by more than this factor (compared to the last rebuild),
+    /// the tree will be rebuilt instead of refitted.
+    pub rebuild_threshold: f64,
+    stats: BvhStats,
+}
+
+impl Default for Bvh {
+    fn default() -> Self {
+        Self {
+            bvh: crate::Bvh::default(),
+            indices: EntityHashMap::default(),
+            built: false,
+            build_cost: 0.0,
+            rebuild_threshold: 2.0,
+            stats: BvhStats::default(),
+        }
+    }
+}
 
 impl Bvh {
     /// Clear the BVH.
     pub fn clear(&mut self) {
-        self.0.clear();
+        self.bvh.clear();
+        self.indices.clear();
+        self.built = false;
+        self.build_cost = 0.0;
     }
 
     /// Build the BVH from the given entries.
     pub fn build(&mut self, entries: Vec<EntityBvhEntry>) {
-        self.0 = crate::Bvh::build(entries, |entry| entry.hitbox);
+        self.bvh = crate::Bvh::build(entries, |entry| entry.hitbox);
+        self.indices = self
+            .bvh
+            .elements()
+            .iter()
+            .enumerate()
+            .map(|(idx, entry)| (entry.entity, idx))
+            .collect();
+        self.built = true;
+        self.build_cost = self.bvh.cost();
+        self.stats.rebuilds += 1;
+        self.stats.cost_ratio = 1.0;
+    }
+
+    /// Update the BVH with the given entries.
+    ///
+    /// If the same entities are in the BVH, the tree is only refitted, it is only rebuilt
+    /// if entities were added or removed, or if the quality of the tree degraded too much.
+    pub fn update(&mut self, entries: Vec<EntityBvhEntry>) {
+        let start = std::time::Instant::now();
+
+        // A BVH that was never built has no entities either, so it would never be built without entries.
+        let same_entities = self.built
+            && entries.len() == self.indices.len()
+            && entries
+                .iter()
+                .all(|entry| self.indices.contains_key(&entry.entity));
+
+        if !same_entities {
+            self.build(entries);
+            self.stats.last_update = start.elapsed();
+            return;
+        }
+
+        let elements = self.bvh.elements_mut();
+        let mut moved = false;
+
+        for entry in entries {
+            let element = &mut elements[self.indices[&entry.entity]];
+            // The collision groups do not affect the tree.
+            element.layer = entry.layer;
+            element.mask = entry.mask;
+
+            if element.hitbox != entry.hitbox {
+                element.hitbox = entry.hitbox;
+                moved = true;
+            }
+        }
+
+        if !moved {
+            self.stats.skipped += 1;
+            self.stats.last_update = start.elapsed();
+            return;
+        }
+
+        let cost = self.bvh.refit(|entry| entry.hitbox);
+        self.stats.refits += 1;
+        self.stats.cost_ratio = if self.build_cost > 0.0 {
+            cost / self.build_cost
+        } else {
+            1.0
+        };
+
+        if self.stats.cost_ratio > self.rebuild_threshold {
+            let entries = self.bvh.elements().to_vec();
+            self.build(entries);
+        }
+
+        self.stats.last_update = start.elapsed();
+    }
+
+    pub fn stats(&self) -> BvhStats {
+        self.stats
     }
 
     /// Get all entities that are contained or intersect with the given AABB.
     pub fn get_in_range(&self, target: Aabb) -> impl Iterator<Item = &EntityBvhEntry> + '_ {
-        self.0.range(target, move |entry| entry.hitbox)
+        self.bvh.range(target, move |entry| entry.hitbox)
+    }
+
+    /// Get the entity that is closest to the point and the distance to it.
+    pub fn get_closest_to(&self, point: DVec3) -> Option<(&EntityBvhEntry, f64)> {
+        self.bvh
+            .get_closest(point, |entry| entry.hitbox)
+            .map(|(entry, dist2)| (entry, dist2.sqrt()))
+    }
+
+    /// Get all entities whose hitbox is inside (or intersects) the sphere.
+    pub fn get_in_sphere(
+        &self,
+        center: DVec3,
+        radius: f64,
+    ) -> impl Iterator<Item = &EntityBvhEntry> + '_ {
+        self.bvh
+            .in_sphere(center, radius, |entry: &EntityBvhEntry| entry.hitbox)
+    }
+
+    /// Get the first entity hit by a ray and the distance to it.
+    ///
+    /// Only entities closer than `max_distance` are hit.
+    pub fn raycast(
+        &self,
+        origin: DVec3,
+        direction: DVec3,
+        max_distance: f64,
+    ) -> Option<(&EntityBvhEntry, f64)> {
+        let direction = direction.normalize_or_zero();
+
+        if direction == DVec3::ZERO {
+            return None;
+        }
+
+        self.bvh
+            .raycast(origin, direction, max_distance, |entry| entry.hitbox)
+    }
+
+    /// Get the `k` entities closest to the point and the distances to them, sorted by the distance.
+    pub fn k_nearest(&self, point: DVec3, k: usize) -> Vec<(&EntityBvhEntry, f64)> {
+        self.bvh
+            .k_nearest(point, k, |entry| entry.hitbox)
+            .into_iter()
+            .map(|(entry, dist2)| (entry, dist2.sqrt()))
+            .collect()
+    }
+
+    /// Get all entities that are contained or intersect with the given AABB
+    /// and can collide with the given collision layer and mask (see [`EntityBvhEntry::can_collide`]).
+    pub fn get_in_range_masked(
+        &self,
+        target: Aabb,
+        layer: u32,
+        mask: u32,
+    ) -> impl Iterator<Item = &EntityBvhEntry> + '_ {
+        self.get_in_range(target)
+            .filter(move |entry| entry.can_collide(layer, mask))
     }
 }
@@ -2,6 +2,7 @@ pub mod build;
 pub mod bvh_resource;
 mod node;
 mod query;
+mod refit;
 mod utils;
 
 use arrayvec::ArrayVec;
@@ -37,6 +38,12 @@ impl<T> Bvh<T> {
 impl<T> Bvh<T> {
     fn root(&self) -> Node<'_, T> {
         let root = self.root;
+
+        // A BVH that was never built only has the dummy node, whose children point back to itself.
+        if root == 0 || self.elements.is_empty() {
+            return Node::Leaf(&[]);
+        }
+
         if root < 0 {
             return Node::Leaf(&self.elements[..]);
         }
@@ -181,3 +188,18 @@ impl BvhNode {
         &root.nodes[right as usize]
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use valence::math::DVec3;
+
+    use super::*;
+
+    #[test]
+    fn empty_bvh_has_no_results() {
+        let bvh = Bvh::<Aabb>::default();
+
+        assert!(bvh.k_nearest(DVec3::ZERO, 3, |aabb| *aabb).is_empty());
+        assert!(bvh.get_closest(DVec3::ZERO, |aabb| *aabb).is_none());
+    }
+}
@@ -1,2 +1,5 @@
 mod closest;
+mod nearest;
 mod range;
+mod raycast;
+mod sphere;
@@ -0,0 +1,64 @@
+use std::fmt::Debug;
+
+use arrayvec::ArrayVec;
+use valence::math::{Aabb, DVec3};
+
+use crate::{utils::AabbExt, Bvh, Node};
+
+impl<T: Debug> Bvh<T> {
+    /// Returns the `k` closest elements to the target and the distance squared to them,
+    /// sorted by the distance.
+    pub fn k_nearest(
+        &self,
+        target: DVec3,
+        k: usize,
+        get_aabb: impl Fn(&T) -> Aabb,
+    ) -> Vec<(&T, f64)> {
+        let mut nearest: Vec<(&T, f64)> = Vec::with_capacity(k + 1);
+
+        if k == 0 {
+            return nearest;
+        }
+
+        // The distance squared an element needs to have to be one of the k nearest elements.
+        let max_dist2 = |nearest: &Vec<(&T, f64)>| {
+            if nearest.len() < k {
+                f64::INFINITY
+            } else {
+                nearest[k - 1].1
+            }
+        };
+
+        let mut stack: ArrayVec<Node<'_, T>, 64> = ArrayVec::new();
+        stack.push(self.root());
+
+        while let Some(node) = stack.pop() {
+            match node {
+                Node::Internal(internal) => {
+                    if internal.aabb.dist2(target) > max_dist2(&nearest) {
+                        continue;
+                    }
+
+                    for child in internal.children(self) {
+                        stack.push(child);
+                    }
+                }
+                Node::Leaf(leaf) => {
+                    for elem in leaf {
+                        let dist2 = get_aabb(elem).dist2(target);
+
+                        if dist2 >= max_dist2(&nearest) {
+                            continue;
+                        }
+
+                        let idx = nearest.partition_point(|(_, other)| *other <= dist2);
+                        nearest.insert(idx, (elem, dist2));
+                        nearest.truncate(k);
+                    }
+                }
+            }
+        }
+
+        nearest
+    }
+}
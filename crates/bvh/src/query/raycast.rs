@@ -0,0 +1,60 @@
+use std::fmt::Debug;
+
+use arrayvec::ArrayVec;
+use valence::math::{Aabb, DVec3};
+
+use crate::{utils::AabbExt, Bvh, Node};
+
+impl<T: Debug> Bvh<T> {
+    /// Returns the first element that is hit by the ray and the distance along the ray to it.
+    ///
+    /// The direction does not have to be normalized, the distance is in multiples of its length.
+    pub fn raycast(
+        &self,
+        origin: DVec3,
+        direction: DVec3,
+        max_distance: f64,
+        get_aabb: impl Fn(&T) -> Aabb,
+    ) -> Option<(&T, f64)> {
+        let inv_direction = direction.recip();
+        let mut closest: Option<(&T, f64)> = None;
+
+        let mut stack: ArrayVec<Node<'_, T>, 64> = ArrayVec::new();
+        stack.push(self.root());
+
+        while let Some(node) = stack.pop() {
+            let max_distance = closest.map_or(max_distance, |(_, distance)| distance);
+
+            match node {
+                Node::Internal(internal) => {
+                    let hit = internal
+                        .aabb
+                        .ray_intersection(origin, inv_direction)
+                        .is_some_and(|distance| distance <= max_distance);
+
+                    if !hit {
+                        continue;
+                    }
+
+                    for child in internal.children(self) {
+                        stack.push(child);
+                    }
+                }
+                Node::Leaf(leaf) => {
+                    for elem in leaf {
+                        let Some(distance) = get_aabb(elem).ray_intersection(origin, inv_direction)
+                        else {
+                            continue;
+                        };
+
+                        if distance <= closest.map_or(max_distance, |(_, distance)| distance) {
+                            closest = Some((elem, distance));
+                        }
+                    }
+                }
+            }
+        }
+
+        closest
+    }
+}
@@ -0,0 +1,24 @@
+use std::fmt::Debug;
+
+use valence::math::{Aabb, DVec3};
+
+use crate::{
+    utils::{AabbExt, GetAabb},
+    Bvh,
+};
+
+impl<T: Debug> Bvh<T> {
+    /// Returns all elements that are inside (or intersect) the sphere.
+    pub fn in_sphere<'a>(
+        &'a self,
+        center: DVec3,
+        radius: f64,
+        get_aabb: impl GetAabb<T> + Copy + 'a,
+    ) -> impl Iterator<Item = &'a T> + 'a {
+        let bounds = Aabb::new(center - DVec3::splat(radius), center + DVec3::splat(radius));
+        let radius2 = radius * radius;
+
+        self.range(bounds, get_aabb)
+            .filter(move |elem| get_aabb(elem).dist2(center) <= radius2)
+    }
+}
@@ -0,0 +1,80 @@
+use valence::math::Aabb;
+
+use crate::{utils::GetAabb, Bvh};
+
+impl<T> Bvh<T> {
+    pub fn elements(&self) -> &[T] {
+        &self.elements
+    }
+
+    /// The elements can be modified in place, [`Bvh::refit`] has to be called afterwards
+    /// if the AABBs of the elements changed.
+    pub fn elements_mut(&mut self) -> &mut [T] {
+        &mut self.elements
+    }
+
+    /// Recalculate the AABBs of all nodes without changing the structure of the tree.
+    ///
+    /// This is a lot cheaper than rebuilding the tree, but the quality of the tree degrades
+    /// if the elements move far away from their original position.
+    ///
+    /// Returns the new cost of the tree (see [`Bvh::cost`]).
+    pub fn refit(&mut self, get_aabb: impl GetAabb<T>) -> f64 {
+        if self.root <= 0 {
+            return 0.0;
+        }
+
+        let (_, cost) = self.refit_node(self.root as usize, &get_aabb);
+        cost
+    }
+
+    /// The sum of the surface areas of all nodes, a lower cost means faster queries.
+    pub fn cost(&self) -> f64 {
+        if self.root <= 0 {
+            return 0.0;
+        }
+
+        self.node_cost(self.root as usize)
+    }
+
+    fn refit_node(&mut self, idx: usize, get_aabb: &impl GetAabb<T>) -> (Aabb, f64) {
+        let node = self.nodes[idx];
+
+        let (aabb, children_cost) = if node.left < 0 {
+            let start = (-node.left - 1) as usize;
+            let len = node.right as usize;
+
+            let aabb = self.elements[start..start + len]
+                .iter()
+                .map(get_aabb)
+                .reduce(|a, b| a.union(b))
+                .unwrap_or(Aabb::ZERO);
+
+            (aabb, 0.0)
+        } else {
+            let (left, left_cost) = self.refit_node(node.left as usize, get_aabb);
+            let (right, right_cost) = self.refit_node(node.right as usize, get_aabb);
+
+            (left.union(right), left_cost + right_cost)
+        };
+
+        self.nodes[idx].aabb = aabb;
+        (aabb, children_cost + surface_area(&aabb))
+    }
+
+    fn node_cost(&self, idx: usize) -> f64 {
+        let node = &self.nodes[idx];
+        let cost = surface_area(&node.aabb);
+
+        if node.left < 0 {
+            cost
+        } else {
+            cost + self.node_cost(node.left as usize) + self.node_cost(node.right as usize)
+        }
+    }
+}
+
+fn surface_area(aabb: &Aabb) -> f64 {
+    let size = aabb.max() - aabb.min();
+    2.0 * (size.x * size.y + size.y * size.z + size.z * size.x)
+}
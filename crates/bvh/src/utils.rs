@@ -5,6 +5,9 @@ use crate::node::BvhNode;
 
 pub trait AabbExt {
     fn dist2(&self, point: DVec3) -> f64;
+    /// The distance along a ray to the point where it enters the AABB
+    /// (`0.0` if the origin is inside of the AABB).
+    fn ray_intersection(&self, origin: DVec3, inv_direction: DVec3) -> Option<f64>;
 }
 
 impl AabbExt for Aabb {
@@ -13,6 +16,20 @@ impl AabbExt for Aabb {
 
         (point - clamped).length_squared()
     }
+
+    fn ray_intersection(&self, origin: DVec3, inv_direction: DVec3) -> Option<f64> {
+        let t1 = (self.min() - origin) * inv_direction;
+        let t2 = (self.max() - origin) * inv_direction;
+
+        let t_min = t1.min(t2).max_element();
+        let t_max = t1.max(t2).min_element();
+
+        if t_max < 0.0 || t_min > t_max {
+            return None;
+        }
+
+        Some(t_min.max(0.0))
+    }
 }
 
 /// get number of threads that is pow of 2
@@ -0,0 +1,135 @@
+//! A serializable version of the [`ChatChannelConfig`], so it can be loaded from files.
+//!
+//! Formatters and filters are referenced by their name in a [`ChatFormatters`] registry.
+
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use utils::formula::{FormulaRegistry, UnknownFormula};
+use valence::prelude::*;
+
+use crate::{
+    format_message_default, muffle_message_default, ChatChannelConfig, FilterResult,
+    ProximityConfig, SpectatorFilter,
+};
+
+/// The formatters and filters that can be referenced by a [`ChatChannelConfigData`].
+///
+/// The default formatters are registered as `"default"`.
+#[derive(Resource)]
+pub struct ChatFormatters {
+    /// The parameters are: `sender_name`, `message`.
+    pub format_message: FormulaRegistry<fn(&Username, &str) -> Text>,
+    /// The parameters are: `message`.
+    pub message_filter: FormulaRegistry<fn(&str) -> FilterResult>,
+    /// The parameters are: `message`.
+    pub muffle_message: FormulaRegistry<fn(Text) -> Text>,
+}
+
+impl Default for ChatFormatters {
+    fn default() -> Self {
+        Self {
+            format_message: FormulaRegistry::new().with("default", format_message_default as _),
+            message_filter: FormulaRegistry::new(),
+            muffle_message: FormulaRegistry::new().with("default", muffle_message_default as _),
+        }
+    }
+}
+
+/// The serializable version of the [`ChatChannelConfig`].
+///
+/// See [`ChatChannelConfig`] for the documentation of the fields.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChatChannelConfigData {
+    pub hide_msg_for_sender: bool,
+    pub required_prefix: Option<String>,
+    pub chat_cooldown: Option<Duration>,
+    pub global_prefix: Option<Text>,
+    /// The name of a formatter in [`ChatFormatters::format_message`].
+    pub format_message: String,
+    /// The name of a filter in [`ChatFormatters::message_filter`].
+    pub message_filter: Option<String>,
+    pub proximity: Option<ProximityConfigData>,
+    pub history_capacity: usize,
+    pub replay_on_join: usize,
+    pub spectators: SpectatorFilter,
+}
+
+impl Default for ChatChannelConfigData {
+    fn default() -> Self {
+        let config = ChatChannelConfig::default();
+
+        Self {
+            hide_msg_for_sender: config.hide_msg_for_sender,
+            required_prefix: config.required_prefix,
+            chat_cooldown: config.chat_cooldown,
+            global_prefix: config.global_prefix,
+            format_message: "default".to_string(),
+            message_filter: None,
+            proximity: None,
+            history_capacity: config.history_capacity,
+            replay_on_join: config.replay_on_join,
+            spectators: config.spectators,
+        }
+    }
+}
+
+/// The serializable version of the [`ProximityConfig`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProximityConfigData {
+    pub radius: f64,
+    #[serde(default)]
+    pub muffle_distance: Option<f64>,
+    /// The name of a function in [`ChatFormatters::muffle_message`].
+    #[serde(default = "default_name")]
+    pub muffle_message: String,
+}
+
+fn default_name() -> String {
+    "default".to_string()
+}
+
+impl ChatChannelConfigData {
+    /// Create the config, fails if a formatter or filter is not registered in the [`ChatFormatters`].
+    pub fn to_config(
+        &self,
+        formatters: &ChatFormatters,
+    ) -> Result<ChatChannelConfig, UnknownFormula> {
+        let message_filter = match &self.message_filter {
+            Some(name) => {
+                let filter = formatters.message_filter.resolve(name).copied()?;
+                Some(Box::new(filter) as Box<dyn Fn(&str) -> FilterResult + Send + Sync>)
+            }
+            None => None,
+        };
+
+        let proximity = match &self.proximity {
+            Some(proximity) => Some(ProximityConfig {
+                radius: proximity.radius,
+                muffle_distance: proximity.muffle_distance,
+                muffle_message: formatters
+                    .muffle_message
+                    .resolve(&proximity.muffle_message)
+                    .copied()?,
+            }),
+            None => None,
+        };
+
+        Ok(ChatChannelConfig {
+            hide_msg_for_sender: self.hide_msg_for_sender,
+            required_prefix: self.required_prefix.clone(),
+            chat_cooldown: self.chat_cooldown,
+            global_prefix: self.global_prefix.clone(),
+            format_message: formatters
+                .format_message
+                .resolve(&self.format_message)
+                .copied()?,
+            message_filter,
+            proximity,
+            history_capacity: self.history_capacity,
+            replay_on_join: self.replay_on_join,
+            spectators: self.spectators,
+        })
+    }
+}
@@ -0,0 +1,273 @@
+use std::collections::HashMap;
+
+use utils::{
+    custom_items::ItemStackCustomItemExt,
+    damage::{DamageType, DeathEvent},
+};
+use valence::{client::VisibleChunkLayer, entity::entity::CustomName, prelude::*, text::IntoText};
+
+use crate::ChatChannels;
+
+/// Who receives the death messages.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DeathMessageTarget {
+    /// All players on the server.
+    Server,
+    /// All players on the layer of the victim.
+    Layer,
+    /// The members of the chat channel that are able to read.
+    Channel(u64),
+}
+
+/// The templates of the death messages for one damage type.
+///
+/// The placeholders `{victim}`, `{killer}` and `{weapon}` are replaced with the names of the entities and the item.
+#[derive(Clone, Debug)]
+pub struct DeathMessageTemplate {
+    /// The message if there is no killer.
+    pub message: String,
+    /// The message if the victim was killed by another entity.
+    pub by_killer: Option<String>,
+    /// The message if the killer used a weapon with a custom name (like vanilla).
+    pub by_killer_with_weapon: Option<String>,
+}
+
+impl DeathMessageTemplate {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            by_killer: None,
+            by_killer_with_weapon: None,
+        }
+    }
+
+    pub fn with_killer(mut self, message: impl Into<String>) -> Self {
+        self.by_killer = Some(message.into());
+        self
+    }
+
+    pub fn with_weapon(mut self, message: impl Into<String>) -> Self {
+        self.by_killer_with_weapon = Some(message.into());
+        self
+    }
+}
+
+/// The config for the death messages that are sent by the [`DeathMessagePlugin`].
+#[derive(Resource, Clone, Debug)]
+pub struct DeathMessageConfig {
+    pub target: DeathMessageTarget,
+    pub templates: HashMap<DamageType, DeathMessageTemplate>,
+    /// The template for damage types without a template.
+    pub fallback: DeathMessageTemplate,
+    /// The name of entities that are not players and have no custom name.
+    pub unknown_name: String,
+    pub color: Color,
+}
+
+impl Default for DeathMessageConfig {
+    fn default() -> Self {
+        let templates = [
+            (
+                DamageType::Melee,
+                DeathMessageTemplate::new("{victim} died")
+                    .with_killer("{victim} was slain by {killer}")
+                    .with_weapon("{victim} was slain by {killer} using {weapon}"),
+            ),
+            (
+                DamageType::Projectile,
+                DeathMessageTemplate::new("{victim} was shot")
+                    .with_killer("{victim} was shot by {killer}")
+                    .with_weapon("{victim} was shot by {killer} using {weapon}"),
+            ),
+            (
+                DamageType::Fall,
+                DeathMessageTemplate::new("{victim} fell from a high place")
+                    .with_killer("{victim} was doomed to fall by {killer}"),
+            ),
+            (
+                DamageType::Fire,
+                DeathMessageTemplate::new("{victim} burned to death")
+                    .with_killer("{victim} was burned to a crisp while fighting {killer}"),
+            ),
+            (
+                DamageType::Wither,
+                DeathMessageTemplate::new("{victim} withered away")
+                    .with_killer("{victim} withered away whilst fighting {killer}"),
+            ),
+            (
+                DamageType::Explosion,
+                DeathMessageTemplate::new("{victim} blew up")
+                    .with_killer("{victim} was blown up by {killer}"),
+            ),
+            (
+                DamageType::Lightning,
+                DeathMessageTemplate::new("{victim} was struck by lightning")
+                    .with_killer("{victim} was struck by lightning whilst fighting {killer}"),
+            ),
+            (
+                DamageType::WorldBorder,
+                DeathMessageTemplate::new("{victim} left the confines of this world").with_killer(
+                    "{victim} left the confines of this world whilst fighting {killer}",
+                ),
+            ),
+            (
+                DamageType::Drowning,
+                DeathMessageTemplate::new("{victim} drowned")
+                    .with_killer("{victim} drowned whilst trying to escape {killer}"),
+            ),
+        ];
+
+        Self {
+            target: DeathMessageTarget::Server,
+            templates: templates.into_iter().collect(),
+            fallback: DeathMessageTemplate::new("{victim} died")
+                .with_killer("{victim} was killed by {killer}"),
+            unknown_name: "Unknown".to_owned(),
+            color: Color::WHITE,
+        }
+    }
+}
+
+/// An event that is emitted when a death message was sent.
+#[derive(Event, Clone, Debug)]
+pub struct DeathMessageEvent {
+    pub victim: Entity,
+    pub message: Text,
+}
+
+/// Sends a death message for every [`DeathEvent`], see [`DeathMessageConfig`].
+pub struct DeathMessagePlugin;
+
+impl Plugin for DeathMessagePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<DeathMessageEvent>()
+            .init_resource::<DeathMessageConfig>()
+            .add_systems(PostUpdate, send_death_messages);
+    }
+}
+
+/// Replace the `{name}` placeholders in the template.
+fn format_template(template: &str, placeholders: &[(&str, Text)]) -> Text {
+    let mut text = Text::default();
+    let mut rest = template;
+
+    while let Some(start) = rest.find('{') {
+        let Some(len) = rest[start..].find('}') else {
+            break;
+        };
+
+        let key = &rest[start + 1..start + len];
+
+        match placeholders.iter().find(|(name, _)| *name == key) {
+            Some((_, value)) => {
+                text += rest[..start].to_owned();
+                text += value.clone();
+            }
+            // Unknown placeholders are kept as they are.
+            None => text += rest[..start + len + 1].to_owned(),
+        }
+
+        rest = &rest[start + len + 1..];
+    }
+
+    text + rest.to_owned()
+}
+
+fn send_death_messages(
+    mut events: EventReader<DeathEvent>,
+    config: Res<DeathMessageConfig>,
+    names: Query<(Option<&Username>, Option<&CustomName>)>,
+    mut clients: Query<(&mut Client, &VisibleChunkLayer)>,
+    layers: Query<&EntityLayerId>,
+    channels: Option<Res<ChatChannels>>,
+    mut message_writer: EventWriter<DeathMessageEvent>,
+) {
+    let name = |entity: Entity| -> Text {
+        match names.get(entity) {
+            Ok((Some(username), _)) => username.0.clone().into_text(),
+            Ok((None, Some(CustomName(Some(name))))) => name.clone(),
+            _ => config.unknown_name.clone().into_text(),
+        }
+    };
+
+    for event in events.read() {
+        let blow = &event.killing_blow;
+        let template = config
+            .templates
+            .get(&blow.damage_type)
+            .unwrap_or(&config.fallback);
+
+        let weapon_name = blow
+            .weapon
+            .as_ref()
+            .and_then(|weapon| weapon.display_name())
+            .map(|name| {
+                name.parse::<Text>()
+                    .unwrap_or_else(|_| name.to_owned().into_text())
+            });
+
+        let message = match (event.attacker, &weapon_name) {
+            (Some(_), Some(_)) => template
+                .by_killer_with_weapon
+                .as_ref()
+                .or(template.by_killer.as_ref()),
+            (Some(_), None) => template.by_killer.as_ref(),
+            (None, _) => None,
+        }
+        .unwrap_or(&template.message);
+
+        let mut placeholders = vec![("victim", name(event.victim))];
+
+        if let Some(attacker) = event.attacker {
+            placeholders.push(("killer", name(attacker)));
+        }
+
+        if let Some(weapon_name) = weapon_name {
+            placeholders.push(("weapon", weapon_name));
+        }
+
+        let message = format_template(message, &placeholders).color(config.color);
+
+        match config.target {
+            DeathMessageTarget::Server => {
+                for (mut client, _) in clients.iter_mut() {
+                    client.send_chat_message(message.clone());
+                }
+            }
+            DeathMessageTarget::Layer => {
+                let Ok(layer) = layers.get(event.victim) else {
+                    continue;
+                };
+
+                for (mut client, visible_layer) in clients.iter_mut() {
+                    if visible_layer.0 == layer.0 {
+                        client.send_chat_message(message.clone());
+                    }
+                }
+            }
+            DeathMessageTarget::Channel(id) => {
+                let Some(channel) = channels
+                    .as_ref()
+                    .and_then(|channels| channels.channels.get(&id))
+                else {
+                    continue;
+                };
+
+                for (member, member_config) in channel.members.iter() {
+                    if !member_config.permission.can_read() {
+                        continue;
+                    }
+
+                    if let Ok((mut client, _)) = clients.get_mut(*member) {
+                        client.send_chat_message(message.clone());
+                    }
+                }
+            }
+        }
+
+        message_writer.send(DeathMessageEvent {
+            victim: event.victim,
+            message,
+        });
+    }
+}
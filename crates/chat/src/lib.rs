@@ -1,18 +1,73 @@
+pub mod data;
+mod death_messages;
+mod mute;
+mod signing;
+mod whisper;
+
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{HashMap, HashSet, VecDeque},
     time::{Duration, Instant},
 };
 
 use bevy_ecs::{entity::EntityHashMap, query::QueryData};
-use valence::{message::ChatMessageEvent, prelude::*};
+pub use death_messages::{
+    DeathMessageConfig, DeathMessageEvent, DeathMessagePlugin, DeathMessageTarget,
+    DeathMessageTemplate,
+};
+use mute::expire_mutes;
+pub use mute::{GlobalMuteList, MuteList};
+use serde::{Deserialize, Serialize};
+use signing::{
+    capture_signed_chat, init_signed_chat, register_chat_type, send_player_message,
+    sync_chat_sessions, FormattedChatType, PendingSignatures, SignedChat,
+};
+pub use signing::{ChatMessageSignature, ChatSession};
+use utils::{
+    rate_limit::{RateLimitKind, RateLimitPlugin, RateLimits},
+    spectator::Spectating,
+};
+use valence::{
+    message::ChatMessageEvent, player_list::PlayerListSet, prelude::*, text::IntoText, uuid::Uuid,
+};
+use whisper::{whisper_commands, whisper_system};
+pub use whisper::{ReplyEvent, WhisperConfig, WhisperEvent};
 
 /// The active chat channels that can be used by the players.
 #[derive(Default, Resource)]
 pub struct ChatChannels {
-    /// Maps the channel id to the active channel config and to the player-channel-config of each player in the channel.
-    channels: HashMap<u64, (ChatChannelConfig, EntityHashMap<PlayerChatChannelConfig>)>,
+    /// Maps the channel id to the active channel.
+    channels: HashMap<u64, ChatChannel>,
     /// Maps a player to the channels they are in, the first set is the channels with a required prefix, the second set is the channels without a required prefix.
     players_to_channels: EntityHashMap<(HashSet<u64>, HashSet<u64>)>,
+    /// History replays that will be sent in the next tick (`channel_id`, `player_entity`, `count`).
+    pending_replays: Vec<(u64, Entity, usize)>,
+}
+
+/// A chat channel.
+struct ChatChannel {
+    config: ChatChannelConfig,
+    /// The player-channel-config of each player in the channel.
+    members: EntityHashMap<PlayerChatChannelConfig>,
+    /// The last messages sent in this channel (oldest first).
+    history: VecDeque<ChatHistoryEntry>,
+}
+
+/// A message that was sent in a chat channel.
+#[derive(Clone, Debug)]
+pub struct ChatHistoryEntry {
+    pub sender: Entity,
+    pub sender_uuid: Uuid,
+    pub sender_name: String,
+    /// The message (without the required prefix) after it was filtered.
+    pub message: String,
+    /// The message as it was sent to the players (with all prefixes and formatting applied).
+    pub formatted: Text,
+    pub time: Instant,
+    /// The signature of the message.
+    ///
+    /// The signature is only kept if the message was not modified (by a required prefix or a filter),
+    /// otherwise it would not be valid for the message anymore.
+    pub signature: Option<ChatMessageSignature>,
 }
 
 impl ChatChannels {
@@ -22,8 +77,30 @@ impl ChatChannels {
 
     /// Add a new chat channel to the chat channels.
     pub fn add_channel(&mut self, channel_id: u64, config: ChatChannelConfig) {
-        self.channels
-            .insert(channel_id, (config, EntityHashMap::default()));
+        self.channels.insert(
+            channel_id,
+            ChatChannel {
+                history: VecDeque::with_capacity(config.history_capacity),
+                config,
+                members: EntityHashMap::default(),
+            },
+        );
+    }
+
+    /// Replace the config of a channel, the members and the history are kept.
+    ///
+    /// The channel is added if it does not exist.
+    pub fn set_channel_config(&mut self, channel_id: u64, config: ChatChannelConfig) {
+        match self.channels.get_mut(&channel_id) {
+            Some(channel) => {
+                while channel.history.len() > config.history_capacity {
+                    channel.history.pop_front();
+                }
+
+                channel.config = config;
+            }
+            None => self.add_channel(channel_id, config),
+        }
     }
 
     /// Add a player to a channel.
@@ -43,8 +120,8 @@ impl ChatChannels {
         player_entity: Entity,
         player_config: PlayerChatChannelConfig,
     ) -> Option<()> {
-        let (channel_config, channel_members) = self.channels.get_mut(&channel_id)?;
-        channel_members.insert(player_entity, player_config);
+        let channel = self.channels.get_mut(&channel_id)?;
+        channel.members.insert(player_entity, player_config);
 
         if !self.players_to_channels.contains_key(&player_entity) {
             self.players_to_channels
@@ -54,12 +131,17 @@ impl ChatChannels {
         let (with_prefix, without_prefix) =
             self.players_to_channels.get_mut(&player_entity).unwrap();
 
-        if channel_config.required_prefix.is_some() {
+        if channel.config.required_prefix.is_some() {
             with_prefix.insert(channel_id);
         } else {
             without_prefix.insert(channel_id);
         }
 
+        if channel.config.replay_on_join > 0 {
+            self.pending_replays
+                .push((channel_id, player_entity, channel.config.replay_on_join));
+        }
+
         Some(())
     }
 
@@ -69,8 +151,8 @@ impl ChatChannels {
         channel_id: u64,
         player_entity: Entity,
     ) -> Option<()> {
-        let (_, channel_members) = self.channels.get_mut(&channel_id)?;
-        channel_members.remove(&player_entity);
+        let channel = self.channels.get_mut(&channel_id)?;
+        channel.members.remove(&player_entity);
 
         if let Some((with_prefix, without_prefix)) =
             self.players_to_channels.get_mut(&player_entity)
@@ -84,16 +166,47 @@ impl ChatChannels {
 
     /// Remove a player from all channels.
     pub fn remove_player(&mut self, player_entity: Entity) {
-        for (_, (_, channel_members)) in self.channels.iter_mut() {
-            channel_members.remove(&player_entity);
+        for channel in self.channels.values_mut() {
+            channel.members.remove(&player_entity);
         }
 
         self.players_to_channels.remove(&player_entity);
     }
+
+    /// The message history of a channel (oldest first).
+    ///
+    /// Returns `None` if the channel does not exist.
+    pub fn history(&self, channel_id: u64) -> Option<impl Iterator<Item = &ChatHistoryEntry>> {
+        Some(self.channels.get(&channel_id)?.history.iter())
+    }
+
+    /// Send the last `count` messages of a channel to a player (in the next tick).
+    ///
+    /// Messages from players the receiver muted will be skipped.
+    pub fn replay_history(&mut self, channel_id: u64, player_entity: Entity, count: usize) {
+        self.pending_replays
+            .push((channel_id, player_entity, count));
+    }
+
+    /// Add a message to the history of a channel.
+    fn record(&mut self, channel_id: u64, entry: ChatHistoryEntry) {
+        let Some(channel) = self.channels.get_mut(&channel_id) else {
+            return;
+        };
+
+        if channel.config.history_capacity == 0 {
+            return;
+        }
+
+        while channel.history.len() >= channel.config.history_capacity {
+            channel.history.pop_front();
+        }
+
+        channel.history.push_back(entry);
+    }
 }
 
 /// A general config of a chat channel.
-#[derive(Default)]
 pub struct ChatChannelConfig {
     /// If the chat message should be hidden to the sender.
     pub hide_msg_for_sender: bool,
@@ -103,7 +216,140 @@ pub struct ChatChannelConfig {
     /// A cooldown for the chat message.
     pub chat_cooldown: Option<Duration>,
     /// The global prefix that will be applied to all messages in this channel.
-    pub global_prefix: Option<String>,
+    pub global_prefix: Option<Text>,
+    /// Formats the message of a player.
+    ///
+    /// The parameters are: `sender_name`, `message` (without the required prefix).
+    ///
+    /// The result will be prefixed with the global prefix and the player's prefix.
+    pub format_message: fn(&Username, &str) -> Text,
+    /// A filter that is applied to every message (without the required prefix) before it is sent.
+    ///
+    /// This can be used to block, censor or flag messages (e.g. profanity filters, spam detection).
+    pub message_filter: Option<Box<dyn Fn(&str) -> FilterResult + Send + Sync>>,
+    /// If `Some`, messages will only be delivered to members that are close to the sender.
+    pub proximity: Option<ProximityConfig>,
+    /// The amount of messages that will be kept in the channel's history.
+    ///
+    /// Messages of proximity channels will not be recorded.
+    pub history_capacity: usize,
+    /// The amount of messages from the history that will be sent to players that join the channel.
+    pub replay_on_join: usize,
+    /// Which members can read and write in the channel, based on the [`Spectating`] component.
+    pub spectators: SpectatorFilter,
+}
+
+impl Default for ChatChannelConfig {
+    fn default() -> Self {
+        Self {
+            hide_msg_for_sender: false,
+            required_prefix: None,
+            chat_cooldown: None,
+            global_prefix: None,
+            format_message: format_message_default,
+            message_filter: None,
+            proximity: None,
+            history_capacity: 0,
+            replay_on_join: 0,
+            spectators: SpectatorFilter::default(),
+        }
+    }
+}
+
+/// Filters the members of a chat channel by the [`Spectating`] component.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SpectatorFilter {
+    /// Spectators and other players can use the channel.
+    #[default]
+    Everyone,
+    /// Only spectators can read and write in the channel (e.g. a chat for dead players in a minigame).
+    SpectatorsOnly,
+    /// Spectators can not read or write in the channel.
+    NoSpectators,
+}
+
+impl SpectatorFilter {
+    pub fn allows(self, spectating: bool) -> bool {
+        match self {
+            Self::Everyone => true,
+            Self::SpectatorsOnly => spectating,
+            Self::NoSpectators => !spectating,
+        }
+    }
+}
+
+/// Configuration for proximity (local) chat channels.
+pub struct ProximityConfig {
+    /// Only members within this radius (in blocks) of the sender will receive the message.
+    pub radius: f64,
+    /// Members further away than this distance will receive a muffled message.
+    ///
+    /// If `None`, messages will never be muffled.
+    pub muffle_distance: Option<f64>,
+    /// The function that muffles a message.
+    ///
+    /// The parameters are: `message` (with all prefixes applied).
+    pub muffle_message: fn(Text) -> Text,
+}
+
+impl ProximityConfig {
+    /// A proximity config that muffles messages beyond half of the radius.
+    pub fn with_radius(radius: f64) -> Self {
+        Self {
+            radius,
+            muffle_distance: Some(radius / 2.0),
+            muffle_message: muffle_message_default,
+        }
+    }
+}
+
+/// The default muffle function, it makes the message gray and italic.
+pub fn muffle_message_default(message: Text) -> Text {
+    message.color(Color::GRAY).italic()
+}
+
+/// The default message formatter, it only returns the message itself.
+pub fn format_message_default(_sender_name: &Username, message: &str) -> Text {
+    message.to_string().into_text()
+}
+
+/// The result of a [`ChatChannelConfig::message_filter`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum FilterResult {
+    /// The message will be sent unchanged.
+    Allow,
+    /// The message will not be sent, a [`ChatMessageBlockedEvent`] will be emitted.
+    Block {
+        /// Why the message was blocked.
+        reason: Option<String>,
+    },
+    /// The message will be replaced by the given message.
+    Censor(String),
+    /// The message will be sent unchanged, but a [`ChatMessageFlaggedEvent`] will be emitted.
+    Flag {
+        /// Why the message was flagged.
+        reason: String,
+    },
+}
+
+/// An event that will be fired if a message was blocked by a [`ChatChannelConfig::message_filter`].
+#[derive(Event, Debug)]
+pub struct ChatMessageBlockedEvent {
+    pub sender: Entity,
+    pub channel_id: u64,
+    /// The message (without the required prefix) that was blocked.
+    pub message: String,
+    pub reason: Option<String>,
+}
+
+/// An event that will be fired if a message was flagged by a [`ChatChannelConfig::message_filter`].
+#[derive(Event, Debug)]
+pub struct ChatMessageFlaggedEvent {
+    pub sender: Entity,
+    pub channel_id: u64,
+    /// The message (without the required prefix) that was flagged.
+    pub message: String,
+    pub reason: String,
 }
 
 /// A config for a player that is specific to a chat channel.
@@ -112,7 +358,7 @@ pub struct PlayerChatChannelConfig {
     /// The player's permission for the channel.
     pub permission: ChatChannelPermission,
     /// The player's prefix for the channel.
-    pub prefix: Option<String>,
+    pub prefix: Option<Text>,
 }
 
 /// The permissions for a specific chat channel of a player.
@@ -143,18 +389,61 @@ impl ChatChannelPermission {
 /// This needs to be attached to the player in order to use the chat system.
 #[derive(Default, Component)]
 pub struct ChatAbility {
-    /// Messages from players with that name will be ignored.
-    pub muted_players: HashSet<String>, // TODO: should this be the player's UUID instead?
+    /// Messages from these players will be ignored.
+    pub muted_players: MuteList,
     /// The last time the player sent a message.
     pub last_message_time: Option<Instant>,
+    /// The player this player last had a private conversation with (used for replies).
+    pub last_messaged: Option<Entity>,
+}
+
+impl ChatAbility {
+    /// Ignore the messages of a player, if `duration` is `None` the mute is permanent.
+    pub fn mute(&mut self, uuid: Uuid, duration: Option<Duration>) {
+        self.muted_players.mute(uuid, duration);
+    }
+
+    /// Stop ignoring the messages of a player.
+    ///
+    /// Returns `true` if the player was muted.
+    pub fn unmute(&mut self, uuid: Uuid) -> bool {
+        self.muted_players.unmute(uuid)
+    }
 }
 
 pub struct ChatPlugin;
 
 impl Plugin for ChatPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(PreUpdate, chat_system)
-            .insert_resource(ChatChannels::default());
+        if !app.is_plugin_added::<RateLimitPlugin>() {
+            app.add_plugins(RateLimitPlugin);
+        }
+
+        app.add_event::<ChatMessageBlockedEvent>()
+            .add_event::<ChatMessageFlaggedEvent>()
+            .add_event::<WhisperEvent>()
+            .add_event::<ReplyEvent>()
+            .add_systems(Startup, register_chat_type)
+            .add_systems(
+                PreUpdate,
+                (
+                    init_signed_chat,
+                    capture_signed_chat,
+                    chat_system,
+                    replay_chat_history,
+                )
+                    .chain(),
+            )
+            .add_systems(PostUpdate, sync_chat_sessions.after(PlayerListSet))
+            .add_systems(
+                Update,
+                (expire_mutes, (whisper_commands, whisper_system).chain()),
+            )
+            .insert_resource(ChatChannels::default())
+            .init_resource::<PendingSignatures>()
+            .init_resource::<FormattedChatType>()
+            .init_resource::<GlobalMuteList>()
+            .init_resource::<WhisperConfig>();
     }
 }
 
@@ -163,17 +452,58 @@ impl Plugin for ChatPlugin {
 struct ChatQuery {
     entity: Entity,
     name: &'static Username,
+    uuid: &'static UniqueId,
     chat_ability: &'static mut ChatAbility,
     client: &'static mut Client,
+    position: &'static Position,
+    layer: &'static EntityLayerId,
+    spectating: Has<Spectating>,
+    signed_chat: Option<&'static mut SignedChat>,
 }
 
+#[allow(clippy::too_many_arguments)]
 fn chat_system(
-    channels: Res<ChatChannels>,
+    mut channels: ResMut<ChatChannels>,
+    pending_signatures: Res<PendingSignatures>,
+    chat_type: Res<FormattedChatType>,
+    global_mutes: Res<GlobalMuteList>,
     mut clients: Query<ChatQuery>,
     mut events: EventReader<ChatMessageEvent>,
+    mut blocked_writer: EventWriter<ChatMessageBlockedEvent>,
+    mut flagged_writer: EventWriter<ChatMessageFlaggedEvent>,
+    mut rate_limits: RateLimits,
 ) {
+    let mut new_history = vec![];
+
     for event in events.read() {
+        let Ok(mut sender) = clients.get_mut(event.client) else {
+            continue;
+        };
+
+        let sender_uuid = sender.uuid.0;
+        let sender_spectating = sender.spectating;
+
+        if global_mutes.mutes.is_muted(sender_uuid) {
+            if let Some(muted_message) = &global_mutes.muted_message {
+                sender.client.send_chat_message(muted_message.clone());
+            }
+            continue;
+        }
+
+        // Spammed messages are dropped, a `RateLimitExceededEvent` is emitted instead.
+        if !rate_limits.check(event.client, RateLimitKind::Chat) {
+            continue;
+        }
+
         let chat_message = event.message.to_string();
+        let signature = pending_signatures
+            .0
+            .get(&event.client)
+            .and_then(|signatures| {
+                signatures
+                    .iter()
+                    .find(|signature| signature.timestamp == event.timestamp)
+            });
         let Some((channels_with_prefix, channels_without_prefix)) =
             channels.players_to_channels.get(&event.client)
         else {
@@ -196,19 +526,16 @@ fn chat_system(
                 break;
             }
 
-            let player_channel_config = channels
-                .channels
-                .get(channel_id)
-                .unwrap()
-                .1
-                .get(&event.client)
-                .unwrap();
+            let channel = channels.channels.get(channel_id).unwrap();
+            let player_channel_config = channel.members.get(&event.client).unwrap();
 
-            if !player_channel_config.permission.can_write() {
+            if !player_channel_config.permission.can_write()
+                || !channel.config.spectators.allows(sender_spectating)
+            {
                 continue;
             }
 
-            let (channel_config, channel_members) = channels.channels.get(channel_id).unwrap();
+            let (channel_config, channel_members) = (&channel.config, &channel.members);
 
             let mut message = chat_message.clone();
             if let Some(prefix) = &channel_config.required_prefix {
@@ -220,6 +547,30 @@ fn chat_system(
                 sent_to_prefixed_channels = true;
             }
 
+            if let Some(filter) = &channel_config.message_filter {
+                match filter(&message) {
+                    FilterResult::Allow => {}
+                    FilterResult::Block { reason } => {
+                        blocked_writer.send(ChatMessageBlockedEvent {
+                            sender: event.client,
+                            channel_id: *channel_id,
+                            message,
+                            reason,
+                        });
+                        continue;
+                    }
+                    FilterResult::Censor(censored) => message = censored,
+                    FilterResult::Flag { reason } => {
+                        flagged_writer.send(ChatMessageFlaggedEvent {
+                            sender: event.client,
+                            channel_id: *channel_id,
+                            message: message.clone(),
+                            reason,
+                        });
+                    }
+                }
+            }
+
             // Chat cooldown
             {
                 let Ok(mut sender) = clients.get_mut(event.client) else {
@@ -237,20 +588,32 @@ fn chat_system(
                 sender.chat_ability.last_message_time = Some(Instant::now());
             }
 
+            let (sender_name, sender_position, sender_layer) = {
+                let Ok(sender) = clients.get(event.client) else {
+                    continue;
+                };
+                (sender.name.clone(), sender.position.0, sender.layer.0)
+            };
+
+            let mut formatted = (channel_config.format_message)(&sender_name, &message);
+
             // Apply the player's prefix and the global prefix.
             if let Some(player_prefix) = &player_channel_config.prefix {
-                message = format!("{}{}", player_prefix, message);
+                formatted = player_prefix.clone() + formatted;
             }
 
             if let Some(global_prefix) = &channel_config.global_prefix {
-                message = format!("{}{}", global_prefix, message);
+                formatted = global_prefix.clone() + formatted;
             }
 
-            let sender_name = {
-                let Ok(sender) = clients.get(event.client) else {
-                    continue;
-                };
-                sender.name.to_string()
+            let entry = ChatHistoryEntry {
+                sender: event.client,
+                sender_uuid,
+                sender_name: sender_name.to_string(),
+                signature: signature.filter(|_| message == chat_message).cloned(),
+                message,
+                formatted,
+                time: Instant::now(),
             };
 
             for (player_entity, player_config) in channel_members.iter() {
@@ -258,7 +621,9 @@ fn chat_system(
                     continue;
                 };
 
-                if !player_config.permission.can_read() {
+                if !player_config.permission.can_read()
+                    || !channel_config.spectators.allows(receiver.spectating)
+                {
                     continue;
                 }
 
@@ -266,12 +631,88 @@ fn chat_system(
                     continue;
                 }
 
-                if receiver.chat_ability.muted_players.contains(&sender_name) {
+                if receiver.chat_ability.muted_players.is_muted(sender_uuid) {
                     continue;
                 }
 
-                receiver.client.send_chat_message(&message);
+                if let Some(proximity) = &channel_config.proximity {
+                    if receiver.layer.0 != sender_layer {
+                        continue;
+                    }
+
+                    let distance = receiver.position.0.distance(sender_position);
+                    if distance > proximity.radius {
+                        continue;
+                    }
+
+                    if proximity
+                        .muffle_distance
+                        .is_some_and(|muffle_distance| distance > muffle_distance)
+                    {
+                        let muffled = (proximity.muffle_message)(entry.formatted.clone());
+                        send_player_message(
+                            &mut receiver.client,
+                            receiver.signed_chat.as_deref_mut(),
+                            &chat_type,
+                            &entry,
+                            muffled,
+                        );
+                        continue;
+                    }
+                }
+
+                send_player_message(
+                    &mut receiver.client,
+                    receiver.signed_chat.as_deref_mut(),
+                    &chat_type,
+                    &entry,
+                    entry.formatted.clone(),
+                );
+            }
+
+            if channel_config.proximity.is_none() {
+                new_history.push((*channel_id, entry));
+            }
+        }
+    }
+
+    for (channel_id, entry) in new_history {
+        channels.record(channel_id, entry);
+    }
+}
+
+fn replay_chat_history(
+    mut channels: ResMut<ChatChannels>,
+    chat_type: Res<FormattedChatType>,
+    mut clients: Query<(&mut Client, &ChatAbility, Option<&mut SignedChat>)>,
+) {
+    if channels.pending_replays.is_empty() {
+        return;
+    }
+
+    for (channel_id, player_entity, count) in std::mem::take(&mut channels.pending_replays) {
+        let Some(channel) = channels.channels.get(&channel_id) else {
+            continue;
+        };
+
+        let Ok((mut client, chat_ability, mut signed_chat)) = clients.get_mut(player_entity) else {
+            continue;
+        };
+
+        let skip = channel.history.len().saturating_sub(count);
+
+        for entry in channel.history.iter().skip(skip) {
+            if chat_ability.muted_players.is_muted(entry.sender_uuid) {
+                continue;
             }
+
+            send_player_message(
+                &mut client,
+                signed_chat.as_deref_mut(),
+                &chat_type,
+                entry,
+                entry.formatted.clone(),
+            );
         }
     }
 }
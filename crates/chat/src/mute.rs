@@ -0,0 +1,108 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use valence::{prelude::*, text::IntoText, uuid::Uuid};
+
+use crate::ChatAbility;
+
+/// A list of muted players, identified by their UUID.
+#[derive(Default, Clone, Debug)]
+pub struct MuteList {
+    /// Maps the UUID of a muted player to the time the mute expires (`None` if the mute is permanent).
+    mutes: HashMap<Uuid, Option<Instant>>,
+}
+
+impl MuteList {
+    /// Mute a player, if `duration` is `None` the mute is permanent.
+    ///
+    /// Muting an already muted player replaces the previous mute.
+    pub fn mute(&mut self, uuid: Uuid, duration: Option<Duration>) {
+        self.mutes
+            .insert(uuid, duration.map(|duration| Instant::now() + duration));
+    }
+
+    /// Unmute a player.
+    ///
+    /// Returns `true` if the player was muted.
+    pub fn unmute(&mut self, uuid: Uuid) -> bool {
+        self.mutes.remove(&uuid).is_some()
+    }
+
+    /// Check if a player is muted.
+    pub fn is_muted(&self, uuid: Uuid) -> bool {
+        match self.mutes.get(&uuid) {
+            Some(Some(expires)) => *expires > Instant::now(),
+            Some(None) => true,
+            None => false,
+        }
+    }
+
+    /// The remaining time of a mute, `None` if the player is not muted or the mute is permanent.
+    pub fn remaining(&self, uuid: Uuid) -> Option<Duration> {
+        self.mutes
+            .get(&uuid)
+            .copied()
+            .flatten()
+            .map(|expires| expires.saturating_duration_since(Instant::now()))
+    }
+
+    /// Iterate over all muted players (expired mutes are skipped).
+    pub fn iter(&self) -> impl Iterator<Item = Uuid> + '_ {
+        let now = Instant::now();
+        self.mutes
+            .iter()
+            .filter(move |(_, expires)| expires.map_or(true, |expires| expires > now))
+            .map(|(uuid, _)| *uuid)
+    }
+
+    /// Check if any mute is expired.
+    pub fn has_expired(&self) -> bool {
+        let now = Instant::now();
+        self.mutes
+            .values()
+            .any(|expires| expires.is_some_and(|expires| expires <= now))
+    }
+
+    /// Remove all mutes that are expired.
+    pub fn remove_expired(&mut self) {
+        let now = Instant::now();
+        self.mutes
+            .retain(|_, expires| expires.map_or(true, |expires| expires > now));
+    }
+}
+
+/// Players on this list can not send any chat messages or private messages,
+/// regardless of the per-player mute lists.
+#[derive(Resource)]
+pub struct GlobalMuteList {
+    pub mutes: MuteList,
+    /// The message that will be sent to muted players if they try to chat.
+    pub muted_message: Option<Text>,
+}
+
+impl Default for GlobalMuteList {
+    fn default() -> Self {
+        Self {
+            mutes: MuteList::default(),
+            muted_message: Some("You are muted".color(Color::RED)),
+        }
+    }
+}
+
+pub(crate) fn expire_mutes(
+    mut global_mutes: ResMut<GlobalMuteList>,
+    mut abilities: Query<&mut ChatAbility>,
+) {
+    // Only mutate if something expired, so the change detection is not triggered every tick.
+    if global_mutes.mutes.has_expired() {
+        global_mutes.mutes.remove_expired();
+    }
+
+    for mut ability in abilities.iter_mut() {
+        if ability.muted_players.has_expired() {
+            ability.muted_players.remove_expired();
+        }
+    }
+}
@@ -0,0 +1,343 @@
+//! Forwarding of signed chat messages.
+//!
+//! Messages that were not modified by a channel keep the signature of the sender, so the receiving
+//! clients can verify them. The clients disconnect on signed messages from unknown chat sessions
+//! or with a broken message chain, these messages are sent as system messages instead.
+
+use std::{borrow::Cow, collections::VecDeque};
+
+use bevy_ecs::entity::EntityHashMap;
+use valence::{
+    event_loop::PacketEvent,
+    ident,
+    nbt::{compound, List},
+    player_list::Listed,
+    prelude::*,
+    protocol::{
+        packets::play::{
+            chat_message_s2c::{MessageFilterType, MessageSignature},
+            player_list_s2c::{ChatData, PlayerListActions, PlayerListEntry},
+            ChatMessageC2s, ChatMessageS2c, MessageAcknowledgmentC2s, PlayerListS2c,
+            PlayerSessionC2s,
+        },
+        VarInt, WritePacket,
+    },
+    registry::{codec::RegistryValue, RegistryCodec},
+    text::IntoText,
+    uuid::Uuid,
+};
+
+use crate::ChatHistoryEntry;
+
+/// The number of messages a client can acknowledge at once.
+const LAST_SEEN_WINDOW: usize = 20;
+
+/// The public chat session of a player, the clients use it to verify the messages of the player.
+#[derive(Component, Clone, Debug)]
+pub struct ChatSession {
+    pub session_id: Uuid,
+    /// The expiry time of the public key (in milliseconds since the unix epoch).
+    pub expires_at: i64,
+    pub public_key: Box<[u8]>,
+    /// The signature of the public key (signed by Mojang).
+    pub key_signature: Box<[u8]>,
+}
+
+/// The signature of a chat message, as it was sent by the client.
+#[derive(Clone, Debug)]
+pub struct ChatMessageSignature {
+    pub signature: Box<[u8; 256]>,
+    pub salt: u64,
+    pub timestamp: u64,
+    /// The chat session the message was signed with.
+    pub session_id: Uuid,
+    /// The index of the message in the message chain of the session.
+    pub index: i32,
+    /// The signatures of the messages the sender has seen, they are part of the signed data.
+    pub last_seen: Vec<Box<[u8; 256]>>,
+}
+
+/// The signed chat state of a client.
+#[derive(Component)]
+pub(crate) struct SignedChat {
+    /// The chat session of the client (updated before the [`ChatSession`] component is inserted).
+    session_id: Option<Uuid>,
+    /// The index of the next message the client signs.
+    next_index: i32,
+    /// The signatures that were forwarded to the client, the first [`LAST_SEEN_WINDOW`] entries can be acknowledged.
+    tracked: VecDeque<Option<Box<[u8; 256]>>>,
+    /// The chat sessions the client knows, with the index of the last message forwarded from the session.
+    known_sessions: EntityHashMap<(Uuid, Option<i32>)>,
+}
+
+impl Default for SignedChat {
+    fn default() -> Self {
+        Self {
+            session_id: None,
+            next_index: 0,
+            tracked: std::iter::repeat(None).take(LAST_SEEN_WINDOW).collect(),
+            known_sessions: EntityHashMap::default(),
+        }
+    }
+}
+
+impl SignedChat {
+    /// Drops the first `offset` tracked signatures, the client will not acknowledge them anymore.
+    fn apply_offset(&mut self, offset: i32) {
+        let max_offset = self.tracked.len() - LAST_SEEN_WINDOW;
+
+        if let Ok(offset) = usize::try_from(offset) {
+            if offset <= max_offset {
+                self.tracked.drain(..offset);
+            }
+        }
+    }
+
+    /// Returns the signatures the client acknowledged in a chat message (the last seen messages).
+    fn acknowledge(&mut self, offset: i32, acknowledged: [u8; 3]) -> Vec<Box<[u8; 256]>> {
+        self.apply_offset(offset);
+
+        let mut last_seen = vec![];
+        for (idx, entry) in self.tracked.iter_mut().take(LAST_SEEN_WINDOW).enumerate() {
+            if acknowledged[idx / 8] & (1 << (idx % 8)) == 0 {
+                *entry = None;
+            } else if let Some(signature) = entry {
+                last_seen.push(signature.clone());
+            }
+        }
+
+        last_seen
+    }
+
+    /// Whether the client can verify a message of `sender`, this is the case if it knows the
+    /// session of the message and did not receive a later message of the session.
+    fn can_verify(&self, sender: Entity, signature: &ChatMessageSignature) -> bool {
+        self.known_sessions
+            .get(&sender)
+            .is_some_and(|(session_id, last_index)| {
+                *session_id == signature.session_id
+                    && last_index.map_or(true, |last_index| signature.index > last_index)
+            })
+    }
+}
+
+/// The signatures of the chat messages received in this tick.
+#[derive(Resource, Default)]
+pub(crate) struct PendingSignatures(pub(crate) EntityHashMap<Vec<ChatMessageSignature>>);
+
+/// The chat type of the forwarded messages, it only shows the formatted (unsigned) content.
+#[derive(Resource, Default)]
+pub(crate) struct FormattedChatType(i32);
+
+pub(crate) fn register_chat_type(
+    mut codec: ResMut<RegistryCodec>,
+    mut chat_type: ResMut<FormattedChatType>,
+) {
+    let chat_types = codec.registry_mut(ident!("minecraft:chat_type"));
+
+    chat_type.0 = chat_types.len() as i32;
+    chat_types.push(RegistryValue {
+        name: ident!("valence_extra:formatted").into(),
+        element: compound! {
+            "chat" => compound! {
+                "translation_key" => "%s",
+                "parameters" => List::String(vec!["content".to_string()]),
+            },
+            "narration" => compound! {
+                "translation_key" => "chat.type.text.narrate",
+                "parameters" => List::String(vec!["sender".to_string(), "content".to_string()]),
+            },
+        },
+    });
+}
+
+pub(crate) fn init_signed_chat(mut commands: Commands, clients: Query<Entity, Added<Client>>) {
+    for entity in clients.iter() {
+        commands.entity(entity).insert(SignedChat::default());
+    }
+}
+
+/// Valence does not handle the chat sessions and signatures, so they are read from the packets.
+pub(crate) fn capture_signed_chat(
+    mut commands: Commands,
+    mut packets: EventReader<PacketEvent>,
+    mut clients: Query<&mut SignedChat>,
+    mut pending: ResMut<PendingSignatures>,
+) {
+    pending.0.clear();
+
+    for packet in packets.read() {
+        let Ok(mut signed_chat) = clients.get_mut(packet.client) else {
+            continue;
+        };
+
+        if let Some(pkt) = packet.decode::<PlayerSessionC2s>() {
+            signed_chat.session_id = Some(pkt.session_id);
+            signed_chat.next_index = 0;
+
+            commands.entity(packet.client).insert(ChatSession {
+                session_id: pkt.session_id,
+                expires_at: pkt.expires_at,
+                public_key: pkt.public_key_data.into(),
+                key_signature: pkt.key_signature.into(),
+            });
+        } else if let Some(pkt) = packet.decode::<MessageAcknowledgmentC2s>() {
+            signed_chat.apply_offset(pkt.message_index.0);
+        } else if let Some(pkt) = packet.decode::<ChatMessageC2s>() {
+            let last_seen = signed_chat.acknowledge(pkt.message_index.0, pkt.acknowledgement);
+
+            let (Some(signature), Some(session_id)) = (pkt.signature, signed_chat.session_id)
+            else {
+                continue;
+            };
+
+            let index = signed_chat.next_index;
+            signed_chat.next_index += 1;
+
+            pending
+                .0
+                .entry(packet.client)
+                .or_default()
+                .push(ChatMessageSignature {
+                    signature: Box::new(*signature),
+                    salt: pkt.salt,
+                    timestamp: pkt.timestamp,
+                    session_id,
+                    index,
+                    last_seen,
+                });
+        }
+    }
+}
+
+/// Sends the chat sessions of the players to the clients, must run after valence added the players to the player list.
+pub(crate) fn sync_chat_sessions(
+    sessions: Query<(Entity, &UniqueId, Ref<ChatSession>), With<Listed>>,
+    mut clients: Query<(&mut Client, &mut SignedChat)>,
+    mut removed: RemovedComponents<ChatSession>,
+) {
+    let removed = removed.read().collect::<Vec<_>>();
+    let sessions_changed = sessions.iter().any(|(_, _, session)| session.is_changed());
+
+    for (mut client, mut signed_chat) in clients.iter_mut() {
+        for entity in &removed {
+            signed_chat.known_sessions.remove(entity);
+        }
+
+        if !sessions_changed && !signed_chat.is_added() {
+            continue;
+        }
+
+        let mut entries = vec![];
+        for (entity, uuid, session) in sessions.iter() {
+            if !session.is_changed() && signed_chat.known_sessions.contains_key(&entity) {
+                continue;
+            }
+
+            signed_chat
+                .known_sessions
+                .insert(entity, (session.session_id, None));
+
+            entries.push(PlayerListEntry {
+                player_uuid: uuid.0,
+                chat_data: Some(ChatData {
+                    session_id: session.session_id,
+                    key_expiry_time: session.expires_at,
+                    public_key: &session.public_key,
+                    public_key_signature: &session.key_signature,
+                }),
+                ..Default::default()
+            });
+        }
+
+        if !entries.is_empty() {
+            client.write_packet(&PlayerListS2c {
+                actions: PlayerListActions::new().with_initialize_chat(true),
+                entries: Cow::Owned(entries),
+            });
+        }
+    }
+}
+
+/// Sends a chat message to a client.
+///
+/// The message is forwarded with the signature of the sender if the client can verify it and
+/// `formatted` is the formatted message of the entry (e.g. not muffled), otherwise it is sent as a system message.
+pub(crate) fn send_player_message(
+    client: &mut Client,
+    signed_chat: Option<&mut SignedChat>,
+    chat_type: &FormattedChatType,
+    entry: &ChatHistoryEntry,
+    formatted: Text,
+) {
+    let (Some(signed_chat), Some(signature)) = (signed_chat, &entry.signature) else {
+        client.send_chat_message(formatted);
+        return;
+    };
+
+    if formatted != entry.formatted || !signed_chat.can_verify(entry.sender, signature) {
+        client.send_chat_message(formatted);
+        return;
+    }
+
+    client.write_packet(&ChatMessageS2c {
+        sender: entry.sender_uuid,
+        index: VarInt(signature.index),
+        message_signature: Some(&signature.signature),
+        message: entry.message.as_str().into(),
+        time_stamp: signature.timestamp,
+        salt: signature.salt,
+        previous_messages: signature
+            .last_seen
+            .iter()
+            .map(|last_seen| MessageSignature {
+                message_id: -1,
+                signature: Some(last_seen),
+            })
+            .collect(),
+        unsigned_content: Some(Cow::Owned(formatted)),
+        filter_type: MessageFilterType::PassThrough,
+        filter_type_bits: None,
+        chat_type: VarInt(chat_type.0),
+        network_name: Cow::Owned(entry.sender_name.clone().into_text()),
+        network_target_name: None,
+    });
+
+    signed_chat
+        .known_sessions
+        .insert(entry.sender, (signature.session_id, Some(signature.index)));
+    signed_chat
+        .tracked
+        .push_back(Some(signature.signature.clone()));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signature(byte: u8) -> Box<[u8; 256]> {
+        Box::new([byte; 256])
+    }
+
+    #[test]
+    fn acknowledged_signatures_are_last_seen() {
+        let mut signed_chat = SignedChat::default();
+        signed_chat.tracked.push_back(Some(signature(1)));
+        signed_chat.tracked.push_back(Some(signature(2)));
+
+        // The client acknowledged both messages, the first one was dropped from the window.
+        let mut acknowledged = [0; 3];
+        acknowledged[2] = 0b1100;
+
+        let last_seen = signed_chat.acknowledge(2, acknowledged);
+        assert_eq!(last_seen, vec![signature(1), signature(2)]);
+        assert_eq!(signed_chat.tracked.len(), LAST_SEEN_WINDOW);
+    }
+
+    #[test]
+    fn offset_cannot_shrink_the_window() {
+        let mut signed_chat = SignedChat::default();
+        signed_chat.apply_offset(5);
+        assert_eq!(signed_chat.tracked.len(), LAST_SEEN_WINDOW);
+    }
+}
@@ -0,0 +1,221 @@
+use valence::{
+    command::manager::CommandExecutionEvent, ecs::query::QueryData, prelude::*, text::IntoText,
+};
+
+use crate::{ChatAbility, ChatChannels, GlobalMuteList};
+
+/// An event that sends a private message from one player to another.
+#[derive(Event)]
+pub struct WhisperEvent {
+    pub sender: Entity,
+    pub receiver: Entity,
+    pub message: String,
+}
+
+/// An event that sends a private message to the player the sender last had a conversation with.
+#[derive(Event)]
+pub struct ReplyEvent {
+    pub sender: Entity,
+    pub message: String,
+}
+
+/// The config for private messages.
+#[derive(Resource)]
+pub struct WhisperConfig {
+    /// Handle the `/msg <player> <message>` (`/tell`, `/w`) and `/r <message>` commands.
+    pub enable_commands: bool,
+    /// The message that will be shown to the sender.
+    ///
+    /// The parameters are: `receiver_name`, `message`.
+    pub format_sent: fn(&Username, &str) -> Text,
+    /// The message that will be shown to the receiver.
+    ///
+    /// The parameters are: `sender_name`, `message`.
+    pub format_received: fn(&Username, &str) -> Text,
+    /// All private messages will be sent to the members of this channel that are able to read (social spy).
+    pub social_spy_channel: Option<u64>,
+    /// The message that will be shown to the social spy channel.
+    ///
+    /// The parameters are: `sender_name`, `receiver_name`, `message`.
+    pub format_social_spy: fn(&Username, &Username, &str) -> Text,
+}
+
+impl Default for WhisperConfig {
+    fn default() -> Self {
+        Self {
+            enable_commands: true,
+            format_sent: |receiver, message| {
+                format!("You whisper to {}: {}", receiver.0, message)
+                    .color(Color::GRAY)
+                    .italic()
+            },
+            format_received: |sender, message| {
+                format!("{} whispers to you: {}", sender.0, message)
+                    .color(Color::GRAY)
+                    .italic()
+            },
+            social_spy_channel: None,
+            format_social_spy: |sender, receiver, message| {
+                format!("[Spy] {} -> {}: {}", sender.0, receiver.0, message).color(Color::DARK_GRAY)
+            },
+        }
+    }
+}
+
+/// Converts the `/msg` and `/r` commands into [`WhisperEvent`]s and [`ReplyEvent`]s.
+pub(crate) fn whisper_commands(
+    config: Res<WhisperConfig>,
+    mut commands: EventReader<CommandExecutionEvent>,
+    mut clients: Query<(Entity, &Username, &mut Client)>,
+    mut whisper_writer: EventWriter<WhisperEvent>,
+    mut reply_writer: EventWriter<ReplyEvent>,
+) {
+    if !config.enable_commands {
+        commands.clear();
+        return;
+    }
+
+    for event in commands.read() {
+        let (name, args) = event
+            .command
+            .split_once(' ')
+            .unwrap_or((event.command.as_str(), ""));
+
+        match name {
+            "msg" | "tell" | "w" => {
+                let Some((receiver_name, message)) = args.split_once(' ') else {
+                    continue;
+                };
+
+                let receiver = clients
+                    .iter()
+                    .find(|(_, username, _)| username.0.eq_ignore_ascii_case(receiver_name))
+                    .map(|(entity, _, _)| entity);
+
+                match receiver {
+                    Some(receiver) => {
+                        whisper_writer.send(WhisperEvent {
+                            sender: event.executor,
+                            receiver,
+                            message: message.to_string(),
+                        });
+                    }
+                    None => {
+                        if let Ok((_, _, mut client)) = clients.get_mut(event.executor) {
+                            client.send_chat_message(
+                                format!("Player {receiver_name} was not found").color(Color::RED),
+                            );
+                        }
+                    }
+                }
+            }
+            "r" | "reply" if !args.is_empty() => {
+                reply_writer.send(ReplyEvent {
+                    sender: event.executor,
+                    message: args.to_string(),
+                });
+            }
+            _ => {}
+        }
+    }
+}
+
+#[derive(QueryData)]
+#[query_data(mutable)]
+pub(crate) struct WhisperQuery {
+    name: &'static Username,
+    uuid: &'static UniqueId,
+    client: &'static mut Client,
+    chat_ability: &'static mut ChatAbility,
+}
+
+pub(crate) fn whisper_system(
+    config: Res<WhisperConfig>,
+    channels: Res<ChatChannels>,
+    global_mutes: Res<GlobalMuteList>,
+    mut whispers: EventReader<WhisperEvent>,
+    mut replies: EventReader<ReplyEvent>,
+    mut clients: Query<WhisperQuery>,
+) {
+    let mut messages = whispers
+        .read()
+        .map(|whisper| {
+            (
+                whisper.sender,
+                Some(whisper.receiver),
+                whisper.message.clone(),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    for reply in replies.read() {
+        let receiver = clients
+            .get(reply.sender)
+            .ok()
+            .and_then(|sender| sender.chat_ability.last_messaged);
+
+        messages.push((reply.sender, receiver, reply.message.clone()));
+    }
+
+    for (sender, receiver, message) in messages {
+        let Some(receiver) = receiver.filter(|receiver| clients.contains(*receiver)) else {
+            if let Ok(mut sender) = clients.get_mut(sender) {
+                sender
+                    .client
+                    .send_chat_message("There is nobody to reply to".color(Color::RED));
+            }
+            continue;
+        };
+
+        let Ok([mut sender_query, mut receiver_query]) = clients.get_many_mut([sender, receiver])
+        else {
+            continue;
+        };
+
+        if global_mutes.mutes.is_muted(sender_query.uuid.0) {
+            if let Some(muted_message) = &global_mutes.muted_message {
+                sender_query.client.send_chat_message(muted_message.clone());
+            }
+            continue;
+        }
+
+        let sender_name = sender_query.name.clone();
+        let receiver_name = receiver_query.name.clone();
+
+        sender_query
+            .client
+            .send_chat_message((config.format_sent)(&receiver_name, &message));
+        sender_query.chat_ability.last_messaged = Some(receiver);
+
+        // Muted players do not notice that their message was not delivered.
+        if !receiver_query
+            .chat_ability
+            .muted_players
+            .is_muted(sender_query.uuid.0)
+        {
+            receiver_query
+                .client
+                .send_chat_message((config.format_received)(&sender_name, &message));
+            receiver_query.chat_ability.last_messaged = Some(sender);
+        }
+
+        let Some(spy_channel) = config
+            .social_spy_channel
+            .and_then(|id| channels.channels.get(&id))
+        else {
+            continue;
+        };
+
+        let spy_message = (config.format_social_spy)(&sender_name, &receiver_name, &message);
+
+        for (spy, spy_config) in spy_channel.members.iter() {
+            if !spy_config.permission.can_read() || *spy == sender || *spy == receiver {
+                continue;
+            }
+
+            if let Ok(mut spy_query) = clients.get_mut(*spy) {
+                spy_query.client.send_chat_message(spy_message.clone());
+            }
+        }
+    }
+}
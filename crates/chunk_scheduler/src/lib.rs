@@ -0,0 +1,244 @@
+use valence::{
+    client::{UpdateClientsSet, ViewDistance},
+    entity::OldPosition,
+    prelude::*,
+};
+
+/// Configuration of the chunk send scheduler.
+///
+/// Chunks are not sent directly by this crate, instead the view distance of a client
+/// is lowered after a transfer and then raised ring by ring as the budget allows.
+/// This means the chunks closest to the client are always sent first.
+#[derive(Resource)]
+pub struct ChunkSendConfig {
+    /// The maximum amount of chunks a single client can receive per tick while it is throttled.
+    pub client_budget: u32,
+    /// The maximum amount of chunks that can be sent to all throttled clients combined per tick.
+    pub global_budget: u32,
+    /// The view distance a client starts with after it was transferred.
+    pub initial_view_distance: u8,
+    /// If a client moves further than this (in blocks) within a single tick,
+    /// the movement will be treated as a teleport and the chunk sending will be throttled.
+    ///
+    /// If `None`, only layer changes and [`TransferEvent`]s will throttle the client.
+    pub teleport_threshold: Option<f64>,
+}
+
+impl Default for ChunkSendConfig {
+    fn default() -> Self {
+        Self {
+            client_budget: 32,
+            global_budget: 256,
+            initial_view_distance: 2,
+            teleport_threshold: Some(64.0),
+        }
+    }
+}
+
+/// Attached to clients whose chunk sending is currently being throttled.
+///
+/// The component will be removed once the client reached its target view distance.
+#[derive(Component)]
+pub struct ChunkThrottle {
+    /// The view distance the client will have once all chunks are sent.
+    pub target_view_distance: u8,
+    /// Budget that was assigned to the client, but was not enough to send the next ring of chunks.
+    accumulated_budget: u32,
+}
+
+impl ChunkThrottle {
+    pub fn new(target_view_distance: u8) -> Self {
+        Self {
+            target_view_distance,
+            accumulated_budget: 0,
+        }
+    }
+}
+
+/// An event that moves a client to another layer and/or position.
+///
+/// The chunk packets for the new location will be throttled according to the [`ChunkSendConfig`].
+#[derive(Event)]
+pub struct TransferEvent {
+    pub client: Entity,
+    /// The layer the client will be moved to, if `None` the client stays on its current layer.
+    pub layer: Option<Entity>,
+    /// The position the client will be teleported to.
+    pub position: DVec3,
+}
+
+pub struct ChunkSchedulerPlugin;
+
+impl Plugin for ChunkSchedulerPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<TransferEvent>()
+            .init_resource::<ChunkSendConfig>()
+            .add_systems(Update, transfer_system)
+            .add_systems(
+                PostUpdate,
+                (detect_teleports, schedule_chunks)
+                    .chain()
+                    .before(UpdateClientsSet),
+            );
+    }
+}
+
+/// The amount of chunks in the ring with the given radius (square view).
+fn ring_size(radius: u8) -> u32 {
+    if radius == 0 {
+        1
+    } else {
+        8 * radius as u32
+    }
+}
+
+fn start_throttle(
+    commands: &mut Commands,
+    entity: Entity,
+    view_distance: &mut ViewDistance,
+    throttle: Option<&ChunkThrottle>,
+    config: &ChunkSendConfig,
+) {
+    // If the client is already throttled, keep the original target view distance.
+    let target = throttle
+        .map(|t| t.target_view_distance)
+        .unwrap_or(view_distance.get());
+
+    view_distance.set(config.initial_view_distance.min(target));
+    commands.entity(entity).insert(ChunkThrottle::new(target));
+}
+
+#[allow(clippy::type_complexity)]
+fn transfer_system(
+    mut commands: Commands,
+    mut events: EventReader<TransferEvent>,
+    mut clients: Query<
+        (
+            &mut Position,
+            &mut EntityLayerId,
+            &mut VisibleChunkLayer,
+            &mut VisibleEntityLayers,
+            &mut ViewDistance,
+            Option<&ChunkThrottle>,
+        ),
+        With<Client>,
+    >,
+    config: Res<ChunkSendConfig>,
+) {
+    for event in events.read() {
+        let Ok((
+            mut position,
+            mut layer_id,
+            mut visible_chunk_layer,
+            mut visible_entity_layers,
+            mut view_distance,
+            throttle,
+        )) = clients.get_mut(event.client)
+        else {
+            continue;
+        };
+
+        if let Some(layer) = event.layer {
+            layer_id.0 = layer;
+            visible_chunk_layer.0 = layer;
+            visible_entity_layers.0.clear();
+            visible_entity_layers.0.insert(layer);
+        }
+
+        position.0 = event.position;
+
+        start_throttle(
+            &mut commands,
+            event.client,
+            &mut view_distance,
+            throttle,
+            &config,
+        );
+    }
+}
+
+/// Throttles clients that changed their layer or moved too far within a single tick.
+#[allow(clippy::type_complexity)]
+fn detect_teleports(
+    mut commands: Commands,
+    mut clients: Query<
+        (
+            Entity,
+            &Position,
+            &OldPosition,
+            Ref<VisibleChunkLayer>,
+            &mut ViewDistance,
+            Option<Ref<ChunkThrottle>>,
+        ),
+        With<Client>,
+    >,
+    config: Res<ChunkSendConfig>,
+) {
+    for (entity, position, old_position, visible_chunk_layer, mut view_distance, throttle) in
+        clients.iter_mut()
+    {
+        let layer_changed = visible_chunk_layer.is_changed() && !visible_chunk_layer.is_added();
+
+        let teleported = config
+            .teleport_threshold
+            .is_some_and(|threshold| position.0.distance(old_position.get()) > threshold);
+
+        if !layer_changed && !teleported {
+            continue;
+        }
+
+        // The transfer system already throttled the client this tick.
+        if throttle.as_ref().is_some_and(|t| t.is_added()) {
+            continue;
+        }
+
+        start_throttle(
+            &mut commands,
+            entity,
+            &mut view_distance,
+            throttle.as_deref(),
+            &config,
+        );
+    }
+}
+
+/// Raises the view distance of throttled clients ring by ring.
+///
+/// Clients with the smallest view distance are handled first, so the chunks closest
+/// to every client are prioritized over far away chunks of other clients.
+fn schedule_chunks(
+    mut commands: Commands,
+    mut clients: Query<(Entity, &mut ViewDistance, &mut ChunkThrottle), With<Client>>,
+    config: Res<ChunkSendConfig>,
+) {
+    let mut throttled = clients.iter_mut().collect::<Vec<_>>();
+    throttled.sort_by_key(|(_, view_distance, _)| view_distance.get());
+
+    let mut global_budget = config.global_budget;
+
+    for (entity, mut view_distance, mut throttle) in throttled {
+        let budget = config.client_budget.min(global_budget);
+        global_budget -= budget;
+        throttle.accumulated_budget += budget;
+
+        let mut current = view_distance.get();
+
+        while current < throttle.target_view_distance {
+            let cost = ring_size(current + 1);
+            if throttle.accumulated_budget < cost {
+                break;
+            }
+
+            throttle.accumulated_budget -= cost;
+            current += 1;
+        }
+
+        if current != view_distance.get() {
+            view_distance.set(current);
+        }
+
+        if current >= throttle.target_view_distance {
+            commands.entity(entity).remove::<ChunkThrottle>();
+        }
+    }
+}
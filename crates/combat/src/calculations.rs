@@ -13,6 +13,15 @@ pub fn damage_after_armor(damage: f32, armor_points: f32, toughness: f32) -> f32
     damage * damage_multiplier
 }
 
+/// Calculates how much the attack is charged (0.0 - 1.0).
+/// (java behavior)
+pub fn attack_charge(weapon_attack_speed: f32, last_attack: Instant) -> f32 {
+    let elapsed_ticks = last_attack.elapsed().as_millis() as f32 / 50.0;
+    let t = 20.0 / weapon_attack_speed;
+
+    ((elapsed_ticks + 0.5) / t).clamp(0.0, 1.0)
+}
+
 /// Calculates a damage multiplier based on the attack cooldown.
 /// (java behavior)
 pub fn attack_cooldown_base_damage(weapon_attack_speed: f32, last_attack: Instant) -> f32 {
@@ -86,7 +95,7 @@ pub fn enchant_punch(base_knockback: Vec3, level: u32) -> Vec3 {
         return base_knockback;
     }
 
-    base_knockback + base_knockback.normalize() * (level as f32 + 1.0)
+    base_knockback + base_knockback.normalize_or_zero() * (level as f32 + 1.0)
 }
 
 /// Calculates the fire aspect burn time and damage per second.
@@ -119,3 +128,46 @@ pub fn enchant_flame(level: u32) -> (Duration, f32) {
 
     (burn_time, damage_per_second)
 }
+
+/// Calculates the velocity (in blocks per second) of a player launched by the riptide enchantment.
+/// (java behavior)
+pub fn enchant_riptide(direction: Vec3, level: u32) -> Vec3 {
+    // https://minecraft.fandom.com/wiki/Riptide
+    if level == 0 {
+        return Vec3::ZERO;
+    }
+
+    // TODO: set based on tick rate
+    direction.normalize_or_zero() * 3.0 * ((1.0 + level as f32) / 4.0) * 20.0
+}
+
+/// Calculates the speed (in blocks per second) a trident with the loyalty enchantment returns to its owner.
+/// (java behavior, this is the speed the trident approaches while flying back)
+pub fn enchant_loyalty(level: u32) -> f32 {
+    // https://minecraft.fandom.com/wiki/Loyalty
+    // TODO: set based on tick rate
+    level as f32 * 20.0
+}
+
+/// Combines the knockback resistance of the armor, the `generic.knockback_resistance` attribute
+/// and effects, the result is clamped to 0.0 - 1.0.
+/// (java behavior)
+pub fn knockback_resistance(armor: f32, attribute: f32, effect: f32) -> f32 {
+    (armor + attribute + effect).clamp(0.0, 1.0)
+}
+
+/// The multiplier of a charged weapon, it grows linearly from 1.0 (not charged) to 2.0 (fully charged).
+pub fn charge_linear(charge: f32) -> f32 {
+    1.0 + charge.clamp(0.0, 1.0)
+}
+
+/// The multiplier of a charged weapon, like [`charge_linear`] but it grows with the square of the charge,
+/// so only (almost) fully charged attacks are rewarded.
+pub fn charge_quadratic(charge: f32) -> f32 {
+    1.0 + charge.clamp(0.0, 1.0).powi(2)
+}
+
+/// The multiplier of a hit in a combo, every hit after the first adds 10% (up to 2.0).
+pub fn combo_linear(combo: u32) -> f32 {
+    (1.0 + combo.saturating_sub(1) as f32 * 0.1).min(2.0)
+}
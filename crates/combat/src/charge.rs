@@ -0,0 +1,298 @@
+//! Weapons that are charged by holding the use item button (right-click), e.g. hammers or greatswords.
+//!
+//! The charge is shown while charging and scales the damage and knockback of the next attack with the weapon.
+
+use std::{
+    collections::HashSet,
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+use utils::custom_items::{CustomItems, ItemStackCustomItemExt};
+use valence::{
+    event_loop::PacketEvent,
+    interact_item::InteractItemEvent,
+    inventory::HeldItem,
+    prelude::*,
+    protocol::{
+        packets::play::{player_action_c2s::PlayerAction, ExperienceBarUpdateS2c, PlayerActionC2s},
+        VarInt, WritePacket,
+    },
+};
+
+use crate::{formulas::CombatFormulas, CombatState, PlayerCombatConfig};
+
+/// Clients repeat the use of items that can not be used (e.g. swords) every 4 ticks while the button is held,
+/// the charge is released if there was no use for this long.
+const RELEASE_TIMEOUT: Duration = Duration::from_millis(300);
+/// The number of segments of the charge bar in the action bar.
+const ACTION_BAR_SEGMENTS: usize = 20;
+
+/// How the charge is shown to the player while charging.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ChargeDisplay {
+    /// The experience bar is filled with the charge (it is emptied after the release).
+    #[default]
+    ExperienceBar,
+    /// A bar in the action bar.
+    ActionBar,
+    None,
+}
+
+/// The configuration of a weapon that can be charged.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChargeWeaponConfig {
+    /// The time it takes to fully charge the weapon.
+    pub charge_time: Duration,
+    /// The damage multiplier of the charge.
+    ///
+    /// The name of a formula in [`CombatFormulas::charge_curve`].
+    pub damage_curve: String,
+    /// The knockback multiplier of the charge.
+    ///
+    /// The name of a formula in [`CombatFormulas::charge_curve`].
+    pub knockback_curve: String,
+    pub display: ChargeDisplay,
+}
+
+impl Default for ChargeWeaponConfig {
+    fn default() -> Self {
+        Self {
+            charge_time: Duration::from_secs(1),
+            damage_curve: "linear".to_string(),
+            knockback_curve: "linear".to_string(),
+            display: ChargeDisplay::ExperienceBar,
+        }
+    }
+}
+
+impl PlayerCombatConfig {
+    /// The charge configuration of the weapon, custom items are looked up in [`Self::custom_charge_weapons`] first.
+    pub fn charge_weapon(
+        &self,
+        weapon: &ItemStack,
+        custom_items: &CustomItems,
+    ) -> Option<&ChargeWeaponConfig> {
+        let custom_item_id = weapon.custom_item_id().or_else(|| {
+            custom_items
+                .names
+                .get(weapon.display_name()?)
+                .map(String::as_str)
+        });
+
+        custom_item_id
+            .and_then(|id| self.custom_charge_weapons.get(id))
+            .or_else(|| self.charge_weapons.get(&weapon.item))
+    }
+}
+
+/// Attached to players that are currently charging a weapon.
+#[derive(Component)]
+pub struct WeaponCharge {
+    pub started: Instant,
+    /// The hotbar slot of the weapon.
+    pub slot: u16,
+    pub config: ChargeWeaponConfig,
+    last_use: Instant,
+}
+
+impl WeaponCharge {
+    /// The charge (0.0 - 1.0) if the weapon was released now.
+    pub fn charge(&self) -> f32 {
+        charge(self.started.elapsed(), &self.config)
+    }
+}
+
+/// Attached to players that released a charged weapon, the charge is used by the next attack with the weapon.
+#[derive(Component, Clone)]
+pub struct ChargedAttack {
+    /// The charge (0.0 - 1.0).
+    pub charge: f32,
+    /// The hotbar slot of the weapon, attacks with other items do not use the charge.
+    pub slot: u16,
+    pub config: ChargeWeaponConfig,
+}
+
+impl ChargedAttack {
+    /// The damage and knockback multipliers of the charge.
+    pub(crate) fn multipliers(&self, formulas: &CombatFormulas) -> (f32, f32) {
+        let curve = |name: &str| {
+            formulas
+                .charge_curve
+                .resolve_or_warn(Some(name))
+                .map_or(1.0, |curve| curve(self.charge))
+        };
+
+        (
+            curve(&self.config.damage_curve),
+            curve(&self.config.knockback_curve),
+        )
+    }
+}
+
+/// An event that is emitted when a player starts charging a weapon.
+#[derive(Event, Debug, Clone)]
+pub struct ChargeStartEvent {
+    pub player: Entity,
+    pub weapon: ItemStack,
+}
+
+/// An event that is emitted when a player releases a charged weapon.
+#[derive(Event, Debug, Clone)]
+pub struct ChargeReleaseEvent {
+    pub player: Entity,
+    pub weapon: ItemStack,
+    /// The charge (0.0 - 1.0).
+    pub charge: f32,
+}
+
+fn charge(elapsed: Duration, config: &ChargeWeaponConfig) -> f32 {
+    if config.charge_time.is_zero() {
+        return 1.0;
+    }
+
+    (elapsed.as_secs_f32() / config.charge_time.as_secs_f32()).min(1.0)
+}
+
+pub(crate) fn start_weapon_charge(
+    mut commands: Commands,
+    mut events: EventReader<InteractItemEvent>,
+    mut query: Query<(
+        &CombatState,
+        &Inventory,
+        &HeldItem,
+        Option<&mut WeaponCharge>,
+    )>,
+    mut start_writer: EventWriter<ChargeStartEvent>,
+    custom_items: Res<CustomItems>,
+) {
+    for event in events.read() {
+        if event.hand != Hand::Main {
+            continue;
+        }
+
+        let Ok((state, inventory, held_item, weapon_charge)) = query.get_mut(event.client) else {
+            continue;
+        };
+
+        let slot = held_item.slot();
+
+        // The client repeats the use while the button is held.
+        if let Some(mut weapon_charge) = weapon_charge {
+            if weapon_charge.slot == slot {
+                weapon_charge.last_use = Instant::now();
+                continue;
+            }
+        }
+
+        let weapon = inventory.slot(slot);
+
+        let Some(config) = state
+            .combat_config
+            .charge_weapon(weapon, &custom_items)
+            .cloned()
+        else {
+            continue;
+        };
+
+        commands.entity(event.client).insert(WeaponCharge {
+            started: Instant::now(),
+            slot,
+            config,
+            last_use: Instant::now(),
+        });
+
+        start_writer.send(ChargeStartEvent {
+            player: event.client,
+            weapon: weapon.clone(),
+        });
+    }
+}
+
+/// Releases the charge once the player stops holding the button (or switches the item) and shows the charge while charging.
+pub(crate) fn update_weapon_charges(
+    mut commands: Commands,
+    mut packets: EventReader<PacketEvent>,
+    mut query: Query<(Entity, &WeaponCharge, &Inventory, &HeldItem, &mut Client)>,
+    mut release_writer: EventWriter<ChargeReleaseEvent>,
+) {
+    // Items that can be used (e.g. shields) send a packet when the button is released.
+    let released: HashSet<Entity> = packets
+        .read()
+        .filter_map(|packet| {
+            let pkt = packet.decode::<PlayerActionC2s>()?;
+            (pkt.action == PlayerAction::ReleaseUseItem).then_some(packet.client)
+        })
+        .collect();
+
+    for (entity, weapon_charge, inventory, held_item, mut client) in query.iter_mut() {
+        let charge = if released.contains(&entity) {
+            Some(weapon_charge.charge())
+        } else if weapon_charge.last_use.elapsed() >= RELEASE_TIMEOUT
+            || held_item.slot() != weapon_charge.slot
+        {
+            // The button was released around the last use.
+            Some(charge(
+                weapon_charge.last_use - weapon_charge.started,
+                &weapon_charge.config,
+            ))
+        } else {
+            None
+        };
+
+        let Some(charge) = charge else {
+            show_charge(
+                &mut client,
+                weapon_charge.config.display,
+                weapon_charge.charge(),
+            );
+            continue;
+        };
+
+        if weapon_charge.config.display == ChargeDisplay::ExperienceBar {
+            show_charge(&mut client, ChargeDisplay::ExperienceBar, 0.0);
+        }
+
+        commands
+            .entity(entity)
+            .remove::<WeaponCharge>()
+            .insert(ChargedAttack {
+                charge,
+                slot: weapon_charge.slot,
+                config: weapon_charge.config.clone(),
+            });
+
+        release_writer.send(ChargeReleaseEvent {
+            player: entity,
+            weapon: inventory.slot(weapon_charge.slot).clone(),
+            charge,
+        });
+    }
+}
+
+fn show_charge(client: &mut Client, display: ChargeDisplay, charge: f32) {
+    match display {
+        ChargeDisplay::ExperienceBar => client.write_packet(&ExperienceBarUpdateS2c {
+            bar: charge,
+            level: VarInt(0),
+            total_xp: VarInt(0),
+        }),
+        ChargeDisplay::ActionBar => {
+            let filled = (charge * ACTION_BAR_SEGMENTS as f32).round() as usize;
+            let color = if charge >= 1.0 {
+                Color::GREEN
+            } else {
+                Color::YELLOW
+            };
+
+            client.set_action_bar(
+                "|".repeat(filled).color(color)
+                    + "|"
+                        .repeat(ACTION_BAR_SEGMENTS - filled)
+                        .color(Color::DARK_GRAY),
+            );
+        }
+        ChargeDisplay::None => {}
+    }
+}
@@ -0,0 +1,117 @@
+//! The formulas used by the combat system, they are referenced by name in the [`PlayerCombatConfig`](crate::PlayerCombatConfig).
+
+use std::time::{Duration, Instant};
+
+use utils::formula::FormulaRegistry;
+use valence::{math::Vec3, prelude::*};
+
+use crate::calculations;
+
+/// The parameters are: `damage`, `armor_points`, `toughness`.
+pub type ArmorFormula = Box<dyn Fn(f32, f32, f32) -> f32 + Send + Sync>;
+/// The parameters are: `armor_resistance`, `attribute_resistance`, `effect_resistance`.
+///
+/// Returns the combined knockback resistance (0.0 - 1.0).
+pub type KnockbackResistanceFormula = Box<dyn Fn(f32, f32, f32) -> f32 + Send + Sync>;
+/// The parameters are: `weapon_attack_speed`, `last_attack`.
+pub type AttackCooldownFormula = Box<dyn Fn(f32, Instant) -> f32 + Send + Sync>;
+/// The parameters are: `base_damage`, `enchantment_level`.
+pub type EnchantmentDamageFormula = Box<dyn Fn(f32, u32) -> f32 + Send + Sync>;
+/// The parameters are: `base_knockback_vector`, `enchantment_level`.
+pub type EnchantmentKnockbackFormula = Box<dyn Fn(Vec3, u32) -> Vec3 + Send + Sync>;
+/// The parameters are: `enchantment_level`.
+///
+/// Returns the burn time and damage per second.
+pub type EnchantmentBurnFormula = Box<dyn Fn(u32) -> (Duration, f32) + Send + Sync>;
+/// The parameters are: `look_direction`, `enchantment_level`.
+///
+/// Returns the velocity of the launched player.
+pub type EnchantmentRiptideFormula = Box<dyn Fn(Vec3, u32) -> Vec3 + Send + Sync>;
+/// The parameters are: `enchantment_level`.
+///
+/// Returns the speed of the returning trident.
+pub type EnchantmentLoyaltyFormula = Box<dyn Fn(u32) -> f32 + Send + Sync>;
+
+/// The parameters are: `charge` (0.0 - 1.0).
+///
+/// Returns the damage or knockback multiplier of a charged weapon.
+pub type ChargeCurveFormula = Box<dyn Fn(f32) -> f32 + Send + Sync>;
+
+/// The parameters are: `combo` (the number of consecutive hits, starting at 1).
+///
+/// Returns the damage or knockback multiplier of the hit.
+pub type ComboCurveFormula = Box<dyn Fn(u32) -> f32 + Send + Sync>;
+
+/// All formulas that can be referenced by a [`PlayerCombatConfig`](crate::PlayerCombatConfig).
+///
+/// The vanilla formulas are registered as `"vanilla"` (armor and knockback resistance), `"vanilla_base_damage"`, `"vanilla_enchantment_damage"` (cooldowns),
+/// `"vanilla_sharpness"`, `"vanilla_power"`, `"vanilla_knockback"`, `"vanilla_punch"`, `"vanilla_fire_aspect"`, `"vanilla_flame"`, `"vanilla_riptide"` and `"vanilla_loyalty"` (enchantments).
+///
+/// The charge curves are registered as `"linear"` and `"quadratic"`, the combo curve as `"linear"`.
+#[derive(Resource)]
+pub struct CombatFormulas {
+    pub armor: FormulaRegistry<ArmorFormula>,
+    pub knockback_resistance: FormulaRegistry<KnockbackResistanceFormula>,
+    pub attack_cooldown: FormulaRegistry<AttackCooldownFormula>,
+    pub enchantment_damage: FormulaRegistry<EnchantmentDamageFormula>,
+    pub enchantment_knockback: FormulaRegistry<EnchantmentKnockbackFormula>,
+    pub enchantment_burn: FormulaRegistry<EnchantmentBurnFormula>,
+    pub enchantment_riptide: FormulaRegistry<EnchantmentRiptideFormula>,
+    pub enchantment_loyalty: FormulaRegistry<EnchantmentLoyaltyFormula>,
+    pub charge_curve: FormulaRegistry<ChargeCurveFormula>,
+    pub combo_curve: FormulaRegistry<ComboCurveFormula>,
+}
+
+impl Default for CombatFormulas {
+    fn default() -> Self {
+        Self {
+            armor: FormulaRegistry::new()
+                .with("vanilla", Box::new(calculations::damage_after_armor) as _),
+            knockback_resistance: FormulaRegistry::new()
+                .with("vanilla", Box::new(calculations::knockback_resistance) as _),
+            attack_cooldown: FormulaRegistry::new()
+                .with(
+                    "vanilla_base_damage",
+                    Box::new(calculations::attack_cooldown_base_damage) as _,
+                )
+                .with(
+                    "vanilla_enchantment_damage",
+                    Box::new(calculations::attack_cooldown_enchantment_damage) as _,
+                ),
+            enchantment_damage: FormulaRegistry::new()
+                .with(
+                    "vanilla_sharpness",
+                    Box::new(calculations::enchant_sharpness_damage) as _,
+                )
+                .with(
+                    "vanilla_power",
+                    Box::new(calculations::enchant_power_damage) as _,
+                ),
+            enchantment_knockback: FormulaRegistry::new()
+                .with(
+                    "vanilla_knockback",
+                    Box::new(calculations::enchant_knockback) as _,
+                )
+                .with("vanilla_punch", Box::new(calculations::enchant_punch) as _),
+            enchantment_burn: FormulaRegistry::new()
+                .with(
+                    "vanilla_fire_aspect",
+                    Box::new(calculations::enchant_fire_aspect) as _,
+                )
+                .with("vanilla_flame", Box::new(calculations::enchant_flame) as _),
+            enchantment_riptide: FormulaRegistry::new().with(
+                "vanilla_riptide",
+                Box::new(calculations::enchant_riptide) as _,
+            ),
+            enchantment_loyalty: FormulaRegistry::new().with(
+                "vanilla_loyalty",
+                Box::new(calculations::enchant_loyalty) as _,
+            ),
+            charge_curve: FormulaRegistry::new()
+                .with("linear", Box::new(calculations::charge_linear) as _)
+                .with("quadratic", Box::new(calculations::charge_quadratic) as _),
+            combo_curve: FormulaRegistry::new()
+                .with("linear", Box::new(calculations::combo_linear) as _),
+        }
+    }
+}
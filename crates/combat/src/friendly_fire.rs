@@ -0,0 +1,53 @@
+//! Projectiles pass through the allies of their owner if friendly fire is disabled,
+//! instead of hitting them without dealing any damage.
+
+use physics::{projectile::Projectile, PassThroughEntities};
+use valence::{ecs::entity::EntityHashSet, prelude::*};
+
+use crate::{CombatState, Team};
+
+/// Update the [`PassThroughEntities`] of projectiles whose owner has a [`CombatState`].
+///
+/// This runs in `PostUpdate`, so projectiles spawned during `Update` are already filtered
+/// before the physics move them for the first time.
+pub(crate) fn pass_through_allies(
+    mut commands: Commands,
+    projectiles: Query<(Entity, Ref<Projectile>)>,
+    owners: Query<(&CombatState, Option<&Team>)>,
+    targets: Query<(Entity, &Team, Option<&CombatState>)>,
+    changed_teams: Query<(), Changed<Team>>,
+    mut removed_teams: RemovedComponents<Team>,
+) {
+    let teams_changed = !changed_teams.is_empty() || removed_teams.read().count() > 0;
+
+    for (entity, projectile) in projectiles.iter() {
+        if !teams_changed && !projectile.is_added() {
+            continue;
+        }
+
+        let Some(owner) = projectile.owner else {
+            continue;
+        };
+
+        let Ok((owner_state, owner_team)) = owners.get(owner) else {
+            continue;
+        };
+
+        let config = &owner_state.combat_config;
+
+        let allies = targets
+            .iter()
+            .filter(|(target, target_team, target_state)| {
+                *target != owner
+                    && config.is_friendly(owner_team, Some(target_team))
+                    && (config.friendly_fire_damage_multiplier == 0.0
+                        || target_state.is_some_and(|state| {
+                            state.combat_config.friendly_fire_damage_taken_multiplier == 0.0
+                        }))
+            })
+            .map(|(target, ..)| target)
+            .collect::<EntityHashSet>();
+
+        commands.entity(entity).insert(PassThroughEntities(allies));
+    }
+}
@@ -0,0 +1,151 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use valence::{keepalive::Ping, prelude::*};
+
+use crate::CombatState;
+
+/// Enables lag compensation for combat.
+///
+/// If this resource exists, attacks are validated against the position the victim had
+/// when the attacker saw it (the current time minus the ping of the attacker),
+/// instead of the current position. The knockback direction is calculated from that position as well.
+#[derive(Resource)]
+pub struct LagCompensationConfig {
+    /// How long positions are kept in the [`PositionHistory`].
+    /// Attackers with a higher ping are compensated as if they had this ping.
+    pub history: Duration,
+    /// The maximum distance between the eyes of the attacker and the (historical) hitbox of the victim
+    /// for weapons with the vanilla reach, the limit is adjusted for weapons with a different reach.
+    ///
+    /// If `None`, the reach will not be validated.
+    pub max_reach: Option<f64>,
+}
+
+impl Default for LagCompensationConfig {
+    fn default() -> Self {
+        Self {
+            history: Duration::from_millis(500),
+            max_reach: Some(4.5),
+        }
+    }
+}
+
+/// The recent positions of an entity, this will be added to all entities with a [`CombatState`]
+/// if the [`LagCompensationConfig`] exists.
+#[derive(Component, Default)]
+pub struct PositionHistory {
+    positions: VecDeque<(Instant, DVec3)>,
+}
+
+impl PositionHistory {
+    /// The position the entity had at the given time.
+    ///
+    /// Returns the oldest known position if the time is older than the history.
+    pub fn position_at(&self, time: Instant) -> Option<DVec3> {
+        self.positions
+            .iter()
+            .rev()
+            .find(|(recorded, _)| *recorded <= time)
+            .or(self.positions.front())
+            .map(|(_, position)| *position)
+    }
+
+    fn record(&mut self, position: DVec3, max_age: Duration) {
+        let now = Instant::now();
+        self.positions.push_back((now, position));
+
+        while self
+            .positions
+            .front()
+            .is_some_and(|(recorded, _)| now.duration_since(*recorded) > max_age)
+        {
+            self.positions.pop_front();
+        }
+    }
+}
+
+/// The position of the victim as seen by an attacker with the given ping.
+pub(crate) fn compensated_position(
+    history: Option<&PositionHistory>,
+    ping: Option<&Ping>,
+    config: &LagCompensationConfig,
+    current: DVec3,
+) -> DVec3 {
+    let (Some(history), Some(ping)) = (history, ping) else {
+        return current;
+    };
+
+    let delay = Duration::from_millis(ping.0.max(0) as u64).min(config.history);
+
+    Instant::now()
+        .checked_sub(delay)
+        .and_then(|time| history.position_at(time))
+        .unwrap_or(current)
+}
+
+pub(crate) fn record_position_history(
+    mut commands: Commands,
+    config: Option<Res<LagCompensationConfig>>,
+    mut entities: Query<(Entity, &Position, Option<&mut PositionHistory>), With<CombatState>>,
+) {
+    let Some(config) = config else {
+        return;
+    };
+
+    for (entity, position, history) in entities.iter_mut() {
+        match history {
+            Some(mut history) => history.record(position.0, config.history),
+            None => {
+                let mut history = PositionHistory::default();
+                history.record(position.0, config.history);
+                commands.entity(entity).insert(history);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn position_at_returns_the_last_position_before_the_time() {
+        let start = Instant::now();
+        let history = PositionHistory {
+            positions: VecDeque::from([
+                (start, DVec3::new(0.0, 0.0, 0.0)),
+                (start + Duration::from_millis(50), DVec3::new(1.0, 0.0, 0.0)),
+                (
+                    start + Duration::from_millis(100),
+                    DVec3::new(2.0, 0.0, 0.0),
+                ),
+            ]),
+        };
+
+        assert_eq!(
+            history.position_at(start + Duration::from_millis(75)),
+            Some(DVec3::new(1.0, 0.0, 0.0))
+        );
+        assert_eq!(
+            history.position_at(start + Duration::from_millis(200)),
+            Some(DVec3::new(2.0, 0.0, 0.0))
+        );
+    }
+
+    #[test]
+    fn position_at_falls_back_to_the_oldest_position() {
+        let start = Instant::now() + Duration::from_secs(1);
+        let history = PositionHistory {
+            positions: VecDeque::from([(start, DVec3::new(3.0, 0.0, 0.0))]),
+        };
+
+        assert_eq!(
+            history.position_at(Instant::now()),
+            Some(DVec3::new(3.0, 0.0, 0.0))
+        );
+        assert_eq!(PositionHistory::default().position_at(start), None);
+    }
+}
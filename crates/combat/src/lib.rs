@@ -4,28 +4,57 @@ use std::{
 };
 
 use bevy_ecs::query::QueryData;
-use calculations::damage_after_armor;
+use charge::{ChargeReleaseEvent, ChargeStartEvent, ChargeWeaponConfig, ChargedAttack};
 use fall_damage::FallingState;
+use formulas::CombatFormulas;
+use lag_compensation::{compensated_position, record_position_history};
+pub use lag_compensation::{LagCompensationConfig, PositionHistory};
+use physics::projectile::look_direction;
+use serde::{Deserialize, Serialize};
+use shield::{ParryConfig, ParryEvent};
+use trident::{TridentConfig, Weather};
 use utils::{
-    damage::{DamageEvent, StartBurningEvent},
+    custom_items::CustomItems,
+    damage::{DamageEvent, DamageType, StartBurningEvent},
+    effects::{self, EntityStatusesExt},
     enchantments::{Enchantment, ItemStackEnchantmentsExt},
-    item_values::{CombatSystem, EquipmentExt},
-    ItemKindExt,
+    formula::UnknownFormula,
+    item_values::{CombatSystem, EquipmentExt, ItemStackExt, DEFAULT_REACH},
+    knockback::{ApplyKnockbackEvent, KnockbackPlugin, KnockbackResistanceEffect},
+    pvp::PvpCheck,
+    rate_limit::{RateLimitKind, RateLimitPlugin, RateLimits},
+    spawn::SpawnProtection,
+    spectator::Spectating,
 };
 use valence::{
     entity::{
         attributes::{EntityAttribute, EntityAttributes},
+        entity::Flags,
         living::StuckArrowCount,
-        EntityId, EntityStatuses, Velocity,
+        EntityId, EntityStatuses,
     },
     hand_swing::HandSwingEvent,
     inventory::{HeldItem, UpdateSelectedSlotEvent},
+    keepalive::Ping,
+    math::Aabb,
     prelude::*,
+    protocol::{sound::SoundCategory, Sound},
 };
+use visibility::CulledEntities;
 
 pub mod calculations;
+pub mod charge;
+pub mod formulas;
+mod friendly_fire;
+mod lag_compensation;
+pub mod shield;
+pub mod trident;
 
 const BASE_HIT_COOLDOWN: Duration = Duration::from_millis(500);
+/// The maximum distance between the attacker and the entities hit by a sweep attack.
+const SWEEP_RANGE: f64 = 3.0;
+/// The horizontal knockback of entities hit by a sweep attack.
+const SWEEP_KNOCKBACK: f32 = 0.4;
 
 /// Attached to every player that participates in combat.
 #[derive(Component)]
@@ -38,12 +67,20 @@ pub struct CombatState {
     pub last_attack: Instant,
     /// The player is sprinting.
     pub sprinting: bool,
+    /// The player already dealt a sprint hit during the current sprint.
+    pub sprint_hit: bool,
     /// The player is sneaking.
     pub sneaking: bool,
     /// The combat config for the player.
     pub combat_config: PlayerCombatConfig,
     /// The player is currently blocking with a shield.
     pub blocking: bool,
+    /// The last time the player started blocking with a shield (used for parrying).
+    pub last_block_start: Instant,
+    /// The number of consecutive hits on [`Self::combo_victim`] (reset when the player is hit or after [`PlayerCombatConfig::combo_timeout`]).
+    pub combo: u32,
+    /// The entity the current combo is on.
+    pub combo_victim: Option<Entity>,
 }
 
 impl Default for CombatState {
@@ -53,15 +90,23 @@ impl Default for CombatState {
             last_got_hit: Instant::now(),
             last_attack: Instant::now(),
             sprinting: false,
+            sprint_hit: false,
             sneaking: false,
             combat_config: PlayerCombatConfig::default(),
             blocking: false,
+            last_block_start: Instant::now(),
+            combo: 0,
+            combo_victim: None,
         }
     }
 }
 
 /// Contains configuration options mostly multipliers for the player.
 /// They will usually not be changed during the game.
+///
+/// Formulas are referenced by their name in the [`CombatFormulas`] resource.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct PlayerCombatConfig {
     /// The combat system that will be used to determine the weapon damage.
     /// This only affects the damage, not the actual cooldown, change [`Self::attack_cooldown_multiplier`] for that.
@@ -72,7 +117,10 @@ pub struct PlayerCombatConfig {
     pub combat_system: CombatSystem,
     /// How many arrows can be in the player at once.
     pub arrows_stick: u8,
-    /// Teams considered friendly.
+    /// Teams considered friendly (in addition to the own team of the player).
+    ///
+    /// Projectiles of the player pass through friendly entities if friendly fire is disabled
+    /// (see [`Self::friendly_fire_damage_multiplier`]).
     pub friendly_teams: HashSet<u16>,
     /// The minimum time between two attacks. (This is not the attack cooldown, but the minimum time before another attack can be registered).
     pub hit_cooldown: Duration,
@@ -82,6 +130,12 @@ pub struct PlayerCombatConfig {
     ///
     /// If `Some`, then the multiplier will be applied to the 1.9+ attack cooldown.
     pub attack_cooldown_multiplier: Option<f32>,
+    /// The minimum attack charge (0.0 - 1.0) that is needed for a critical hit (only with an attack cooldown).
+    pub min_charge_for_crit: f32,
+    /// The minimum attack charge (0.0 - 1.0) that is needed for a sweep attack with a sword.
+    ///
+    /// If this is `None` or there is no attack cooldown, swords will not sweep.
+    pub min_charge_for_sweep: Option<f32>,
 
     /// Multiplier for armor points.
     pub armor_points_multiplier: f32,
@@ -89,6 +143,11 @@ pub struct PlayerCombatConfig {
     pub armor_toughness_multiplier: f32,
     /// Multiplier for the knockback resistance applied by armor.
     pub armor_knockback_resistance_multiplier: f32,
+    /// The formula that combines the knockback resistance of the armor, the `generic.knockback_resistance`
+    /// attribute and the [`KnockbackResistanceEffect`].
+    ///
+    /// The name of a formula in [`CombatFormulas::knockback_resistance`].
+    pub knockback_resistance_formula: String,
 
     /// Horizontal knockback the player deals.
     pub horizontal_knockback: PlayerStateDependantValue,
@@ -100,6 +159,15 @@ pub struct PlayerCombatConfig {
     /// Multiplier of the vertical knockback the player takes.
     pub vertical_knockback_received_multiplier: PlayerStateDependantValue,
 
+    /// Only the first hit of a sprint counts as a sprint hit (vanilla),
+    /// the player has to start sprinting again (w-tap) for the next sprint hit.
+    pub sprint_hit_reset: bool,
+    /// Stop the sprint of the player (also on the client) after a sprint hit.
+    pub stop_sprint_on_hit: bool,
+    /// Multiplier of the horizontal knockback of a sprint hit (1.0 is vanilla),
+    /// this can be used to reward w-tapping.
+    pub w_tap_bonus: f32,
+
     /// The random chance of a critical hit (0.0 - 1.0).
     pub random_critical_hit_chance: PlayerStateDependantValue,
     /// The random chance of a critical hit while falling (0.0 - 1.0), vanilla is 100%.
@@ -124,21 +192,66 @@ pub struct PlayerCombatConfig {
 
     /// The formula that should be used to calculate the received damage after armor.
     ///
-    /// The parameters are: `damage`, `armor_points`, `toughness`.
-    pub armor_formula: fn(f32, f32, f32) -> f32,
+    /// The name of a formula in [`CombatFormulas::armor`].
+    pub armor_formula: String,
 
     /// Attack cooldown damage multiplier for weapon damage formula
     ///
-    /// The parameters are: `weapon_attack_speed`, `last_attack`.
-    pub damage_cooldown_formula_base_damage: fn(f32, Instant) -> f32,
+    /// The name of a formula in [`CombatFormulas::attack_cooldown`].
+    pub damage_cooldown_formula_base_damage: String,
 
     /// Attack cooldown damage multiplier for enchantments formula
     ///
-    /// The parameters are: `weapon_attack_speed`, `last_attack`.
-    pub damage_cooldown_enchantment_formula: fn(f32, Instant) -> f32,
+    /// The name of a formula in [`CombatFormulas::attack_cooldown`].
+    pub damage_cooldown_enchantment_formula: String,
 
     /// The configuration of combat relevant enchantments.
     pub enchantment_config: CombatEnchantmentConfig,
+
+    /// The configuration for throwing tridents.
+    ///
+    /// If this is `None`, the player can not throw tridents.
+    pub trident: Option<TridentConfig>,
+
+    /// Weapons that can be charged by holding right-click, the charge scales the next attack with the weapon.
+    #[serde(with = "utils::serialization::item_kind_map")]
+    pub charge_weapons: HashMap<ItemKind, ChargeWeaponConfig>,
+    /// Like [`Self::charge_weapons`], but for custom items by their id (see [`CustomItems`]).
+    pub custom_charge_weapons: HashMap<String, ChargeWeaponConfig>,
+
+    /// The configuration for parrying melee hits with a shield.
+    ///
+    /// If this is `None`, the player can not parry.
+    pub parry: Option<ParryConfig>,
+
+    /// The combo ends if the player does not hit the victim again within this time.
+    pub combo_timeout: Duration,
+    /// The damage multiplier of a combo.
+    ///
+    /// The name of a formula in [`CombatFormulas::combo_curve`].
+    ///
+    /// If this is `None`, the combo does not change the damage.
+    pub combo_damage_curve: Option<String>,
+    /// The knockback multiplier of a combo.
+    ///
+    /// The name of a formula in [`CombatFormulas::combo_curve`].
+    ///
+    /// If this is `None`, the combo does not change the knockback.
+    pub combo_knockback_curve: Option<String>,
+
+    /// Players in [`GameMode::Spectator`] can attack (vanilla spectators can not attack).
+    pub spectator_game_mode_attacks: bool,
+}
+
+impl PlayerCombatConfig {
+    /// Check if an entity of the `other` team is friendly to the player of the `own` team.
+    pub fn is_friendly(&self, own: Option<&Team>, other: Option<&Team>) -> bool {
+        let Some(other) = other else {
+            return false;
+        };
+
+        own == Some(other) || self.friendly_teams.contains(&other.0)
+    }
 }
 
 /// The current state of the player's movement.
@@ -150,6 +263,7 @@ enum PlayerMovementState {
 }
 
 /// Values that depend on the current state of the player.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PlayerStateDependantValue {
     pub base: f32,
     pub sprinting: f32,
@@ -178,43 +292,61 @@ impl PlayerStateDependantValue {
     }
 }
 
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct CombatEnchantmentConfig {
     /// The formula to calculate the damage after applying the sharpness enchantment.
     ///
-    /// The parameters are: `weapon_base_damage`, `sharpness_level`.
+    /// The name of a formula in [`CombatFormulas::enchantment_damage`].
     ///
     /// If this is `None`, the enchantment will not be usable by the player.
-    pub sharpness_formula: Option<fn(f32, u32) -> f32>,
+    pub sharpness_formula: Option<String>,
     /// The formula to calculate the knockback after applying the knockback enchantment.
     ///
-    /// The parameters are: `base_knockback_vector`, `knockback_level`.
+    /// The name of a formula in [`CombatFormulas::enchantment_knockback`].
     ///
     /// If this is `None`, the enchantment will not be usable by the player.
-    pub knockback_formula: Option<fn(Vec3, u32) -> Vec3>,
+    pub knockback_formula: Option<String>,
     /// The formula to calculate the burn time and damage per second after applying the fire aspect enchantment.
     ///
-    /// The parameters are: `fire_aspect_level`.
+    /// The name of a formula in [`CombatFormulas::enchantment_burn`].
     ///
     /// If this is `None`, the enchantment will not be usable by the player.
-    pub fire_aspect_formula: Option<fn(u32) -> (Duration, f32)>,
+    pub fire_aspect_formula: Option<String>,
     /// The formula to calculate the burn time and damage per second after applying the flame enchantment.
     ///
-    /// The parameters are: `fire_aspect_level`.
+    /// The name of a formula in [`CombatFormulas::enchantment_burn`].
     ///
     /// If this is `None`, the enchantment will not be usable by the player.
-    pub flame_formula: Option<fn(u32) -> (Duration, f32)>,
+    pub flame_formula: Option<String>,
     /// The formula to calculate the damage after applying the power enchantment.
     ///
-    /// The parameters are: `base_arrow_damage`, `power_level`.
+    /// The name of a formula in [`CombatFormulas::enchantment_damage`].
     ///
     /// If this is `None`, the enchantment will not be usable by the player.
-    pub power_formula: Option<fn(f32, u32) -> f32>,
+    pub power_formula: Option<String>,
     /// The formula to calculate the knockback after applying the punch enchantment.
     ///
-    /// The parameters are: `base_knockback_vector`, `punch_level`.
+    /// The name of a formula in [`CombatFormulas::enchantment_knockback`].
     ///
     /// If this is `None`, the enchantment will not be usable by the player.
-    pub punch_formula: Option<fn(Vec3, u32) -> Vec3>,
+    pub punch_formula: Option<String>,
+    /// The formula to calculate the velocity of a player that uses a trident with riptide.
+    ///
+    /// The name of a formula in [`CombatFormulas::enchantment_riptide`].
+    ///
+    /// If this is `None`, the enchantment will not be usable by the player.
+    pub riptide_formula: Option<String>,
+    /// The formula to calculate the speed of a trident with loyalty that returns to the player.
+    ///
+    /// The name of a formula in [`CombatFormulas::enchantment_loyalty`].
+    ///
+    /// If this is `None`, the enchantment will not be usable by the player.
+    pub loyalty_formula: Option<String>,
+    /// The damage of the lightning that strikes entities hit by a trident with channeling during a thunderstorm.
+    ///
+    /// If this is `None`, the enchantment will not be usable by the player.
+    pub channeling_damage: Option<f32>,
     // TODO: thorns,
 }
 
@@ -226,9 +358,12 @@ impl Default for PlayerCombatConfig {
             friendly_teams: HashSet::new(),
             hit_cooldown: BASE_HIT_COOLDOWN,
             attack_cooldown_multiplier: None,
+            min_charge_for_crit: 0.9,
+            min_charge_for_sweep: Some(0.9),
             armor_points_multiplier: 1.0,
             armor_toughness_multiplier: 1.0,
             armor_knockback_resistance_multiplier: 1.0,
+            knockback_resistance_formula: "vanilla".to_string(),
             horizontal_knockback: PlayerStateDependantValue {
                 base: 0.4,
                 sprinting: 0.8,
@@ -253,6 +388,9 @@ impl Default for PlayerCombatConfig {
                 sneaking: 1.0,
                 in_air: 0.8,
             },
+            sprint_hit_reset: true,
+            stop_sprint_on_hit: false,
+            w_tap_bonus: 1.0,
             random_critical_hit_chance: PlayerStateDependantValue::always(0.0),
             critical_hit_chance_falling: 1.0,
             critical_hit_damage_multiplier: 1.5,
@@ -262,17 +400,123 @@ impl Default for PlayerCombatConfig {
             fire_duration_multiplier: PlayerStateDependantValue::always(1.0),
             friendly_fire_damage_multiplier: 0.0,
             friendly_fire_damage_taken_multiplier: 0.0,
-            armor_formula: calculations::damage_after_armor,
-            enchantment_config: CombatEnchantmentConfig {
-                sharpness_formula: Some(calculations::enchant_sharpness_damage),
-                knockback_formula: Some(calculations::enchant_knockback),
-                fire_aspect_formula: Some(calculations::enchant_fire_aspect),
-                flame_formula: Some(calculations::enchant_flame),
-                power_formula: Some(calculations::enchant_power_damage),
-                punch_formula: Some(calculations::enchant_punch),
-            },
-            damage_cooldown_formula_base_damage: calculations::attack_cooldown_base_damage,
-            damage_cooldown_enchantment_formula: calculations::attack_cooldown_enchantment_damage,
+            armor_formula: "vanilla".to_string(),
+            enchantment_config: CombatEnchantmentConfig::default(),
+            damage_cooldown_formula_base_damage: "vanilla_base_damage".to_string(),
+            damage_cooldown_enchantment_formula: "vanilla_enchantment_damage".to_string(),
+            trident: Some(TridentConfig::default()),
+            charge_weapons: HashMap::new(),
+            custom_charge_weapons: HashMap::new(),
+            parry: None,
+            combo_timeout: Duration::from_secs(2),
+            combo_damage_curve: None,
+            combo_knockback_curve: None,
+            spectator_game_mode_attacks: false,
+        }
+    }
+}
+
+impl PlayerCombatConfig {
+    /// Check that all formulas of the config are registered,
+    /// this should be done once when the config is loaded (unknown formulas are skipped during combat).
+    pub fn validate(&self, formulas: &CombatFormulas) -> Result<(), UnknownFormula> {
+        formulas
+            .knockback_resistance
+            .validate(Some(&self.knockback_resistance_formula))?;
+        formulas.armor.validate(Some(&self.armor_formula))?;
+        formulas
+            .attack_cooldown
+            .validate(Some(&self.damage_cooldown_formula_base_damage))?;
+        formulas
+            .attack_cooldown
+            .validate(Some(&self.damage_cooldown_enchantment_formula))?;
+        formulas
+            .combo_curve
+            .validate(self.combo_damage_curve.as_deref())?;
+        formulas
+            .combo_curve
+            .validate(self.combo_knockback_curve.as_deref())?;
+
+        for charge_weapon in self
+            .charge_weapons
+            .values()
+            .chain(self.custom_charge_weapons.values())
+        {
+            formulas
+                .charge_curve
+                .validate(Some(&charge_weapon.damage_curve))?;
+            formulas
+                .charge_curve
+                .validate(Some(&charge_weapon.knockback_curve))?;
+        }
+
+        self.enchantment_config.validate(formulas)
+    }
+}
+
+impl CombatEnchantmentConfig {
+    /// Check that all formulas of the config are registered.
+    pub fn validate(&self, formulas: &CombatFormulas) -> Result<(), UnknownFormula> {
+        let damage = [&self.sharpness_formula, &self.power_formula];
+        let knockback = [&self.knockback_formula, &self.punch_formula];
+        let burn = [&self.fire_aspect_formula, &self.flame_formula];
+
+        for name in damage {
+            formulas.enchantment_damage.validate(name.as_deref())?;
+        }
+
+        for name in knockback {
+            formulas.enchantment_knockback.validate(name.as_deref())?;
+        }
+
+        for name in burn {
+            formulas.enchantment_burn.validate(name.as_deref())?;
+        }
+
+        formulas
+            .enchantment_riptide
+            .validate(self.riptide_formula.as_deref())?;
+        formulas
+            .enchantment_loyalty
+            .validate(self.loyalty_formula.as_deref())
+    }
+
+    /// Replace the formulas of the enchantments (e.g. with the formulas of a [`CustomItem`](utils::custom_items::CustomItem)).
+    pub fn with_overrides(&self, overrides: &HashMap<Enchantment, String>) -> Self {
+        let mut config = self.clone();
+
+        for (enchantment, formula) in overrides {
+            let formula = Some(formula.clone());
+
+            match enchantment {
+                Enchantment::Sharpness => config.sharpness_formula = formula,
+                Enchantment::Knockback => config.knockback_formula = formula,
+                Enchantment::FireAspect => config.fire_aspect_formula = formula,
+                Enchantment::Flame => config.flame_formula = formula,
+                Enchantment::Power => config.power_formula = formula,
+                Enchantment::Punch => config.punch_formula = formula,
+                Enchantment::Riptide => config.riptide_formula = formula,
+                Enchantment::Loyalty => config.loyalty_formula = formula,
+                _ => {}
+            }
+        }
+
+        config
+    }
+}
+
+impl Default for CombatEnchantmentConfig {
+    fn default() -> Self {
+        Self {
+            sharpness_formula: Some("vanilla_sharpness".to_string()),
+            knockback_formula: Some("vanilla_knockback".to_string()),
+            fire_aspect_formula: Some("vanilla_fire_aspect".to_string()),
+            flame_formula: Some("vanilla_flame".to_string()),
+            power_formula: Some("vanilla_power".to_string()),
+            punch_formula: Some("vanilla_punch".to_string()),
+            riptide_formula: Some("vanilla_riptide".to_string()),
+            loyalty_formula: Some("vanilla_loyalty".to_string()),
+            channeling_damage: Some(5.0),
         }
     }
 }
@@ -290,38 +534,57 @@ fn apply_enchantments(
     mut base_knockback: Vec3,
     enchantments: HashMap<Enchantment, u32>,
     enchantment_config: &CombatEnchantmentConfig,
+    formulas: &CombatFormulas,
 ) -> EnchantmentValues {
     let mut burn = None;
 
     for (enchant, level) in enchantments {
         match enchant {
             Enchantment::Sharpness => {
-                if let Some(formula) = &enchantment_config.sharpness_formula {
+                if let Some(formula) = formulas
+                    .enchantment_damage
+                    .resolve_or_warn(enchantment_config.sharpness_formula.as_deref())
+                {
                     base_damage = formula(base_damage, level);
                 }
             }
             Enchantment::Knockback => {
-                if let Some(formula) = &enchantment_config.knockback_formula {
+                if let Some(formula) = formulas
+                    .enchantment_knockback
+                    .resolve_or_warn(enchantment_config.knockback_formula.as_deref())
+                {
                     base_knockback = formula(base_knockback, level);
                 }
             }
             Enchantment::FireAspect => {
-                if let Some(formula) = &enchantment_config.fire_aspect_formula {
+                if let Some(formula) = formulas
+                    .enchantment_burn
+                    .resolve_or_warn(enchantment_config.fire_aspect_formula.as_deref())
+                {
                     burn = Some(formula(level));
                 }
             }
             Enchantment::Flame => {
-                if let Some(formula) = &enchantment_config.flame_formula {
+                if let Some(formula) = formulas
+                    .enchantment_burn
+                    .resolve_or_warn(enchantment_config.flame_formula.as_deref())
+                {
                     burn = Some(formula(level));
                 }
             }
             Enchantment::Power => {
-                if let Some(formula) = &enchantment_config.power_formula {
+                if let Some(formula) = formulas
+                    .enchantment_damage
+                    .resolve_or_warn(enchantment_config.power_formula.as_deref())
+                {
                     base_damage = formula(base_damage, level);
                 }
             }
             Enchantment::Punch => {
-                if let Some(formula) = &enchantment_config.punch_formula {
+                if let Some(formula) = formulas
+                    .enchantment_knockback
+                    .resolve_or_warn(enchantment_config.punch_formula.as_deref())
+                {
                     base_knockback = formula(base_knockback, level);
                 }
             }
@@ -343,10 +606,13 @@ pub struct Team(pub u16);
 #[derive(QueryData)]
 #[query_data(mutable)]
 struct CombatQuery {
+    entity: Entity,
     client: Option<&'static mut Client>,
     entity_id: &'static EntityId,
     position: &'static Position,
-    velocity: &'static mut Velocity,
+    look: &'static Look,
+    hitbox: &'static Hitbox,
+    flags: Option<&'static mut Flags>,
     state: &'static mut CombatState,
     statuses: &'static mut EntityStatuses,
     // To retrieve the weapon used.
@@ -359,34 +625,182 @@ struct CombatQuery {
     stuck_arrow_count: Option<&'static mut StuckArrowCount>,
     // Used for the attack cooldown
     attributes: &'static mut EntityAttributes,
+    layer: &'static EntityLayerId,
+    ping: Option<&'static Ping>,
+    position_history: Option<&'static PositionHistory>,
+    spawn_protection: Option<&'static SpawnProtection>,
+    spectating: Has<Spectating>,
+    culled: Option<&'static CulledEntities>,
+    game_mode: Option<&'static GameMode>,
+    knockback_resistance_effect: Option<&'static KnockbackResistanceEffect>,
+    charged_attack: Option<&'static ChargedAttack>,
+}
+
+impl CombatQueryItem<'_> {
+    /// The combined knockback resistance (0.0 - 1.0), see [`PlayerCombatConfig::knockback_resistance_formula`].
+    fn knockback_resistance(&self, formulas: &CombatFormulas, custom_items: &CustomItems) -> f32 {
+        let config = &self.state.combat_config;
+
+        let armor = self.equipment.knockback_resistance(custom_items)
+            * config.armor_knockback_resistance_multiplier;
+        let attribute = self
+            .attributes
+            .get_compute_value(EntityAttribute::GenericKnockbackResistance)
+            .unwrap_or(0.0) as f32;
+        let effect = self
+            .knockback_resistance_effect
+            .map_or(0.0, |effect| effect.0);
+
+        match formulas
+            .knockback_resistance
+            .resolve_or_warn(Some(&config.knockback_resistance_formula))
+        {
+            Some(formula) => formula(armor, attribute, effect),
+            None => calculations::knockback_resistance(armor, attribute, effect),
+        }
+    }
+}
+
+/// An event that is emitted for every attack of a player, before the attack is validated.
+///
+/// This can be used to detect cheats (e.g. reach or kill aura).
+#[derive(Event, Clone, Copy, Debug)]
+pub struct AttackEvent {
+    pub attacker: Entity,
+    pub victim: Entity,
+    /// The distance between the eyes of the attacker and the hitbox of the victim (lag compensated if enabled).
+    pub reach: f64,
+    /// The reach of the weapon the attacker used (see [`ItemStackExt::reach`]).
+    pub weapon_reach: f64,
+    /// The angle (in degrees) between the look direction of the attacker and the center of the hitbox of the victim.
+    pub angle: f32,
+}
+
+impl AttackEvent {
+    fn new(
+        attacker_ent: Entity,
+        victim_ent: Entity,
+        attacker: &CombatQueryItem,
+        victim_hitbox: Aabb,
+        weapon_reach: f64,
+    ) -> Self {
+        let eye_height = if attacker.state.sneaking { 1.27 } else { 1.62 };
+        let eye = attacker.position.0 + DVec3::new(0.0, eye_height, 0.0);
+
+        let closest = eye.clamp(victim_hitbox.min(), victim_hitbox.max());
+        let center = (victim_hitbox.min() + victim_hitbox.max()) / 2.0;
+
+        let to_victim = (center - eye).as_vec3();
+        let angle = if to_victim.length_squared() > 1.0e-6 {
+            look_direction(attacker.look)
+                .angle_between(to_victim)
+                .to_degrees()
+        } else {
+            0.0
+        };
+
+        Self {
+            attacker: attacker_ent,
+            victim: victim_ent,
+            reach: eye.distance(closest),
+            weapon_reach,
+            angle,
+        }
+    }
+
+    /// The reach as if the weapon had the vanilla reach, so it can be compared against fixed limits.
+    pub fn normalized_reach(&self) -> f64 {
+        self.reach - (self.weapon_reach - DEFAULT_REACH as f64)
+    }
+}
+
+/// An event that is emitted for every melee hit that continues (or starts) a combo.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct ComboEvent {
+    pub attacker: Entity,
+    pub victim: Entity,
+    /// The number of consecutive hits on the victim (including this hit).
+    pub combo: u32,
 }
 
 pub struct CombatPlugin;
 
 impl Plugin for CombatPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(
-            Update,
-            (
-                combat_system,
-                update_last_attack_on_item_switch,
-                on_hand_swing,
-            ),
-        );
+        if !app.is_plugin_added::<KnockbackPlugin>() {
+            app.add_plugins(KnockbackPlugin);
+        }
+
+        if !app.is_plugin_added::<RateLimitPlugin>() {
+            app.add_plugins(RateLimitPlugin);
+        }
+
+        app.add_event::<AttackEvent>()
+            .add_event::<ChargeStartEvent>()
+            .add_event::<ChargeReleaseEvent>()
+            .add_event::<ParryEvent>()
+            .add_event::<ComboEvent>()
+            .init_resource::<CombatFormulas>()
+            .init_resource::<CustomItems>()
+            .init_resource::<Weather>()
+            .add_systems(
+                Update,
+                (
+                    record_position_history.before(combat_system),
+                    combat_system,
+                    update_last_attack_on_item_switch,
+                    // The swing is sent after the attack, so it can not reset the cooldown before the attack is handled.
+                    on_hand_swing.after(combat_system),
+                    trident::start_trident_charge,
+                    trident::release_trident,
+                    trident::trident_entity_hit,
+                    trident::trident_block_hit,
+                    trident::return_tridents,
+                    trident::despawn_lightning,
+                    charge::start_weapon_charge,
+                    charge::update_weapon_charges
+                        .after(charge::start_weapon_charge)
+                        .before(combat_system),
+                    shield::start_blocking.before(combat_system),
+                    shield::stop_blocking.before(combat_system),
+                    shield::end_stagger,
+                    reset_combo_on_hit.after(combat_system),
+                ),
+            )
+            .add_systems(PostUpdate, friendly_fire::pass_through_allies);
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn combat_system(
+    mut commands: Commands,
     mut query: Query<CombatQuery>,
+    culled: Query<&CulledEntities>,
+    mut layers: Query<&mut ChunkLayer>,
     mut damage_event_writer: EventWriter<DamageEvent>,
     mut start_burn_event_writer: EventWriter<StartBurningEvent>,
+    mut knockback_writer: EventWriter<ApplyKnockbackEvent>,
+    mut attack_writer: EventWriter<AttackEvent>,
+    mut parry_writer: EventWriter<ParryEvent>,
+    mut combo_writer: EventWriter<ComboEvent>,
     mut sprinting_events: EventReader<SprintEvent>,
     mut sneaking_events: EventReader<SneakEvent>,
     mut interact_entity_events: EventReader<InteractEntityEvent>,
+    mut pvp_check: PvpCheck,
+    formulas: Res<CombatFormulas>,
+    custom_items: Res<CustomItems>,
+    lag_compensation: Option<Res<LagCompensationConfig>>,
+    mut rate_limits: RateLimits,
 ) {
+    let mut sweeps = vec![];
+
     for &SprintEvent { client, state } in sprinting_events.read() {
         if let Ok(mut client) = query.get_mut(client) {
             client.state.sprinting = state == SprintState::Start;
+
+            if state == SprintState::Start {
+                client.state.sprint_hit = false;
+            }
         }
     }
 
@@ -411,19 +825,106 @@ fn combat_system(
             continue;
         }
 
+        if !rate_limits.check(attacker_ent, RateLimitKind::Attack) {
+            continue;
+        }
+
         let Ok([mut attacker, mut victim]) = query.get_many_mut([attacker_ent, victim_ent]) else {
             continue;
         };
 
+        // Attacks of and on spectators pass through.
+        if attacker.spectating || victim.spectating {
+            continue;
+        }
+
+        if attacker.game_mode == Some(&GameMode::Spectator)
+            && !attacker.state.combat_config.spectator_game_mode_attacks
+        {
+            continue;
+        }
+
+        // Entities that are hidden from the attacker can not be hit.
+        if attacker
+            .culled
+            .is_some_and(|culled| culled.contains(victim_ent))
+        {
+            continue;
+        }
+
+        // Every attack resets the cooldown, even if it does not land.
+        let last_attack = std::mem::replace(&mut attacker.state.last_attack, Instant::now());
+
+        let victim_position = match &lag_compensation {
+            Some(config) => compensated_position(
+                victim.position_history,
+                attacker.ping,
+                config,
+                victim.position.0,
+            ),
+            None => victim.position.0,
+        };
+
+        let weapon_reach = match (attacker.held_item, attacker.inventory) {
+            (Some(held_item), Some(inventory)) => {
+                inventory.slot(held_item.slot()).reach(&custom_items)
+            }
+            _ => DEFAULT_REACH,
+        } as f64;
+
+        let attack = AttackEvent::new(
+            attacker_ent,
+            victim_ent,
+            &attacker,
+            victim
+                .hitbox
+                .get()
+                .translate(victim_position - victim.position.0),
+            weapon_reach,
+        );
+        attack_writer.send(attack);
+
         if attacker.state.last_hit.elapsed() < attacker.state.combat_config.hit_cooldown {
             continue;
         }
 
+        if attacker.client.is_some()
+            && victim.client.is_some()
+            && !pvp_check.check(attacker_ent, victim_ent)
+        {
+            continue;
+        }
+
+        if victim
+            .spawn_protection
+            .is_some_and(|protection| protection.is_active())
+        {
+            continue;
+        }
+
+        // Attacking someone ends the spawn protection of the attacker.
+        if attacker
+            .spawn_protection
+            .is_some_and(|protection| protection.break_on_attack)
+        {
+            commands.entity(attacker_ent).remove::<SpawnProtection>();
+        }
+
         let attacker_config = &attacker.state.combat_config;
         let victim_config = &victim.state.combat_config;
 
+        // The sprint can also be stopped by other plugins (e.g. if the player is too hungry).
+        let sprinting = attacker.state.sprinting
+            && !attacker
+                .flags
+                .as_ref()
+                .is_some_and(|flags| !flags.sprinting());
+
+        let sprint_hit =
+            sprinting && !(attacker_config.sprint_hit_reset && attacker.state.sprint_hit);
+
         let attacker_state = match (
-            attacker.state.sprinting,
+            sprint_hit,
             attacker.state.sneaking,
             attacker.falling_state.falling,
         ) {
@@ -444,18 +945,58 @@ fn combat_system(
             _ => PlayerMovementState::None,
         };
 
-        let direction = (victim.position.0 - attacker.position.0)
-            .normalize()
-            .as_vec3();
+        // Weapons with a longer (or shorter) reach move the limit by the difference to the vanilla reach.
+        if lag_compensation
+            .as_ref()
+            .and_then(|config| config.max_reach)
+            .is_some_and(|max_reach| attack.normalized_reach() > max_reach)
+        {
+            continue;
+        }
+
+        // The hit is parried if the victim started blocking right before it (facing the attacker).
+        if let Some(parry) = &victim_config.parry {
+            let towards_attacker = (attacker.position.0 - victim_position).as_vec3();
+
+            if victim.state.blocking
+                && victim.state.last_block_start.elapsed() <= parry.window
+                && look_direction_horizontal(victim.look).dot(towards_attacker) > 0.0
+            {
+                victim.statuses.play_shield_block();
+                attacker.state.combo = 0;
+                attacker.state.combo_victim = None;
+
+                shield::stagger(
+                    &mut commands,
+                    attacker_ent,
+                    &mut attacker.state,
+                    &mut attacker.attributes,
+                    parry,
+                );
+
+                parry_writer.send(ParryEvent {
+                    player: victim_ent,
+                    attacker: attacker_ent,
+                });
+                continue;
+            }
+        }
+
+        let direction = knockback_direction(attacker.position.0, victim_position, attacker.look);
 
         let weapon = match (attacker.held_item, attacker.inventory) {
             (Some(held_item), Some(inventory)) => inventory.slot(held_item.slot()),
-            _ => return,
+            _ => continue,
         };
 
-        let knockback_xz = attacker_config
+        let mut knockback_xz = attacker_config
             .horizontal_knockback
             .current(&attacker_state);
+
+        if sprint_hit {
+            knockback_xz *= attacker_config.w_tap_bonus;
+        }
+
         let knockback_y = attacker_config.vertical_knockback.current(&attacker_state);
 
         // TODO: set based on tick rate
@@ -467,27 +1008,105 @@ fn combat_system(
         );
 
         let weapon_echants = weapon.enchantments();
-        let mut base_damage = weapon.item.attack_damage(&attacker_config.combat_system);
-
-        if let Some(cooldown_multiplier) = &attacker_config.attack_cooldown_multiplier {
-            base_damage = base_damage
-                * (attacker_config.damage_cooldown_formula_base_damage)(
-                    weapon.item.attack_speed(),
-                    attacker.state.last_attack,
-                )
-                * cooldown_multiplier;
-        }
+        let sweeping_level = weapon_echants
+            .get(&Enchantment::SweepingEdge)
+            .copied()
+            .unwrap_or(0);
+        let base_damage = weapon.attack_damage(&attacker_config.combat_system, &custom_items);
 
         let EnchantmentValues {
             mut damage,
             mut knockback,
             burn,
-        } = apply_enchantments(
-            base_damage,
-            knockback,
-            weapon_echants,
-            &attacker_config.enchantment_config,
-        );
+        } = match custom_items.get(weapon) {
+            Some(custom_item) if !custom_item.enchantment_formulas.is_empty() => {
+                apply_enchantments(
+                    base_damage,
+                    knockback,
+                    weapon_echants,
+                    &attacker_config
+                        .enchantment_config
+                        .with_overrides(&custom_item.enchantment_formulas),
+                    &formulas,
+                )
+            }
+            _ => apply_enchantments(
+                base_damage,
+                knockback,
+                weapon_echants,
+                &attacker_config.enchantment_config,
+                &formulas,
+            ),
+        };
+
+        // The attack speed the client uses for the cooldown (see `update_last_attack_on_item_switch`).
+        let attack_speed = attacker_config
+            .attack_cooldown_multiplier
+            .map(|cooldown_multiplier| weapon.attack_speed(&custom_items) * cooldown_multiplier);
+
+        let charge = match attack_speed {
+            Some(attack_speed) => calculations::attack_charge(attack_speed, last_attack),
+            None => 1.0,
+        };
+
+        // The base damage and the damage of the enchantments are scaled separately by the cooldown.
+        let mut enchantment_damage = damage - base_damage;
+        let mut base_damage = base_damage;
+
+        if let Some(attack_speed) = attack_speed {
+            if let Some(cooldown_formula) = formulas
+                .attack_cooldown
+                .resolve_or_warn(Some(&attacker_config.damage_cooldown_formula_base_damage))
+            {
+                base_damage *= cooldown_formula(attack_speed, last_attack);
+            }
+
+            if let Some(cooldown_formula) = formulas
+                .attack_cooldown
+                .resolve_or_warn(Some(&attacker_config.damage_cooldown_enchantment_formula))
+            {
+                enchantment_damage *= cooldown_formula(attack_speed, last_attack);
+            }
+        }
+
+        damage = base_damage + enchantment_damage;
+
+        // The charge is only used by attacks with the charged weapon.
+        if let Some(charged_attack) = attacker.charged_attack.filter(|charged_attack| {
+            attacker
+                .held_item
+                .is_some_and(|held_item| held_item.slot() == charged_attack.slot)
+        }) {
+            let (damage_multiplier, knockback_multiplier) = charged_attack.multipliers(&formulas);
+
+            damage *= damage_multiplier;
+            knockback *= knockback_multiplier;
+
+            commands.entity(attacker_ent).remove::<ChargedAttack>();
+        }
+
+        // Hits on the same victim continue the combo, until the attacker is hit or the combo times out.
+        let combo = if attacker.state.combo_victim == Some(victim_ent)
+            && attacker.state.last_hit.elapsed() <= attacker_config.combo_timeout
+        {
+            attacker.state.combo + 1
+        } else {
+            1
+        };
+
+        if let Some(combo_formula) = formulas
+            .combo_curve
+            .resolve_or_warn(attacker_config.combo_damage_curve.as_deref())
+        {
+            damage *= combo_formula(combo);
+        }
+
+        if let Some(combo_formula) = formulas
+            .combo_curve
+            .resolve_or_warn(attacker_config.combo_knockback_curve.as_deref())
+        {
+            knockback *= combo_formula(combo);
+        }
 
         if let Some((burn_time, burn_dps)) = burn {
             let burn_event = StartBurningEvent {
@@ -507,52 +1126,81 @@ fn combat_system(
             start_burn_event_writer.send(burn_event);
         }
 
-        // let enchantment_extra_dmg = damage - base_damage;
-
-        // TODO: add this back
-
-        // TODO: im not sure if this is actually applied after the base damage.
-        // if let Some(cooldown_multiplier) = &attacker_config.attack_cooldown_multiplier {
-        //     damage = damage
-        //         * (attacker_config.damage_cooldown_enchantment_formula)(
-        //             weapon.item.attack_speed(),
-        //             attacker.state.last_attack,
-        //         )
-        //         * cooldown_multiplier;
-        // }
-
         damage *= attacker_config.damage_multiplier.current(&attacker_state);
 
-        damage = damage_after_armor(
-            damage,
-            victim.equipment.armor_points() * victim_config.armor_points_multiplier,
-            victim.equipment.armor_toughness() * victim_config.armor_toughness_multiplier,
-        );
+        // Attacks that are not charged enough can not crit (only with an attack cooldown).
+        let can_crit = attack_speed.is_none() || charge >= attacker_config.min_charge_for_crit;
+        let mut critical_hit = false;
+
+        if let Some(armor_formula) = formulas
+            .armor
+            .resolve_or_warn(Some(&victim_config.armor_formula))
+        {
+            damage = armor_formula(
+                damage,
+                victim.equipment.armor_points(&custom_items)
+                    * victim_config.armor_points_multiplier,
+                victim.equipment.armor_toughness(&custom_items)
+                    * victim_config.armor_toughness_multiplier,
+            );
+        }
 
         damage *= victim_config.damage_taken_multiplier.current(&victim_state);
 
-        if let (Some(attacker_team), Some(victim_team)) = (attacker.team, victim.team) {
-            if attacker_team == victim_team {
-                damage *= attacker_config.friendly_fire_damage_multiplier;
-                damage *= victim_config.friendly_fire_damage_taken_multiplier;
-            }
+        if attacker_config.is_friendly(attacker.team, victim.team) {
+            damage *= attacker_config.friendly_fire_damage_multiplier;
+            damage *= victim_config.friendly_fire_damage_taken_multiplier;
         }
 
-        if attacker_config
-            .random_critical_hit_chance
-            .current(&attacker_state)
-            + if attacker.falling_state.falling {
-                attacker_config.critical_hit_chance_falling
-            } else {
-                0.0
-            }
-            > rand::random::<f32>()
+        if can_crit
+            && attacker_config
+                .random_critical_hit_chance
+                .current(&attacker_state)
+                + if attacker.falling_state.falling {
+                    attacker_config.critical_hit_chance_falling
+                } else {
+                    0.0
+                }
+                > rand::random::<f32>()
         {
+            critical_hit = true;
             damage *= attacker_config.critical_hit_damage_multiplier;
+
+            if let Ok(mut layer) = layers.get_mut(victim.layer.0) {
+                effects::spawn_critical_hit_particles(&mut layer, victim.hitbox.get());
+            }
         }
 
-        let knockback_resistance = victim.equipment.knockback_resistance()
-            * victim_config.armor_knockback_resistance_multiplier;
+        // Vanilla only sweeps with a charged sword while standing on the ground.
+        let can_sweep = attack_speed.is_some()
+            && attacker_config
+                .min_charge_for_sweep
+                .is_some_and(|min_charge| charge >= min_charge)
+            && !critical_hit
+            && !sprint_hit
+            && !attacker.falling_state.falling
+            && is_sword(weapon.item);
+
+        if can_sweep {
+            let ratio = sweeping_level as f32 / (sweeping_level as f32 + 1.0);
+
+            sweeps.push(SweepAttack {
+                attacker: attacker_ent,
+                victim: victim_ent,
+                layer: attacker.layer.0,
+                attacker_position: attacker.position.0,
+                area: {
+                    let hitbox = victim.hitbox.get();
+                    let grow = DVec3::new(1.0, 0.25, 1.0);
+                    Aabb::new(hitbox.min() - grow, hitbox.max() + grow)
+                },
+                direction: look_direction_horizontal(attacker.look),
+                damage: 1.0 + ratio * base_damage,
+                weapon: weapon.clone(),
+            });
+        }
+
+        let knockback_resistance = victim.knockback_resistance(&formulas, &custom_items);
 
         knockback.x *= 1.0 - knockback_resistance;
         knockback.z *= 1.0 - knockback_resistance;
@@ -571,24 +1219,172 @@ fn combat_system(
         knockback.z *= knockback_received_xz_mult;
         knockback.y *= knockback_received_y_mult;
 
-        if let Some(mut client) = victim.client {
-            client.set_velocity(knockback);
-        } else {
-            victim.velocity.0 += knockback;
+        // A broken formula or config value should not send the victim to NaN.
+        if !knockback.is_finite() {
+            knockback = Vec3::ZERO;
+        }
+
+        if !damage.is_finite() {
+            damage = 0.0;
+        }
+
+        knockback_writer.send(ApplyKnockbackEvent::add(victim_ent, knockback));
+
+        if sprint_hit {
+            attacker.state.sprint_hit = true;
+
+            if attacker.state.combat_config.stop_sprint_on_hit {
+                attacker.state.sprinting = false;
+
+                if let Some(flags) = attacker.flags.as_mut() {
+                    flags.set_sprinting(false);
+                }
+            }
         }
 
         let now = Instant::now();
 
         attacker.state.last_hit = now;
         attacker.state.last_attack = now;
+        attacker.state.combo = combo;
+        attacker.state.combo_victim = Some(victim_ent);
         victim.state.last_got_hit = now;
 
+        combo_writer.send(ComboEvent {
+            attacker: attacker_ent,
+            victim: victim_ent,
+            combo,
+        });
+
         damage_event_writer.send(DamageEvent {
             victim: victim_ent,
             attacker: Some(attacker_ent),
             damage,
+            damage_type: DamageType::Melee,
+            weapon: Some(weapon.clone()),
+            projectile: None,
+            direct_source: None,
+            area_of_effect: false,
         });
     }
+
+    for sweep in sweeps {
+        if let Ok(mut layer) = layers.get_mut(sweep.layer) {
+            effects::spawn_sweep_particles(&mut layer, sweep.attacker_position, sweep.direction);
+            layer.play_sound(
+                Sound::EntityPlayerAttackSweep,
+                SoundCategory::Player,
+                sweep.attacker_position,
+                1.0,
+                1.0,
+            );
+        }
+
+        let hidden = culled.get(sweep.attacker).ok();
+
+        for mut target in query.iter_mut() {
+            if target.entity == sweep.attacker
+                || target.entity == sweep.victim
+                || target.layer.0 != sweep.layer
+                || !target.hitbox.get().intersects(sweep.area)
+                || target.position.0.distance_squared(sweep.attacker_position)
+                    > SWEEP_RANGE * SWEEP_RANGE
+                || target.spectating
+                || hidden.is_some_and(|hidden| hidden.contains(target.entity))
+                || target
+                    .spawn_protection
+                    .is_some_and(|protection| protection.is_active())
+            {
+                continue;
+            }
+
+            let config = &target.state.combat_config;
+            let mut damage = sweep.damage;
+
+            if let Some(armor_formula) = formulas.armor.resolve_or_warn(Some(&config.armor_formula))
+            {
+                damage = armor_formula(
+                    damage,
+                    target.equipment.armor_points(&custom_items) * config.armor_points_multiplier,
+                    target.equipment.armor_toughness(&custom_items)
+                        * config.armor_toughness_multiplier,
+                );
+            }
+
+            // TODO: set based on tick rate
+            let knockback = sweep.direction
+                * SWEEP_KNOCKBACK
+                * 20.0
+                * (1.0 - target.knockback_resistance(&formulas, &custom_items));
+
+            knockback_writer.send(ApplyKnockbackEvent::add(target.entity, knockback));
+
+            target.state.last_got_hit = Instant::now();
+
+            damage_event_writer.send(DamageEvent {
+                victim: target.entity,
+                attacker: Some(sweep.attacker),
+                damage,
+                damage_type: DamageType::Melee,
+                weapon: Some(sweep.weapon.clone()),
+                projectile: None,
+                direct_source: None,
+                area_of_effect: true,
+            });
+        }
+    }
+}
+
+/// A sweep attack that hits the entities around the victim.
+struct SweepAttack {
+    attacker: Entity,
+    victim: Entity,
+    layer: Entity,
+    attacker_position: DVec3,
+    /// The entities inside this area are hit.
+    area: Aabb,
+    direction: Vec3,
+    damage: f32,
+    weapon: ItemStack,
+}
+
+fn is_sword(item: ItemKind) -> bool {
+    matches!(
+        item,
+        ItemKind::WoodenSword
+            | ItemKind::StoneSword
+            | ItemKind::IronSword
+            | ItemKind::GoldenSword
+            | ItemKind::DiamondSword
+            | ItemKind::NetheriteSword
+    )
+}
+
+/// The horizontal direction the victim is knocked back in.
+///
+/// If the attacker and the victim are (almost) at the same position, the direction
+/// the attacker is looking at is used instead (like in vanilla).
+fn knockback_direction(attacker: DVec3, victim: DVec3, attacker_look: &Look) -> Vec3 {
+    let offset = (victim - attacker).as_vec3();
+    let horizontal = Vec3::new(offset.x, 0.0, offset.z);
+
+    if horizontal.length_squared() > 1.0e-4 && horizontal.is_finite() {
+        return offset.normalize();
+    }
+
+    look_direction_horizontal(attacker_look)
+}
+
+/// The horizontal direction of the yaw of an entity.
+fn look_direction_horizontal(look: &Look) -> Vec3 {
+    let yaw = look.yaw.to_radians();
+    let direction = Vec3::new(-yaw.sin(), 0.0, yaw.cos());
+
+    if direction.is_finite() {
+        direction
+    } else {
+        Vec3::ZERO
+    }
 }
 
 // TODO: new combat system is has not been tested i think
@@ -598,6 +1394,7 @@ fn combat_system(
 fn update_last_attack_on_item_switch(
     mut query: Query<CombatQuery>,
     mut events: EventReader<UpdateSelectedSlotEvent>,
+    custom_items: Res<CustomItems>,
 ) {
     for event in events.read() {
         if let Ok(mut combat_query) = query.get_mut(event.client) {
@@ -610,7 +1407,7 @@ fn update_last_attack_on_item_switch(
                     (combat_query.held_item, combat_query.inventory)
                 {
                     let held_item = inventory.slot(held_item.slot());
-                    let attack_speed = held_item.item.attack_speed() * cooldown_multiplier;
+                    let attack_speed = held_item.attack_speed(&custom_items) * cooldown_multiplier;
 
                     combat_query
                         .attributes
@@ -631,7 +1428,7 @@ fn update_last_attack_on_item_switch(
                     &state.state.combat_config.attack_cooldown_multiplier
                 {
                     let held_item = inventory.slot(held_item.slot());
-                    let attack_speed = held_item.item.attack_speed() * cooldown_multiplier;
+                    let attack_speed = held_item.attack_speed(&custom_items) * cooldown_multiplier;
 
                     state
                         .attributes
@@ -642,6 +1439,22 @@ fn update_last_attack_on_item_switch(
     }
 }
 
+/// Getting hit by another entity (e.g. a melee hit or a projectile) ends the combo of the player.
+fn reset_combo_on_hit(mut query: Query<&mut CombatState>, mut events: EventReader<DamageEvent>) {
+    for event in events.read() {
+        if event.attacker.is_none() {
+            continue;
+        }
+
+        if let Ok(mut state) = query.get_mut(event.victim) {
+            if state.combo_victim.is_some() {
+                state.combo = 0;
+                state.combo_victim = None;
+            }
+        }
+    }
+}
+
 fn on_hand_swing(mut query: Query<CombatQuery>, mut events: EventReader<HandSwingEvent>) {
     for event in events.read() {
         if let Ok(mut combat_query) = query.get_mut(event.client) {
@@ -649,3 +1462,34 @@ fn on_hand_swing(mut query: Query<CombatQuery>, mut events: EventReader<HandSwin
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn look(yaw: f32) -> Look {
+        Look { yaw, pitch: 0.0 }
+    }
+
+    #[test]
+    fn knockback_away_from_the_attacker() {
+        let direction = knockback_direction(DVec3::ZERO, DVec3::new(2.0, 0.0, 0.0), &look(0.0));
+        assert!(direction.abs_diff_eq(Vec3::X, 1.0e-6));
+    }
+
+    #[test]
+    fn knockback_uses_the_look_on_the_same_position() {
+        let position = DVec3::new(1.0, 64.0, 1.0);
+        let direction = knockback_direction(position, position, &look(0.0));
+        assert!(direction.abs_diff_eq(Vec3::Z, 1.0e-6));
+    }
+
+    #[test]
+    fn knockback_uses_the_look_on_nan_positions() {
+        let direction = knockback_direction(DVec3::ZERO, DVec3::NAN, &look(90.0));
+        assert!(direction.abs_diff_eq(Vec3::NEG_X, 1.0e-6));
+
+        let direction = knockback_direction(DVec3::ZERO, DVec3::NAN, &look(f32::NAN));
+        assert_eq!(direction, Vec3::ZERO);
+    }
+}
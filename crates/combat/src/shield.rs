@@ -0,0 +1,163 @@
+//! Blocking with shields and parrying (blocking right before a melee hit lands).
+
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use valence::{
+    entity::{
+        attributes::{EntityAttribute, EntityAttributes},
+        living::LivingFlags,
+    },
+    event_loop::PacketEvent,
+    interact_item::InteractItemEvent,
+    inventory::{player_inventory::PlayerInventory, HeldItem},
+    prelude::*,
+    protocol::packets::play::{player_action_c2s::PlayerAction, PlayerActionC2s},
+    uuid::Uuid,
+};
+
+use crate::CombatState;
+
+/// The id of the movement speed modifier of staggered entities.
+const STAGGER_MODIFIER: Uuid = Uuid::from_u128(0x5b1c_63a4_2f0e_4d6b_9a87_3c1e_0d4f_a9b2);
+
+/// The configuration of parrying.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ParryConfig {
+    /// A melee hit is parried if the player started blocking at most this long before the hit.
+    pub window: Duration,
+    /// How long the attacker of a parried hit is staggered.
+    pub stagger_duration: Duration,
+    /// The movement speed of a staggered attacker is reduced by this fraction (0.0 - 1.0).
+    pub stagger_slowness: f32,
+}
+
+impl Default for ParryConfig {
+    fn default() -> Self {
+        Self {
+            window: Duration::from_millis(200),
+            stagger_duration: Duration::from_millis(1000),
+            stagger_slowness: 0.5,
+        }
+    }
+}
+
+/// An event that is emitted when a player parries a melee hit.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct ParryEvent {
+    /// The player that parried the hit.
+    pub player: Entity,
+    /// The attacker, it is staggered (see [`Staggered`]).
+    pub attacker: Entity,
+}
+
+/// Attached to entities that are staggered after a hit was parried,
+/// their movement speed is reduced until the stagger ends.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct Staggered {
+    pub until: Instant,
+}
+
+/// Start blocking once the player uses a shield.
+///
+/// Other players see the raised shield through the [`LivingFlags`].
+pub(crate) fn start_blocking(
+    mut events: EventReader<InteractItemEvent>,
+    mut query: Query<(
+        &mut CombatState,
+        &Inventory,
+        &HeldItem,
+        Option<&mut LivingFlags>,
+    )>,
+) {
+    for event in events.read() {
+        let Ok((mut state, inventory, held_item, flags)) = query.get_mut(event.client) else {
+            continue;
+        };
+
+        let slot = match event.hand {
+            Hand::Main => held_item.slot(),
+            Hand::Off => PlayerInventory::SLOT_OFFHAND,
+        };
+
+        if inventory.slot(slot).item != ItemKind::Shield || state.blocking {
+            continue;
+        }
+
+        state.blocking = true;
+        state.last_block_start = Instant::now();
+
+        if let Some(mut flags) = flags {
+            flags.set_using_item(true);
+            flags.set_off_hand_active(event.hand == Hand::Off);
+        }
+    }
+}
+
+/// Stop blocking once the player releases the use item button.
+pub(crate) fn stop_blocking(
+    mut packets: EventReader<PacketEvent>,
+    mut query: Query<(&mut CombatState, Option<&mut LivingFlags>)>,
+) {
+    for packet in packets.read() {
+        let Some(pkt) = packet.decode::<PlayerActionC2s>() else {
+            continue;
+        };
+
+        if pkt.action != PlayerAction::ReleaseUseItem {
+            continue;
+        }
+
+        let Ok((mut state, flags)) = query.get_mut(packet.client) else {
+            continue;
+        };
+
+        if !state.blocking {
+            continue;
+        }
+
+        state.blocking = false;
+
+        if let Some(mut flags) = flags {
+            flags.set_using_item(false);
+            flags.set_off_hand_active(false);
+        }
+    }
+}
+
+/// Slow down the attacker and reset its attack cooldown.
+pub(crate) fn stagger(
+    commands: &mut Commands,
+    attacker: Entity,
+    state: &mut CombatState,
+    attributes: &mut EntityAttributes,
+    config: &ParryConfig,
+) {
+    state.last_attack = Instant::now();
+    state.last_hit = Instant::now();
+
+    attributes.set_multiply_total_modifier(
+        EntityAttribute::GenericMovementSpeed,
+        STAGGER_MODIFIER,
+        -config.stagger_slowness.clamp(0.0, 1.0) as f64,
+    );
+
+    commands.entity(attacker).insert(Staggered {
+        until: Instant::now() + config.stagger_duration,
+    });
+}
+
+pub(crate) fn end_stagger(
+    mut commands: Commands,
+    mut query: Query<(Entity, &Staggered, &mut EntityAttributes)>,
+) {
+    for (entity, staggered, mut attributes) in query.iter_mut() {
+        if staggered.until > Instant::now() {
+            continue;
+        }
+
+        attributes.remove_modifier(EntityAttribute::GenericMovementSpeed, STAGGER_MODIFIER);
+        commands.entity(entity).remove::<Staggered>();
+    }
+}
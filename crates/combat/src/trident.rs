@@ -0,0 +1,524 @@
+//! Throwing tridents and the trident enchantments (loyalty, riptide and channeling).
+
+use std::time::{Duration, Instant};
+
+use bevy_ecs::query::QueryData;
+use physics::{
+    projectile::{look_direction, Projectile},
+    Acceleration, BlockCollisionConfig, Drag, EntityBlockCollisionEvent, EntityCollisionConfig,
+    EntityEntityCollisionEvent, StopOnBlockCollision,
+};
+use serde::{Deserialize, Serialize};
+pub use utils::weather::Weather;
+use utils::{
+    damage::{DamageEvent, DamageType},
+    enchantments::{Enchantment, ItemStackEnchantmentsExt},
+    inventory::InventoryExt,
+    pvp::PvpCheck,
+    spectator::Spectating,
+};
+use valence::{
+    block::{PropName, PropValue},
+    entity::{
+        entity::NoGravity, lightning::LightningEntityBundle, trident::TridentEntityBundle,
+        EntityKind, Velocity,
+    },
+    event_loop::PacketEvent,
+    interact_item::InteractItemEvent,
+    inventory::HeldItem,
+    prelude::*,
+    protocol::{
+        packets::play::{player_action_c2s::PlayerAction, PlayerActionC2s},
+        sound::SoundCategory,
+        Sound,
+    },
+};
+
+use crate::{formulas::CombatFormulas, CombatState, Team};
+
+/// The minimum time a trident has to be charged before it can be thrown (vanilla: 10 ticks).
+const MIN_CHARGE_TIME: Duration = Duration::from_millis(500);
+/// The distance at which a trident is picked up.
+const PICKUP_DISTANCE: f64 = 1.5;
+/// How long a trident without loyalty stays in the block it hit before it despawns (vanilla: 1200 ticks).
+const STUCK_LIFETIME: Duration = Duration::from_secs(60);
+/// How long a lightning bolt summoned by channeling is visible.
+const LIGHTNING_DURATION: Duration = Duration::from_secs(1);
+
+/// The configuration for thrown tridents.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TridentConfig {
+    /// The speed (in blocks per second) of a thrown trident.
+    pub throw_speed: f32,
+    /// The gravity (in blocks per second squared) of a thrown trident.
+    pub gravity: f32,
+    /// The damage a thrown trident deals.
+    pub damage: f32,
+}
+
+impl Default for TridentConfig {
+    fn default() -> Self {
+        Self {
+            // TODO: set based on tick rate
+            throw_speed: 2.5 * 20.0,
+            gravity: 20.0,
+            damage: 8.0,
+        }
+    }
+}
+
+/// Attached to players that are currently charging a trident.
+#[derive(Component)]
+pub struct TridentCharge(pub Instant);
+
+/// Attached to thrown tridents.
+#[derive(Component)]
+pub struct ThrownTrident {
+    /// The player that threw the trident.
+    pub owner: Entity,
+    /// The trident item, this will be given back to the player that picks up the trident.
+    pub item: ItemStack,
+    /// The trident already hit an entity or a block.
+    pub hit: bool,
+    /// The trident is flying back to its owner (loyalty).
+    pub returning: bool,
+    /// Thrown in creative mode, the owner kept the trident, so it is not given back when it is picked up.
+    pub creative: bool,
+    /// The slot the trident was thrown from, the trident is put back into it if it is still empty.
+    slot: u16,
+    stuck_since: Option<Instant>,
+    damage: f32,
+    loyalty: u32,
+    channeling: u32,
+}
+
+/// A lightning bolt that was summoned by channeling.
+#[derive(Component)]
+struct ChannelingLightning(Instant);
+
+#[derive(QueryData)]
+#[query_data(mutable)]
+struct TridentQuery {
+    client: &'static mut Client,
+    inventory: &'static mut Inventory,
+    held_item: &'static HeldItem,
+    position: &'static Position,
+    look: &'static Look,
+    layer: &'static EntityLayerId,
+    hitbox: &'static Hitbox,
+    game_mode: &'static GameMode,
+    state: &'static CombatState,
+    charge: Option<&'static TridentCharge>,
+}
+
+pub(crate) fn start_trident_charge(
+    mut commands: Commands,
+    mut events: EventReader<InteractItemEvent>,
+    query: Query<(&Inventory, &HeldItem), With<CombatState>>,
+) {
+    for event in events.read() {
+        let Ok((inventory, held_item)) = query.get(event.client) else {
+            continue;
+        };
+
+        if inventory.slot(held_item.slot()).item == ItemKind::Trident {
+            commands
+                .entity(event.client)
+                .insert(TridentCharge(Instant::now()));
+        }
+    }
+}
+
+/// Throws the trident or launches the player (riptide) once the player releases the use item button.
+pub(crate) fn release_trident(
+    mut commands: Commands,
+    mut packets: EventReader<PacketEvent>,
+    mut query: Query<TridentQuery>,
+    layers: Query<&ChunkLayer>,
+    formulas: Res<CombatFormulas>,
+    weather: Res<Weather>,
+) {
+    for packet in packets.read() {
+        let Some(pkt) = packet.decode::<PlayerActionC2s>() else {
+            continue;
+        };
+
+        if pkt.action != PlayerAction::ReleaseUseItem {
+            continue;
+        }
+
+        let Ok(mut player) = query.get_mut(packet.client) else {
+            continue;
+        };
+
+        let Some(charge) = player.charge else {
+            continue;
+        };
+
+        commands.entity(packet.client).remove::<TridentCharge>();
+
+        if charge.0.elapsed() < MIN_CHARGE_TIME {
+            continue;
+        }
+
+        let slot = player.held_item.slot();
+        let trident = player.inventory.slot(slot).clone();
+
+        if trident.item != ItemKind::Trident {
+            continue;
+        }
+
+        let config = &player.state.combat_config;
+        let enchantments = trident.enchantments();
+        let level = |enchantment: Enchantment| enchantments.get(&enchantment).copied().unwrap_or(0);
+
+        let direction = look_direction(player.look);
+
+        // Tridents with riptide can not be thrown.
+        if level(Enchantment::Riptide) > 0 {
+            let in_water = layers.get(player.layer.0).is_ok_and(|layer| {
+                utils::intersects_block(&player.hitbox.get(), layer, |state| {
+                    state.to_kind() == BlockKind::Water
+                        || state.get(PropName::Waterlogged) == Some(PropValue::True)
+                })
+            });
+
+            if !in_water && !weather.raining {
+                continue;
+            }
+
+            if let Some(riptide_formula) = formulas
+                .enchantment_riptide
+                .resolve_or_warn(config.enchantment_config.riptide_formula.as_deref())
+            {
+                let velocity = riptide_formula(direction, level(Enchantment::Riptide));
+                let position = player.position.0;
+
+                player.client.set_velocity(velocity);
+                player.client.play_sound(
+                    Sound::ItemTridentRiptide1,
+                    SoundCategory::Player,
+                    position,
+                    1.0,
+                    1.0,
+                );
+            }
+
+            continue;
+        }
+
+        let Some(trident_config) = &config.trident else {
+            continue;
+        };
+
+        let loyalty = if formulas
+            .enchantment_loyalty
+            .resolve_or_warn(config.enchantment_config.loyalty_formula.as_deref())
+            .is_some()
+        {
+            level(Enchantment::Loyalty)
+        } else {
+            0
+        };
+
+        let channeling = if config.enchantment_config.channeling_damage.is_some() {
+            level(Enchantment::Channeling)
+        } else {
+            0
+        };
+
+        let eye_position = player.position.0 + DVec3::new(0.0, 1.62, 0.0);
+
+        commands.spawn((
+            TridentEntityBundle {
+                position: Position(eye_position + direction.as_dvec3()),
+                look: *player.look,
+                velocity: Velocity(direction * trident_config.throw_speed),
+                entity_no_gravity: NoGravity(true),
+                layer: *player.layer,
+                ..Default::default()
+            },
+            Acceleration(Vec3::new(0.0, -trident_config.gravity, 0.0)),
+            Drag(Vec3::splat(0.99 / 20.0)),
+            EntityCollisionConfig::default(),
+            BlockCollisionConfig::default(),
+            StopOnBlockCollision::all(),
+            Projectile {
+                owner: Some(packet.client),
+            },
+            ThrownTrident {
+                owner: packet.client,
+                item: trident,
+                hit: false,
+                returning: false,
+                creative: *player.game_mode == GameMode::Creative,
+                slot,
+                stuck_since: None,
+                damage: trident_config.damage,
+                loyalty,
+                channeling,
+            },
+        ));
+
+        player.client.play_sound(
+            Sound::ItemTridentThrow,
+            SoundCategory::Player,
+            eye_position,
+            1.0,
+            1.0,
+        );
+
+        if *player.game_mode != GameMode::Creative {
+            player.inventory.set_slot(slot, ItemStack::EMPTY);
+        }
+    }
+}
+
+/// Tridents go through the same checks as melee attacks (hit cooldown, PvP and friendly fire),
+/// denied hits pass through the entity.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn trident_entity_hit(
+    mut commands: Commands,
+    mut events: EventReader<EntityEntityCollisionEvent>,
+    mut tridents: Query<(&mut ThrownTrident, &mut Velocity, &EntityLayerId)>,
+    positions: Query<&Position>,
+    mut combatants: Query<(&mut CombatState, Option<&Team>)>,
+    targets: Query<(Has<Client>, Has<Spectating>)>,
+    mut pvp_check: PvpCheck,
+    weather: Res<Weather>,
+    mut damage_writer: EventWriter<DamageEvent>,
+) {
+    for event in events.read() {
+        let Ok((mut trident, mut velocity, layer)) = tridents.get_mut(event.entity1) else {
+            continue;
+        };
+
+        if trident.hit || trident.returning || event.entity2 == trident.owner {
+            continue;
+        }
+
+        let Ok((victim_is_player, victim_spectating)) = targets.get(event.entity2) else {
+            continue;
+        };
+
+        if victim_spectating {
+            continue;
+        }
+
+        let Ok((owner_state, owner_team)) = combatants.get(trident.owner) else {
+            continue;
+        };
+
+        let owner_config = &owner_state.combat_config;
+
+        if owner_state.last_hit.elapsed() < owner_config.hit_cooldown {
+            continue;
+        }
+
+        let mut damage = trident.damage;
+
+        if let Ok((victim_state, victim_team)) = combatants.get(event.entity2) {
+            if owner_config.is_friendly(owner_team, victim_team) {
+                damage *= owner_config.friendly_fire_damage_multiplier;
+                damage *= victim_state
+                    .combat_config
+                    .friendly_fire_damage_taken_multiplier;
+            }
+        }
+
+        if damage <= 0.0 {
+            continue;
+        }
+
+        if victim_is_player
+            && targets
+                .get(trident.owner)
+                .is_ok_and(|(owner_is_player, _)| owner_is_player)
+            && !pvp_check.check(trident.owner, event.entity2)
+        {
+            continue;
+        }
+
+        if let Ok((mut owner_state, _)) = combatants.get_mut(trident.owner) {
+            owner_state.last_hit = Instant::now();
+        }
+
+        trident.hit = true;
+        // The trident bounces off the entity it hit.
+        velocity.0 *= Vec3::new(-0.01, -0.1, -0.01);
+
+        damage_writer.send(DamageEvent {
+            victim: event.entity2,
+            attacker: Some(trident.owner),
+            damage,
+            damage_type: DamageType::Projectile,
+            weapon: Some(trident.item.clone()),
+            projectile: Some(EntityKind::TRIDENT),
+            direct_source: Some(event.entity1),
+            area_of_effect: false,
+        });
+
+        if trident.channeling > 0 && weather.thundering {
+            let lightning_damage = combatants
+                .get(trident.owner)
+                .ok()
+                .and_then(|(state, _)| state.combat_config.enchantment_config.channeling_damage);
+
+            if let (Some(lightning_damage), Ok(position)) =
+                (lightning_damage, positions.get(event.entity2))
+            {
+                commands.spawn((
+                    LightningEntityBundle {
+                        position: *position,
+                        layer: *layer,
+                        ..Default::default()
+                    },
+                    ChannelingLightning(Instant::now()),
+                ));
+
+                damage_writer.send(DamageEvent {
+                    victim: event.entity2,
+                    attacker: Some(trident.owner),
+                    damage: lightning_damage,
+                    damage_type: DamageType::Lightning,
+                    weapon: Some(trident.item.clone()),
+                    projectile: None,
+                    direct_source: None,
+                    area_of_effect: false,
+                });
+            }
+        }
+
+        if trident.loyalty > 0 {
+            trident.returning = true;
+        }
+    }
+}
+
+pub(crate) fn trident_block_hit(
+    mut events: EventReader<EntityBlockCollisionEvent>,
+    mut tridents: Query<&mut ThrownTrident>,
+) {
+    for event in events.read() {
+        let Ok(mut trident) = tridents.get_mut(event.entity) else {
+            continue;
+        };
+
+        trident.hit = true;
+
+        if trident.loyalty > 0 {
+            trident.returning = true;
+        } else if trident.stuck_since.is_none() {
+            trident.stuck_since = Some(Instant::now());
+        }
+    }
+}
+
+/// Flies tridents with loyalty back to their owner and gives the tridents back once they are picked up.
+///
+/// Returning tridents can only be picked up by their owner, tridents without loyalty by every player.
+#[allow(clippy::type_complexity)]
+pub(crate) fn return_tridents(
+    mut commands: Commands,
+    mut tridents: Query<(
+        Entity,
+        &ThrownTrident,
+        &Position,
+        &EntityLayerId,
+        &mut Velocity,
+        Has<BlockCollisionConfig>,
+    )>,
+    mut players: Query<(
+        Entity,
+        &Position,
+        &EntityLayerId,
+        &mut Inventory,
+        &CombatState,
+    )>,
+    formulas: Res<CombatFormulas>,
+) {
+    for (entity, trident, position, layer, mut velocity, block_collisions) in tridents.iter_mut() {
+        let owner_left = !players.contains(trident.owner);
+
+        if (trident.loyalty > 0 && owner_left)
+            || trident
+                .stuck_since
+                .is_some_and(|stuck_since| stuck_since.elapsed() > STUCK_LIFETIME)
+        {
+            commands.entity(entity).insert(Despawned);
+            continue;
+        }
+
+        // Only tridents that stopped (or are returning) can be picked up.
+        if trident.hit || trident.returning {
+            // Players with a full inventory can not pick up the trident, but others still can.
+            let picked_up_by =
+                players
+                    .iter_mut()
+                    .find(|(player, player_position, player_layer, inventory, _)| {
+                        let eye_position = player_position.0 + DVec3::new(0.0, 1.62, 0.0);
+
+                        (!trident.returning || *player == trident.owner)
+                            && player_layer.0 == layer.0
+                            && (eye_position.distance(position.0) < PICKUP_DISTANCE
+                                || player_position.0.distance(position.0) < PICKUP_DISTANCE)
+                            && (trident.creative
+                                || inventory.can_add_item(&trident.item, Some(trident.slot)))
+                    });
+
+            if let Some((_, _, _, mut inventory, _)) = picked_up_by {
+                // The trident would be duplicated, the player kept it when it was thrown in creative mode.
+                if !trident.creative {
+                    inventory.add_item(&trident.item, Some(trident.slot));
+                }
+
+                commands.entity(entity).insert(Despawned);
+                continue;
+            }
+        }
+
+        if !trident.returning {
+            continue;
+        }
+
+        let Ok((_, owner_position, _, _, state)) = players.get(trident.owner) else {
+            continue;
+        };
+
+        let Some(loyalty_formula) = formulas.enchantment_loyalty.resolve_or_warn(
+            state
+                .combat_config
+                .enchantment_config
+                .loyalty_formula
+                .as_deref(),
+        ) else {
+            continue;
+        };
+
+        if block_collisions {
+            // Returning tridents fly through blocks.
+            commands.entity(entity).remove::<(
+                BlockCollisionConfig,
+                StopOnBlockCollision,
+                Acceleration,
+                Drag,
+            )>();
+        }
+
+        let eye_position = owner_position.0 + DVec3::new(0.0, 1.62, 0.0);
+        let direction = (eye_position - position.0).normalize_or_zero().as_vec3();
+        velocity.0 = direction * loyalty_formula(trident.loyalty);
+    }
+}
+
+pub(crate) fn despawn_lightning(
+    mut commands: Commands,
+    lightning: Query<(Entity, &ChannelingLightning)>,
+) {
+    for (entity, lightning) in lightning.iter() {
+        if lightning.0.elapsed() > LIGHTNING_DURATION {
+            commands.entity(entity).insert(Despawned);
+        }
+    }
+}
@@ -0,0 +1,256 @@
+//! Stores the [`DamageEvent`] and [`DeathEvent`] streams (e.g. for moderation review or analytics).
+//!
+//! The log is opt-in, insert a [`CombatLog`] resource with a [`CombatLogSink`] to enable it.
+//! The records are collected in batches and written on a background thread, so a slow storage does not block the tick.
+
+mod json;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+
+use std::{
+    thread::JoinHandle,
+    time::{Duration, Instant, SystemTime},
+};
+
+pub use json::JsonLinesSink;
+use serde::{Deserialize, Serialize};
+use shutdown::{Shutdown, ShutdownSet};
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteSink;
+use utils::damage::{DamageEvent, DamageType, DeathEvent};
+use valence::prelude::*;
+
+/// An entity in a [`CombatLogRecord`].
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LoggedEntity {
+    /// The bits of the [`Entity`], only unique while the server is running.
+    pub entity: u64,
+    /// The uuid of players.
+    pub uuid: Option<String>,
+    /// The username of players.
+    pub name: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CombatLogKind {
+    Damage,
+    Death,
+}
+
+/// A single [`DamageEvent`] or [`DeathEvent`].
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CombatLogRecord {
+    pub kind: CombatLogKind,
+    /// Milliseconds since the unix epoch.
+    pub timestamp: u64,
+    pub victim: LoggedEntity,
+    pub attacker: Option<LoggedEntity>,
+    /// The damage (of the killing blow for deaths).
+    pub damage: f32,
+    pub damage_type: DamageType,
+    /// The item kind of the weapon (e.g. `"diamond_sword"`).
+    pub weapon: Option<String>,
+    /// The entity kind of the projectile.
+    pub projectile: Option<String>,
+}
+
+/// A storage backend for the [`CombatLog`].
+///
+/// The sink is moved to a background thread, so it is allowed to block.
+pub trait CombatLogSink: Send + 'static {
+    /// Store a batch of records.
+    fn write(&mut self, records: &[CombatLogRecord]) -> std::io::Result<()>;
+    /// Called once when the log is closed.
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Collects the combat records and sends them in batches to the [`CombatLogSink`].
+///
+/// The remaining records are written when the server shuts down (see [`ShutdownSet::FlushStores`])
+/// or when the resource is dropped.
+#[derive(Resource)]
+pub struct CombatLog {
+    sender: Option<flume::Sender<Vec<CombatLogRecord>>>,
+    thread: Option<JoinHandle<()>>,
+    pending: Vec<CombatLogRecord>,
+    last_flush: Instant,
+    /// The records are written once this many records are pending.
+    pub batch_size: usize,
+    /// The pending records are written at least this often.
+    pub flush_interval: Duration,
+    /// If damage that did not kill the victim should be logged.
+    pub log_damage: bool,
+}
+
+impl CombatLog {
+    pub fn new(mut sink: impl CombatLogSink) -> Self {
+        let (sender, receiver) = flume::unbounded::<Vec<CombatLogRecord>>();
+
+        // The thread ends once the sender is dropped.
+        let thread = std::thread::spawn(move || {
+            for batch in receiver.iter() {
+                if let Err(e) = sink.write(&batch) {
+                    tracing::error!("failed to write {} combat log records: {e}", batch.len());
+                }
+            }
+
+            if let Err(e) = sink.flush() {
+                tracing::error!("failed to flush the combat log: {e}");
+            }
+        });
+
+        Self {
+            sender: Some(sender),
+            thread: Some(thread),
+            pending: Vec::new(),
+            last_flush: Instant::now(),
+            batch_size: 256,
+            flush_interval: Duration::from_secs(5),
+            log_damage: true,
+        }
+    }
+
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size;
+        self
+    }
+
+    pub fn with_flush_interval(mut self, flush_interval: Duration) -> Self {
+        self.flush_interval = flush_interval;
+        self
+    }
+
+    pub fn with_log_damage(mut self, log_damage: bool) -> Self {
+        self.log_damage = log_damage;
+        self
+    }
+
+    /// Add a record, it will be written with the next batch.
+    pub fn push(&mut self, record: CombatLogRecord) {
+        self.pending.push(record);
+    }
+
+    /// Send the pending records to the background thread.
+    pub fn flush(&mut self) {
+        self.last_flush = Instant::now();
+
+        if self.pending.is_empty() {
+            return;
+        }
+
+        let batch = std::mem::take(&mut self.pending);
+
+        if let Some(sender) = &self.sender {
+            if sender.send(batch).is_err() {
+                tracing::error!("the combat log thread stopped, records were dropped");
+            }
+        }
+    }
+
+    /// Write the pending records and wait until the background thread wrote everything,
+    /// records that are added afterwards are dropped.
+    pub fn close(&mut self) {
+        self.flush();
+        self.sender = None;
+
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for CombatLog {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+/// Records the [`DamageEvent`]s and [`DeathEvent`]s if a [`CombatLog`] exists.
+pub struct CombatLogPlugin;
+
+impl Plugin for CombatLogPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            PostUpdate,
+            (record_combat_events, flush_combat_log)
+                .chain()
+                .run_if(resource_exists::<CombatLog>),
+        )
+        .add_systems(
+            Shutdown,
+            close_combat_log
+                .in_set(ShutdownSet::FlushStores)
+                .run_if(resource_exists::<CombatLog>),
+        );
+    }
+}
+
+fn timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|time| time.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+fn record(
+    kind: CombatLogKind,
+    event: &DamageEvent,
+    entity: impl Fn(Entity) -> LoggedEntity,
+) -> CombatLogRecord {
+    CombatLogRecord {
+        kind,
+        timestamp: timestamp(),
+        victim: entity(event.victim),
+        attacker: event.attacker.map(&entity),
+        damage: event.damage,
+        damage_type: event.damage_type,
+        weapon: event
+            .weapon
+            .as_ref()
+            .filter(|weapon| !weapon.is_empty())
+            .map(|weapon| weapon.item.to_str().to_owned()),
+        projectile: event.projectile.map(|kind| format!("{kind:?}")),
+    }
+}
+
+fn record_combat_events(
+    mut log: ResMut<CombatLog>,
+    mut damage_events: EventReader<DamageEvent>,
+    mut death_events: EventReader<DeathEvent>,
+    players: Query<(&UniqueId, &Username)>,
+) {
+    let entity = |entity: Entity| {
+        let player = players.get(entity).ok();
+
+        LoggedEntity {
+            entity: entity.to_bits(),
+            uuid: player.map(|(uuid, _)| uuid.0.to_string()),
+            name: player.map(|(_, name)| name.0.clone()),
+        }
+    };
+
+    if log.log_damage {
+        for event in damage_events.read() {
+            log.push(record(CombatLogKind::Damage, event, entity));
+        }
+    } else {
+        damage_events.clear();
+    }
+
+    for event in death_events.read() {
+        log.push(record(CombatLogKind::Death, &event.killing_blow, entity));
+    }
+}
+
+fn flush_combat_log(mut log: ResMut<CombatLog>) {
+    if log.pending.len() >= log.batch_size || log.last_flush.elapsed() >= log.flush_interval {
+        log.flush();
+    }
+}
+
+fn close_combat_log(mut log: ResMut<CombatLog>) {
+    log.close();
+}
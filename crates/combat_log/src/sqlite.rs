@@ -0,0 +1,80 @@
+use std::path::Path;
+
+use rusqlite::{params, Connection};
+
+use crate::{CombatLogRecord, CombatLogSink};
+
+/// Stores the records in the `combat_log` table of a SQLite database.
+pub struct SqliteSink {
+    connection: Connection,
+}
+
+impl SqliteSink {
+    /// Open (or create) the database and create the `combat_log` table if it does not exist.
+    pub fn open(path: impl AsRef<Path>) -> rusqlite::Result<Self> {
+        let connection = Connection::open(path)?;
+
+        connection.execute_batch(
+            "CREATE TABLE IF NOT EXISTS combat_log (
+                id INTEGER PRIMARY KEY,
+                kind TEXT NOT NULL,
+                timestamp INTEGER NOT NULL,
+                victim_entity INTEGER NOT NULL,
+                victim_uuid TEXT,
+                victim_name TEXT,
+                attacker_entity INTEGER,
+                attacker_uuid TEXT,
+                attacker_name TEXT,
+                damage REAL NOT NULL,
+                damage_type TEXT NOT NULL,
+                weapon TEXT,
+                projectile TEXT
+            );
+            CREATE INDEX IF NOT EXISTS combat_log_victim ON combat_log (victim_uuid);
+            CREATE INDEX IF NOT EXISTS combat_log_attacker ON combat_log (attacker_uuid);",
+        )?;
+
+        Ok(Self { connection })
+    }
+}
+
+impl CombatLogSink for SqliteSink {
+    fn write(&mut self, records: &[CombatLogRecord]) -> std::io::Result<()> {
+        let write = |connection: &mut Connection| -> rusqlite::Result<()> {
+            let transaction = connection.transaction()?;
+
+            {
+                let mut statement = transaction.prepare_cached(
+                    "INSERT INTO combat_log (
+                        kind, timestamp, victim_entity, victim_uuid, victim_name,
+                        attacker_entity, attacker_uuid, attacker_name,
+                        damage, damage_type, weapon, projectile
+                    ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+                )?;
+
+                for record in records {
+                    let attacker = record.attacker.as_ref();
+
+                    statement.execute(params![
+                        format!("{:?}", record.kind),
+                        record.timestamp as i64,
+                        record.victim.entity as i64,
+                        record.victim.uuid,
+                        record.victim.name,
+                        attacker.map(|attacker| attacker.entity as i64),
+                        attacker.and_then(|attacker| attacker.uuid.clone()),
+                        attacker.and_then(|attacker| attacker.name.clone()),
+                        record.damage,
+                        format!("{:?}", record.damage_type),
+                        record.weapon,
+                        record.projectile,
+                    ])?;
+                }
+            }
+
+            transaction.commit()
+        };
+
+        write(&mut self.connection).map_err(std::io::Error::other)
+    }
+}
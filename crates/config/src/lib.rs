@@ -0,0 +1,257 @@
+//! Loads config files (TOML or RON, based on the file extension) into resources
+//! and reloads them while the server is running when the files change.
+//!
+//! A [`ConfigReloadedEvent`] is emitted after every reload, so values that were derived
+//! from a config (e.g. per-player configs) can be refreshed.
+//!
+//! The configs for the resources of the combat, physics and chat crates are behind the features of the same name.
+
+#[cfg(any(feature = "chat", feature = "combat", feature = "physics"))]
+mod typed;
+
+use std::{
+    any::{type_name, TypeId},
+    error::Error,
+    fmt,
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime},
+};
+
+use serde::de::DeserializeOwned;
+#[cfg(feature = "chat")]
+pub use typed::{ChatChannelEntry, ChatChannelsConfig};
+#[cfg(feature = "combat")]
+pub use typed::{CombatDefaults, DefaultCombatConfig};
+#[cfg(feature = "physics")]
+pub use typed::{PhysicsConfig, PhysicsProfileData, PhysicsProfileEntry};
+use valence::prelude::*;
+
+/// A config file could not be loaded.
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(std::io::Error),
+    Toml(toml::de::Error),
+    Ron(ron::error::SpannedError),
+    /// The file extension is not `toml` or `ron`.
+    UnknownFormat(PathBuf),
+    /// The config was loaded, but could not be applied.
+    Apply(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "{e}"),
+            Self::Toml(e) => write!(f, "{e}"),
+            Self::Ron(e) => write!(f, "{e}"),
+            Self::UnknownFormat(path) => {
+                write!(f, "unknown config format of `{}`", path.display())
+            }
+            Self::Apply(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl Error for ConfigError {}
+
+/// Load a config file, the format is based on the file extension (`toml` or `ron`).
+pub fn load_config<D: DeserializeOwned>(path: &Path) -> Result<D, ConfigError> {
+    let content = std::fs::read_to_string(path).map_err(ConfigError::Io)?;
+
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some("toml") => toml::from_str(&content).map_err(ConfigError::Toml),
+        Some("ron") => ron::from_str(&content).map_err(ConfigError::Ron),
+        _ => Err(ConfigError::UnknownFormat(path.to_path_buf())),
+    }
+}
+
+/// An event that is emitted after a config file was (re)loaded and applied.
+#[derive(Event, Clone, Debug)]
+pub struct ConfigReloadedEvent {
+    pub path: PathBuf,
+    /// The type of the config, see [`ConfigReloadedEvent::is`].
+    pub config: TypeId,
+    pub config_name: &'static str,
+}
+
+impl ConfigReloadedEvent {
+    /// Check if the reloaded config is of type `T`.
+    pub fn is<T: 'static>(&self) -> bool {
+        self.config == TypeId::of::<T>()
+    }
+}
+
+/// The parameters are: `path`, `world`.
+type ApplyConfigFn = Box<dyn Fn(&Path, &mut World) -> Result<(), ConfigError> + Send + Sync>;
+
+struct WatchedConfig {
+    path: PathBuf,
+    config: TypeId,
+    config_name: &'static str,
+    /// The file was loaded (or failed to load) at least once.
+    loaded: bool,
+    modified: Option<SystemTime>,
+    apply: ApplyConfigFn,
+}
+
+/// The config files that are watched for changes.
+#[derive(Resource)]
+pub struct ConfigFiles {
+    configs: Vec<WatchedConfig>,
+    last_poll: Instant,
+    /// How often the modification times of the files are checked.
+    pub poll_interval: Duration,
+}
+
+impl Default for ConfigFiles {
+    fn default() -> Self {
+        Self {
+            configs: Vec::new(),
+            last_poll: Instant::now(),
+            poll_interval: Duration::from_secs(2),
+        }
+    }
+}
+
+impl ConfigFiles {
+    /// The paths of the watched config files.
+    pub fn paths(&self) -> impl Iterator<Item = &Path> + '_ {
+        self.configs.iter().map(|config| config.path.as_path())
+    }
+
+    /// Reload all config files on the next poll, even if they did not change.
+    pub fn reload_all(&mut self) {
+        for config in self.configs.iter_mut() {
+            config.loaded = false;
+        }
+
+        self.last_poll = Instant::now()
+            .checked_sub(self.poll_interval)
+            .unwrap_or(self.last_poll);
+    }
+}
+
+pub trait ConfigAppExt {
+    /// Load the resource `T` from a config file and reload it when the file changes.
+    ///
+    /// If the file can not be loaded on startup, the default value is used.
+    fn add_config<T: Resource + DeserializeOwned + Default>(
+        &mut self,
+        path: impl Into<PathBuf>,
+    ) -> &mut Self;
+
+    /// Load `D` from a config file and apply it to the world (e.g. to update a resource that
+    /// is not serializable), the config is applied again when the file changes.
+    ///
+    /// The parameters of `apply` are: `config`, `world`.
+    fn add_config_with<D: DeserializeOwned + 'static>(
+        &mut self,
+        path: impl Into<PathBuf>,
+        apply: fn(D, &mut World) -> Result<(), String>,
+    ) -> &mut Self;
+}
+
+impl ConfigAppExt for App {
+    fn add_config<T: Resource + DeserializeOwned + Default>(
+        &mut self,
+        path: impl Into<PathBuf>,
+    ) -> &mut Self {
+        self.init_resource::<T>();
+
+        self.add_config_with::<T>(path, |config, world| {
+            world.insert_resource(config);
+            Ok(())
+        })
+    }
+
+    fn add_config_with<D: DeserializeOwned + 'static>(
+        &mut self,
+        path: impl Into<PathBuf>,
+        apply: fn(D, &mut World) -> Result<(), String>,
+    ) -> &mut Self {
+        if !self.is_plugin_added::<ConfigPlugin>() {
+            self.add_plugins(ConfigPlugin);
+        }
+
+        let mut config = WatchedConfig {
+            path: path.into(),
+            config: TypeId::of::<D>(),
+            config_name: type_name::<D>(),
+            loaded: false,
+            modified: None,
+            apply: Box::new(move |path, world| {
+                apply(load_config(path)?, world).map_err(ConfigError::Apply)
+            }),
+        };
+
+        reload_config(&mut config, self.world_mut());
+
+        self.world_mut()
+            .resource_mut::<ConfigFiles>()
+            .configs
+            .push(config);
+        self
+    }
+}
+
+/// Loads the typed configs (see [`ConfigAppExt`]) and reloads them when the files change.
+pub struct ConfigPlugin;
+
+impl Plugin for ConfigPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ConfigReloadedEvent>()
+            .init_resource::<ConfigFiles>()
+            .add_systems(First, reload_configs);
+
+        #[cfg(feature = "combat")]
+        app.add_systems(Update, typed::apply_combat_defaults);
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+/// Apply the config if the file changed since it was loaded the last time.
+fn reload_config(config: &mut WatchedConfig, world: &mut World) {
+    let modified = modified_time(&config.path);
+
+    if config.loaded && modified == config.modified {
+        return;
+    }
+
+    // Failed loads are not retried until the file changes again.
+    config.loaded = true;
+    config.modified = modified;
+
+    match (config.apply)(&config.path, world) {
+        Ok(()) => {
+            tracing::info!("loaded config `{}`", config.path.display());
+
+            world.send_event(ConfigReloadedEvent {
+                path: config.path.clone(),
+                config: config.config,
+                config_name: config.config_name,
+            });
+        }
+        Err(e) => {
+            tracing::error!("failed to load config `{}`: {e}", config.path.display());
+        }
+    }
+}
+
+fn reload_configs(world: &mut World) {
+    world.resource_scope(|world, mut files: Mut<ConfigFiles>| {
+        if files.last_poll.elapsed() < files.poll_interval {
+            return;
+        }
+
+        files.last_poll = Instant::now();
+
+        for config in files.configs.iter_mut() {
+            reload_config(config, world);
+        }
+    });
+}
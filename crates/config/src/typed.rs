@@ -0,0 +1,193 @@
+//! Configs for the resources of the other crates, each crate is behind a feature of the same name.
+
+#[cfg(feature = "chat")]
+use chat::{data::ChatChannelConfigData, ChatChannels, ChatFormatters};
+#[cfg(feature = "combat")]
+use combat::{formulas::CombatFormulas, CombatState, PlayerCombatConfig};
+#[cfg(feature = "physics")]
+use physics::settings::{PhysicsProfile, PhysicsSettings};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "physics")]
+use valence::entity::EntityKind;
+use valence::prelude::*;
+
+#[cfg(feature = "combat")]
+use crate::ConfigReloadedEvent;
+
+/// The [`PlayerCombatConfig`] for players with the [`DefaultCombatConfig`] component.
+///
+/// The formulas are checked with the [`CombatFormulas`] resource (or the default formulas if it does not exist).
+///
+/// Load it with `app.add_config_with(path, CombatDefaults::apply)`.
+#[cfg(feature = "combat")]
+#[derive(Resource, Clone, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct CombatDefaults(pub PlayerCombatConfig);
+
+#[cfg(feature = "combat")]
+impl CombatDefaults {
+    pub fn apply(self, world: &mut World) -> Result<(), String> {
+        let result = match world.get_resource::<CombatFormulas>() {
+            Some(formulas) => self.0.validate(formulas),
+            None => self.0.validate(&CombatFormulas::default()),
+        };
+
+        result.map_err(|e| format!("combat defaults: {e}"))?;
+        world.insert_resource(self);
+
+        Ok(())
+    }
+}
+
+/// Players with this component get their [`CombatState::combat_config`] from the [`CombatDefaults`],
+/// when the component is added and when the config is reloaded.
+///
+/// Changes to the combat config of a player (e.g. by a kit) are overwritten on reload,
+/// remove this component from players that should keep their own config.
+#[cfg(feature = "combat")]
+#[derive(Component, Default)]
+pub struct DefaultCombatConfig;
+
+#[cfg(feature = "combat")]
+pub(crate) fn apply_combat_defaults(
+    defaults: Option<Res<CombatDefaults>>,
+    mut events: EventReader<ConfigReloadedEvent>,
+    mut players: Query<(&mut CombatState, Ref<DefaultCombatConfig>)>,
+) {
+    let Some(defaults) = defaults else {
+        return;
+    };
+
+    let reloaded = events.read().any(|event| event.is::<CombatDefaults>());
+
+    for (mut state, marker) in players.iter_mut() {
+        if reloaded || marker.is_added() {
+            state.combat_config = defaults.0.clone();
+        }
+    }
+}
+
+/// The serializable version of the [`PhysicsProfile`].
+#[cfg(feature = "physics")]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct PhysicsProfileData {
+    pub gravity: f32,
+    pub drag: f32,
+    #[serde(default = "default_terminal_velocity")]
+    pub terminal_velocity: f32,
+    #[serde(default)]
+    pub restitution: f32,
+}
+
+#[cfg(feature = "physics")]
+fn default_terminal_velocity() -> f32 {
+    100.0
+}
+
+#[cfg(feature = "physics")]
+impl From<PhysicsProfileData> for PhysicsProfile {
+    fn from(data: PhysicsProfileData) -> Self {
+        Self {
+            gravity: data.gravity,
+            drag: data.drag,
+            terminal_velocity: data.terminal_velocity,
+            restitution: data.restitution,
+        }
+    }
+}
+
+/// The profile of an entity kind.
+#[cfg(feature = "physics")]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct PhysicsProfileEntry {
+    /// The protocol id of the entity kind.
+    pub entity_kind: i32,
+    #[serde(flatten)]
+    pub profile: PhysicsProfileData,
+}
+
+/// Overrides the values of the [`PhysicsSettings`], profiles that are not in the config are kept.
+///
+/// Load it with `app.add_config_with(path, PhysicsConfig::apply)`.
+#[cfg(feature = "physics")]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PhysicsConfig {
+    pub default_profile: Option<PhysicsProfileData>,
+    pub profiles: Vec<PhysicsProfileEntry>,
+}
+
+#[cfg(feature = "physics")]
+impl PhysicsConfig {
+    pub fn apply(self, world: &mut World) -> Result<(), String> {
+        let mut settings = world.get_resource_or_insert_with(PhysicsSettings::default);
+
+        if let Some(default_profile) = self.default_profile {
+            settings.default_profile = default_profile.into();
+        }
+
+        for entry in self.profiles {
+            settings
+                .profiles
+                .insert(EntityKind::new(entry.entity_kind), entry.profile.into());
+        }
+
+        Ok(())
+    }
+}
+
+/// A chat channel in the [`ChatChannelsConfig`].
+#[cfg(feature = "chat")]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ChatChannelEntry {
+    pub id: u64,
+    #[serde(flatten)]
+    pub config: ChatChannelConfigData,
+}
+
+/// The chat channels, existing channels keep their members and history when they are reloaded.
+///
+/// The formatters are resolved with the [`ChatFormatters`] resource (or the default formatters if it does not exist).
+///
+/// Load it with `app.add_config_with(path, ChatChannelsConfig::apply)`.
+#[cfg(feature = "chat")]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChatChannelsConfig {
+    pub channels: Vec<ChatChannelEntry>,
+}
+
+#[cfg(feature = "chat")]
+impl ChatChannelsConfig {
+    pub fn apply(self, world: &mut World) -> Result<(), String> {
+        let default_formatters;
+        let formatters = match world.get_resource::<ChatFormatters>() {
+            Some(formatters) => formatters,
+            None => {
+                default_formatters = ChatFormatters::default();
+                &default_formatters
+            }
+        };
+
+        // Resolve all channels first, so a broken config does not apply partially.
+        let configs = self
+            .channels
+            .iter()
+            .map(|entry| {
+                entry
+                    .config
+                    .to_config(formatters)
+                    .map(|config| (entry.id, config))
+                    .map_err(|e| format!("chat channel {}: {e}", entry.id))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut channels = world.get_resource_or_insert_with(ChatChannels::default);
+
+        for (id, config) in configs {
+            channels.set_channel_config(id, config);
+        }
+
+        Ok(())
+    }
+}
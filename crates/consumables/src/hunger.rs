@@ -0,0 +1,193 @@
+use serde::{Deserialize, Serialize};
+use utils::damage::DamageEvent;
+use valence::{
+    ecs::entity::EntityHashMap,
+    entity::{entity::Flags, living::Health},
+    prelude::*,
+    protocol::{packets::play::HealthUpdateS2c, VarInt, WritePacket},
+};
+
+/// The maximum food level of an entity.
+pub const MAX_FOOD: i32 = 20;
+/// The exhaustion that is needed to lose one saturation (or food) point.
+pub const EXHAUSTION_PER_FOOD: f32 = 4.0;
+
+/// The hunger of an entity.
+///
+/// The food level and saturation of clients is synced with their health.
+#[derive(Component, Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Hunger {
+    /// The food level (0 - 20).
+    pub food: i32,
+    /// The saturation, this can not be higher than the food level.
+    pub saturation: f32,
+    /// Exhaustion is added by actions like sprinting or attacking,
+    /// every [`EXHAUSTION_PER_FOOD`] points one saturation (or food) point is removed.
+    pub exhaustion: f32,
+}
+
+impl Default for Hunger {
+    fn default() -> Self {
+        Self {
+            food: MAX_FOOD,
+            saturation: 5.0,
+            exhaustion: 0.0,
+        }
+    }
+}
+
+impl Hunger {
+    pub fn is_full(&self) -> bool {
+        self.food >= MAX_FOOD
+    }
+
+    /// Restore food and saturation (e.g. after eating).
+    pub fn eat(&mut self, food: i32, saturation: f32) {
+        self.food = (self.food + food).clamp(0, MAX_FOOD);
+        self.saturation = (self.saturation + saturation).clamp(0.0, self.food as f32);
+    }
+
+    pub fn add_exhaustion(&mut self, exhaustion: f32) {
+        self.exhaustion += exhaustion;
+    }
+
+    /// The entity has enough food to sprint.
+    pub fn can_sprint(&self, config: &ExhaustionConfig) -> bool {
+        self.food > config.min_food_to_sprint
+    }
+}
+
+/// How much exhaustion is added by the actions of an entity with [`Hunger`].
+#[derive(Resource, Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExhaustionConfig {
+    /// Added for every attack.
+    pub attack: f32,
+    /// Added every time the entity takes damage.
+    pub damage_taken: f32,
+    /// Added for every block the entity sprints.
+    pub sprint_per_block: f32,
+    /// Entities with this food level (or less) can not sprint.
+    pub min_food_to_sprint: i32,
+}
+
+impl Default for ExhaustionConfig {
+    /// The vanilla values.
+    fn default() -> Self {
+        Self {
+            attack: 0.1,
+            damage_taken: 0.1,
+            sprint_per_block: 0.1,
+            min_food_to_sprint: 6,
+        }
+    }
+}
+
+/// Adds exhaustion for attacks and for taking damage.
+pub(crate) fn combat_exhaustion(
+    mut interact_events: EventReader<InteractEntityEvent>,
+    mut damage_events: EventReader<DamageEvent>,
+    mut query: Query<&mut Hunger>,
+    config: Res<ExhaustionConfig>,
+) {
+    for event in interact_events.read() {
+        if !matches!(event.interact, EntityInteraction::Attack) {
+            continue;
+        }
+
+        if let Ok(mut hunger) = query.get_mut(event.client) {
+            hunger.add_exhaustion(config.attack);
+        }
+    }
+
+    for event in damage_events.read() {
+        if let Ok(mut hunger) = query.get_mut(event.victim) {
+            hunger.add_exhaustion(config.damage_taken);
+        }
+    }
+}
+
+/// Adds exhaustion for the distance an entity sprinted.
+pub(crate) fn sprint_exhaustion(
+    mut query: Query<(Entity, &Position, &Flags, &mut Hunger)>,
+    mut last_positions: Local<EntityHashMap<DVec3>>,
+    config: Res<ExhaustionConfig>,
+) {
+    let mut positions = EntityHashMap::default();
+
+    for (entity, position, flags, mut hunger) in query.iter_mut() {
+        positions.insert(entity, position.0);
+
+        if !flags.sprinting() {
+            continue;
+        }
+
+        let Some(last_position) = last_positions.get(&entity) else {
+            continue;
+        };
+
+        let offset = position.0 - *last_position;
+        let distance = offset.x.hypot(offset.z) as f32;
+
+        if distance > 0.0 {
+            hunger.add_exhaustion(distance * config.sprint_per_block);
+        }
+    }
+
+    *last_positions = positions;
+}
+
+/// Stop entities from sprinting if they do not have enough food.
+///
+/// Clients already do this on their own, this is only needed in case the client does not respect the food level.
+pub(crate) fn block_sprinting(
+    mut events: EventReader<SprintEvent>,
+    mut query: Query<(&Hunger, &mut Flags)>,
+    config: Res<ExhaustionConfig>,
+) {
+    for event in events.read() {
+        if event.state != SprintState::Start {
+            continue;
+        }
+
+        let Ok((hunger, mut flags)) = query.get_mut(event.client) else {
+            continue;
+        };
+
+        if !hunger.can_sprint(&config) {
+            flags.set_sprinting(false);
+        }
+    }
+}
+
+/// Removes saturation and food once enough exhaustion was accumulated.
+pub(crate) fn exhaustion_system(mut query: Query<&mut Hunger>) {
+    for mut hunger in query.iter_mut() {
+        if hunger.exhaustion < EXHAUSTION_PER_FOOD {
+            continue;
+        }
+
+        hunger.exhaustion -= EXHAUSTION_PER_FOOD;
+
+        if hunger.saturation > 0.0 {
+            hunger.saturation = (hunger.saturation - 1.0).max(0.0);
+        } else {
+            hunger.food = (hunger.food - 1).max(0);
+        }
+    }
+}
+
+/// Send the food level and saturation to the client.
+#[allow(clippy::type_complexity)]
+pub(crate) fn sync_hunger(
+    mut query: Query<(&mut Client, &Hunger, &Health), Or<(Changed<Hunger>, Changed<Health>)>>,
+) {
+    for (mut client, hunger, health) in query.iter_mut() {
+        client.write_packet(&HealthUpdateS2c {
+            health: health.0,
+            food: VarInt(hunger.food),
+            food_saturation: hunger.saturation,
+        });
+    }
+}
@@ -0,0 +1,383 @@
+pub mod hunger;
+
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use hunger::{ExhaustionConfig, Hunger};
+use serde::{Deserialize, Serialize};
+use utils::sound::{SoundBuilder, Sounds};
+use valence::{
+    entity::{living::Health, player::AbsorptionAmount},
+    event_loop::PacketEvent,
+    interact_item::InteractItemEvent,
+    inventory::{player_inventory::PlayerInventory, HeldItem, UpdateSelectedSlotEvent},
+    prelude::*,
+    protocol::{
+        packets::play::{player_action_c2s::PlayerAction, PlayerActionC2s},
+        sound::SoundCategory,
+        Sound,
+    },
+};
+
+/// The time it takes to eat most food items (vanilla: 32 ticks).
+const DEFAULT_EAT_TIME: Duration = Duration::from_millis(1600);
+/// The maximum health of an entity that is healed by a consumable.
+const MAX_HEALTH: f32 = 20.0;
+
+/// What happens when an item is consumed.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Consumable {
+    /// The time it takes to consume the item.
+    pub eat_time: Duration,
+    /// The food points that are restored.
+    pub food: i32,
+    /// The saturation that is restored.
+    pub saturation: f32,
+    /// The health that is restored instantly.
+    pub heal: f32,
+    /// The absorption (extra health) the entity will have after consuming the item.
+    ///
+    /// This does not stack with absorption the entity already has.
+    pub absorption: f32,
+    /// Effects that will be part of the [`ItemConsumedEvent`].
+    pub effects: Vec<ConsumableEffect>,
+    /// The item can be consumed even if the entity is not hungry.
+    pub always_edible: bool,
+}
+
+impl Default for Consumable {
+    fn default() -> Self {
+        Self {
+            eat_time: DEFAULT_EAT_TIME,
+            food: 0,
+            saturation: 0.0,
+            heal: 0.0,
+            absorption: 0.0,
+            effects: vec![],
+            always_edible: false,
+        }
+    }
+}
+
+impl Consumable {
+    pub fn food(food: i32, saturation: f32) -> Self {
+        Self {
+            food,
+            saturation,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_absorption(mut self, absorption: f32) -> Self {
+        self.absorption = absorption;
+        self
+    }
+
+    pub fn with_effect(mut self, effect: ConsumableEffect) -> Self {
+        self.effects.push(effect);
+        self
+    }
+
+    pub fn always_edible(mut self) -> Self {
+        self.always_edible = true;
+        self
+    }
+}
+
+/// An effect that can be applied by consuming an item.
+///
+/// The effects are not applied by this crate, instead they are sent with the [`ItemConsumedEvent`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ConsumableEffect {
+    /// The name of the effect (e.g. `"regeneration"`).
+    pub effect: String,
+    /// The amplifier of the effect (starting at 0).
+    pub amplifier: u8,
+    pub duration: Duration,
+    /// The chance that the effect will be applied (0.0 - 1.0).
+    pub chance: f32,
+}
+
+impl ConsumableEffect {
+    pub fn new(effect: impl Into<String>, amplifier: u8, duration: Duration) -> Self {
+        Self {
+            effect: effect.into(),
+            amplifier,
+            duration,
+            chance: 1.0,
+        }
+    }
+}
+
+/// All items that can be consumed.
+#[derive(Resource, Clone, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Consumables {
+    #[serde(with = "utils::serialization::item_kind_map")]
+    pub items: HashMap<ItemKind, Consumable>,
+}
+
+impl Default for Consumables {
+    /// The vanilla values of common food items.
+    fn default() -> Self {
+        Self {
+            items: HashMap::from([
+                (ItemKind::Apple, Consumable::food(4, 2.4)),
+                (ItemKind::Bread, Consumable::food(5, 6.0)),
+                (ItemKind::BakedPotato, Consumable::food(5, 6.0)),
+                (ItemKind::Carrot, Consumable::food(3, 3.6)),
+                (ItemKind::CookedBeef, Consumable::food(8, 12.8)),
+                (ItemKind::CookedPorkchop, Consumable::food(8, 12.8)),
+                (ItemKind::CookedChicken, Consumable::food(6, 7.2)),
+                (ItemKind::CookedMutton, Consumable::food(6, 9.6)),
+                (ItemKind::CookedCod, Consumable::food(5, 6.0)),
+                (ItemKind::CookedSalmon, Consumable::food(6, 9.6)),
+                (ItemKind::GoldenCarrot, Consumable::food(6, 14.4)),
+                (
+                    ItemKind::GoldenApple,
+                    Consumable::food(4, 9.6)
+                        .with_absorption(4.0)
+                        .with_effect(ConsumableEffect::new(
+                            "regeneration",
+                            1,
+                            Duration::from_secs(5),
+                        ))
+                        .always_edible(),
+                ),
+                (
+                    ItemKind::EnchantedGoldenApple,
+                    Consumable::food(4, 9.6)
+                        .with_absorption(16.0)
+                        .with_effect(ConsumableEffect::new(
+                            "regeneration",
+                            1,
+                            Duration::from_secs(20),
+                        ))
+                        .with_effect(ConsumableEffect::new(
+                            "resistance",
+                            0,
+                            Duration::from_secs(300),
+                        ))
+                        .with_effect(ConsumableEffect::new(
+                            "fire_resistance",
+                            0,
+                            Duration::from_secs(300),
+                        ))
+                        .always_edible(),
+                ),
+            ]),
+        }
+    }
+}
+
+/// Attached to entities that are currently consuming an item.
+#[derive(Component)]
+pub struct Eating {
+    pub item: ItemKind,
+    /// The slot the item is in.
+    pub slot: u16,
+    pub started: Instant,
+}
+
+/// An event that is emitted after an entity finished consuming an item.
+#[derive(Event, Clone)]
+pub struct ItemConsumedEvent {
+    pub entity: Entity,
+    pub item: ItemKind,
+    /// The effects that should be applied to the entity (the chances are already rolled).
+    pub effects: Vec<ConsumableEffect>,
+}
+
+/// An event that is emitted if an entity stopped consuming an item before it was finished.
+#[derive(Event, Clone)]
+pub struct EatingInterruptedEvent {
+    pub entity: Entity,
+    pub item: ItemKind,
+}
+
+pub struct ConsumablesPlugin;
+
+impl Plugin for ConsumablesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ItemConsumedEvent>()
+            .add_event::<EatingInterruptedEvent>()
+            .init_resource::<Consumables>()
+            .init_resource::<ExhaustionConfig>()
+            .add_systems(
+                Update,
+                (
+                    (start_eating, interrupt_eating, finish_eating).chain(),
+                    (
+                        hunger::combat_exhaustion,
+                        hunger::sprint_exhaustion,
+                        hunger::block_sprinting,
+                    )
+                        .before(hunger::exhaustion_system),
+                    hunger::exhaustion_system,
+                    hunger::sync_hunger,
+                ),
+            );
+    }
+}
+
+fn start_eating(
+    mut commands: Commands,
+    mut events: EventReader<InteractItemEvent>,
+    query: Query<(&Inventory, &HeldItem, Option<&Hunger>)>,
+    consumables: Res<Consumables>,
+) {
+    for event in events.read() {
+        let Ok((inventory, held_item, hunger)) = query.get(event.client) else {
+            continue;
+        };
+
+        let slot = match event.hand {
+            Hand::Main => held_item.slot(),
+            Hand::Off => PlayerInventory::SLOT_OFFHAND,
+        };
+
+        let item = inventory.slot(slot).item;
+
+        let Some(consumable) = consumables.items.get(&item) else {
+            continue;
+        };
+
+        if !consumable.always_edible && hunger.is_some_and(Hunger::is_full) {
+            continue;
+        }
+
+        commands.entity(event.client).insert(Eating {
+            item,
+            slot,
+            started: Instant::now(),
+        });
+    }
+}
+
+/// Stop eating if the player releases the use button or switches the item.
+fn interrupt_eating(
+    mut commands: Commands,
+    mut packets: EventReader<PacketEvent>,
+    mut slot_events: EventReader<UpdateSelectedSlotEvent>,
+    query: Query<(Entity, &Eating, &Inventory)>,
+    consumables: Res<Consumables>,
+    mut interrupted_writer: EventWriter<EatingInterruptedEvent>,
+) {
+    let released = packets.read().filter_map(|packet| {
+        let pkt = packet.decode::<PlayerActionC2s>()?;
+        (pkt.action == PlayerAction::ReleaseUseItem).then_some(packet.client)
+    });
+
+    let switched = slot_events.read().map(|event| event.client);
+
+    // The item could also be removed from the inventory while eating.
+    let removed = query
+        .iter()
+        .filter(|(_, eating, inventory)| inventory.slot(eating.slot).item != eating.item)
+        .map(|(entity, _, _)| entity);
+
+    let stopped = released.chain(switched).chain(removed).collect::<Vec<_>>();
+
+    for entity in stopped {
+        let Ok((_, eating, inventory)) = query.get(entity) else {
+            continue;
+        };
+
+        // The client releases the use button right after it finished eating.
+        let finished = inventory.slot(eating.slot).item == eating.item
+            && consumables
+                .items
+                .get(&eating.item)
+                .is_some_and(|consumable| eating.started.elapsed() >= consumable.eat_time);
+
+        if finished {
+            continue;
+        }
+
+        commands.entity(entity).remove::<Eating>();
+        interrupted_writer.send(EatingInterruptedEvent {
+            entity,
+            item: eating.item,
+        });
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn finish_eating(
+    mut commands: Commands,
+    mut query: Query<(
+        Entity,
+        &Eating,
+        &mut Inventory,
+        &Position,
+        &EntityLayerId,
+        &GameMode,
+        Option<&mut Hunger>,
+        Option<&mut Health>,
+        Option<&mut AbsorptionAmount>,
+    )>,
+    mut sounds: Sounds,
+    consumables: Res<Consumables>,
+    mut consumed_writer: EventWriter<ItemConsumedEvent>,
+) {
+    for (entity, eating, mut inventory, position, layer, game_mode, hunger, health, absorption) in
+        query.iter_mut()
+    {
+        let Some(consumable) = consumables.items.get(&eating.item) else {
+            commands.entity(entity).remove::<Eating>();
+            continue;
+        };
+
+        if eating.started.elapsed() < consumable.eat_time {
+            continue;
+        }
+
+        commands.entity(entity).remove::<Eating>();
+
+        if *game_mode != GameMode::Creative {
+            let stack = inventory.slot(eating.slot);
+
+            if stack.count > 1 {
+                let amount = stack.count - 1;
+                inventory.set_slot_amount(eating.slot, amount);
+            } else {
+                inventory.set_slot(eating.slot, ItemStack::EMPTY);
+            }
+        }
+
+        if let Some(mut hunger) = hunger {
+            hunger.eat(consumable.food, consumable.saturation);
+        }
+
+        if let Some(mut health) = health {
+            if consumable.heal > 0.0 {
+                health.0 = (health.0 + consumable.heal).min(MAX_HEALTH);
+            }
+        }
+
+        if let Some(mut absorption) = absorption {
+            if consumable.absorption > absorption.0 {
+                absorption.0 = consumable.absorption;
+            }
+        }
+
+        SoundBuilder::new(Sound::EntityPlayerBurp, position.0)
+            .category(SoundCategory::Player)
+            .volume(0.5)
+            .play(&mut sounds, layer.0);
+
+        consumed_writer.send(ItemConsumedEvent {
+            entity,
+            item: eating.item,
+            effects: consumable
+                .effects
+                .iter()
+                .filter(|effect| effect.chance >= 1.0 || rand::random::<f32>() < effect.chance)
+                .cloned()
+                .collect(),
+        });
+    }
+}
@@ -0,0 +1,42 @@
+//! The formulas used by the fall damage system, they are referenced by name in the [`FallingStateConfig`](crate::FallingStateConfig).
+
+use utils::formula::FormulaRegistry;
+use valence::prelude::*;
+
+/// The parameters are: `damage`, `feather_falling_level`.
+pub type FeatherFallingFormula = Box<dyn Fn(f64, u32) -> f64 + Send + Sync>;
+/// The parameters are: `fall_distance`, `jump_boost_level`.
+pub type JumpBoostFormula = Box<dyn Fn(f64, u32) -> f64 + Send + Sync>;
+
+/// All formulas that can be referenced by a [`FallingStateConfig`](crate::FallingStateConfig).
+///
+/// The vanilla formulas are registered as `"vanilla_feather_falling"` and `"vanilla_jump_boost"`.
+#[derive(Resource)]
+pub struct FallDamageFormulas {
+    pub feather_falling: FormulaRegistry<FeatherFallingFormula>,
+    pub jump_boost: FormulaRegistry<JumpBoostFormula>,
+}
+
+impl Default for FallDamageFormulas {
+    fn default() -> Self {
+        Self {
+            feather_falling: FormulaRegistry::new().with(
+                "vanilla_feather_falling",
+                Box::new(vanilla_feather_falling) as _,
+            ),
+            jump_boost: FormulaRegistry::new()
+                .with("vanilla_jump_boost", Box::new(vanilla_jump_boost) as _),
+        }
+    }
+}
+
+/// Every level of feather falling reduces the damage by 12%, up to 80%.
+pub fn vanilla_feather_falling(damage: f64, level: u32) -> f64 {
+    let protection = (level * 3).min(20) as f64;
+    damage * (1.0 - protection * 0.04)
+}
+
+/// Every level of jump boost allows the entity to fall one block further without taking damage.
+pub fn vanilla_jump_boost(fall_distance: f64, level: u32) -> f64 {
+    (fall_distance - level as f64).max(0.0)
+}
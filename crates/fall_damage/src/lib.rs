@@ -1,5 +1,26 @@
-use utils::damage::DamageEvent;
-use valence::prelude::*;
+pub mod formulas;
+
+use std::collections::{HashMap, HashSet};
+
+use formulas::FallDamageFormulas;
+use serde::{Deserialize, Serialize};
+use utils::{
+    damage::{DamageEvent, DamageType},
+    enchantments::{Enchantment, ItemStackEnchantmentsExt},
+    formula::UnknownFormula,
+};
+use valence::{
+    block::{PropName, PropValue},
+    ecs::query::QueryData,
+    entity::{entity::Flags, Velocity},
+    prelude::*,
+    teleport::TeleportState,
+};
+
+/// The gravity (in blocks per second squared) used to calculate the bounce velocity of clients.
+const GRAVITY: f64 = 32.0;
+/// Moving down further than this in a single tick is treated as a teleport (entities fall at most ~4 blocks per tick).
+const TELEPORT_DISTANCE: f64 = 10.0;
 
 #[derive(Component, Default)]
 pub struct FallingState {
@@ -7,6 +28,13 @@ pub struct FallingState {
     pub fall_start: DVec3,
     pub falling: bool,
     pub in_air: bool,
+    /// The distance the entity fell since it was on the ground the last time.
+    ///
+    /// Only downwards movement is added to this, so an entity that moves up and down while
+    /// in the air (e.g. on bubble columns or when gliding) keeps its accumulated distance.
+    pub fall_distance: f64,
+    /// The y position of the entity in the last tick.
+    last_y: Option<f64>,
     pub falling_state_config: FallingStateConfig,
 }
 
@@ -16,16 +44,76 @@ impl FallingState {
             fall_start: start_pos,
             falling: false,
             in_air: false,
+            fall_distance: 0.0,
+            last_y: Some(start_pos.y),
             falling_state_config: FallingStateConfig::default(),
         }
     }
+
+    /// Reset the fall, as if the entity was on the ground at the given position.
+    pub fn reset(&mut self, position: DVec3) {
+        self.falling = false;
+        self.fall_start = position;
+        self.in_air = false;
+        self.fall_distance = 0.0;
+    }
+}
+
+/// Entities with this component fall slowly and do not accumulate any fall distance.
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct SlowFalling;
+
+/// Entities with this component are gliding (e.g. with an elytra) and do not accumulate any fall distance.
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct Gliding;
+
+/// Entities with this component are levitating, this resets their fall distance.
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct Levitation;
+
+/// Entities with this component do not take fall damage, but their fall distance is still tracked.
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct NoFallDamage;
+
+/// The jump boost effect of an entity, entities with this component can fall further without taking damage.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct JumpBoost {
+    /// The level of the effect (starting at 1).
+    pub level: u32,
 }
 
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct FallingStateConfig {
     /// The minimum distance the entity can fall without taking damage.
     pub no_damage_distance: f64,
     /// The damage dealt per block (after the no_damage_distance).
     pub damage_per_block: f64,
+    /// Entities inside these blocks (e.g. ladders or water) will not take fall damage and their fall distance is reset.
+    #[serde(with = "utils::serialization::block_kind_set")]
+    pub fall_reset_blocks: HashSet<BlockKind>,
+    /// Changes what happens when the entity lands on (or in) one of these blocks.
+    #[serde(with = "utils::serialization::block_kind_map")]
+    pub landing_block_overrides: HashMap<BlockKind, LandingBehavior>,
+    /// The name of the formula in [`FallDamageFormulas::feather_falling`] that reduces the damage
+    /// if the entity wears boots with feather falling.
+    pub feather_falling_formula: Option<String>,
+    /// The name of the formula in [`FallDamageFormulas::jump_boost`] that reduces the fall distance
+    /// if the entity has the [`JumpBoost`] component.
+    pub jump_boost_formula: Option<String>,
+}
+
+/// What happens when an entity lands on a block.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum LandingBehavior {
+    /// The fall damage is multiplied by this value.
+    DamageMultiplier(f64),
+    /// The entity takes no damage and bounces back up (like on slime blocks).
+    ///
+    /// Sneaking entities do not bounce and take the full damage.
+    Bounce,
+    /// The entity takes no damage.
+    CancelFall,
 }
 
 impl Default for FallingStateConfig {
@@ -33,10 +121,70 @@ impl Default for FallingStateConfig {
         Self {
             no_damage_distance: 3.0,
             damage_per_block: 1.0,
+            fall_reset_blocks: HashSet::from([
+                BlockKind::Ladder,
+                BlockKind::Vine,
+                BlockKind::Scaffolding,
+                BlockKind::Water,
+                BlockKind::BubbleColumn,
+                BlockKind::Cobweb,
+                BlockKind::TwistingVines,
+                BlockKind::TwistingVinesPlant,
+                BlockKind::WeepingVines,
+                BlockKind::WeepingVinesPlant,
+            ]),
+            landing_block_overrides: [
+                BEDS.iter()
+                    .map(|bed| (*bed, LandingBehavior::DamageMultiplier(0.5)))
+                    .collect::<Vec<_>>(),
+                vec![
+                    (BlockKind::HayBlock, LandingBehavior::DamageMultiplier(0.2)),
+                    (BlockKind::SlimeBlock, LandingBehavior::Bounce),
+                    (BlockKind::Water, LandingBehavior::CancelFall),
+                    (BlockKind::PowderSnow, LandingBehavior::CancelFall),
+                ],
+            ]
+            .concat()
+            .into_iter()
+            .collect(),
+            feather_falling_formula: Some("vanilla_feather_falling".to_string()),
+            jump_boost_formula: Some("vanilla_jump_boost".to_string()),
         }
     }
 }
 
+const BEDS: [BlockKind; 16] = [
+    BlockKind::WhiteBed,
+    BlockKind::OrangeBed,
+    BlockKind::MagentaBed,
+    BlockKind::LightBlueBed,
+    BlockKind::YellowBed,
+    BlockKind::LimeBed,
+    BlockKind::PinkBed,
+    BlockKind::GrayBed,
+    BlockKind::LightGrayBed,
+    BlockKind::CyanBed,
+    BlockKind::PurpleBed,
+    BlockKind::BlueBed,
+    BlockKind::BrownBed,
+    BlockKind::GreenBed,
+    BlockKind::RedBed,
+    BlockKind::BlackBed,
+];
+
+impl FallingStateConfig {
+    /// Check that all formulas of the config are registered,
+    /// this should be done once when the config is loaded (unknown formulas are skipped).
+    pub fn validate(&self, formulas: &FallDamageFormulas) -> Result<(), UnknownFormula> {
+        formulas
+            .feather_falling
+            .validate(self.feather_falling_formula.as_deref())?;
+        formulas
+            .jump_boost
+            .validate(self.jump_boost_formula.as_deref())
+    }
+}
+
 impl FallingState {
     pub fn on_ground(&self) -> bool {
         !self.falling
@@ -47,49 +195,200 @@ pub struct FallDamagePlugin;
 
 impl Plugin for FallDamagePlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, fall_damage_system);
+        app.init_resource::<FallDamageFormulas>()
+            .add_systems(Update, fall_damage_system);
     }
 }
 
+/// The landing behavior of the block the entity is standing in or on.
+fn landing_behavior(
+    config: &FallingStateConfig,
+    position: DVec3,
+    layer: &ChunkLayer,
+) -> Option<LandingBehavior> {
+    // Blocks without collision (like powder snow) are at the feet of the entity,
+    // the others are right below them.
+    [position.y, position.y - 0.01].into_iter().find_map(|y| {
+        let pos = BlockPos::new(
+            position.x.floor() as i32,
+            y.floor() as i32,
+            position.z.floor() as i32,
+        );
+
+        let state = layer.block(pos)?.state;
+        config
+            .landing_block_overrides
+            .get(&state.to_kind())
+            .copied()
+    })
+}
+
+#[derive(QueryData)]
+#[query_data(mutable)]
+struct FallQuery {
+    entity: Entity,
+    state: &'static mut FallingState,
+    position: &'static Position,
+    hitbox: &'static Hitbox,
+    velocity: Option<&'static mut Velocity>,
+    client: Option<&'static mut Client>,
+    teleport_state: Option<&'static TeleportState>,
+    flags: Option<&'static Flags>,
+    equipment: Option<&'static Equipment>,
+    jump_boost: Option<&'static JumpBoost>,
+    slow_falling: Has<SlowFalling>,
+    gliding: Has<Gliding>,
+    levitation: Has<Levitation>,
+    no_fall_damage: Has<NoFallDamage>,
+}
+
 fn fall_damage_system(
-    mut query: Query<(Entity, &mut FallingState, &Position, &Hitbox)>,
+    mut query: Query<FallQuery>,
     layers: Query<&ChunkLayer, With<EntityLayer>>, // TODO: Get the correct layer that the entity is on
+    formulas: Res<FallDamageFormulas>,
     mut event_writer: EventWriter<DamageEvent>,
 ) {
-    for (entity, mut fall_damage_state, position, hitbox) in query.iter_mut() {
+    for FallQueryItem {
+        entity,
+        state: mut fall_damage_state,
+        position,
+        hitbox,
+        velocity,
+        client,
+        teleport_state,
+        flags,
+        equipment,
+        jump_boost,
+        slow_falling,
+        gliding,
+        levitation,
+        no_fall_damage,
+    } in query.iter_mut()
+    {
         let layer = layers.single();
 
+        // How far the entity moved down since the last tick.
+        let moved_down = fall_damage_state
+            .last_y
+            .map_or(0.0, |last_y| last_y - position.0.y);
+        fall_damage_state.last_y = Some(position.0.y);
+
+        // The whole teleport distance would be added to the fall distance otherwise.
+        let teleported = teleport_state.is_some_and(|state| state.pending_teleports() > 0)
+            || moved_down > TELEPORT_DISTANCE;
+
+        if slow_falling || gliding || levitation || teleported {
+            fall_damage_state.reset(position.0);
+            continue;
+        }
+
+        let fall_reset = utils::intersects_block(&hitbox.get(), layer, |state| {
+            fall_damage_state
+                .falling_state_config
+                .fall_reset_blocks
+                .contains(&state.to_kind())
+                || (fall_damage_state
+                    .falling_state_config
+                    .fall_reset_blocks
+                    .contains(&BlockKind::Water)
+                    && state.get(PropName::Waterlogged) == Some(PropValue::True))
+        });
+
+        if fall_reset {
+            fall_damage_state.reset(position.0);
+            continue;
+        }
+
         let is_on_ground = utils::is_on_block(&hitbox.get(), layer);
 
         if is_on_ground {
             if fall_damage_state.falling {
-                let blocks_fallen = (fall_damage_state.fall_start.y - position.0.y).max(0.0);
+                let config = &fall_damage_state.falling_state_config;
+                let mut blocks_fallen = fall_damage_state.fall_distance + moved_down.max(0.0);
 
-                if blocks_fallen > fall_damage_state.falling_state_config.no_damage_distance {
-                    let damage = (blocks_fallen
-                        - fall_damage_state.falling_state_config.no_damage_distance)
-                        * fall_damage_state.falling_state_config.damage_per_block;
+                if let (Some(jump_boost), Some(jump_boost_formula)) = (
+                    jump_boost,
+                    formulas
+                        .jump_boost
+                        .resolve_or_warn(config.jump_boost_formula.as_deref()),
+                ) {
+                    blocks_fallen = jump_boost_formula(blocks_fallen, jump_boost.level);
+                }
+
+                let multiplier = match landing_behavior(config, position.0, layer) {
+                    Some(LandingBehavior::DamageMultiplier(multiplier)) => multiplier,
+                    Some(LandingBehavior::Bounce)
+                        if flags.is_some_and(|flags| flags.sneaking()) =>
+                    {
+                        1.0
+                    }
+                    Some(LandingBehavior::Bounce) => {
+                        // The velocity at the moment of the impact is not known (clients move themselves
+                        // and physics entities are stopped by the block), so it is derived from the fall distance.
+                        let speed = (2.0 * GRAVITY * blocks_fallen).sqrt() as f32;
+
+                        if let Some(mut client) = client {
+                            client.set_velocity([0.0, speed, 0.0]);
+                        } else if let Some(mut velocity) = velocity {
+                            // Non client entities are moved by the physics crate.
+                            velocity.0.y = speed;
+                        }
+                        0.0
+                    }
+                    Some(LandingBehavior::CancelFall) => 0.0,
+                    None => 1.0,
+                };
+
+                if blocks_fallen > config.no_damage_distance && !no_fall_damage {
+                    let mut damage = (blocks_fallen - config.no_damage_distance)
+                        * config.damage_per_block
+                        * multiplier;
+
+                    let feather_falling = equipment
+                        .and_then(|equipment| {
+                            equipment
+                                .feet()
+                                .enchantments()
+                                .get(&Enchantment::FeatherFalling)
+                                .copied()
+                        })
+                        .unwrap_or(0);
+
+                    if feather_falling > 0 {
+                        if let Some(feather_falling_formula) = formulas
+                            .feather_falling
+                            .resolve_or_warn(config.feather_falling_formula.as_deref())
+                        {
+                            damage = feather_falling_formula(damage, feather_falling);
+                        }
+                    }
 
                     if damage > 0.0 {
                         event_writer.send(DamageEvent {
                             victim: entity,
                             attacker: None,
                             damage: damage as f32,
+                            damage_type: DamageType::Fall,
+                            weapon: None,
+                            projectile: None,
+                            direct_source: None,
+                            area_of_effect: false,
                         });
                     }
                 }
-
-                fall_damage_state.falling = false;
-                fall_damage_state.fall_start = position.0;
-                fall_damage_state.in_air = false;
             }
+
+            fall_damage_state.reset(position.0);
         } else {
             // player is falling
             fall_damage_state.in_air = true;
-            if fall_damage_state.fall_start.y <= position.0.y {
-                fall_damage_state.fall_start.y = position.0.y;
-            } else {
+
+            if moved_down > 0.0 {
+                fall_damage_state.fall_distance += moved_down;
                 fall_damage_state.falling = true;
+            } else if fall_damage_state.fall_distance == 0.0 {
+                // The entity is still moving up after leaving the ground (e.g. jumping).
+                fall_damage_state.fall_start.y = position.0.y;
             }
         }
     }
@@ -0,0 +1,281 @@
+use std::{collections::HashMap, time::Duration};
+
+use combat::{CombatState, PlayerCombatConfig, PlayerStateDependantValue};
+use serde::{Deserialize, Serialize};
+use utils::{
+    enchantments::{Enchantment, ItemStackEnchantmentsExt},
+    item_values::CombatSystem,
+};
+use valence::{
+    ecs::world::Command,
+    nbt::{Compound, Value},
+    prelude::*,
+};
+
+/// The first slot of the hotbar in the player inventory.
+const HOTBAR_START: u16 = 36;
+const HEAD_SLOT: u16 = 5;
+const CHEST_SLOT: u16 = 6;
+const LEGS_SLOT: u16 = 7;
+const FEET_SLOT: u16 = 8;
+const OFFHAND_SLOT: u16 = 45;
+
+/// A kit/loadout that can be applied to a player.
+///
+/// Kits can be (de)serialized with serde, so they can be loaded from RON or JSON files.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Kit {
+    pub name: String,
+    /// Clear the inventory of the player before the kit is applied.
+    #[serde(default = "default_true")]
+    pub clear_inventory: bool,
+    /// The items of the kit by slot, slots `0..9` are the hotbar and `9..36` the main inventory.
+    #[serde(default)]
+    pub items: HashMap<u16, KitItem>,
+    #[serde(default)]
+    pub equipment: KitEquipment,
+    /// Overrides for the combat config of the player.
+    #[serde(default)]
+    pub combat: Option<CombatOverrides>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_count() -> i8 {
+    1
+}
+
+/// An item of a kit.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KitItem {
+    #[serde(with = "utils::serialization::item_kind")]
+    pub item: ItemKind,
+    #[serde(default = "default_count")]
+    pub count: i8,
+    /// The enchantments are stored in the NBT of the item.
+    #[serde(default)]
+    pub enchantments: HashMap<Enchantment, u32>,
+    /// The custom name of the item.
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub unbreakable: bool,
+}
+
+impl KitItem {
+    pub fn new(item: ItemKind, count: i8) -> Self {
+        Self {
+            item,
+            count,
+            enchantments: HashMap::new(),
+            name: None,
+            unbreakable: false,
+        }
+    }
+
+    pub fn with_enchantment(mut self, enchantment: Enchantment, level: u32) -> Self {
+        self.enchantments.insert(enchantment, level);
+        self
+    }
+
+    pub fn to_stack(&self) -> ItemStack {
+        let mut stack = ItemStack::new(self.item, self.count, None);
+
+        if !self.enchantments.is_empty() {
+            stack.set_enchantments(&self.enchantments);
+        }
+
+        if let Some(name) = &self.name {
+            let mut display = Compound::new();
+            display.insert("Name", item_name_json(name));
+            stack
+                .nbt
+                .get_or_insert_with(Compound::new)
+                .insert("display", display);
+        }
+
+        if self.unbreakable {
+            stack
+                .nbt
+                .get_or_insert_with(Compound::new)
+                .insert("Unbreakable", Value::Byte(1));
+        }
+
+        stack
+    }
+}
+
+/// The custom name of an item is a json text component.
+fn item_name_json(name: &str) -> String {
+    format!(
+        "{{\"text\":\"{}\",\"italic\":false}}",
+        name.replace('\\', "\\\\").replace('"', "\\\"")
+    )
+}
+
+/// The armor and offhand item of a kit.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct KitEquipment {
+    #[serde(default)]
+    pub head: Option<KitItem>,
+    #[serde(default)]
+    pub chest: Option<KitItem>,
+    #[serde(default)]
+    pub legs: Option<KitItem>,
+    #[serde(default)]
+    pub feet: Option<KitItem>,
+    #[serde(default)]
+    pub offhand: Option<KitItem>,
+}
+
+/// Values of the [`PlayerCombatConfig`] that will be overwritten by a kit.
+///
+/// Fields that are `None` keep the current value of the player.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CombatOverrides {
+    pub combat_system: Option<CombatSystem>,
+    pub arrows_stick: Option<u8>,
+    pub hit_cooldown: Option<Duration>,
+    pub attack_cooldown_multiplier: Option<f32>,
+    pub armor_points_multiplier: Option<f32>,
+    pub armor_toughness_multiplier: Option<f32>,
+    pub armor_knockback_resistance_multiplier: Option<f32>,
+    pub critical_hit_damage_multiplier: Option<f32>,
+    /// Overwrites the damage multiplier for every movement state.
+    pub damage_multiplier: Option<f32>,
+    /// Overwrites the damage taken multiplier for every movement state.
+    pub damage_taken_multiplier: Option<f32>,
+}
+
+impl CombatOverrides {
+    pub fn apply(&self, config: &mut PlayerCombatConfig) {
+        if let Some(combat_system) = self.combat_system {
+            config.combat_system = combat_system;
+        }
+        if let Some(arrows_stick) = self.arrows_stick {
+            config.arrows_stick = arrows_stick;
+        }
+        if let Some(hit_cooldown) = self.hit_cooldown {
+            config.hit_cooldown = hit_cooldown;
+        }
+        if let Some(multiplier) = self.attack_cooldown_multiplier {
+            config.attack_cooldown_multiplier = Some(multiplier);
+        }
+        if let Some(multiplier) = self.armor_points_multiplier {
+            config.armor_points_multiplier = multiplier;
+        }
+        if let Some(multiplier) = self.armor_toughness_multiplier {
+            config.armor_toughness_multiplier = multiplier;
+        }
+        if let Some(multiplier) = self.armor_knockback_resistance_multiplier {
+            config.armor_knockback_resistance_multiplier = multiplier;
+        }
+        if let Some(multiplier) = self.critical_hit_damage_multiplier {
+            config.critical_hit_damage_multiplier = multiplier;
+        }
+        if let Some(multiplier) = self.damage_multiplier {
+            config.damage_multiplier = PlayerStateDependantValue::always(multiplier);
+        }
+        if let Some(multiplier) = self.damage_taken_multiplier {
+            config.damage_taken_multiplier = PlayerStateDependantValue::always(multiplier);
+        }
+    }
+}
+
+/// Apply a kit to the inventory, equipment and combat state of a player.
+pub fn apply_kit(
+    kit: &Kit,
+    inventory: &mut Inventory,
+    equipment: &mut Equipment,
+    combat_state: Option<&mut CombatState>,
+) {
+    if kit.clear_inventory {
+        for slot in 0..inventory.slot_count() {
+            inventory.set_slot(slot, ItemStack::EMPTY);
+        }
+    }
+
+    for (slot, item) in kit.items.iter() {
+        // Kit slots start at the hotbar, the player inventory starts with the crafting grid and armor.
+        let inventory_slot = if *slot < 9 {
+            HOTBAR_START + slot
+        } else {
+            *slot
+        };
+
+        if inventory_slot < inventory.slot_count() {
+            inventory.set_slot(inventory_slot, item.to_stack());
+        }
+    }
+
+    let equipment_items = [
+        (&kit.equipment.head, HEAD_SLOT),
+        (&kit.equipment.chest, CHEST_SLOT),
+        (&kit.equipment.legs, LEGS_SLOT),
+        (&kit.equipment.feet, FEET_SLOT),
+        (&kit.equipment.offhand, OFFHAND_SLOT),
+    ];
+
+    for (item, slot) in equipment_items {
+        let stack = item
+            .as_ref()
+            .map(KitItem::to_stack)
+            .unwrap_or(ItemStack::EMPTY);
+
+        if !kit.clear_inventory && stack.is_empty() {
+            continue;
+        }
+
+        inventory.set_slot(slot, stack.clone());
+
+        match slot {
+            HEAD_SLOT => equipment.set_head(stack),
+            CHEST_SLOT => equipment.set_chest(stack),
+            LEGS_SLOT => equipment.set_legs(stack),
+            FEET_SLOT => equipment.set_feet(stack),
+            _ => equipment.set_off_hand(stack),
+        }
+    }
+
+    if let (Some(overrides), Some(combat_state)) = (&kit.combat, combat_state) {
+        overrides.apply(&mut combat_state.combat_config);
+    }
+}
+
+struct ApplyKit {
+    entity: Entity,
+    kit: Kit,
+}
+
+impl Command for ApplyKit {
+    fn apply(self, world: &mut World) {
+        let mut query = world.query::<(&mut Inventory, &mut Equipment, Option<&mut CombatState>)>();
+
+        if let Ok((mut inventory, mut equipment, combat_state)) = query.get_mut(world, self.entity)
+        {
+            apply_kit(
+                &self.kit,
+                &mut inventory,
+                &mut equipment,
+                combat_state.map(|state| state.into_inner()),
+            );
+        }
+    }
+}
+
+pub trait KitCommandsExt {
+    /// Apply a kit to an entity (see [`apply_kit`]).
+    fn apply_kit(&mut self, entity: Entity, kit: &Kit);
+}
+
+impl KitCommandsExt for Commands<'_, '_> {
+    fn apply_kit(&mut self, entity: Entity, kit: &Kit) {
+        self.add(ApplyKit {
+            entity,
+            kit: kit.clone(),
+        });
+    }
+}
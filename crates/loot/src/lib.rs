@@ -0,0 +1,470 @@
+//! Weighted loot tables for entity deaths and broken blocks.
+//!
+//! Loot tables can be (de)serialized with serde, so they can be loaded from RON or JSON files.
+
+use std::collections::HashMap;
+
+use rand::{seq::SliceRandom, Rng};
+use serde::{Deserialize, Serialize};
+use utils::{
+    damage::DeathEvent,
+    enchantments::{Enchantment, ItemStackEnchantmentsExt},
+};
+use valence::{inventory::HeldItem, prelude::*};
+
+/// A range of counts (both inclusive).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CountRange {
+    pub min: u32,
+    pub max: u32,
+}
+
+impl CountRange {
+    pub fn new(min: u32, max: u32) -> Self {
+        Self { min, max }
+    }
+
+    pub fn exact(count: u32) -> Self {
+        Self::new(count, count)
+    }
+
+    pub fn roll(&self, rng: &mut impl Rng) -> u32 {
+        if self.max <= self.min {
+            return self.min;
+        }
+
+        rng.gen_range(self.min..=self.max)
+    }
+}
+
+impl Default for CountRange {
+    fn default() -> Self {
+        Self::exact(1)
+    }
+}
+
+fn no_bonus() -> CountRange {
+    CountRange::exact(0)
+}
+
+fn default_weight() -> u32 {
+    1
+}
+
+/// A condition that has to be met for a [`LootPool`] or [`LootEntry`] to be used.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum LootCondition {
+    /// The victim was killed by a player.
+    KilledByPlayer,
+    /// A random chance (0.0 - 1.0).
+    RandomChance(f32),
+    /// A random chance that increases with the looting level (like vanilla wither skeleton skulls).
+    RandomChanceWithLooting { chance: f32, per_level: f32 },
+    /// The looting (or fortune for blocks) level is at least this value.
+    MinLooting(u32),
+    /// The category of the victim (see [`EntityLoot::category`]).
+    Category(String),
+}
+
+impl LootCondition {
+    pub fn test(&self, ctx: &LootContext, rng: &mut impl Rng) -> bool {
+        match self {
+            Self::KilledByPlayer => ctx.killed_by_player,
+            Self::RandomChance(chance) => rng.gen::<f32>() < *chance,
+            Self::RandomChanceWithLooting { chance, per_level } => {
+                rng.gen::<f32>() < chance + per_level * ctx.looting as f32
+            }
+            Self::MinLooting(level) => ctx.looting >= *level,
+            Self::Category(category) => ctx.category.as_ref() == Some(category),
+        }
+    }
+}
+
+/// Random enchantments that are added to the dropped item.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RandomEnchantments {
+    /// The enchantments that can be picked.
+    pub pool: Vec<Enchantment>,
+    /// The number of enchantments, conflicting enchantments are skipped.
+    #[serde(default)]
+    pub count: CountRange,
+    /// The chance (0.0 - 1.0) that the item is enchanted at all.
+    #[serde(default = "always")]
+    pub chance: f32,
+}
+
+fn always() -> f32 {
+    1.0
+}
+
+impl RandomEnchantments {
+    fn apply(&self, stack: &mut ItemStack, rng: &mut impl Rng) {
+        if rng.gen::<f32>() >= self.chance {
+            return;
+        }
+
+        let mut pool = self.pool.clone();
+        pool.shuffle(rng);
+
+        let mut enchantments = HashMap::new();
+        let count = self.count.roll(rng) as usize;
+
+        for enchantment in pool {
+            if enchantments.len() >= count {
+                break;
+            }
+
+            if enchantments
+                .keys()
+                .any(|other| enchantment.conflicts_with(other))
+            {
+                continue;
+            }
+
+            enchantments.insert(enchantment, rng.gen_range(1..=enchantment.max_level()));
+        }
+
+        stack.add_enchantments(&enchantments);
+    }
+}
+
+/// An item that can be picked from a [`LootPool`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LootEntry {
+    /// The item, [`ItemKind::Air`] drops nothing (to make drops less likely).
+    #[serde(with = "utils::serialization::item_kind")]
+    pub item: ItemKind,
+    #[serde(default = "default_weight")]
+    pub weight: u32,
+    #[serde(default)]
+    pub count: CountRange,
+    /// Additional items per looting level (like the vanilla `looting_enchant` function).
+    #[serde(default = "no_bonus")]
+    pub looting_bonus: CountRange,
+    #[serde(default)]
+    pub enchantments: Option<RandomEnchantments>,
+    #[serde(default)]
+    pub conditions: Vec<LootCondition>,
+}
+
+impl LootEntry {
+    pub fn new(item: ItemKind, weight: u32) -> Self {
+        Self {
+            item,
+            weight,
+            count: CountRange::default(),
+            looting_bonus: no_bonus(),
+            enchantments: None,
+            conditions: vec![],
+        }
+    }
+
+    pub fn with_count(mut self, count: CountRange) -> Self {
+        self.count = count;
+        self
+    }
+
+    pub fn with_looting_bonus(mut self, looting_bonus: CountRange) -> Self {
+        self.looting_bonus = looting_bonus;
+        self
+    }
+
+    pub fn with_enchantments(mut self, enchantments: RandomEnchantments) -> Self {
+        self.enchantments = Some(enchantments);
+        self
+    }
+
+    pub fn with_condition(mut self, condition: LootCondition) -> Self {
+        self.conditions.push(condition);
+        self
+    }
+
+    fn roll(&self, ctx: &LootContext, rng: &mut impl Rng, items: &mut Vec<ItemStack>) {
+        if self.item == ItemKind::Air {
+            return;
+        }
+
+        let mut count = self.count.roll(rng);
+
+        for _ in 0..ctx.looting {
+            count += self.looting_bonus.roll(rng);
+        }
+
+        let max_stack = self.item.max_stack().max(1) as u32;
+
+        while count > 0 {
+            let stack_count = count.min(max_stack);
+            count -= stack_count;
+
+            let mut stack = ItemStack::new(self.item, stack_count as i8, None);
+
+            if let Some(enchantments) = &self.enchantments {
+                enchantments.apply(&mut stack, rng);
+            }
+
+            items.push(stack);
+        }
+    }
+}
+
+/// A pool of weighted entries, every roll picks one entry.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LootPool {
+    #[serde(default)]
+    pub rolls: CountRange,
+    pub entries: Vec<LootEntry>,
+    #[serde(default)]
+    pub conditions: Vec<LootCondition>,
+}
+
+impl LootPool {
+    pub fn new(rolls: CountRange) -> Self {
+        Self {
+            rolls,
+            entries: vec![],
+            conditions: vec![],
+        }
+    }
+
+    pub fn with_entry(mut self, entry: LootEntry) -> Self {
+        self.entries.push(entry);
+        self
+    }
+
+    pub fn with_condition(mut self, condition: LootCondition) -> Self {
+        self.conditions.push(condition);
+        self
+    }
+}
+
+/// A loot table, every pool is rolled independently.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LootTable {
+    pub pools: Vec<LootPool>,
+}
+
+impl LootTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_pool(mut self, pool: LootPool) -> Self {
+        self.pools.push(pool);
+        self
+    }
+}
+
+/// The context the conditions of a loot table are checked against.
+#[derive(Debug, Clone, Default)]
+pub struct LootContext {
+    /// The looting level of the weapon (or the fortune level of the tool for blocks).
+    pub looting: u32,
+    /// The victim was killed (or the block was broken) by a player.
+    pub killed_by_player: bool,
+    /// The category of the victim (e.g. `"undead"` or `"boss"`).
+    pub category: Option<String>,
+}
+
+/// Roll the loot table.
+pub fn roll(table: &LootTable, ctx: &LootContext) -> Vec<ItemStack> {
+    roll_with_rng(table, ctx, &mut rand::thread_rng())
+}
+
+/// Roll the loot table with the given random number generator (e.g. for seeded loot).
+pub fn roll_with_rng(table: &LootTable, ctx: &LootContext, rng: &mut impl Rng) -> Vec<ItemStack> {
+    let mut items = vec![];
+
+    for pool in table.pools.iter() {
+        if !pool
+            .conditions
+            .iter()
+            .all(|condition| condition.test(ctx, rng))
+        {
+            continue;
+        }
+
+        for _ in 0..pool.rolls.roll(rng) {
+            let entries = pool
+                .entries
+                .iter()
+                .filter(|entry| {
+                    entry
+                        .conditions
+                        .iter()
+                        .all(|condition| condition.test(ctx, rng))
+                })
+                .collect::<Vec<_>>();
+
+            if let Ok(entry) = entries.choose_weighted(rng, |entry| entry.weight) {
+                entry.roll(ctx, rng, &mut items);
+            }
+        }
+    }
+
+    items
+}
+
+/// The loot of an entity that is dropped when it dies.
+#[derive(Component, Debug, Clone)]
+pub struct EntityLoot {
+    pub table: LootTable,
+    /// The category that is checked by [`LootCondition::Category`].
+    pub category: Option<String>,
+}
+
+impl EntityLoot {
+    pub fn new(table: LootTable) -> Self {
+        Self {
+            table,
+            category: None,
+        }
+    }
+
+    pub fn with_category(mut self, category: impl Into<String>) -> Self {
+        self.category = Some(category.into());
+        self
+    }
+}
+
+/// The loot tables of blocks, see [`BlockBrokenEvent`].
+#[derive(Resource, Debug, Clone, Default)]
+pub struct BlockLootTables(pub HashMap<BlockKind, LootTable>);
+
+/// Send this event when a block was broken, to drop the loot of the block (see [`BlockLootTables`]).
+#[derive(Event, Clone, Copy, Debug)]
+pub struct BlockBrokenEvent {
+    /// The player that broke the block, the fortune level of their held item is used.
+    pub player: Option<Entity>,
+    pub layer: Entity,
+    pub position: BlockPos,
+    pub block: BlockKind,
+}
+
+/// Where the loot came from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LootSource {
+    Entity(Entity),
+    Block(BlockPos),
+}
+
+/// An event that is emitted when loot was rolled, the items are not spawned.
+#[derive(Event, Clone, Debug)]
+pub struct LootDroppedEvent {
+    pub source: LootSource,
+    /// The killer or the player that broke the block.
+    pub player: Option<Entity>,
+    pub layer: Entity,
+    pub position: DVec3,
+    pub items: Vec<ItemStack>,
+}
+
+pub struct LootPlugin;
+
+impl Plugin for LootPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<BlockBrokenEvent>()
+            .add_event::<LootDroppedEvent>()
+            .init_resource::<BlockLootTables>()
+            .add_systems(PostUpdate, (entity_loot, block_loot));
+    }
+}
+
+/// The level of the enchantment on the held item of the player.
+fn held_enchantment_level(
+    players: &Query<(&Inventory, &HeldItem)>,
+    player: Entity,
+    enchantment: Enchantment,
+) -> u32 {
+    players
+        .get(player)
+        .ok()
+        .and_then(|(inventory, held_item)| {
+            inventory
+                .slot(held_item.slot())
+                .enchantments()
+                .get(&enchantment)
+                .copied()
+        })
+        .unwrap_or(0)
+}
+
+fn entity_loot(
+    mut events: EventReader<DeathEvent>,
+    victims: Query<(&EntityLoot, &Position, &EntityLayerId)>,
+    players: Query<(), With<Client>>,
+    mut loot_writer: EventWriter<LootDroppedEvent>,
+) {
+    for event in events.read() {
+        let Ok((loot, position, layer)) = victims.get(event.victim) else {
+            continue;
+        };
+
+        let looting = event
+            .killing_blow
+            .weapon
+            .as_ref()
+            .and_then(|weapon| weapon.enchantments().get(&Enchantment::Looting).copied())
+            .unwrap_or(0);
+
+        let ctx = LootContext {
+            looting,
+            killed_by_player: event
+                .attacker
+                .is_some_and(|attacker| players.contains(attacker)),
+            category: loot.category.clone(),
+        };
+
+        let items = roll(&loot.table, &ctx);
+
+        if items.is_empty() {
+            continue;
+        }
+
+        loot_writer.send(LootDroppedEvent {
+            source: LootSource::Entity(event.victim),
+            player: event.attacker,
+            layer: layer.0,
+            position: position.0,
+            items,
+        });
+    }
+}
+
+fn block_loot(
+    mut events: EventReader<BlockBrokenEvent>,
+    tables: Res<BlockLootTables>,
+    players: Query<(&Inventory, &HeldItem)>,
+    mut loot_writer: EventWriter<LootDroppedEvent>,
+) {
+    for event in events.read() {
+        let Some(table) = tables.0.get(&event.block) else {
+            continue;
+        };
+
+        let ctx = LootContext {
+            looting: event.player.map_or(0, |player| {
+                held_enchantment_level(&players, player, Enchantment::Fortune)
+            }),
+            killed_by_player: event.player.is_some(),
+            category: None,
+        };
+
+        let items = roll(table, &ctx);
+
+        if items.is_empty() {
+            continue;
+        }
+
+        loot_writer.send(LootDroppedEvent {
+            source: LootSource::Block(event.position),
+            player: event.player,
+            layer: event.layer,
+            position: DVec3::new(
+                event.position.x as f64 + 0.5,
+                event.position.y as f64 + 0.5,
+                event.position.z as f64 + 0.5,
+            ),
+            items,
+        });
+    }
+}
@@ -0,0 +1,356 @@
+use std::collections::HashMap;
+
+use valence::{
+    inventory::{ClickMode, ClickSlotEvent},
+    prelude::*,
+};
+
+/// A callback that will be called if a player clicks on a button of a menu.
+pub type MenuCallback = fn(&mut Commands, &MenuClick);
+
+/// Information about a click on a menu button.
+#[derive(Debug, Clone)]
+pub struct MenuClick {
+    pub player: Entity,
+    /// The entity of the menu (the entity with the [`Menu`] and [`Inventory`] components).
+    pub menu: Entity,
+    pub slot: u16,
+    pub button: i8,
+    pub mode: ClickMode,
+}
+
+/// An item in a menu that can be clicked.
+#[derive(Clone)]
+pub struct MenuButton {
+    pub item: ItemStack,
+    pub on_click: Option<MenuCallback>,
+}
+
+impl MenuButton {
+    pub fn new(item: ItemStack) -> Self {
+        Self {
+            item,
+            on_click: None,
+        }
+    }
+
+    pub fn with_callback(mut self, on_click: MenuCallback) -> Self {
+        self.on_click = Some(on_click);
+        self
+    }
+}
+
+/// Splits a list of buttons into pages.
+pub struct Pagination {
+    /// All buttons that will be split into pages.
+    pub entries: Vec<MenuButton>,
+    /// The slots the buttons of the current page are shown in.
+    pub content_slots: Vec<u16>,
+    /// The slot and item of the button that opens the previous page.
+    pub previous_button: (u16, ItemStack),
+    /// The slot and item of the button that opens the next page.
+    pub next_button: (u16, ItemStack),
+    page: usize,
+}
+
+impl Pagination {
+    pub fn new(
+        entries: Vec<MenuButton>,
+        content_slots: Vec<u16>,
+        previous_button: (u16, ItemStack),
+        next_button: (u16, ItemStack),
+    ) -> Self {
+        Self {
+            entries,
+            content_slots,
+            previous_button,
+            next_button,
+            page: 0,
+        }
+    }
+
+    /// The current page (starting at 0).
+    pub fn page(&self) -> usize {
+        self.page
+    }
+
+    pub fn page_count(&self) -> usize {
+        if self.content_slots.is_empty() {
+            return 0;
+        }
+
+        self.entries.len().div_ceil(self.content_slots.len()).max(1)
+    }
+
+    /// Set the current page, the page is clamped to the last page.
+    pub fn set_page(&mut self, page: usize) {
+        self.page = page.min(self.page_count().saturating_sub(1));
+    }
+
+    pub fn next_page(&mut self) {
+        self.set_page(self.page + 1);
+    }
+
+    pub fn previous_page(&mut self) {
+        self.set_page(self.page.saturating_sub(1));
+    }
+
+    /// The button shown in the slot on the current page.
+    fn entry_at(&self, slot: u16) -> Option<&MenuButton> {
+        let idx = self.content_slots.iter().position(|s| *s == slot)?;
+        self.entries.get(self.page * self.content_slots.len() + idx)
+    }
+}
+
+/// A server side inventory GUI.
+///
+/// Spawn this together with an [`Inventory`] and open it with [`OpenInventory`] (or [`open_menu`]).
+/// Players can not take items out of menus, the inventory will be set to `readonly` automatically.
+#[derive(Component, Default)]
+pub struct Menu {
+    buttons: HashMap<u16, MenuButton>,
+    names: HashMap<String, u16>,
+    pagination: Option<Pagination>,
+}
+
+impl Menu {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_button(mut self, slot: u16, button: MenuButton) -> Self {
+        self.set_button(slot, button);
+        self
+    }
+
+    pub fn with_pagination(mut self, pagination: Pagination) -> Self {
+        self.pagination = Some(pagination);
+        self
+    }
+
+    pub fn set_button(&mut self, slot: u16, button: MenuButton) {
+        self.buttons.insert(slot, button);
+    }
+
+    /// Set a button and give its slot a name, so it can be found with [`Menu::slot`].
+    pub fn set_named_button(&mut self, name: impl Into<String>, slot: u16, button: MenuButton) {
+        self.names.insert(name.into(), slot);
+        self.set_button(slot, button);
+    }
+
+    pub fn remove_button(&mut self, slot: u16) -> Option<MenuButton> {
+        self.names.retain(|_, s| *s != slot);
+        self.buttons.remove(&slot)
+    }
+
+    pub fn button(&self, slot: u16) -> Option<&MenuButton> {
+        self.buttons.get(&slot)
+    }
+
+    /// The slot of a named button.
+    pub fn slot(&self, name: &str) -> Option<u16> {
+        self.names.get(name).copied()
+    }
+
+    pub fn pagination(&self) -> Option<&Pagination> {
+        self.pagination.as_ref()
+    }
+
+    pub fn pagination_mut(&mut self) -> Option<&mut Pagination> {
+        self.pagination.as_mut()
+    }
+
+    /// Write the buttons (and the current page) into the inventory.
+    fn render(&self, inventory: &mut Inventory) {
+        for slot in 0..inventory.slot_count() {
+            let item = self
+                .pagination
+                .as_ref()
+                .and_then(|pagination| self.render_pagination(pagination, slot))
+                .or_else(|| self.buttons.get(&slot).map(|button| button.item.clone()))
+                .unwrap_or(ItemStack::EMPTY);
+
+            if inventory.slot(slot) != &item {
+                inventory.set_slot(slot, item);
+            }
+        }
+    }
+
+    fn render_pagination(&self, pagination: &Pagination, slot: u16) -> Option<ItemStack> {
+        if pagination.content_slots.contains(&slot) {
+            return Some(
+                pagination
+                    .entry_at(slot)
+                    .map(|button| button.item.clone())
+                    .unwrap_or(ItemStack::EMPTY),
+            );
+        }
+
+        if slot == pagination.previous_button.0 && pagination.page > 0 {
+            return Some(pagination.previous_button.1.clone());
+        }
+
+        if slot == pagination.next_button.0 && pagination.page + 1 < pagination.page_count() {
+            return Some(pagination.next_button.1.clone());
+        }
+
+        None
+    }
+}
+
+/// Attached to players that currently have a menu open.
+#[derive(Component)]
+pub struct ViewingMenu(pub Entity);
+
+/// An event that will be fired if a player opened a menu.
+#[derive(Event, Debug)]
+pub struct MenuOpenEvent {
+    pub player: Entity,
+    pub menu: Entity,
+}
+
+/// An event that will be fired if a player closed a menu (or opened another inventory).
+#[derive(Event, Debug)]
+pub struct MenuCloseEvent {
+    pub player: Entity,
+    pub menu: Entity,
+}
+
+/// An event that will be fired for every click on a menu slot (also for slots without a button).
+#[derive(Event, Debug)]
+pub struct MenuClickEvent(pub MenuClick);
+
+/// Open a menu for a player.
+pub fn open_menu(commands: &mut Commands, player: Entity, menu: Entity) {
+    commands.entity(player).insert(OpenInventory::new(menu));
+}
+
+/// Close the currently open menu of a player.
+pub fn close_menu(commands: &mut Commands, player: Entity) {
+    commands.entity(player).remove::<OpenInventory>();
+}
+
+pub struct MenusPlugin;
+
+impl Plugin for MenusPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<MenuOpenEvent>()
+            .add_event::<MenuCloseEvent>()
+            .add_event::<MenuClickEvent>()
+            .add_systems(
+                Update,
+                (track_open_menus, handle_menu_clicks, render_menus).chain(),
+            );
+    }
+}
+
+fn render_menus(mut menus: Query<(Ref<Menu>, &mut Inventory), Changed<Menu>>) {
+    for (menu, mut inventory) in menus.iter_mut() {
+        if menu.is_added() {
+            inventory.readonly = true;
+        }
+
+        menu.render(&mut inventory);
+    }
+}
+
+fn track_open_menus(
+    mut commands: Commands,
+    players: Query<(Entity, Option<&OpenInventory>, Option<&ViewingMenu>), With<Client>>,
+    changed: Query<(), Changed<OpenInventory>>,
+    mut closed: RemovedComponents<OpenInventory>,
+    menus: Query<(), With<Menu>>,
+    mut open_writer: EventWriter<MenuOpenEvent>,
+    mut close_writer: EventWriter<MenuCloseEvent>,
+) {
+    let mut updated = closed.read().collect::<Vec<_>>();
+    updated.extend(
+        players
+            .iter()
+            .filter_map(|(entity, ..)| changed.contains(entity).then_some(entity)),
+    );
+
+    for player in updated {
+        let Ok((_, open_inventory, viewing)) = players.get(player) else {
+            continue;
+        };
+
+        let now_viewing = open_inventory
+            .map(|open_inventory| open_inventory.entity)
+            .filter(|entity| menus.contains(*entity));
+
+        if viewing.map(|viewing| viewing.0) == now_viewing {
+            continue;
+        }
+
+        if let Some(viewing) = viewing {
+            close_writer.send(MenuCloseEvent {
+                player,
+                menu: viewing.0,
+            });
+        }
+
+        match now_viewing {
+            Some(menu) => {
+                open_writer.send(MenuOpenEvent { player, menu });
+                commands.entity(player).insert(ViewingMenu(menu));
+            }
+            None => {
+                commands.entity(player).remove::<ViewingMenu>();
+            }
+        }
+    }
+}
+
+fn handle_menu_clicks(
+    mut commands: Commands,
+    mut events: EventReader<ClickSlotEvent>,
+    players: Query<&ViewingMenu>,
+    mut menus: Query<(&mut Menu, &Inventory)>,
+    mut click_writer: EventWriter<MenuClickEvent>,
+) {
+    for event in events.read() {
+        let Ok(viewing) = players.get(event.client) else {
+            continue;
+        };
+
+        let Ok((mut menu, inventory)) = menus.get_mut(viewing.0) else {
+            continue;
+        };
+
+        // Clicks outside of the window or in the inventory of the player.
+        if event.slot_id < 0 || event.slot_id as u16 >= inventory.slot_count() {
+            continue;
+        }
+
+        let click = MenuClick {
+            player: event.client,
+            menu: viewing.0,
+            slot: event.slot_id as u16,
+            button: event.button,
+            mode: event.mode,
+        };
+
+        let mut callback = menu.buttons.get(&click.slot).and_then(|b| b.on_click);
+
+        if let Some(pagination) = menu.pagination.as_mut() {
+            if pagination.content_slots.contains(&click.slot) {
+                callback = pagination.entry_at(click.slot).and_then(|b| b.on_click);
+            } else if click.slot == pagination.previous_button.0 && pagination.page > 0 {
+                pagination.previous_page();
+                callback = None;
+            } else if click.slot == pagination.next_button.0
+                && pagination.page + 1 < pagination.page_count()
+            {
+                pagination.next_page();
+                callback = None;
+            }
+        }
+
+        if let Some(callback) = callback {
+            callback(&mut commands, &click);
+        }
+
+        click_writer.send(MenuClickEvent(click));
+    }
+}
@@ -0,0 +1,228 @@
+//! Capture points (or control points) that are captured by the team with the most players inside of a [`TriggerZone`].
+
+use std::{collections::HashMap, time::Duration};
+
+use bevy_time::Time;
+use bossbar::{BossBarId, BossBars};
+use combat::Team;
+use physics::zone::TriggerZone;
+use valence::prelude::*;
+
+/// A point that can be captured by teams, the entity also needs a [`TriggerZone`].
+///
+/// The team with the most players inside of the zone makes progress, if the point is owned by another team
+/// it is neutralized first. If multiple teams have the same number of players inside, the point is contested
+/// and the progress is frozen.
+#[derive(Component, Clone, Debug)]
+pub struct CapturePoint {
+    /// The time one player needs to capture (or neutralize) the point.
+    pub capture_time: Duration,
+    /// Every additional player of the capturing team (more than the second largest team) speeds up the
+    /// capture by this fraction (e.g. `0.5` for +50% per player).
+    pub extra_player_speed: f32,
+    /// The progress that is lost per second if no team is inside of the zone.
+    pub decay: f32,
+    /// The progress of this boss bar is set to the capture progress.
+    pub boss_bar: Option<BossBarId>,
+    owner: Option<u16>,
+    capturing: Option<u16>,
+    progress: f32,
+    contested: bool,
+}
+
+impl CapturePoint {
+    pub fn new(capture_time: Duration) -> Self {
+        Self {
+            capture_time,
+            extra_player_speed: 0.0,
+            decay: 0.0,
+            boss_bar: None,
+            owner: None,
+            capturing: None,
+            progress: 0.0,
+            contested: false,
+        }
+    }
+
+    pub fn with_owner(mut self, team: u16) -> Self {
+        self.owner = Some(team);
+        self
+    }
+
+    pub fn with_extra_player_speed(mut self, extra_player_speed: f32) -> Self {
+        self.extra_player_speed = extra_player_speed;
+        self
+    }
+
+    pub fn with_decay(mut self, decay: f32) -> Self {
+        self.decay = decay;
+        self
+    }
+
+    pub fn with_boss_bar(mut self, boss_bar: BossBarId) -> Self {
+        self.boss_bar = Some(boss_bar);
+        self
+    }
+
+    /// The team that owns the point.
+    pub fn owner(&self) -> Option<u16> {
+        self.owner
+    }
+
+    /// The team that is currently capturing (or neutralizing) the point.
+    pub fn capturing(&self) -> Option<u16> {
+        self.capturing
+    }
+
+    /// The progress (0.0 - 1.0) of the [`CapturePoint::capturing`] team.
+    pub fn progress(&self) -> f32 {
+        self.progress
+    }
+
+    pub fn is_contested(&self) -> bool {
+        self.contested
+    }
+}
+
+/// An event that is emitted when a team captured a point.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct PointCapturedEvent {
+    pub point: Entity,
+    pub team: u16,
+}
+
+/// An event that is emitted when a team removed the owner of a point.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct PointNeutralizedEvent {
+    pub point: Entity,
+    /// The team that owned the point.
+    pub previous_owner: u16,
+    pub team: u16,
+}
+
+/// An event that is emitted when a point becomes contested or is no longer contested.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct PointContestedEvent {
+    pub point: Entity,
+    pub contested: bool,
+}
+
+pub struct CapturePointPlugin;
+
+impl Plugin for CapturePointPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<PointCapturedEvent>()
+            .add_event::<PointNeutralizedEvent>()
+            .add_event::<PointContestedEvent>()
+            .add_systems(
+                Update,
+                (update_capture_points, update_capture_boss_bars).chain(),
+            );
+    }
+}
+
+fn update_capture_points(
+    time: Res<Time>,
+    mut points: Query<(Entity, &mut CapturePoint, &TriggerZone)>,
+    teams: Query<&Team>,
+    mut captured_writer: EventWriter<PointCapturedEvent>,
+    mut neutralized_writer: EventWriter<PointNeutralizedEvent>,
+    mut contested_writer: EventWriter<PointContestedEvent>,
+) {
+    let dt = time.delta_seconds();
+
+    for (entity, mut point, zone) in points.iter_mut() {
+        let mut players = HashMap::<u16, u32>::new();
+
+        for team in zone.entities().filter_map(|entity| teams.get(entity).ok()) {
+            *players.entry(team.0).or_default() += 1;
+        }
+
+        let mut counts = players.iter().collect::<Vec<_>>();
+        counts.sort_by(|a, b| b.1.cmp(a.1));
+
+        let largest = counts.first().map(|(team, count)| (**team, **count));
+        let second = counts.get(1).map_or(0, |(_, count)| **count);
+
+        let contested = largest.is_some_and(|(_, count)| count == second);
+
+        if contested != point.contested {
+            point.contested = contested;
+            contested_writer.send(PointContestedEvent {
+                point: entity,
+                contested,
+            });
+        }
+
+        if contested {
+            continue;
+        }
+
+        let Some((team, count)) = largest else {
+            if point.decay > 0.0 && point.progress > 0.0 {
+                point.progress = (point.progress - point.decay * dt).max(0.0);
+
+                if point.progress == 0.0 {
+                    point.capturing = None;
+                }
+            }
+            continue;
+        };
+
+        let speed = 1.0 + point.extra_player_speed * (count - second - 1) as f32;
+        let step = speed * dt / point.capture_time.as_secs_f32().max(f32::EPSILON);
+
+        match point.capturing {
+            // Another team made progress, that has to be undone first.
+            Some(capturing) if capturing != team => {
+                point.progress = (point.progress - step).max(0.0);
+
+                if point.progress == 0.0 {
+                    point.capturing = None;
+                }
+            }
+            _ if point.owner == Some(team) => {}
+            _ => {
+                point.capturing = Some(team);
+                point.progress += step;
+
+                if point.progress >= 1.0 {
+                    point.progress = 0.0;
+                    point.capturing = None;
+
+                    match point.owner.take() {
+                        Some(previous_owner) => {
+                            neutralized_writer.send(PointNeutralizedEvent {
+                                point: entity,
+                                previous_owner,
+                                team,
+                            });
+                        }
+                        None => {
+                            point.owner = Some(team);
+                            captured_writer.send(PointCapturedEvent {
+                                point: entity,
+                                team,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn update_capture_boss_bars(
+    points: Query<&CapturePoint, Changed<CapturePoint>>,
+    boss_bars: Option<ResMut<BossBars>>,
+) {
+    let Some(mut boss_bars) = boss_bars else {
+        return;
+    };
+
+    for point in points.iter() {
+        if let Some(bar) = point.boss_bar.and_then(|id| boss_bars.get_mut(id)) {
+            bar.set_progress(point.progress);
+        }
+    }
+}
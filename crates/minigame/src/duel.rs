@@ -0,0 +1,682 @@
+//! Duels (1v1) between two players, requested with events or the `/duel` command.
+//!
+//! Accepted duels teleport both players to a free [`DuelArena`] where they can only fight each other
+//! (see [`PvpRules::scope_to_pair`]). Their inventory, position and health are restored after the duel.
+//! Players that leave during a duel are restored when they join again.
+
+use std::{
+    collections::{BTreeSet, HashMap},
+    time::{Duration, Instant},
+};
+
+use combat::{CombatState, PlayerCombatConfig};
+use kits::{Kit, KitCommandsExt};
+use regions::{RegionId, Regions};
+use utils::{damage::DeathEvent, pvp::PvpRules};
+use valence::{
+    client::{VisibleChunkLayer, VisibleEntityLayers},
+    command::manager::CommandExecutionEvent,
+    ecs::query::QueryData,
+    entity::living::Health,
+    prelude::*,
+    text::IntoText,
+    uuid::Uuid,
+};
+
+/// An arena for duels, every arena can only be used by one duel at a time.
+#[derive(Clone, Debug)]
+pub struct DuelArena {
+    pub layer: Entity,
+    /// The spawn positions of the challenger and the challenged player.
+    pub spawns: [DVec3; 2],
+    /// The region (in [`Regions`]) of the arena, players that leave it are teleported back to their spawn.
+    pub region: Option<RegionId>,
+}
+
+#[derive(Resource, Clone, Debug)]
+pub struct DuelConfig {
+    pub arenas: Vec<DuelArena>,
+    /// The kit both players get when the duel starts.
+    ///
+    /// If `None`, the players keep their inventory.
+    pub kit: Option<Kit>,
+    /// Requests that were not accepted within this time expire.
+    pub request_timeout: Duration,
+    /// The duel ends in a draw after this time.
+    ///
+    /// If `None`, the duel only ends if a player dies or leaves.
+    pub max_duration: Option<Duration>,
+    /// Handle the `/duel <player>`, `/duel accept <player>` and `/duel deny <player>` commands
+    /// and send chat messages about requests and results to the players.
+    pub enable_commands: bool,
+}
+
+impl Default for DuelConfig {
+    fn default() -> Self {
+        Self {
+            arenas: vec![],
+            kit: None,
+            request_timeout: Duration::from_secs(30),
+            max_duration: Some(Duration::from_secs(300)),
+            enable_commands: true,
+        }
+    }
+}
+
+/// Attached to players during a duel.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InDuel {
+    pub opponent: Entity,
+}
+
+/// The state of a player before the duel.
+struct SavedPlayer {
+    entity: Entity,
+    uuid: Uuid,
+    layer: Entity,
+    visible_chunk_layer: Entity,
+    visible_entity_layers: BTreeSet<Entity>,
+    position: DVec3,
+    inventory: Vec<ItemStack>,
+    equipment: Equipment,
+    health: Option<f32>,
+    combat_config: Option<PlayerCombatConfig>,
+}
+
+struct ActiveDuel {
+    players: [SavedPlayer; 2],
+    arena: usize,
+    started: Instant,
+}
+
+/// The pending requests and the running duels.
+#[derive(Resource, Default)]
+pub struct Duels {
+    /// The time the requests expire by `(challenger, target)`.
+    requests: HashMap<(Entity, Entity), Instant>,
+    active: Vec<ActiveDuel>,
+    /// The state of the players that left during a duel, restored when they join again.
+    rejoins: HashMap<Uuid, SavedPlayer>,
+}
+
+impl Duels {
+    /// The challenger sent a duel request to the target that did not expire yet.
+    pub fn has_request(&self, challenger: Entity, target: Entity) -> bool {
+        self.requests
+            .get(&(challenger, target))
+            .is_some_and(|expires| *expires > Instant::now())
+    }
+
+    /// The index (in [`DuelConfig::arenas`]) of the arena the player is dueling in.
+    pub fn arena_of(&self, player: Entity) -> Option<usize> {
+        self.active
+            .iter()
+            .find(|duel| duel.players.iter().any(|saved| saved.entity == player))
+            .map(|duel| duel.arena)
+    }
+
+    fn free_arena(&self, arenas: usize) -> Option<usize> {
+        (0..arenas).find(|arena| !self.active.iter().any(|duel| duel.arena == *arena))
+    }
+}
+
+/// Send this event to request a duel with another player.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct DuelRequestEvent {
+    pub challenger: Entity,
+    pub target: Entity,
+}
+
+/// Send this event to accept the duel request of the challenger.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct DuelAcceptEvent {
+    pub player: Entity,
+    pub challenger: Entity,
+}
+
+/// Send this event to deny the duel request of the challenger.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct DuelDenyEvent {
+    pub player: Entity,
+    pub challenger: Entity,
+}
+
+/// An event that is emitted after a duel was requested (e.g. to notify the target).
+#[derive(Event, Clone, Copy, Debug)]
+pub struct DuelRequestedEvent {
+    pub challenger: Entity,
+    pub target: Entity,
+}
+
+/// An event that is emitted if a duel could not be requested or started.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct DuelRequestFailedEvent {
+    pub challenger: Entity,
+    pub target: Entity,
+    pub reason: DuelFailReason,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DuelFailReason {
+    /// The player challenged themself.
+    SelfRequest,
+    /// One of the players is already in a duel.
+    AlreadyInDuel,
+    /// The challenger did not request a duel (or the request expired).
+    NoRequest,
+    NoFreeArena,
+    /// The target denied the request.
+    Denied,
+}
+
+/// An event that is emitted when a duel starts.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct DuelStartEvent {
+    /// The challenger and the challenged player.
+    pub players: [Entity; 2],
+    /// The index of the arena in [`DuelConfig::arenas`].
+    pub arena: usize,
+}
+
+/// An event that is emitted when a duel ends.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct DuelEndEvent {
+    pub players: [Entity; 2],
+    /// `None` if the duel ended in a draw.
+    pub winner: Option<Entity>,
+    pub reason: DuelEndReason,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DuelEndReason {
+    /// A player died.
+    Death,
+    /// A player left (or disconnected).
+    Left,
+    /// The duel took longer than [`DuelConfig::max_duration`].
+    Timeout,
+}
+
+pub struct DuelPlugin;
+
+impl Plugin for DuelPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DuelConfig>()
+            .init_resource::<Duels>()
+            .init_resource::<PvpRules>()
+            .add_event::<DuelRequestEvent>()
+            .add_event::<DuelAcceptEvent>()
+            .add_event::<DuelDenyEvent>()
+            .add_event::<DuelRequestedEvent>()
+            .add_event::<DuelRequestFailedEvent>()
+            .add_event::<DuelStartEvent>()
+            .add_event::<DuelEndEvent>()
+            .add_systems(
+                Update,
+                (
+                    duel_commands,
+                    handle_duel_requests,
+                    keep_players_in_arena,
+                    end_duels,
+                    duel_messages,
+                )
+                    .chain(),
+            )
+            // Runs after the data of joining players was loaded (e.g. by the persistence crate).
+            .add_systems(PostUpdate, restore_rejoined_players);
+    }
+}
+
+#[derive(QueryData)]
+#[query_data(mutable)]
+struct DuelPlayerQuery {
+    uuid: &'static UniqueId,
+    position: &'static mut Position,
+    layer: &'static mut EntityLayerId,
+    visible_chunk_layer: &'static mut VisibleChunkLayer,
+    visible_entity_layers: &'static mut VisibleEntityLayers,
+    inventory: &'static mut Inventory,
+    equipment: &'static mut Equipment,
+    health: Option<&'static mut Health>,
+    combat_state: Option<&'static mut CombatState>,
+    in_duel: Has<InDuel>,
+}
+
+impl DuelPlayerQueryItem<'_> {
+    fn save(&self, entity: Entity) -> SavedPlayer {
+        SavedPlayer {
+            entity,
+            uuid: self.uuid.0,
+            layer: self.layer.0,
+            visible_chunk_layer: self.visible_chunk_layer.0,
+            visible_entity_layers: self.visible_entity_layers.0.clone(),
+            position: self.position.0,
+            inventory: (0..self.inventory.slot_count())
+                .map(|slot| self.inventory.slot(slot).clone())
+                .collect(),
+            equipment: self.equipment.clone(),
+            health: self.health.as_ref().map(|health| health.0),
+            combat_config: self
+                .combat_state
+                .as_ref()
+                .map(|state| state.combat_config.clone()),
+        }
+    }
+
+    fn restore(&mut self, saved: &SavedPlayer) {
+        self.layer.0 = saved.layer;
+        self.visible_chunk_layer.0 = saved.visible_chunk_layer;
+        self.visible_entity_layers.0 = saved.visible_entity_layers.clone();
+        self.position.0 = saved.position;
+
+        for (slot, stack) in saved.inventory.iter().enumerate() {
+            self.inventory.set_slot(slot as u16, stack.clone());
+        }
+
+        *self.equipment = saved.equipment.clone();
+
+        if let (Some(health), Some(saved)) = (self.health.as_mut(), saved.health) {
+            health.0 = saved;
+        }
+
+        if let (Some(state), Some(saved)) = (self.combat_state.as_mut(), &saved.combat_config) {
+            state.combat_config = saved.clone();
+        }
+    }
+
+    fn teleport(&mut self, layer: Entity, position: DVec3) {
+        if self.layer.0 != layer {
+            self.layer.0 = layer;
+            self.visible_chunk_layer.0 = layer;
+            self.visible_entity_layers.0.clear();
+            self.visible_entity_layers.0.insert(layer);
+        }
+
+        self.position.0 = position;
+    }
+}
+
+/// Converts the `/duel` command into [`DuelRequestEvent`]s, [`DuelAcceptEvent`]s and [`DuelDenyEvent`]s.
+fn duel_commands(
+    config: Res<DuelConfig>,
+    mut commands: EventReader<CommandExecutionEvent>,
+    mut clients: Query<(Entity, &Username, &mut Client)>,
+    mut request_writer: EventWriter<DuelRequestEvent>,
+    mut accept_writer: EventWriter<DuelAcceptEvent>,
+    mut deny_writer: EventWriter<DuelDenyEvent>,
+) {
+    if !config.enable_commands {
+        commands.clear();
+        return;
+    }
+
+    for event in commands.read() {
+        let mut args = event.command.split_whitespace();
+
+        if args.next() != Some("duel") {
+            continue;
+        }
+
+        let (action, name) = match (args.next(), args.next()) {
+            (Some(action @ ("accept" | "deny")), Some(name)) => (Some(action), name),
+            (Some(name), None) => (None, name),
+            _ => continue,
+        };
+
+        let player = clients
+            .iter()
+            .find(|(_, username, _)| username.0.eq_ignore_ascii_case(name))
+            .map(|(entity, _, _)| entity);
+
+        let Some(player) = player else {
+            if let Ok((_, _, mut client)) = clients.get_mut(event.executor) {
+                client.send_chat_message(format!("Player {name} was not found").color(Color::RED));
+            }
+            continue;
+        };
+
+        match action {
+            Some("accept") => {
+                accept_writer.send(DuelAcceptEvent {
+                    player: event.executor,
+                    challenger: player,
+                });
+            }
+            Some(_) => {
+                deny_writer.send(DuelDenyEvent {
+                    player: event.executor,
+                    challenger: player,
+                });
+            }
+            None => {
+                request_writer.send(DuelRequestEvent {
+                    challenger: event.executor,
+                    target: player,
+                });
+            }
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn handle_duel_requests(
+    mut commands: Commands,
+    config: Res<DuelConfig>,
+    mut duels: ResMut<Duels>,
+    mut pvp_rules: ResMut<PvpRules>,
+    mut request_events: EventReader<DuelRequestEvent>,
+    mut accept_events: EventReader<DuelAcceptEvent>,
+    mut deny_events: EventReader<DuelDenyEvent>,
+    mut players: Query<DuelPlayerQuery, With<Client>>,
+    mut requested_writer: EventWriter<DuelRequestedEvent>,
+    mut failed_writer: EventWriter<DuelRequestFailedEvent>,
+    mut start_writer: EventWriter<DuelStartEvent>,
+) {
+    let now = Instant::now();
+    duels.requests.retain(|_, expires| *expires > now);
+
+    for &DuelRequestEvent { challenger, target } in request_events.read() {
+        let fail = |reason| DuelRequestFailedEvent {
+            challenger,
+            target,
+            reason,
+        };
+
+        if challenger == target {
+            failed_writer.send(fail(DuelFailReason::SelfRequest));
+            continue;
+        }
+
+        let Ok([challenger_item, target_item]) = players.get_many([challenger, target]) else {
+            continue;
+        };
+
+        if challenger_item.in_duel || target_item.in_duel {
+            failed_writer.send(fail(DuelFailReason::AlreadyInDuel));
+            continue;
+        }
+
+        duels
+            .requests
+            .insert((challenger, target), now + config.request_timeout);
+
+        requested_writer.send(DuelRequestedEvent { challenger, target });
+    }
+
+    for event in deny_events.read() {
+        if duels
+            .requests
+            .remove(&(event.challenger, event.player))
+            .is_some()
+        {
+            failed_writer.send(DuelRequestFailedEvent {
+                challenger: event.challenger,
+                target: event.player,
+                reason: DuelFailReason::Denied,
+            });
+        }
+    }
+
+    for event in accept_events.read() {
+        let (challenger, target) = (event.challenger, event.player);
+
+        let fail = |reason| DuelRequestFailedEvent {
+            challenger,
+            target,
+            reason,
+        };
+
+        if !duels.has_request(challenger, target) {
+            failed_writer.send(fail(DuelFailReason::NoRequest));
+            continue;
+        }
+
+        let Ok([mut challenger_item, mut target_item]) = players.get_many_mut([challenger, target])
+        else {
+            continue;
+        };
+
+        // Players that started a duel this tick do not have the component yet.
+        if challenger_item.in_duel
+            || target_item.in_duel
+            || duels.arena_of(challenger).is_some()
+            || duels.arena_of(target).is_some()
+        {
+            failed_writer.send(fail(DuelFailReason::AlreadyInDuel));
+            continue;
+        }
+
+        let Some(arena) = duels.free_arena(config.arenas.len()) else {
+            failed_writer.send(fail(DuelFailReason::NoFreeArena));
+            continue;
+        };
+
+        let saved = [challenger_item.save(challenger), target_item.save(target)];
+        let duel_arena = &config.arenas[arena];
+
+        challenger_item.teleport(duel_arena.layer, duel_arena.spawns[0]);
+        target_item.teleport(duel_arena.layer, duel_arena.spawns[1]);
+
+        for (player, opponent) in [(challenger, target), (target, challenger)] {
+            commands.entity(player).insert(InDuel { opponent });
+
+            if let Some(kit) = &config.kit {
+                commands.apply_kit(player, kit);
+            }
+        }
+
+        // Other requests of the players are no longer needed.
+        duels.requests.retain(|(a, b), _| {
+            ![challenger, target].contains(a) && ![challenger, target].contains(b)
+        });
+
+        pvp_rules.scope_to_pair(challenger, target);
+
+        duels.active.push(ActiveDuel {
+            players: saved,
+            arena,
+            started: now,
+        });
+
+        start_writer.send(DuelStartEvent {
+            players: [challenger, target],
+            arena,
+        });
+    }
+}
+
+fn end_duels(
+    mut commands: Commands,
+    config: Res<DuelConfig>,
+    mut duels: ResMut<Duels>,
+    mut pvp_rules: ResMut<PvpRules>,
+    mut death_events: EventReader<DeathEvent>,
+    mut players: Query<DuelPlayerQuery, With<Client>>,
+    mut end_writer: EventWriter<DuelEndEvent>,
+) {
+    let deaths: Vec<Entity> = death_events.read().map(|event| event.victim).collect();
+    let mut ended = vec![];
+
+    for (i, duel) in duels.active.iter().enumerate() {
+        let [a, b] = [duel.players[0].entity, duel.players[1].entity];
+
+        let result = if let Some(loser) = [a, b].into_iter().find(|p| deaths.contains(p)) {
+            Some((Some(if loser == a { b } else { a }), DuelEndReason::Death))
+        } else if let Some(left) = [a, b].into_iter().find(|p| !players.contains(*p)) {
+            Some((Some(if left == a { b } else { a }), DuelEndReason::Left))
+        } else if config
+            .max_duration
+            .is_some_and(|max_duration| duel.started.elapsed() >= max_duration)
+        {
+            Some((None, DuelEndReason::Timeout))
+        } else {
+            None
+        };
+
+        if let Some((winner, reason)) = result {
+            ended.push((i, winner, reason));
+        }
+    }
+
+    // Remove from the back, so the indices stay valid.
+    for (i, winner, reason) in ended.into_iter().rev() {
+        let duel = duels.active.remove(i);
+        let entities = [duel.players[0].entity, duel.players[1].entity];
+
+        for saved in duel.players {
+            if let Ok(mut player) = players.get_mut(saved.entity) {
+                player.restore(&saved);
+                commands.entity(saved.entity).remove::<InDuel>();
+            } else {
+                duels.rejoins.insert(saved.uuid, saved);
+            }
+        }
+
+        pvp_rules.remove_scope(entities[0]);
+
+        end_writer.send(DuelEndEvent {
+            players: entities,
+            winner,
+            reason,
+        });
+    }
+}
+
+/// Teleports players that left the region of their arena back to their spawn.
+fn keep_players_in_arena(
+    config: Res<DuelConfig>,
+    duels: Res<Duels>,
+    regions: Option<Res<Regions>>,
+    mut players: Query<&mut Position, With<InDuel>>,
+) {
+    let Some(regions) = regions else {
+        return;
+    };
+
+    for duel in duels.active.iter() {
+        let Some(arena) = config.arenas.get(duel.arena) else {
+            continue;
+        };
+
+        let Some(region) = arena.region.and_then(|region| regions.get(region)) else {
+            continue;
+        };
+
+        for (saved, spawn) in duel.players.iter().zip(arena.spawns) {
+            if let Ok(mut position) = players.get_mut(saved.entity) {
+                if !region.shape.contains(position.0) {
+                    position.0 = spawn;
+                }
+            }
+        }
+    }
+}
+
+/// Players that left during a duel get their state from before the duel back when they join again.
+fn restore_rejoined_players(
+    mut duels: ResMut<Duels>,
+    mut players: Query<DuelPlayerQuery, Added<Client>>,
+) {
+    if duels.rejoins.is_empty() {
+        return;
+    }
+
+    for mut player in players.iter_mut() {
+        if let Some(saved) = duels.rejoins.remove(&player.uuid.0) {
+            player.restore(&saved);
+        }
+    }
+}
+
+/// Sends chat messages about requests and results (only if [`DuelConfig::enable_commands`] is set).
+fn duel_messages(
+    config: Res<DuelConfig>,
+    mut requested_events: EventReader<DuelRequestedEvent>,
+    mut failed_events: EventReader<DuelRequestFailedEvent>,
+    mut end_events: EventReader<DuelEndEvent>,
+    mut clients: Query<(&Username, &mut Client)>,
+) {
+    if !config.enable_commands {
+        requested_events.clear();
+        failed_events.clear();
+        end_events.clear();
+        return;
+    }
+
+    let name = |clients: &Query<(&Username, &mut Client)>, entity: Entity| {
+        clients
+            .get(entity)
+            .map_or_else(|_| "Unknown".to_string(), |(name, _)| name.0.clone())
+    };
+
+    let send = |clients: &mut Query<(&Username, &mut Client)>, entity: Entity, text: Text| {
+        if let Ok((_, mut client)) = clients.get_mut(entity) {
+            client.send_chat_message(text);
+        }
+    };
+
+    for event in requested_events.read() {
+        let challenger = name(&clients, event.challenger);
+        let target = name(&clients, event.target);
+
+        send(
+            &mut clients,
+            event.target,
+            format!("{challenger} challenged you to a duel, use /duel accept {challenger}")
+                .color(Color::YELLOW),
+        );
+        send(
+            &mut clients,
+            event.challenger,
+            format!("Duel request sent to {target}").color(Color::GRAY),
+        );
+    }
+
+    for event in failed_events.read() {
+        let challenger = name(&clients, event.challenger);
+        let target = name(&clients, event.target);
+
+        let (receiver, message) = match event.reason {
+            DuelFailReason::SelfRequest => {
+                (event.challenger, "You can not duel yourself".to_string())
+            }
+            DuelFailReason::AlreadyInDuel => (
+                event.challenger,
+                format!("You or {target} are already in a duel"),
+            ),
+            DuelFailReason::NoRequest => (
+                event.target,
+                format!("{challenger} did not challenge you to a duel"),
+            ),
+            DuelFailReason::NoFreeArena => (
+                event.target,
+                "There is no free arena, try again later".to_string(),
+            ),
+            DuelFailReason::Denied => (
+                event.challenger,
+                format!("{target} denied your duel request"),
+            ),
+        };
+
+        send(&mut clients, receiver, message.color(Color::RED));
+    }
+
+    for event in end_events.read() {
+        for (player, opponent) in [
+            (event.players[0], event.players[1]),
+            (event.players[1], event.players[0]),
+        ] {
+            let opponent_name = name(&clients, opponent);
+
+            let message = match event.winner {
+                Some(winner) if winner == player => {
+                    format!("You won the duel against {opponent_name}").color(Color::GREEN)
+                }
+                Some(_) => format!("You lost the duel against {opponent_name}").color(Color::RED),
+                None => "The duel ended in a draw".color(Color::GRAY),
+            };
+
+            send(&mut clients, player, message);
+        }
+    }
+}
@@ -0,0 +1,448 @@
+pub mod capture_point;
+pub mod duel;
+pub mod spawner;
+
+use std::time::Duration;
+
+use bevy_time::Time;
+use combat::Team;
+use rand::seq::SliceRandom;
+use valence::prelude::*;
+
+/// The phases of a match, a match goes through them in this order and starts again with [`MatchPhase::Waiting`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum MatchPhase {
+    /// Waiting for enough players to join.
+    Waiting,
+    /// Enough players joined, the countdown is running.
+    Starting,
+    Running,
+    /// The match is over, players can still see the result before the next round starts.
+    Ending,
+}
+
+#[derive(Clone, Debug)]
+pub struct MatchConfig {
+    /// The players that are needed to start the countdown.
+    pub min_players: usize,
+    pub max_players: usize,
+    /// The time between [`MatchPhase::Starting`] and [`MatchPhase::Running`].
+    pub countdown: Duration,
+    /// The match ends without a winner after this time.
+    ///
+    /// If `None`, the match only ends with an [`EndMatchEvent`] (or if all players left).
+    pub max_duration: Option<Duration>,
+    /// The time between [`MatchPhase::Ending`] and the next [`MatchPhase::Waiting`].
+    pub ending_duration: Duration,
+    /// The number of teams, the players are split evenly between them when the match starts.
+    ///
+    /// If `0`, no [`Team`] is assigned.
+    pub teams: u16,
+    /// Players can join a running match (they are put in the smallest team).
+    pub allow_late_join: bool,
+}
+
+impl Default for MatchConfig {
+    fn default() -> Self {
+        Self {
+            min_players: 2,
+            max_players: 16,
+            countdown: Duration::from_secs(10),
+            max_duration: None,
+            ending_duration: Duration::from_secs(5),
+            teams: 0,
+            allow_late_join: false,
+        }
+    }
+}
+
+/// The winner of a match.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MatchWinner {
+    Team(u16),
+    Player(Entity),
+}
+
+/// A match (or arena), players join the match entity.
+#[derive(Component, Debug)]
+pub struct Match {
+    pub config: MatchConfig,
+    phase: MatchPhase,
+    /// The time since the current phase started.
+    phase_time: Duration,
+    players: Vec<Entity>,
+    winner: Option<MatchWinner>,
+}
+
+impl Match {
+    pub fn new(config: MatchConfig) -> Self {
+        Self {
+            config,
+            phase: MatchPhase::Waiting,
+            phase_time: Duration::ZERO,
+            players: vec![],
+            winner: None,
+        }
+    }
+
+    pub fn phase(&self) -> MatchPhase {
+        self.phase
+    }
+
+    /// The time since the current phase started.
+    pub fn phase_time(&self) -> Duration {
+        self.phase_time
+    }
+
+    pub fn players(&self) -> &[Entity] {
+        &self.players
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.players.len() >= self.config.max_players
+    }
+
+    /// The winner of the last match, this is set in [`MatchPhase::Ending`].
+    pub fn winner(&self) -> Option<MatchWinner> {
+        self.winner
+    }
+
+    /// The time until the match starts (only in [`MatchPhase::Starting`]).
+    pub fn remaining_countdown(&self) -> Option<Duration> {
+        (self.phase == MatchPhase::Starting)
+            .then(|| self.config.countdown.saturating_sub(self.phase_time))
+    }
+
+    fn set_phase(&mut self, phase: MatchPhase) -> MatchPhase {
+        self.phase_time = Duration::ZERO;
+        std::mem::replace(&mut self.phase, phase)
+    }
+}
+
+/// The match a player is in.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InMatch(pub Entity);
+
+/// Send this event to add a player to a match.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct JoinMatchEvent {
+    pub player: Entity,
+    pub arena: Entity,
+}
+
+/// Send this event to remove a player from their match.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct LeaveMatchEvent {
+    pub player: Entity,
+}
+
+/// Send this event to end a running match.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct EndMatchEvent {
+    pub arena: Entity,
+    pub winner: Option<MatchWinner>,
+}
+
+/// An event that is emitted after a player joined a match.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct PlayerJoinedMatchEvent {
+    pub player: Entity,
+    pub arena: Entity,
+}
+
+/// An event that is emitted after a player left a match (or disconnected).
+#[derive(Event, Clone, Copy, Debug)]
+pub struct PlayerLeftMatchEvent {
+    pub player: Entity,
+    pub arena: Entity,
+}
+
+/// An event that is emitted if a player could not join a match.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct JoinMatchFailedEvent {
+    pub player: Entity,
+    pub arena: Entity,
+    pub reason: JoinFailReason,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JoinFailReason {
+    Full,
+    /// The match is already running (or ending).
+    AlreadyStarted,
+    /// The player is already in a match.
+    AlreadyInMatch,
+}
+
+/// An event that is emitted when a match changes its phase.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct MatchPhaseChangeEvent {
+    pub arena: Entity,
+    pub old: MatchPhase,
+    pub new: MatchPhase,
+}
+
+/// An event that is emitted every second of the countdown (e.g. to show a title to the players).
+#[derive(Event, Clone, Copy, Debug)]
+pub struct MatchCountdownEvent {
+    pub arena: Entity,
+    pub seconds_left: u64,
+}
+
+pub struct MinigamePlugin;
+
+impl Plugin for MinigamePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<JoinMatchEvent>()
+            .add_event::<LeaveMatchEvent>()
+            .add_event::<EndMatchEvent>()
+            .add_event::<PlayerJoinedMatchEvent>()
+            .add_event::<PlayerLeftMatchEvent>()
+            .add_event::<JoinMatchFailedEvent>()
+            .add_event::<MatchPhaseChangeEvent>()
+            .add_event::<MatchCountdownEvent>()
+            .add_systems(
+                Update,
+                (
+                    leave_matches,
+                    join_matches,
+                    remove_disconnected,
+                    update_matches,
+                )
+                    .chain(),
+            );
+    }
+}
+
+fn leave_matches(
+    mut commands: Commands,
+    mut events: EventReader<LeaveMatchEvent>,
+    players: Query<&InMatch>,
+    mut matches: Query<&mut Match>,
+    mut left_writer: EventWriter<PlayerLeftMatchEvent>,
+) {
+    for event in events.read() {
+        let Ok(in_match) = players.get(event.player) else {
+            continue;
+        };
+
+        if let Ok(mut arena) = matches.get_mut(in_match.0) {
+            arena.players.retain(|player| *player != event.player);
+        }
+
+        commands.entity(event.player).remove::<(InMatch, Team)>();
+
+        left_writer.send(PlayerLeftMatchEvent {
+            player: event.player,
+            arena: in_match.0,
+        });
+    }
+}
+
+fn join_matches(
+    mut commands: Commands,
+    mut events: EventReader<JoinMatchEvent>,
+    players: Query<(Has<InMatch>, Option<&Team>)>,
+    mut matches: Query<&mut Match>,
+    mut joined_writer: EventWriter<PlayerJoinedMatchEvent>,
+    mut failed_writer: EventWriter<JoinMatchFailedEvent>,
+) {
+    for event in events.read() {
+        let Ok(mut arena) = matches.get_mut(event.arena) else {
+            continue;
+        };
+
+        let fail = |reason| JoinMatchFailedEvent {
+            player: event.player,
+            arena: event.arena,
+            reason,
+        };
+
+        let Ok((in_match, _)) = players.get(event.player) else {
+            continue;
+        };
+
+        // Players that joined this tick do not have the component yet.
+        if in_match || arena.players.contains(&event.player) {
+            failed_writer.send(fail(JoinFailReason::AlreadyInMatch));
+            continue;
+        }
+
+        if arena.is_full() {
+            failed_writer.send(fail(JoinFailReason::Full));
+            continue;
+        }
+
+        let late_join = match arena.phase {
+            MatchPhase::Waiting | MatchPhase::Starting => false,
+            MatchPhase::Running if arena.config.allow_late_join => true,
+            _ => {
+                failed_writer.send(fail(JoinFailReason::AlreadyStarted));
+                continue;
+            }
+        };
+
+        let mut player = commands.entity(event.player);
+        player.insert(InMatch(event.arena));
+
+        if late_join && arena.config.teams > 0 {
+            let mut team_sizes = vec![0; arena.config.teams as usize];
+
+            for team in arena
+                .players
+                .iter()
+                .filter_map(|player| players.get(*player).ok()?.1)
+            {
+                if let Some(size) = team_sizes.get_mut(team.0 as usize) {
+                    *size += 1;
+                }
+            }
+
+            let smallest = (0..arena.config.teams)
+                .min_by_key(|team| team_sizes[*team as usize])
+                .unwrap_or(0);
+
+            player.insert(Team(smallest));
+        }
+
+        arena.players.push(event.player);
+
+        joined_writer.send(PlayerJoinedMatchEvent {
+            player: event.player,
+            arena: event.arena,
+        });
+    }
+}
+
+/// Remove players that were despawned (e.g. because they disconnected) from their match.
+fn remove_disconnected(
+    mut matches: Query<(Entity, &mut Match)>,
+    players: Query<(), With<InMatch>>,
+    mut left_writer: EventWriter<PlayerLeftMatchEvent>,
+) {
+    for (entity, mut arena) in matches.iter_mut() {
+        arena.players.retain(|player| {
+            let exists = players.contains(*player);
+
+            if !exists {
+                left_writer.send(PlayerLeftMatchEvent {
+                    player: *player,
+                    arena: entity,
+                });
+            }
+
+            exists
+        });
+    }
+}
+
+fn update_matches(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut matches: Query<(Entity, &mut Match)>,
+    mut end_events: EventReader<EndMatchEvent>,
+    mut phase_writer: EventWriter<MatchPhaseChangeEvent>,
+    mut countdown_writer: EventWriter<MatchCountdownEvent>,
+) {
+    for event in end_events.read() {
+        let Ok((_, mut arena)) = matches.get_mut(event.arena) else {
+            continue;
+        };
+
+        if arena.phase == MatchPhase::Running {
+            arena.winner = event.winner;
+            let old = arena.set_phase(MatchPhase::Ending);
+
+            phase_writer.send(MatchPhaseChangeEvent {
+                arena: event.arena,
+                old,
+                new: MatchPhase::Ending,
+            });
+        }
+    }
+
+    for (entity, mut arena) in matches.iter_mut() {
+        let seconds_before = arena.remaining_countdown().map(ceil_seconds);
+        arena.phase_time += time.delta();
+
+        let new_phase = match arena.phase {
+            MatchPhase::Waiting if arena.players.len() >= arena.config.min_players => {
+                Some(MatchPhase::Starting)
+            }
+            MatchPhase::Starting if arena.players.len() < arena.config.min_players => {
+                Some(MatchPhase::Waiting)
+            }
+            MatchPhase::Starting if arena.phase_time >= arena.config.countdown => {
+                Some(MatchPhase::Running)
+            }
+            MatchPhase::Running
+                if arena.players.is_empty()
+                    || arena
+                        .config
+                        .max_duration
+                        .is_some_and(|max_duration| arena.phase_time >= max_duration) =>
+            {
+                arena.winner = None;
+                Some(MatchPhase::Ending)
+            }
+            MatchPhase::Ending if arena.phase_time >= arena.config.ending_duration => {
+                Some(MatchPhase::Waiting)
+            }
+            _ => None,
+        };
+
+        if let Some(seconds_left) = arena.remaining_countdown().map(ceil_seconds) {
+            if new_phase.is_none() && seconds_before != Some(seconds_left) {
+                countdown_writer.send(MatchCountdownEvent {
+                    arena: entity,
+                    seconds_left,
+                });
+            }
+        }
+
+        let Some(new_phase) = new_phase else {
+            continue;
+        };
+
+        match new_phase {
+            MatchPhase::Starting => {
+                countdown_writer.send(MatchCountdownEvent {
+                    arena: entity,
+                    seconds_left: ceil_seconds(arena.config.countdown),
+                });
+            }
+            MatchPhase::Running => {
+                arena.winner = None;
+
+                if arena.config.teams > 0 {
+                    let mut players = arena.players.clone();
+                    players.shuffle(&mut rand::thread_rng());
+
+                    for (i, player) in players.into_iter().enumerate() {
+                        let team = (i % arena.config.teams as usize) as u16;
+                        commands.entity(player).insert(Team(team));
+                    }
+                }
+            }
+            MatchPhase::Waiting => {
+                // The teams are assigned again when the next round starts.
+                for player in arena.players.iter() {
+                    commands.entity(*player).remove::<Team>();
+                }
+            }
+            MatchPhase::Ending => {}
+        }
+
+        let old = arena.set_phase(new_phase);
+
+        phase_writer.send(MatchPhaseChangeEvent {
+            arena: entity,
+            old,
+            new: new_phase,
+        });
+    }
+}
+
+fn ceil_seconds(duration: Duration) -> u64 {
+    duration.as_secs_f64().ceil() as u64
+}
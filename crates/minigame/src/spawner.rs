@@ -0,0 +1,427 @@
+//! Spawners for PvE arenas that spawn mobs on an interval or in waves.
+
+use std::time::Duration;
+
+use bevy_time::{Time, Timer, TimerMode};
+use combat::CombatState;
+use fall_damage::FallingState;
+use physics::{Acceleration, BlockCollisionConfig, StopOnBlockCollision};
+use rand::{seq::SliceRandom, Rng};
+use utils::damage::{DeathEvent, TakesDamage};
+use valence::{ecs::entity::EntityHashSet, entity::EntityStatuses, math::Aabb, prelude::*};
+
+/// Spawns a mob (e.g. a `ZombieEntityBundle`) and returns its entity.
+///
+/// The parameters are: `commands`, `layer`, `position`.
+pub type MobSpawnFn = fn(&mut Commands, Entity, DVec3) -> Entity;
+
+/// A mob that can be spawned by a [`MobSpawner`].
+#[derive(Clone, Copy, Debug)]
+pub struct SpawnEntry {
+    pub spawn: MobSpawnFn,
+    /// The weight of the entry when a random mob is picked.
+    pub weight: u32,
+}
+
+impl SpawnEntry {
+    pub fn new(spawn: MobSpawnFn) -> Self {
+        Self { spawn, weight: 1 }
+    }
+
+    pub fn with_weight(mut self, weight: u32) -> Self {
+        self.weight = weight;
+        self
+    }
+}
+
+/// A wave of mobs, the next wave starts after all mobs of this wave are dead.
+#[derive(Clone, Debug)]
+pub struct Wave {
+    /// The mobs and how many of them are spawned.
+    pub mobs: Vec<(SpawnEntry, u32)>,
+    /// The time before the wave starts.
+    pub delay: Duration,
+}
+
+impl Wave {
+    pub fn new(delay: Duration) -> Self {
+        Self {
+            mobs: vec![],
+            delay,
+        }
+    }
+
+    pub fn with_mobs(mut self, entry: SpawnEntry, count: u32) -> Self {
+        self.mobs.push((entry, count));
+        self
+    }
+}
+
+/// When the mobs of a [`MobSpawner`] are spawned.
+#[derive(Clone, Debug)]
+pub enum SpawnSchedule {
+    /// Spawn `count` random mobs every interval.
+    Interval {
+        interval: Duration,
+        count: u32,
+        entries: Vec<SpawnEntry>,
+    },
+    /// Spawn the waves one after another.
+    Waves(Vec<Wave>),
+}
+
+/// Marks the mobs spawned by a [`MobSpawner`] (the spawner entity).
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SpawnedBy(pub Entity);
+
+/// Spawns mobs inside of an area.
+///
+/// The mobs are spawned on a random position in the area where they fit (air at the feet and head) and
+/// can stand (a solid block below).
+#[derive(Component, Debug)]
+pub struct MobSpawner {
+    pub layer: Entity,
+    pub area: Aabb,
+    pub schedule: SpawnSchedule,
+    /// The maximum number of mobs of this spawner that are alive at the same time.
+    pub max_alive: usize,
+    /// Add the damage, combat and physics components to the mobs (like the players have).
+    pub combat_components: bool,
+    /// Despawn the mobs when they die.
+    pub despawn_on_death: bool,
+    /// The spawner is paused (the timers are not ticked).
+    pub paused: bool,
+    alive: EntityHashSet,
+    timer: Timer,
+    /// The index of the current wave and the mobs of the wave that still have to be spawned.
+    wave: Option<(usize, Vec<SpawnEntry>)>,
+    finished: bool,
+}
+
+impl MobSpawner {
+    pub fn new(layer: Entity, area: Aabb, schedule: SpawnSchedule) -> Self {
+        let timer = match &schedule {
+            SpawnSchedule::Interval { interval, .. } => Timer::new(*interval, TimerMode::Repeating),
+            SpawnSchedule::Waves(waves) => Timer::new(
+                waves.first().map_or(Duration::ZERO, |wave| wave.delay),
+                TimerMode::Once,
+            ),
+        };
+
+        Self {
+            layer,
+            area,
+            schedule,
+            max_alive: 16,
+            combat_components: true,
+            despawn_on_death: true,
+            paused: false,
+            alive: EntityHashSet::default(),
+            timer,
+            wave: None,
+            finished: false,
+        }
+    }
+
+    pub fn with_max_alive(mut self, max_alive: usize) -> Self {
+        self.max_alive = max_alive;
+        self
+    }
+
+    pub fn without_combat_components(mut self) -> Self {
+        self.combat_components = false;
+        self
+    }
+
+    /// The mobs of this spawner that are alive.
+    pub fn alive(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.alive.iter().copied()
+    }
+
+    /// The index of the current wave.
+    pub fn current_wave(&self) -> Option<usize> {
+        self.wave.as_ref().map(|(index, _)| *index)
+    }
+
+    /// All waves are completed.
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+}
+
+/// An event that is emitted when a mob was spawned by a [`MobSpawner`].
+#[derive(Event, Clone, Copy, Debug)]
+pub struct MobSpawnedEvent {
+    pub spawner: Entity,
+    pub mob: Entity,
+}
+
+/// An event that is emitted when a wave of a [`MobSpawner`] started.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct WaveStartedEvent {
+    pub spawner: Entity,
+    pub wave: usize,
+}
+
+/// An event that is emitted when all mobs of a wave died.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct WaveCompletedEvent {
+    pub spawner: Entity,
+    pub wave: usize,
+    /// This was the last wave.
+    pub last: bool,
+}
+
+pub struct SpawnerPlugin;
+
+impl Plugin for SpawnerPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<MobSpawnedEvent>()
+            .add_event::<WaveStartedEvent>()
+            .add_event::<WaveCompletedEvent>()
+            .add_systems(Update, (remove_dead_mobs, update_spawners).chain());
+    }
+}
+
+/// Returns true if a mob can stand at the position.
+fn is_valid_spawn(layer: &ChunkLayer, position: BlockPos) -> bool {
+    let is_free = |pos: BlockPos| {
+        layer
+            .block(pos)
+            .is_some_and(|block| !block.state.blocks_motion() && !block.state.is_liquid())
+    };
+
+    let below = BlockPos::new(position.x, position.y - 1, position.z);
+
+    is_free(position)
+        && is_free(BlockPos::new(position.x, position.y + 1, position.z))
+        && layer
+            .block(below)
+            .is_some_and(|block| block.state.blocks_motion() && !block.state.is_liquid())
+}
+
+/// Find a random position in the area where a mob can be spawned.
+fn find_spawn_position(layer: &ChunkLayer, area: Aabb, rng: &mut impl Rng) -> Option<DVec3> {
+    const ATTEMPTS: usize = 16;
+
+    let min = area.min().floor().as_ivec3();
+    let max = area.max().floor().as_ivec3();
+
+    for _ in 0..ATTEMPTS {
+        let x = rng.gen_range(min.x..=max.x);
+        let z = rng.gen_range(min.z..=max.z);
+
+        // Search from the top, so mobs are not spawned in caves below the arena.
+        for y in (min.y..=max.y).rev() {
+            if is_valid_spawn(layer, BlockPos::new(x, y, z)) {
+                return Some(DVec3::new(x as f64 + 0.5, y as f64, z as f64 + 0.5));
+            }
+        }
+    }
+
+    None
+}
+
+fn remove_dead_mobs(
+    mut commands: Commands,
+    mut deaths: EventReader<DeathEvent>,
+    mobs: Query<&SpawnedBy>,
+    mut spawners: Query<&mut MobSpawner>,
+    entities: Query<(), Without<Despawned>>,
+) {
+    for event in deaths.read() {
+        let Ok(spawned_by) = mobs.get(event.victim) else {
+            continue;
+        };
+
+        let Ok(mut spawner) = spawners.get_mut(spawned_by.0) else {
+            continue;
+        };
+
+        spawner.alive.remove(&event.victim);
+
+        if spawner.despawn_on_death {
+            commands.entity(event.victim).insert(Despawned);
+        }
+    }
+
+    // Mobs can also be despawned by other plugins.
+    for mut spawner in spawners.iter_mut() {
+        spawner.alive.retain(|mob| entities.contains(*mob));
+    }
+}
+
+fn spawn_mob(
+    commands: &mut Commands,
+    spawner_entity: Entity,
+    spawner: &mut MobSpawner,
+    entry: SpawnEntry,
+    position: DVec3,
+    spawned_writer: &mut EventWriter<MobSpawnedEvent>,
+) {
+    let mob = (entry.spawn)(commands, spawner.layer, position);
+
+    let mut mob_commands = commands.entity(mob);
+    mob_commands.insert(SpawnedBy(spawner_entity));
+
+    if spawner.combat_components {
+        mob_commands.insert((
+            FallingState::new(position),
+            TakesDamage::default(),
+            BlockCollisionConfig::default(),
+            // TODO: set based on tick rate
+            Acceleration(Vec3::new(0.0, -32.0, 0.0)),
+            StopOnBlockCollision::ground(),
+            CombatState::default(),
+            EntityStatuses::default(),
+            Equipment::default(),
+        ));
+    }
+
+    spawner.alive.insert(mob);
+
+    spawned_writer.send(MobSpawnedEvent {
+        spawner: spawner_entity,
+        mob,
+    });
+}
+
+fn update_spawners(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut spawners: Query<(Entity, &mut MobSpawner)>,
+    layers: Query<&ChunkLayer>,
+    mut spawned_writer: EventWriter<MobSpawnedEvent>,
+    mut started_writer: EventWriter<WaveStartedEvent>,
+    mut completed_writer: EventWriter<WaveCompletedEvent>,
+) {
+    let mut rng = rand::thread_rng();
+
+    for (spawner_entity, mut spawner) in spawners.iter_mut() {
+        if spawner.paused || spawner.finished {
+            continue;
+        }
+
+        let Ok(layer) = layers.get(spawner.layer) else {
+            continue;
+        };
+
+        let spawner = &mut *spawner;
+        spawner.timer.tick(time.delta());
+
+        match &spawner.schedule {
+            SpawnSchedule::Interval { count, entries, .. } => {
+                if !spawner.timer.just_finished() {
+                    continue;
+                }
+
+                let entries = entries.clone();
+                let free = spawner.max_alive.saturating_sub(spawner.alive.len());
+
+                for _ in 0..(*count as usize).min(free) {
+                    let Ok(entry) = entries.choose_weighted(&mut rng, |entry| entry.weight) else {
+                        break;
+                    };
+
+                    let Some(position) = find_spawn_position(layer, spawner.area, &mut rng) else {
+                        continue;
+                    };
+
+                    spawn_mob(
+                        &mut commands,
+                        spawner_entity,
+                        spawner,
+                        *entry,
+                        position,
+                        &mut spawned_writer,
+                    );
+                }
+            }
+            SpawnSchedule::Waves(waves) => {
+                match &mut spawner.wave {
+                    None => {
+                        if !spawner.timer.finished() {
+                            continue;
+                        }
+
+                        // The timer of the first wave finished.
+                        let mobs = wave_mobs(waves, 0);
+                        spawner.wave = Some((0, mobs));
+                        started_writer.send(WaveStartedEvent {
+                            spawner: spawner_entity,
+                            wave: 0,
+                        });
+                    }
+                    Some((index, pending)) if pending.is_empty() && spawner.alive.is_empty() => {
+                        let index = *index;
+                        let last = index + 1 >= waves.len();
+
+                        completed_writer.send(WaveCompletedEvent {
+                            spawner: spawner_entity,
+                            wave: index,
+                            last,
+                        });
+
+                        if last {
+                            spawner.finished = true;
+                            continue;
+                        }
+
+                        let next = index + 1;
+
+                        spawner.timer = Timer::new(waves[next].delay, TimerMode::Once);
+                        spawner.wave = Some((next, wave_mobs(waves, next)));
+                        started_writer.send(WaveStartedEvent {
+                            spawner: spawner_entity,
+                            wave: next,
+                        });
+                    }
+                    Some(_) => {}
+                }
+
+                // Wait for the delay of the wave before spawning.
+                if !spawner.timer.finished() {
+                    continue;
+                }
+
+                while spawner.alive.len() < spawner.max_alive {
+                    let Some((_, pending)) = &mut spawner.wave else {
+                        break;
+                    };
+
+                    let Some(entry) = pending.last().copied() else {
+                        break;
+                    };
+
+                    let Some(position) = find_spawn_position(layer, spawner.area, &mut rng) else {
+                        break;
+                    };
+
+                    pending.pop();
+
+                    spawn_mob(
+                        &mut commands,
+                        spawner_entity,
+                        spawner,
+                        entry,
+                        position,
+                        &mut spawned_writer,
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// The mobs of the wave in a random order.
+fn wave_mobs(waves: &[Wave], index: usize) -> Vec<SpawnEntry> {
+    let mut mobs = waves
+        .get(index)
+        .into_iter()
+        .flat_map(|wave| wave.mobs.iter())
+        .flat_map(|(entry, count)| std::iter::repeat(*entry).take(*count as usize))
+        .collect::<Vec<_>>();
+
+    mobs.shuffle(&mut rand::thread_rng());
+    mobs
+}
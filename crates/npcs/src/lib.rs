@@ -0,0 +1,202 @@
+use valence::{
+    entity::{player::PlayerEntityBundle, EntityId, HeadYaw},
+    keepalive::Ping,
+    player_list::{DisplayName, Listed, PlayerListEntry},
+    prelude::*,
+    protocol::profile::Property,
+    uuid::Uuid,
+};
+
+/// Attached to all NPCs spawned with a [`NpcBuilder`].
+#[derive(Component, Clone, Debug)]
+pub struct Npc {
+    pub name: String,
+}
+
+/// The NPC will look at the closest player in range.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct LookAtPlayers {
+    pub range: f64,
+}
+
+/// The skin of a NPC, this is the `textures` property of a (signed) game profile.
+#[derive(Clone, Debug)]
+pub struct Skin {
+    pub value: String,
+    pub signature: Option<String>,
+}
+
+/// An event that is emitted if a player interacts with (or attacks) a NPC.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct NpcInteractEvent {
+    pub client: Entity,
+    pub npc: Entity,
+    pub interact: EntityInteraction,
+}
+
+/// A helper to spawn player NPCs.
+///
+/// ```ignore
+/// let npc = NpcBuilder::new("Shop", layer, position)
+///     .skin(skin)
+///     .look_at_players(8.0)
+///     .spawn(&mut commands);
+///
+/// // Combat components can be added like for players.
+/// commands.entity(npc).insert((CombatState::default(), TakesDamage::default()));
+/// ```
+pub struct NpcBuilder {
+    name: String,
+    layer: Entity,
+    position: DVec3,
+    look: Look,
+    skin: Option<Skin>,
+    look_at_players: Option<f64>,
+}
+
+impl NpcBuilder {
+    /// The name will be shown above the NPC, it can be at most 16 characters long.
+    pub fn new(name: impl Into<String>, layer: Entity, position: DVec3) -> Self {
+        Self {
+            name: name.into(),
+            layer,
+            position,
+            look: Look::default(),
+            skin: None,
+            look_at_players: None,
+        }
+    }
+
+    pub fn look(mut self, look: Look) -> Self {
+        self.look = look;
+        self
+    }
+
+    pub fn skin(mut self, skin: Skin) -> Self {
+        self.skin = Some(skin);
+        self
+    }
+
+    /// Look at the closest player within the range.
+    pub fn look_at_players(mut self, range: f64) -> Self {
+        self.look_at_players = Some(range);
+        self
+    }
+
+    pub fn spawn(self, commands: &mut Commands) -> Entity {
+        let uuid = Uuid::from_u128(rand::random());
+        let name = self.name.chars().take(16).collect::<String>();
+
+        let properties = self
+            .skin
+            .map(|skin| {
+                vec![Property {
+                    name: "textures".into(),
+                    value: skin.value,
+                    signature: skin.signature,
+                }]
+            })
+            .unwrap_or_default();
+
+        let mut npc = commands.spawn((
+            PlayerEntityBundle {
+                layer: EntityLayerId(self.layer),
+                uuid: UniqueId(uuid),
+                position: Position(self.position),
+                look: self.look,
+                head_yaw: HeadYaw(self.look.yaw),
+                ..Default::default()
+            },
+            // The player list entry is needed for the client to render the player (and the skin).
+            (
+                PlayerListEntry,
+                Username(name.clone()),
+                Properties(properties),
+                GameMode::Survival,
+                Ping::default(),
+                DisplayName(None),
+                Listed(false),
+            ),
+            Npc { name },
+        ));
+
+        if let Some(range) = self.look_at_players {
+            npc.insert(LookAtPlayers { range });
+        }
+
+        npc.id()
+    }
+}
+
+pub struct NpcPlugin;
+
+impl Plugin for NpcPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<NpcInteractEvent>()
+            .add_systems(Update, (npc_interactions, look_at_players));
+    }
+}
+
+fn npc_interactions(
+    mut events: EventReader<InteractEntityEvent>,
+    npcs: Query<(), With<Npc>>,
+    mut npc_events: EventWriter<NpcInteractEvent>,
+) {
+    for event in events.read() {
+        if npcs.get(event.entity).is_err() {
+            continue;
+        }
+
+        npc_events.send(NpcInteractEvent {
+            client: event.client,
+            npc: event.entity,
+            interact: event.interact,
+        });
+    }
+}
+
+fn look_at_players(
+    mut npcs: Query<
+        (
+            &Position,
+            &EntityLayerId,
+            &LookAtPlayers,
+            &mut Look,
+            &mut HeadYaw,
+        ),
+        (With<Npc>, Without<Client>),
+    >,
+    players: Query<(&Position, &EntityLayerId), (With<Client>, With<EntityId>)>,
+) {
+    for (position, layer, look_at, mut look, mut head_yaw) in npcs.iter_mut() {
+        let closest = players
+            .iter()
+            .filter(|(_, player_layer)| player_layer.0 == layer.0)
+            .map(|(player_position, _)| player_position.0)
+            .filter(|player_position| player_position.distance(position.0) <= look_at.range)
+            .min_by(|a, b| {
+                a.distance_squared(position.0)
+                    .total_cmp(&b.distance_squared(position.0))
+            });
+
+        let Some(target) = closest else {
+            continue;
+        };
+
+        // Both are players, so the eye height does not matter.
+        let direction = (target - position.0).normalize_or_zero();
+
+        if direction == DVec3::ZERO {
+            continue;
+        }
+
+        let yaw = (-direction.x).atan2(direction.z).to_degrees() as f32;
+        let pitch = (-direction.y).asin().to_degrees() as f32;
+
+        if look.yaw != yaw || look.pitch != pitch {
+            look.yaw = yaw;
+            look.pitch = pitch;
+            head_yaw.0 = yaw;
+        }
+    }
+}
@@ -0,0 +1,47 @@
+use std::time::Duration;
+
+use chat::ChatAbility;
+use serde::{Deserialize, Serialize};
+use valence::{
+    ecs::world::{EntityRef, EntityWorldMut},
+    uuid::Uuid,
+};
+
+/// A player on the [`ChatAbility::muted_players`] list.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct SavedMute {
+    uuid: String,
+    /// `None` if the mute is permanent.
+    remaining: Option<Duration>,
+}
+
+pub(crate) fn save_mutes(entity: EntityRef) -> Option<Vec<SavedMute>> {
+    let mutes = &entity.get::<ChatAbility>()?.muted_players;
+
+    Some(
+        mutes
+            .iter()
+            .filter(|uuid| mutes.is_muted(*uuid))
+            .map(|uuid| SavedMute {
+                uuid: uuid.to_string(),
+                remaining: mutes.remaining(uuid),
+            })
+            .collect(),
+    )
+}
+
+pub(crate) fn load_mutes(entity: &mut EntityWorldMut, mutes: Vec<SavedMute>) {
+    if !entity.contains::<ChatAbility>() {
+        entity.insert(ChatAbility::default());
+    }
+
+    let Some(mut ability) = entity.get_mut::<ChatAbility>() else {
+        return;
+    };
+
+    for mute in mutes {
+        if let Ok(uuid) = Uuid::parse_str(&mute.uuid) {
+            ability.mute(uuid, mute.remaining);
+        }
+    }
+}
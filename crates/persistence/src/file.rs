@@ -0,0 +1,50 @@
+use std::{
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use valence::uuid::Uuid;
+
+use crate::{PlayerData, PlayerStore};
+
+/// Stores every player in a `<uuid>.json` file in a directory.
+pub struct FileStore {
+    directory: PathBuf,
+}
+
+impl FileStore {
+    /// Create the directory if it does not exist.
+    pub fn new(directory: impl AsRef<Path>) -> std::io::Result<Self> {
+        fs::create_dir_all(&directory)?;
+
+        Ok(Self {
+            directory: directory.as_ref().to_path_buf(),
+        })
+    }
+
+    fn path(&self, uuid: Uuid) -> PathBuf {
+        self.directory.join(format!("{uuid}.json"))
+    }
+}
+
+impl PlayerStore for FileStore {
+    fn load(&self, uuid: Uuid) -> std::io::Result<Option<PlayerData>> {
+        let json = match fs::read_to_string(self.path(uuid)) {
+            Ok(json) => json,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+
+        Ok(Some(serde_json::from_str(&json)?))
+    }
+
+    fn save(&self, uuid: Uuid, data: &PlayerData) -> std::io::Result<()> {
+        let path = self.path(uuid);
+        // Write to a temporary file first, so a crash does not leave a half written file.
+        let temp_path = path.with_extension("json.tmp");
+
+        fs::write(&temp_path, serde_json::to_vec(data)?)?;
+        fs::rename(temp_path, path)
+    }
+}
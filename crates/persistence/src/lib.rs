@@ -0,0 +1,446 @@
+//! Saves the data of players (inventory, position, health, experience, ...) when they leave
+//! and loads it again when they join.
+//!
+//! The persistence is opt-in, insert a [`PlayerStorage`] resource with a [`PlayerStore`] to enable it.
+//! Components of other crates can be saved with the player by registering them with [`PlayerDataAppExt`].
+
+#[cfg(feature = "chat")]
+mod chat_mutes;
+mod file;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+
+use std::collections::HashMap;
+
+pub use file::FileStore;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use shutdown::{Shutdown, ShutdownSet};
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteStore;
+use valence::{
+    ecs::world::{Command, EntityRef, EntityWorldMut},
+    entity::living::Health,
+    nbt::Compound,
+    prelude::*,
+    protocol::{packets::play::ExperienceBarUpdateS2c, VarInt, WritePacket},
+    uuid::Uuid,
+};
+
+const HEAD_SLOT: u16 = 5;
+const CHEST_SLOT: u16 = 6;
+const LEGS_SLOT: u16 = 7;
+const FEET_SLOT: u16 = 8;
+const OFFHAND_SLOT: u16 = 45;
+
+/// A serializable [`ItemStack`].
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SavedItem {
+    #[serde(with = "utils::serialization::item_kind")]
+    pub item: ItemKind,
+    pub count: i8,
+    /// The NBT of the item in the binary format.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nbt: Option<Vec<u8>>,
+}
+
+impl SavedItem {
+    pub fn to_stack(&self) -> ItemStack {
+        let nbt = self.nbt.as_ref().and_then(|bytes| {
+            let (nbt, _root): (Compound, String) =
+                valence::nbt::from_binary(&mut bytes.as_slice()).ok()?;
+            Some(nbt)
+        });
+
+        ItemStack::new(self.item, self.count, nbt)
+    }
+}
+
+impl From<&ItemStack> for SavedItem {
+    fn from(stack: &ItemStack) -> Self {
+        let nbt = stack.nbt.as_ref().and_then(|nbt| {
+            let mut bytes = Vec::new();
+            valence::nbt::to_binary(nbt, &mut bytes, "").ok()?;
+            Some(bytes)
+        });
+
+        Self {
+            item: stack.item,
+            count: stack.count,
+            nbt,
+        }
+    }
+}
+
+/// The experience of a player, changes are sent to the client.
+#[derive(Component, Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub struct Experience {
+    pub level: i32,
+    /// The progress to the next level (`0.0..=1.0`).
+    pub progress: f32,
+    pub total: i32,
+}
+
+/// The saved data of a player.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PlayerData {
+    /// The non-empty slots of the player inventory, this includes the armor and the offhand.
+    pub inventory: HashMap<u16, SavedItem>,
+    pub position: Option<[f64; 3]>,
+    /// The yaw and pitch.
+    pub look: Option<[f32; 2]>,
+    pub health: Option<f32>,
+    pub experience: Option<Experience>,
+    /// The data of the components registered with [`PlayerDataAppExt`] by their key.
+    pub extensions: HashMap<String, serde_json::Value>,
+}
+
+/// A storage backend for the [`PlayerStorage`].
+///
+/// Players are loaded when they join and saved when they leave (or when the server shuts down),
+/// this happens on the main thread.
+pub trait PlayerStore: Send + Sync + 'static {
+    /// Load the data of a player, `None` if the player was never saved.
+    fn load(&self, uuid: Uuid) -> std::io::Result<Option<PlayerData>>;
+    fn save(&self, uuid: Uuid, data: &PlayerData) -> std::io::Result<()>;
+}
+
+/// The store that is used to load and save the players.
+#[derive(Resource)]
+pub struct PlayerStorage {
+    store: Box<dyn PlayerStore>,
+}
+
+impl PlayerStorage {
+    pub fn new(store: impl PlayerStore) -> Self {
+        Self {
+            store: Box::new(store),
+        }
+    }
+
+    pub fn load(&self, uuid: Uuid) -> std::io::Result<Option<PlayerData>> {
+        self.store.load(uuid)
+    }
+
+    pub fn save(&self, uuid: Uuid, data: &PlayerData) -> std::io::Result<()> {
+        self.store.save(uuid, data)
+    }
+}
+
+type SaveFn = Box<dyn Fn(EntityRef) -> Option<serde_json::Value> + Send + Sync>;
+type LoadFn =
+    Box<dyn Fn(&mut EntityWorldMut, serde_json::Value) -> serde_json::Result<()> + Send + Sync>;
+
+struct PlayerDataExtension {
+    key: String,
+    save: SaveFn,
+    load: LoadFn,
+}
+
+/// The additional data that is saved with the players, see [`PlayerDataAppExt`].
+#[derive(Resource, Default)]
+pub struct PlayerDataExtensions {
+    extensions: Vec<PlayerDataExtension>,
+}
+
+impl PlayerDataExtensions {
+    /// Register additional data that is stored under `key`.
+    ///
+    /// `save` returns `None` if the player has no data to save.
+    pub fn register<T: Serialize + DeserializeOwned>(
+        &mut self,
+        key: impl Into<String>,
+        save: fn(EntityRef) -> Option<T>,
+        load: fn(&mut EntityWorldMut, T),
+    ) {
+        self.extensions.push(PlayerDataExtension {
+            key: key.into(),
+            save: Box::new(move |entity| {
+                save(entity).and_then(|data| serde_json::to_value(data).ok())
+            }),
+            load: Box::new(move |entity, value| {
+                load(entity, serde_json::from_value(value)?);
+                Ok(())
+            }),
+        });
+    }
+
+    /// Save the component `T` under `key`, the component is inserted when the player is loaded.
+    pub fn register_component<T: Component + Serialize + DeserializeOwned>(
+        &mut self,
+        key: impl Into<String>,
+    ) {
+        self.extensions.push(PlayerDataExtension {
+            key: key.into(),
+            save: Box::new(|entity| {
+                entity
+                    .get::<T>()
+                    .and_then(|component| serde_json::to_value(component).ok())
+            }),
+            load: Box::new(|entity, value| {
+                entity.insert(serde_json::from_value::<T>(value)?);
+                Ok(())
+            }),
+        });
+    }
+}
+
+pub trait PlayerDataAppExt {
+    /// See [`PlayerDataExtensions::register`].
+    fn register_player_data<T: Serialize + DeserializeOwned>(
+        &mut self,
+        key: impl Into<String>,
+        save: fn(EntityRef) -> Option<T>,
+        load: fn(&mut EntityWorldMut, T),
+    ) -> &mut Self;
+
+    /// See [`PlayerDataExtensions::register_component`].
+    fn persist_component<T: Component + Serialize + DeserializeOwned>(
+        &mut self,
+        key: impl Into<String>,
+    ) -> &mut Self;
+}
+
+impl PlayerDataAppExt for App {
+    fn register_player_data<T: Serialize + DeserializeOwned>(
+        &mut self,
+        key: impl Into<String>,
+        save: fn(EntityRef) -> Option<T>,
+        load: fn(&mut EntityWorldMut, T),
+    ) -> &mut Self {
+        self.world_mut()
+            .get_resource_or_insert_with(PlayerDataExtensions::default)
+            .register(key, save, load);
+        self
+    }
+
+    fn persist_component<T: Component + Serialize + DeserializeOwned>(
+        &mut self,
+        key: impl Into<String>,
+    ) -> &mut Self {
+        self.world_mut()
+            .get_resource_or_insert_with(PlayerDataExtensions::default)
+            .register_component::<T>(key);
+        self
+    }
+}
+
+/// An event that is emitted after the data of a joined player was loaded.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct PlayerDataLoadedEvent {
+    pub player: Entity,
+    /// The player was never saved before.
+    pub first_join: bool,
+}
+
+/// The players are loaded in this set.
+///
+/// Systems that initialize new clients (e.g. set the spawn position or give the starting items)
+/// should run before this set, otherwise they overwrite the loaded data.
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LoadPlayerDataSet;
+
+pub struct PersistencePlugin;
+
+impl Plugin for PersistencePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<PlayerDataLoadedEvent>()
+            .init_resource::<PlayerDataExtensions>()
+            .add_systems(
+                Update,
+                load_players
+                    .in_set(LoadPlayerDataSet)
+                    .run_if(resource_exists::<PlayerStorage>),
+            )
+            .add_systems(PostUpdate, send_experience)
+            .add_systems(
+                Shutdown,
+                save_all_players
+                    .in_set(ShutdownSet::FlushStores)
+                    .run_if(resource_exists::<PlayerStorage>),
+            )
+            .observe(save_on_leave);
+
+        #[cfg(feature = "chat")]
+        app.register_player_data("chat_mutes", chat_mutes::save_mutes, chat_mutes::load_mutes);
+    }
+}
+
+/// Collect the data of a player, `None` if the entity is not a player.
+pub fn collect_player_data(
+    entity: EntityRef,
+    extensions: &PlayerDataExtensions,
+) -> Option<(Uuid, PlayerData)> {
+    let uuid = entity.get::<UniqueId>()?.0;
+
+    let inventory = entity
+        .get::<Inventory>()
+        .map(|inventory| {
+            (0..inventory.slot_count())
+                .filter(|slot| !inventory.slot(*slot).is_empty())
+                .map(|slot| (slot, SavedItem::from(inventory.slot(slot))))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let extensions = extensions
+        .extensions
+        .iter()
+        .filter_map(|extension| Some((extension.key.clone(), (extension.save)(entity)?)))
+        .collect();
+
+    Some((
+        uuid,
+        PlayerData {
+            inventory,
+            position: entity.get::<Position>().map(|pos| pos.0.to_array()),
+            look: entity.get::<Look>().map(|look| [look.yaw, look.pitch]),
+            health: entity.get::<Health>().map(|health| health.0),
+            experience: entity.get::<Experience>().copied(),
+            extensions,
+        },
+    ))
+}
+
+fn save_player(entity: EntityRef, storage: &PlayerStorage, extensions: &PlayerDataExtensions) {
+    let Some((uuid, data)) = collect_player_data(entity, extensions) else {
+        return;
+    };
+
+    if let Err(e) = storage.save(uuid, &data) {
+        tracing::error!("failed to save the player {uuid}: {e}");
+    }
+}
+
+fn save_on_leave(
+    trigger: Trigger<OnRemove, Client>,
+    entities: Query<EntityRef>,
+    storage: Option<Res<PlayerStorage>>,
+    extensions: Res<PlayerDataExtensions>,
+) {
+    let (Some(storage), Ok(entity)) = (storage, entities.get(trigger.entity())) else {
+        return;
+    };
+
+    save_player(entity, &storage, &extensions);
+}
+
+fn save_all_players(
+    players: Query<EntityRef, With<Client>>,
+    storage: Res<PlayerStorage>,
+    extensions: Res<PlayerDataExtensions>,
+) {
+    for entity in players.iter() {
+        save_player(entity, &storage, &extensions);
+    }
+}
+
+fn load_players(
+    mut commands: Commands,
+    storage: Res<PlayerStorage>,
+    clients: Query<(Entity, &UniqueId), Added<Client>>,
+) {
+    for (entity, uuid) in clients.iter() {
+        match storage.load(uuid.0) {
+            Ok(data) => commands.add(ApplyPlayerData { entity, data }),
+            Err(e) => tracing::error!("failed to load the player {}: {e}", uuid.0),
+        }
+    }
+}
+
+struct ApplyPlayerData {
+    entity: Entity,
+    data: Option<PlayerData>,
+}
+
+impl Command for ApplyPlayerData {
+    fn apply(self, world: &mut World) {
+        let first_join = self.data.is_none();
+
+        if let Some(mut data) = self.data {
+            world.resource_scope(|world, extensions: Mut<PlayerDataExtensions>| {
+                let Some(mut entity) = world.get_entity_mut(self.entity) else {
+                    return;
+                };
+
+                apply_player_data(&mut entity, &mut data, &extensions);
+            });
+        }
+
+        world.send_event(PlayerDataLoadedEvent {
+            player: self.entity,
+            first_join,
+        });
+    }
+}
+
+fn apply_player_data(
+    entity: &mut EntityWorldMut,
+    data: &mut PlayerData,
+    extensions: &PlayerDataExtensions,
+) {
+    if let Some(mut inventory) = entity.get_mut::<Inventory>() {
+        for slot in 0..inventory.slot_count() {
+            let stack = data
+                .inventory
+                .get(&slot)
+                .map(SavedItem::to_stack)
+                .unwrap_or(ItemStack::EMPTY);
+
+            inventory.set_slot(slot, stack);
+        }
+    }
+
+    // The armor and the offhand are also shown to other players.
+    if let Some(mut equipment) = entity.get_mut::<Equipment>() {
+        let stack = |slot| {
+            data.inventory
+                .get(&slot)
+                .map(SavedItem::to_stack)
+                .unwrap_or(ItemStack::EMPTY)
+        };
+
+        equipment.set_head(stack(HEAD_SLOT));
+        equipment.set_chest(stack(CHEST_SLOT));
+        equipment.set_legs(stack(LEGS_SLOT));
+        equipment.set_feet(stack(FEET_SLOT));
+        equipment.set_off_hand(stack(OFFHAND_SLOT));
+    }
+
+    if let (Some(saved), Some(mut position)) = (data.position, entity.get_mut::<Position>()) {
+        position.0 = DVec3::from_array(saved);
+    }
+
+    if let (Some([yaw, pitch]), Some(mut look)) = (data.look, entity.get_mut::<Look>()) {
+        look.yaw = yaw;
+        look.pitch = pitch;
+    }
+
+    if let (Some(saved), Some(mut health)) = (data.health, entity.get_mut::<Health>()) {
+        health.0 = saved;
+    }
+
+    if let Some(experience) = data.experience {
+        entity.insert(experience);
+    }
+
+    for extension in extensions.extensions.iter() {
+        let Some(value) = data.extensions.remove(&extension.key) else {
+            continue;
+        };
+
+        if let Err(e) = (extension.load)(entity, value) {
+            tracing::warn!("failed to load the player data `{}`: {e}", extension.key);
+        }
+    }
+}
+
+fn send_experience(mut clients: Query<(&mut Client, &Experience), Changed<Experience>>) {
+    for (mut client, experience) in clients.iter_mut() {
+        client.write_packet(&ExperienceBarUpdateS2c {
+            bar: experience.progress,
+            level: VarInt(experience.level),
+            total_xp: VarInt(experience.total),
+        });
+    }
+}
@@ -0,0 +1,61 @@
+use std::{path::Path, sync::Mutex};
+
+use rusqlite::{params, Connection, OptionalExtension};
+use valence::uuid::Uuid;
+
+use crate::{PlayerData, PlayerStore};
+
+/// Stores the players as JSON in the `players` table of a SQLite database.
+pub struct SqliteStore {
+    connection: Mutex<Connection>,
+}
+
+impl SqliteStore {
+    /// Open (or create) the database and create the `players` table if it does not exist.
+    pub fn open(path: impl AsRef<Path>) -> rusqlite::Result<Self> {
+        let connection = Connection::open(path)?;
+
+        connection.execute_batch(
+            "CREATE TABLE IF NOT EXISTS players (
+                uuid TEXT PRIMARY KEY,
+                data TEXT NOT NULL
+            );",
+        )?;
+
+        Ok(Self {
+            connection: Mutex::new(connection),
+        })
+    }
+}
+
+impl PlayerStore for SqliteStore {
+    fn load(&self, uuid: Uuid) -> std::io::Result<Option<PlayerData>> {
+        let connection = self.connection.lock().unwrap();
+
+        let json = connection
+            .query_row(
+                "SELECT data FROM players WHERE uuid = ?1",
+                params![uuid.to_string()],
+                |row| row.get::<_, String>(0),
+            )
+            .optional()
+            .map_err(std::io::Error::other)?;
+
+        json.map(|json| serde_json::from_str(&json).map_err(std::io::Error::from))
+            .transpose()
+    }
+
+    fn save(&self, uuid: Uuid, data: &PlayerData) -> std::io::Result<()> {
+        let json = serde_json::to_string(data)?;
+        let connection = self.connection.lock().unwrap();
+
+        connection
+            .execute(
+                "INSERT INTO players (uuid, data) VALUES (?1, ?2)
+                ON CONFLICT (uuid) DO UPDATE SET data = excluded.data",
+                params![uuid.to_string(), json],
+            )
+            .map(|_| ())
+            .map_err(std::io::Error::other)
+    }
+}
@@ -0,0 +1,170 @@
+//! Deal damage and knockback to all entities in an area (e.g. explosions or spells).
+
+use ::utils::{
+    damage::{DamageEvent, DamageType, TakesDamage},
+    knockback::ApplyKnockbackEvent,
+    line_of_sight,
+};
+use bvh::bvh_resource::{BvhResource, ENTITY_ENTITY_BVH_IDX};
+use valence::{ecs::system::SystemParam, math::Aabb, prelude::*};
+
+use crate::EntityCollisionConfig;
+
+/// The damage multiplier of an entity at the given distance from the center of an [`AreaDamage`].
+///
+/// The parameters are: `distance`, `radius`.
+pub type Falloff = fn(f64, f64) -> f32;
+
+/// The damage decreases linearly with the distance to the center.
+pub fn linear_falloff(distance: f64, radius: f64) -> f32 {
+    (1.0 - distance / radius).clamp(0.0, 1.0) as f32
+}
+
+/// The full damage is dealt to every entity in the radius.
+pub fn no_falloff(_distance: f64, _radius: f64) -> f32 {
+    1.0
+}
+
+/// A helper to deal damage to all entities in an area (e.g. explosions or spells).
+///
+/// The victims are looked up in the entity BVH (entities with an [`EntityCollisionConfig`]),
+/// entities that are not in the BVH (e.g. players) are checked one by one.
+#[derive(SystemParam)]
+pub struct AreaDamage<'w, 's> {
+    bvh: Res<'w, BvhResource>,
+    victims: Query<'w, 's, (Entity, &'static Hitbox, &'static EntityLayerId), With<TakesDamage>>,
+    /// The entities that take damage, but are not in the BVH.
+    unindexed: Query<'w, 's, Entity, (With<TakesDamage>, Without<EntityCollisionConfig>)>,
+    layers: Query<'w, 's, &'static ChunkLayer>,
+    damage_writer: EventWriter<'w, DamageEvent>,
+    knockback_writer: EventWriter<'w, ApplyKnockbackEvent>,
+}
+
+impl AreaDamage<'_, '_> {
+    /// Deal damage to all entities (on the layer) in the radius around the center.
+    ///
+    /// The damage is multiplied by the `falloff` and by how much of the hitbox of the victim
+    /// can be seen from the center (blocks protect from the damage).
+    pub fn deal_area_damage(
+        &mut self,
+        layer: Entity,
+        center: DVec3,
+        radius: f64,
+        base_damage: f32,
+        damage_type: DamageType,
+        falloff: Falloff,
+        source: Option<Entity>,
+    ) {
+        let chunk_layer = self.layers.get(layer).ok();
+
+        for candidate in self.candidates(center, radius) {
+            let Ok((victim, hitbox, victim_layer)) = self.victims.get(candidate) else {
+                continue;
+            };
+
+            if victim_layer.0 != layer {
+                continue;
+            }
+
+            let hitbox = hitbox.get();
+            let closest = center.clamp(hitbox.min(), hitbox.max());
+            let distance = closest.distance(center);
+
+            if distance > radius {
+                continue;
+            }
+
+            let exposure =
+                chunk_layer.map_or(1.0, |chunk_layer| exposure(chunk_layer, center, hitbox));
+            let damage = base_damage * falloff(distance, radius) * exposure;
+
+            if damage <= 0.0 {
+                continue;
+            }
+
+            self.damage_writer.send(DamageEvent {
+                victim,
+                attacker: source,
+                damage,
+                damage_type,
+                weapon: None,
+                projectile: None,
+                direct_source: None,
+                area_of_effect: true,
+            });
+        }
+    }
+
+    /// Knock back all entities (on the layer) in the radius away from the center (e.g. explosions).
+    ///
+    /// The velocity (in blocks per second) is multiplied by the `falloff`.
+    pub fn deal_area_knockback(
+        &mut self,
+        layer: Entity,
+        center: DVec3,
+        radius: f64,
+        velocity: f32,
+        falloff: Falloff,
+    ) {
+        for candidate in self.candidates(center, radius) {
+            let Ok((victim, hitbox, victim_layer)) = self.victims.get(candidate) else {
+                continue;
+            };
+
+            if victim_layer.0 != layer {
+                continue;
+            }
+
+            let hitbox = hitbox.get();
+            let hitbox_center = (hitbox.min() + hitbox.max()) / 2.0;
+            let distance = center.clamp(hitbox.min(), hitbox.max()).distance(center);
+
+            if distance > radius {
+                continue;
+            }
+
+            let direction = (hitbox_center - center).normalize_or_zero().as_vec3();
+
+            self.knockback_writer.send(ApplyKnockbackEvent::add(
+                victim,
+                direction * velocity * falloff(distance, radius),
+            ));
+        }
+    }
+
+    /// The entities that could be in the radius around the center.
+    fn candidates(&self, center: DVec3, radius: f64) -> Vec<Entity> {
+        let mut candidates = self.bvh[ENTITY_ENTITY_BVH_IDX]
+            .get_in_sphere(center, radius)
+            .map(|entry| entry.entity)
+            .collect::<Vec<_>>();
+
+        candidates.extend(self.unindexed.iter());
+        candidates
+    }
+}
+
+/// How much (0.0 - 1.0) of the hitbox can be seen from the position.
+///
+/// The center and the (slightly moved in) corners of the hitbox are checked.
+fn exposure(layer: &ChunkLayer, from: DVec3, hitbox: Aabb) -> f32 {
+    let min = hitbox.min().lerp(hitbox.max(), 0.1);
+    let max = hitbox.max().lerp(hitbox.min(), 0.1);
+
+    let mut points = vec![(min + max) / 2.0];
+
+    for x in [min.x, max.x] {
+        for y in [min.y, max.y] {
+            for z in [min.z, max.z] {
+                points.push(DVec3::new(x, y, z));
+            }
+        }
+    }
+
+    let visible = points
+        .iter()
+        .filter(|point| line_of_sight(layer, from, **point))
+        .count();
+
+    visible as f32 / points.len() as f32
+}
@@ -0,0 +1,160 @@
+//! Arrows that stick in the blocks they hit and can be picked up again.
+
+use std::time::{Duration, Instant};
+
+use ::utils::{
+    enchantments::{Enchantment, ItemStackEnchantmentsExt},
+    inventory::InventoryExt,
+    sound::{SoundBuilder, Sounds},
+};
+use valence::{
+    entity::Velocity,
+    math::Aabb,
+    prelude::*,
+    protocol::{sound::SoundCategory, Sound},
+};
+
+use crate::{
+    Acceleration, BlockCollisionConfig, Drag, EntityBlockCollisionEvent, EntityCollisionConfig,
+    StopOnBlockCollision,
+};
+
+/// How far (horizontally) the hitbox of a player reaches to pick up arrows.
+const PICKUP_RANGE_HORIZONTAL: f64 = 1.0;
+/// How far (vertically) the hitbox of a player reaches to pick up arrows.
+const PICKUP_RANGE_VERTICAL: f64 = 0.5;
+
+/// Arrows with this component stick in the block they hit, instead of sliding along it.
+///
+/// Stuck arrows can be picked up by players walking over them and despawn after [`StickingArrow::lifetime`].
+/// Spawn the arrow with [`ProjectileBuilder::stop_on_block_collision`](crate::projectile::ProjectileBuilder::stop_on_block_collision),
+/// so it stops where it hit the block.
+#[derive(Component, Clone, Debug)]
+pub struct StickingArrow {
+    /// The item players get when they pick up the arrow,
+    /// `None` if the arrow can not be picked up (e.g. shot from a bow with infinity).
+    pub pickup_item: Option<ItemStack>,
+    /// How long the arrow stays in the block before it despawns (vanilla is 60 seconds).
+    pub lifetime: Duration,
+    stuck_since: Option<Instant>,
+}
+
+impl StickingArrow {
+    pub fn new(pickup_item: Option<ItemStack>) -> Self {
+        Self {
+            pickup_item,
+            lifetime: Duration::from_secs(60),
+            stuck_since: None,
+        }
+    }
+
+    /// An arrow shot from the bow, it can only be picked up if the bow does not have infinity.
+    pub fn shot_from(bow: &ItemStack) -> Self {
+        let pickup_item = (!bow.enchantments().contains_key(&Enchantment::Infinity))
+            .then(|| ItemStack::new(ItemKind::Arrow, 1, None));
+
+        Self::new(pickup_item)
+    }
+
+    pub fn with_lifetime(mut self, lifetime: Duration) -> Self {
+        self.lifetime = lifetime;
+        self
+    }
+
+    /// If the arrow is stuck in a block.
+    pub fn is_stuck(&self) -> bool {
+        self.stuck_since.is_some()
+    }
+}
+
+/// Arrows that hit a block stop moving and no longer collide with anything.
+pub(crate) fn stick_arrows(
+    mut commands: Commands,
+    mut events: EventReader<EntityBlockCollisionEvent>,
+    mut arrows: Query<(&mut StickingArrow, &mut Velocity)>,
+) {
+    for event in events.read() {
+        let Ok((mut arrow, mut velocity)) = arrows.get_mut(event.entity) else {
+            continue;
+        };
+
+        if arrow.is_stuck() {
+            continue;
+        }
+
+        arrow.stuck_since = Some(Instant::now());
+        velocity.0 = Vec3::ZERO;
+
+        commands.entity(event.entity).remove::<(
+            Acceleration,
+            Drag,
+            EntityCollisionConfig,
+            BlockCollisionConfig,
+            StopOnBlockCollision,
+        )>();
+    }
+}
+
+/// Players pick up the stuck arrows their hitbox touches, if they have space in their inventory.
+pub(crate) fn pick_up_arrows(
+    mut commands: Commands,
+    mut arrows: Query<(Entity, &mut StickingArrow, &Position, &EntityLayerId), Without<Despawned>>,
+    mut players: Query<(Entity, &Position, &Hitbox, &EntityLayerId, &mut Inventory), With<Client>>,
+    mut sounds: Sounds,
+) {
+    for (arrow_entity, mut arrow, arrow_position, arrow_layer) in arrows.iter_mut() {
+        if !arrow.is_stuck() {
+            continue;
+        }
+
+        // Arrows that can not be picked up stay until their lifetime is over.
+        let Some(item) = arrow.pickup_item.clone() else {
+            continue;
+        };
+
+        for (player, player_position, hitbox, player_layer, mut inventory) in players.iter_mut() {
+            if player_layer.0 != arrow_layer.0 {
+                continue;
+            }
+
+            let hitbox = hitbox.get();
+            let grow = DVec3::new(
+                PICKUP_RANGE_HORIZONTAL,
+                PICKUP_RANGE_VERTICAL,
+                PICKUP_RANGE_HORIZONTAL,
+            );
+
+            if !Aabb::new(hitbox.min() - grow, hitbox.max() + grow).contains_point(arrow_position.0)
+            {
+                continue;
+            }
+
+            if !inventory.add_item(&item, None) {
+                continue;
+            }
+
+            arrow.pickup_item = None;
+            commands.entity(arrow_entity).insert(Despawned);
+
+            SoundBuilder::new(Sound::EntityItemPickup, player_position.0)
+                .category(SoundCategory::Player)
+                .volume(0.2)
+                .play(&mut sounds, player_layer.0);
+            break;
+        }
+    }
+}
+
+pub(crate) fn despawn_stuck_arrows(
+    mut commands: Commands,
+    arrows: Query<(Entity, &StickingArrow), Without<Despawned>>,
+) {
+    for (entity, arrow) in arrows.iter() {
+        if arrow
+            .stuck_since
+            .is_some_and(|since| since.elapsed() >= arrow.lifetime)
+        {
+            commands.entity(entity).insert(Despawned);
+        }
+    }
+}
@@ -0,0 +1,344 @@
+//! Reactions of blocks that are hit by projectiles (e.g. arrows pressing buttons or snowballs breaking pots).
+//!
+//! Valence does not simulate redstone, pressed blocks only change their block state.
+//! Listen to the [`ProjectileBlockReactionEvent`] to react to them.
+//!
+//! No reactions are registered by default, insert [`BlockReactions::vanilla`] to enable the vanilla reactions.
+//! Reactions that break or place blocks are only applied where [`RegionFlag::Build`] is allowed.
+
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use regions::{RegionFlag, RegionFlagCheck};
+use valence::{
+    block::{PropName, PropValue},
+    entity::EntityKind,
+    prelude::*,
+};
+
+use crate::EntityBlockCollisionEvent;
+
+/// A custom reaction, see [`BlockReaction::Custom`].
+///
+/// The parameters are: `layer`, `block_pos`, `block_face_bitmap` (the faces of the block that were hit).
+pub type BlockReactionFn = Arc<dyn Fn(&mut ChunkLayer, BlockPos, u8) + Send + Sync>;
+
+/// What happens when a projectile hits a block.
+#[derive(Clone)]
+pub enum BlockReaction {
+    /// The block is replaced with air.
+    Break,
+    /// The block is powered (buttons, pressure plates and target blocks) for the duration.
+    Press {
+        duration: Duration,
+    },
+    /// Campfires and candles are lit, other blocks are set on fire (on the face that was hit).
+    Ignite,
+    Custom(BlockReactionFn),
+}
+
+/// The reactions of a projectile type.
+#[derive(Clone, Default)]
+pub struct ProjectileReactions {
+    pub blocks: HashMap<BlockKind, BlockReaction>,
+    /// The reaction to blocks without their own reaction.
+    pub any_block: Option<BlockReaction>,
+}
+
+/// The registry of block reactions per projectile type.
+#[derive(Resource, Clone, Default)]
+pub struct BlockReactions {
+    pub projectiles: HashMap<EntityKind, ProjectileReactions>,
+}
+
+impl BlockReactions {
+    /// Arrows press wooden buttons, wooden pressure plates and target blocks,
+    /// snowballs and eggs break decorated pots and small fireballs set blocks on fire.
+    pub fn vanilla() -> Self {
+        let mut reactions = Self::default();
+
+        let arrows = [
+            EntityKind::ARROW,
+            EntityKind::SPECTRAL_ARROW,
+            EntityKind::TRIDENT,
+        ];
+
+        reactions.set(
+            arrows,
+            WOODEN_BUTTONS,
+            BlockReaction::Press {
+                duration: Duration::from_millis(1500),
+            },
+        );
+        reactions.set(
+            arrows,
+            WOODEN_PRESSURE_PLATES,
+            BlockReaction::Press {
+                duration: Duration::from_secs(1),
+            },
+        );
+        reactions.set(
+            arrows,
+            [BlockKind::Target],
+            BlockReaction::Press {
+                duration: Duration::from_secs(1),
+            },
+        );
+        reactions.set(
+            [EntityKind::SNOWBALL, EntityKind::EGG],
+            [BlockKind::DecoratedPot],
+            BlockReaction::Break,
+        );
+        reactions.set_any_block([EntityKind::SMALL_FIREBALL], BlockReaction::Ignite);
+
+        reactions
+    }
+
+    /// Set the reaction of the blocks to the projectiles.
+    pub fn set(
+        &mut self,
+        projectiles: impl IntoIterator<Item = EntityKind>,
+        blocks: impl IntoIterator<Item = BlockKind> + Clone,
+        reaction: BlockReaction,
+    ) {
+        for projectile in projectiles {
+            let entry = self.projectiles.entry(projectile).or_default();
+            for block in blocks.clone() {
+                entry.blocks.insert(block, reaction.clone());
+            }
+        }
+    }
+
+    /// Set the reaction of blocks without their own reaction to the projectiles.
+    pub fn set_any_block(
+        &mut self,
+        projectiles: impl IntoIterator<Item = EntityKind>,
+        reaction: BlockReaction,
+    ) {
+        for projectile in projectiles {
+            self.projectiles.entry(projectile).or_default().any_block = Some(reaction.clone());
+        }
+    }
+
+    /// The reaction of the block to the projectile.
+    pub fn get(&self, projectile: EntityKind, block: BlockKind) -> Option<&BlockReaction> {
+        let reactions = self.projectiles.get(&projectile)?;
+        reactions
+            .blocks
+            .get(&block)
+            .or(reactions.any_block.as_ref())
+    }
+}
+
+/// An event that is emitted when a projectile hit a block with a [`BlockReaction`].
+#[derive(Event, Clone)]
+pub struct ProjectileBlockReactionEvent {
+    pub projectile: Entity,
+    pub projectile_kind: EntityKind,
+    pub layer: Entity,
+    pub block_pos: BlockPos,
+    /// The block before the reaction.
+    pub block: BlockState,
+    pub reaction: BlockReaction,
+}
+
+/// Blocks that were pressed by projectiles and are released later.
+#[derive(Resource, Default)]
+pub(crate) struct PressedBlocks(HashMap<(Entity, BlockPos), Instant>);
+
+pub(crate) fn react_to_projectile_hits(
+    mut events: EventReader<EntityBlockCollisionEvent>,
+    projectiles: Query<(&EntityKind, &EntityLayerId)>,
+    mut layers: Query<&mut ChunkLayer>,
+    reactions: Res<BlockReactions>,
+    region_check: RegionFlagCheck,
+    mut pressed: ResMut<PressedBlocks>,
+    mut reaction_writer: EventWriter<ProjectileBlockReactionEvent>,
+) {
+    for event in events.read() {
+        let Ok((kind, layer_id)) = projectiles.get(event.entity) else {
+            continue;
+        };
+
+        let Ok(mut layer) = layers.get_mut(layer_id.0) else {
+            continue;
+        };
+
+        let Some(block) = layer.block(event.block_pos).map(|block| block.state) else {
+            continue;
+        };
+
+        let Some(reaction) = reactions.get(*kind, block.to_kind()) else {
+            continue;
+        };
+
+        let center = DVec3::new(
+            event.block_pos.x as f64 + 0.5,
+            event.block_pos.y as f64 + 0.5,
+            event.block_pos.z as f64 + 0.5,
+        );
+
+        if matches!(reaction, BlockReaction::Break | BlockReaction::Ignite)
+            && !region_check.is_allowed_at(layer_id.0, center, RegionFlag::Build)
+        {
+            continue;
+        }
+
+        match reaction {
+            BlockReaction::Break => {
+                layer.set_block(event.block_pos, BlockState::AIR);
+            }
+            BlockReaction::Press { duration } => {
+                let Some(powered) = set_powered(block, true) else {
+                    continue;
+                };
+
+                layer.set_block(event.block_pos, powered);
+                pressed
+                    .0
+                    .insert((layer_id.0, event.block_pos), Instant::now() + *duration);
+            }
+            BlockReaction::Ignite => {
+                if !ignite(&mut layer, event.block_pos, block, event.block_face_bitmap) {
+                    continue;
+                }
+            }
+            BlockReaction::Custom(f) => f(&mut layer, event.block_pos, event.block_face_bitmap),
+        }
+
+        reaction_writer.send(ProjectileBlockReactionEvent {
+            projectile: event.entity,
+            projectile_kind: *kind,
+            layer: layer_id.0,
+            block_pos: event.block_pos,
+            block,
+            reaction: reaction.clone(),
+        });
+    }
+}
+
+pub(crate) fn release_pressed_blocks(
+    mut pressed: ResMut<PressedBlocks>,
+    mut layers: Query<&mut ChunkLayer>,
+) {
+    pressed.0.retain(|(layer, block_pos), until| {
+        if *until > Instant::now() {
+            return true;
+        }
+
+        let Ok(mut layer) = layers.get_mut(*layer) else {
+            return false;
+        };
+
+        // The block might have been replaced in the meantime.
+        let released = layer
+            .block(*block_pos)
+            .and_then(|block| set_powered(block.state, false));
+
+        if let Some(released) = released {
+            layer.set_block(*block_pos, released);
+        }
+
+        false
+    });
+}
+
+/// Buttons and pressure plates are `powered`, target blocks have a signal strength.
+fn set_powered(block: BlockState, powered: bool) -> Option<BlockState> {
+    if block.get(PropName::Powered).is_some() {
+        let value = if powered {
+            PropValue::True
+        } else {
+            PropValue::False
+        };
+
+        return Some(block.set(PropName::Powered, value));
+    }
+
+    if block.get(PropName::Power).is_some() {
+        let value = if powered {
+            PropValue::_15
+        } else {
+            PropValue::_0
+        };
+
+        return Some(block.set(PropName::Power, value));
+    }
+
+    None
+}
+
+/// Returns `true` if the block was lit or a fire was placed.
+fn ignite(layer: &mut ChunkLayer, block_pos: BlockPos, block: BlockState, face_bitmap: u8) -> bool {
+    let kind = block.to_kind();
+    let lightable = matches!(kind, BlockKind::Campfire | BlockKind::SoulCampfire)
+        || kind.to_str().ends_with("candle");
+
+    if lightable {
+        if block.get(PropName::Lit) != Some(PropValue::False) {
+            return false;
+        }
+
+        layer.set_block(block_pos, block.set(PropName::Lit, PropValue::True));
+        return true;
+    }
+
+    let Some(face) = FACES
+        .into_iter()
+        .find(|face| face_bitmap & (1 << *face as u8) != 0)
+    else {
+        return false;
+    };
+
+    let fire_pos = block_pos.get_in_direction(face);
+
+    if !layer
+        .block(fire_pos)
+        .is_some_and(|block| block.state.is_air())
+    {
+        return false;
+    }
+
+    layer.set_block(fire_pos, BlockState::FIRE);
+    true
+}
+
+const FACES: [Direction; 6] = [
+    Direction::Down,
+    Direction::Up,
+    Direction::North,
+    Direction::South,
+    Direction::West,
+    Direction::East,
+];
+
+const WOODEN_BUTTONS: [BlockKind; 11] = [
+    BlockKind::OakButton,
+    BlockKind::SpruceButton,
+    BlockKind::BirchButton,
+    BlockKind::JungleButton,
+    BlockKind::AcaciaButton,
+    BlockKind::CherryButton,
+    BlockKind::DarkOakButton,
+    BlockKind::MangroveButton,
+    BlockKind::BambooButton,
+    BlockKind::CrimsonButton,
+    BlockKind::WarpedButton,
+];
+
+const WOODEN_PRESSURE_PLATES: [BlockKind; 11] = [
+    BlockKind::OakPressurePlate,
+    BlockKind::SprucePressurePlate,
+    BlockKind::BirchPressurePlate,
+    BlockKind::JunglePressurePlate,
+    BlockKind::AcaciaPressurePlate,
+    BlockKind::CherryPressurePlate,
+    BlockKind::DarkOakPressurePlate,
+    BlockKind::MangrovePressurePlate,
+    BlockKind::BambooPressurePlate,
+    BlockKind::CrimsonPressurePlate,
+    BlockKind::WarpedPressurePlate,
+];
@@ -1,11 +1,26 @@
+pub mod area_damage;
+pub mod arrow;
+pub mod block_reaction;
+pub mod mount;
+pub mod projectile;
+pub mod settings;
+pub mod trajectory;
 pub mod utils;
+pub mod zone;
 
-use ::utils::aaab::AabbExt;
+use std::sync::Arc;
+
+use ::utils::{aaab::AabbExt, spectator::Spectating};
 use bevy_ecs::query::QueryData;
 use bevy_time::Time;
 use bvh::bvh_resource::{BvhResource, EntityBvhEntry, ENTITY_BLOCK_BVH_IDX, ENTITY_ENTITY_BVH_IDX};
 use utils::swept_aabb_collide;
-use valence::{entity::Velocity, math::Aabb, prelude::*};
+use valence::{
+    ecs::entity::{EntityHashMap, EntityHashSet},
+    entity::Velocity,
+    math::Aabb,
+    prelude::*,
+};
 
 /// The acceleration of an entity.
 #[derive(Component)]
@@ -19,6 +34,45 @@ pub struct SpeedLimit(pub f32);
 #[derive(Component)]
 pub struct Drag(pub Vec3);
 
+/// Makes an entity bounce off blocks instead of stopping.
+///
+/// When the entity collides with a block, the velocity on the colliding axis is reflected
+/// and multiplied by the restitution of that axis (`0.0` = no bounce, `1.0` = no energy loss).
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Restitution(pub Vec3);
+
+impl Restitution {
+    pub fn splat(restitution: f32) -> Self {
+        Self(Vec3::splat(restitution))
+    }
+
+    /// The velocity on an axis after bouncing off a block.
+    ///
+    /// Bounces slower than [`MIN_BOUNCE_SPEED`] are stopped, so resting entities do not jitter.
+    pub fn bounce(velocity: f32, restitution: f32) -> f32 {
+        let bounced = -velocity * restitution;
+
+        if bounced.abs() < MIN_BOUNCE_SPEED {
+            0.0
+        } else {
+            bounced
+        }
+    }
+}
+
+/// Slows down an entity while it slides along the surface of a block.
+///
+/// The velocity along the surface will be multiplied by `1.0 - friction` every second.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Friction(pub f32);
+
+/// The maximum amount of block surfaces an entity can slide along in a single (collision) step.
+const MAX_SLIDES: usize = 3;
+
+/// The minimum speed (in blocks per second) of a bounce.
+// TODO: set based on tick rate
+pub const MIN_BOUNCE_SPEED: f32 = 0.05 * 20.0;
+
 // TODO: add this for entity collisions as well
 // + make this configurable per movement axis.
 
@@ -76,14 +130,169 @@ pub struct BlockCollisionConfig {
     // TODO: have the option to register collisions without stopping the entity
     // from going to the block.
     pub block_collider_hitbox: Option<Aabb>,
+    /// How collisions with blocks are detected.
+    pub detection: CollisionDetection,
+    /// Decides how the entity interacts with each block (see [`BlockBehavior`]).
+    ///
+    /// If `None`, every block with a collision shape is solid.
+    pub block_filter: Option<BlockFilter>,
+}
+
+impl BlockCollisionConfig {
+    pub fn with_block_filter(
+        mut self,
+        filter: impl Fn(BlockState) -> BlockBehavior + Send + Sync + 'static,
+    ) -> Self {
+        self.block_filter = Some(Arc::new(filter));
+        self
+    }
+
+    /// How the entity interacts with a block.
+    pub fn block_behavior(&self, state: BlockState) -> BlockBehavior {
+        match &self.block_filter {
+            Some(filter) => filter(state),
+            None => BlockBehavior::Solid,
+        }
+    }
+}
+
+/// A function that decides how an entity interacts with a block.
+pub type BlockFilter = Arc<dyn Fn(BlockState) -> BlockBehavior + Send + Sync>;
+
+/// How an entity interacts with a block (see [`BlockCollisionConfig::block_filter`]).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BlockBehavior {
+    /// The entity collides with the collision shapes of the block.
+    Solid,
+    /// The entity moves through the block.
+    Passable,
+    /// The entity moves through the block, but its velocity is multiplied by this every tick
+    /// while it is inside of the block (e.g. cobwebs).
+    VelocityModifier(Vec3),
+    /// The entity moves through the block and can not fall faster than [`CLIMB_SPEED`] while it
+    /// is inside of the block (e.g. ladders and vines).
+    Climbable,
+}
+
+/// The maximum speed (in blocks per second) of an entity on a climbable block.
+// TODO: set based on tick rate
+pub const CLIMB_SPEED: f32 = 0.15 * 20.0;
+
+/// The vanilla behavior of blocks, cobwebs slow entities down, and ladders, vines and scaffolding are climbable.
+///
+/// ```ignore
+/// BlockCollisionConfig::default().with_block_filter(physics::vanilla_block_behavior)
+/// ```
+pub fn vanilla_block_behavior(state: BlockState) -> BlockBehavior {
+    match state.to_kind() {
+        BlockKind::Cobweb => BlockBehavior::VelocityModifier(Vec3::new(0.25, 0.05, 0.25)),
+        BlockKind::Ladder
+        | BlockKind::Vine
+        | BlockKind::Scaffolding
+        | BlockKind::TwistingVines
+        | BlockKind::TwistingVinesPlant
+        | BlockKind::WeepingVines
+        | BlockKind::WeepingVinesPlant
+        | BlockKind::CaveVines
+        | BlockKind::CaveVinesPlant => BlockBehavior::Climbable,
+        _ => BlockBehavior::Solid,
+    }
+}
+
+/// The maximum amount of steps the movement of an entity is split into with [`CollisionDetection::Continuous`].
+const MAX_CCD_STEPS: u32 = 64;
+
+/// How collisions of an entity with blocks are detected.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum CollisionDetection {
+    /// A single swept collision test for the whole movement of a tick.
+    #[default]
+    Discrete,
+    /// The movement is split into steps that are at most `max_step` blocks long,
+    /// this prevents very fast entities (e.g. projectiles) from tunneling through thin walls.
+    Continuous { max_step: f64 },
+}
+
+/// The collision groups an entity is part of (bitflags).
+///
+/// Entities without this component are in [`CollisionLayer::DEFAULT`].
+/// The bits that are not used by the constants below can be used for custom groups (e.g. teams).
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CollisionLayer(pub u32);
+
+impl CollisionLayer {
+    pub const DEFAULT: Self = Self(1 << 0);
+    pub const PLAYER: Self = Self(1 << 1);
+    pub const NPC: Self = Self(1 << 2);
+    pub const PROJECTILE: Self = Self(1 << 3);
+
+    pub const fn with(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+}
+
+impl Default for CollisionLayer {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// The collision groups (see [`CollisionLayer`]) an entity can collide with (bitflags).
+///
+/// Two entities only collide if both are in a group the other one can collide with.
+/// Entities without this component can collide with all groups.
+///
+/// ```ignore
+/// // Projectiles that do not hit other projectiles.
+/// CollisionMask::ALL.without(CollisionLayer::PROJECTILE)
+/// ```
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CollisionMask(pub u32);
+
+impl CollisionMask {
+    pub const ALL: Self = Self(u32::MAX);
+    pub const NONE: Self = Self(0);
+
+    pub const fn with(self, layer: CollisionLayer) -> Self {
+        Self(self.0 | layer.0)
+    }
+
+    pub const fn without(self, layer: CollisionLayer) -> Self {
+        Self(self.0 & !layer.0)
+    }
+
+    pub const fn contains(&self, layer: CollisionLayer) -> bool {
+        self.0 & layer.0 != 0
+    }
+}
+
+impl Default for CollisionMask {
+    fn default() -> Self {
+        Self::ALL
+    }
 }
 
+/// Entities this entity passes through, in addition to the ones filtered by the [`CollisionMask`].
+///
+/// This can be used for filters that do not fit into the collision groups
+/// (e.g. projectiles that pass through the allies of their owner).
+#[derive(Component, Clone, Debug, Default)]
+pub struct PassThroughEntities(pub EntityHashSet);
+
 /// The event emitted when an entity collides with another entity.
 #[derive(Event, Debug)]
 pub struct EntityEntityCollisionEvent {
     /// This entity is the one that performed the collision detection.
     pub entity1: Entity,
     pub entity2: Entity,
+    /// When in the movement of this tick the entities collided (0.0 - 1.0).
+    ///
+    /// This is `0.0` if the entities were already overlapping.
+    pub entry_time: f64,
+    /// The normal of the face of `entity2` that was hit.
+    ///
+    /// This is zero if the entities were already overlapping.
+    pub normal: Vec3,
 }
 
 /// The event emitted when an entity collides with a block.
@@ -97,14 +306,62 @@ pub struct EntityBlockCollisionEvent {
 
 impl EntityBlockCollisionEvent {}
 
+/// The event emitted when an entity moved (by the physics) into a different block.
+#[derive(Event, Debug)]
+pub struct EntityBlockChangeEvent {
+    pub entity: Entity,
+    pub old_block: BlockPos,
+    pub new_block: BlockPos,
+}
+
+/// The event emitted when an entity moved (by the physics) into a different chunk.
+#[derive(Event, Debug)]
+pub struct EntityChunkChangeEvent {
+    pub entity: Entity,
+    pub old_chunk: ChunkPos,
+    pub new_chunk: ChunkPos,
+}
+
 pub struct PhysicsPlugin;
 
 impl Plugin for PhysicsPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<EntityEntityCollisionEvent>()
             .add_event::<EntityBlockCollisionEvent>()
+            .add_event::<EntityBlockChangeEvent>()
+            .add_event::<EntityChunkChangeEvent>()
+            .add_event::<mount::MountEvent>()
+            .add_event::<mount::DismountEvent>()
+            .add_event::<zone::ZoneEnterEvent>()
+            .add_event::<zone::ZoneLeaveEvent>()
+            .add_event::<block_reaction::ProjectileBlockReactionEvent>()
             .insert_resource(BvhResource::with_bvhs(2))
-            .add_systems(PreUpdate, (physics_system, rebuild_bvh));
+            .init_resource::<settings::PhysicsSettings>()
+            .init_resource::<block_reaction::BlockReactions>()
+            .init_resource::<block_reaction::PressedBlocks>()
+            .add_systems(
+                PreUpdate,
+                (
+                    settings::apply_default_physics.before(physics_system),
+                    mount::follow_system.before(physics_system),
+                    trajectory::apply_trajectory_controllers.before(physics_system),
+                    physics_system,
+                    mount::move_passengers.after(physics_system),
+                    rebuild_bvh,
+                    zone::update_trigger_zones.after(rebuild_bvh),
+                    arrow::stick_arrows.after(physics_system),
+                    block_reaction::react_to_projectile_hits.after(physics_system),
+                ),
+            )
+            .add_systems(
+                Update,
+                (
+                    (mount::dismount_on_sneak, mount::sync_passengers).chain(),
+                    arrow::pick_up_arrows,
+                    arrow::despawn_stuck_arrows,
+                    block_reaction::release_pressed_blocks,
+                ),
+            );
     }
 }
 
@@ -117,36 +374,45 @@ struct PhysicsQuery {
     pub acceleration: Option<&'static Acceleration>,
     pub hitbox: &'static Hitbox,
     pub drag: Option<&'static Drag>,
+    pub restitution: Option<&'static Restitution>,
+    pub friction: Option<&'static Friction>,
     pub speed_limit: Option<&'static SpeedLimit>,
     pub stop_on_block_collision: Option<&'static StopOnBlockCollision>,
     pub entity_collision_config: Option<&'static EntityCollisionConfig>,
     pub block_collision_config: Option<&'static BlockCollisionConfig>,
+    pub collision_layer: Option<&'static CollisionLayer>,
+    pub collision_mask: Option<&'static CollisionMask>,
+    pub pass_through: Option<&'static PassThroughEntities>,
 }
 
+#[allow(clippy::too_many_arguments)]
 fn physics_system(
     bvh: ResMut<BvhResource>,
     time: Res<Time>,
     mut query: Query<PhysicsQuery, Without<Client>>,
     mut entity_entity_collision_writer: EventWriter<EntityEntityCollisionEvent>,
     mut entity_block_collision_writer: EventWriter<EntityBlockCollisionEvent>,
+    mut block_change_writer: EventWriter<EntityBlockChangeEvent>,
+    mut chunk_change_writer: EventWriter<EntityChunkChangeEvent>,
     // TODO: support for multiple layers
     layer: Query<&ChunkLayer, With<EntityLayer>>,
 ) {
-    /// Helper function to help with creating the ranges used for aabb broadphase.
-    fn create_range(
-        start: i32,
-        step: i32,
-        steps: i32,
-        center: i32,
-    ) -> std::ops::RangeInclusive<i32> {
-        if step > 0 {
-            start - step * (steps + 1)..=center + step * (steps + 2)
-        } else {
-            center + step * (steps + 2)..=start - step * (steps + 1)
-        }
-    }
+    // The movement of the entities that can be hit during this tick, so collisions with moving targets
+    // can be detected in their frame of reference.
+    let movements = query
+        .iter()
+        .filter(|entity| entity.entity_collision_config.is_some())
+        .map(|entity| (entity.entity, entity.velocity.0 * time.delta_seconds()))
+        .collect::<EntityHashMap<_>>();
+
+    let max_movement = movements
+        .values()
+        .map(|movement| movement.length() as f64)
+        .fold(0.0, f64::max);
 
     query.iter_mut().for_each(|mut entity| {
+        let start = entity.position.0;
+
         if let Some(drag) = entity.drag {
             entity.velocity.0 *= 1.0 - drag.0 * time.delta_seconds();
         }
@@ -162,175 +428,348 @@ fn physics_system(
         // TODO: support for multiple layers
         let layer = layer.single();
 
-        let _old_velocity = entity.velocity.0;
-
         if let Some(block_collision_config) = entity.block_collision_config {
             let entity_hitbox = block_collision_config
                 .block_collider_hitbox
                 .unwrap_or(entity.hitbox.get());
 
-            for _ in 0..3 {
-                let velocity_delta = entity.velocity.0 * time.delta_seconds();
-                let (vx, vy, vz) = (velocity_delta.x, velocity_delta.y, velocity_delta.z);
+            if block_collision_config.block_filter.is_some() {
+                apply_block_behaviors(&mut entity, block_collision_config, entity_hitbox, layer);
+            }
 
-                let (step_x, step_y, step_z) = (
-                    if vx > 0.0 { 1 } else { -1 },
-                    if vy > 0.0 { 1 } else { -1 },
-                    if vz > 0.0 { 1 } else { -1 },
-                );
+            let steps = match block_collision_config.detection {
+                CollisionDetection::Discrete => 1,
+                CollisionDetection::Continuous { max_step } => {
+                    let distance = (entity.velocity.0 * time.delta_seconds()).length() as f64;
+                    ((distance / max_step).ceil() as u32).clamp(1, MAX_CCD_STEPS)
+                }
+            };
 
-                let (steps_x, steps_y, steps_z) = (
-                    (entity_hitbox.width_x() / 2.0) as i32,
-                    (entity_hitbox.width_y() / 2.0) as i32,
-                    (entity_hitbox.width_z() / 2.0) as i32,
-                );
+            let delta_seconds = time.delta_seconds() / steps as f32;
 
-                let (x, y, z) = (
-                    entity.position.0.x as i32,
-                    entity.position.0.y as i32,
-                    entity.position.0.z as i32,
-                );
+            for _ in 0..steps {
+                // The hitbox has to follow the entity through the steps.
+                let hitbox = entity_hitbox.translate(entity.position.0 - start);
 
-                let (cx, cy, cz) = (
-                    (entity.position.0.x + velocity_delta.x as f64) as i32,
-                    (entity.position.0.y + velocity_delta.y as f64) as i32,
-                    (entity.position.0.z + velocity_delta.z as f64) as i32,
+                resolve_block_collisions(
+                    &mut entity,
+                    block_collision_config,
+                    hitbox,
+                    delta_seconds,
+                    layer,
+                    &mut entity_block_collision_writer,
                 );
+            }
+        } else {
+            entity.position.0 += (entity.velocity.0 * time.delta_seconds()).as_dvec3();
+        }
 
-                let mut potential_collisions = Vec::new();
+        let (old_block, new_block) = (block_pos(start), block_pos(entity.position.0));
 
-                let x_range = create_range(x, step_x, steps_x, cx);
-                for i in x_range.step_by(step_x.unsigned_abs() as usize) {
-                    let y_range = create_range(y, step_y, steps_y, cy);
+        if old_block != new_block {
+            block_change_writer.send(EntityBlockChangeEvent {
+                entity: entity.entity,
+                old_block,
+                new_block,
+            });
 
-                    for j in y_range.step_by(step_y.unsigned_abs() as usize) {
-                        let z_range = create_range(z, step_z, steps_z, cz);
+            let (old_chunk, new_chunk) = (ChunkPos::from(start), ChunkPos::from(entity.position.0));
 
-                        for k in z_range.step_by(step_z.unsigned_abs() as usize) {
-                            let block_pos = BlockPos { x: i, y: j, z: k };
-                            let block = layer.block(block_pos);
+            if old_chunk != new_chunk {
+                chunk_change_writer.send(EntityChunkChangeEvent {
+                    entity: entity.entity,
+                    old_chunk,
+                    new_chunk,
+                });
+            }
+        }
+
+        if let Some(entity_collision_config) = entity.entity_collision_config {
+            let start_hitbox = match entity_collision_config.entity_collider_hitbox {
+                Some(hitbox) => hitbox.translate(start),
+                None => entity.hitbox.get(),
+            };
 
-                            let Some(block) = block else {
-                                continue;
-                            };
+            let moved = (entity.position.0 - start).as_vec3();
+            let end_hitbox = start_hitbox.translate(moved.as_dvec3());
+
+            // Everything the entity (or a moving target) could have passed through during this tick.
+            let swept_hitbox = Aabb::new(
+                start_hitbox.min().min(end_hitbox.min()) - DVec3::splat(max_movement),
+                start_hitbox.max().max(end_hitbox.max()) + DVec3::splat(max_movement),
+            );
+
+            let collision_layer = entity.collision_layer.copied().unwrap_or_default();
+            let collision_mask = entity.collision_mask.copied().unwrap_or_default();
+
+            for other in bvh[ENTITY_ENTITY_BVH_IDX].get_in_range_masked(
+                swept_hitbox,
+                collision_layer.0,
+                collision_mask.0,
+            ) {
+                if other.entity == entity.entity
+                    || entity
+                        .pass_through
+                        .is_some_and(|pass_through| pass_through.0.contains(&other.entity))
+                {
+                    continue;
+                }
 
-                            if block.state.is_air() {
-                                continue;
-                            }
+                let other_moved = movements.get(&other.entity).copied().unwrap_or(Vec3::ZERO);
 
-                            for collider in block.state.collision_shapes() {
-                                let block_aabb =
-                                    collider.translate(DVec3::new(i as f64, j as f64, k as f64));
+                let (entry_time, normal) = if start_hitbox.intersects(other.hitbox) {
+                    (0.0, Vec3::ZERO)
+                } else if let Some(collision) =
+                    swept_aabb_collide(&start_hitbox, &(moved - other_moved), &other.hitbox)
+                {
+                    (collision.entry_time, collision.face_direction.normal())
+                } else {
+                    continue;
+                };
 
-                                let Some(collision) = swept_aabb_collide(
-                                    &entity_hitbox,
-                                    &velocity_delta,
-                                    &block_aabb,
-                                ) else {
-                                    continue;
-                                };
+                entity_entity_collision_writer.send(EntityEntityCollisionEvent {
+                    entity1: entity.entity,
+                    entity2: other.entity,
+                    entry_time,
+                    normal,
+                });
+            }
+        }
+    });
+}
 
-                                if collision.face_direction.x.is_none()
-                                    && collision.face_direction.y.is_none()
-                                    && collision.face_direction.z.is_none()
-                                {
-                                    continue;
-                                }
+/// Apply the [`BlockBehavior`]s of all blocks the entity is inside of.
+fn apply_block_behaviors(
+    entity: &mut PhysicsQueryItem,
+    block_collision_config: &BlockCollisionConfig,
+    entity_hitbox: Aabb,
+    layer: &ChunkLayer,
+) {
+    for block_pos in ::utils::aabb_full_block_intersections(&entity_hitbox) {
+        let Some(block) = layer.block(block_pos) else {
+            continue;
+        };
+
+        match block_collision_config.block_behavior(block.state) {
+            BlockBehavior::Solid | BlockBehavior::Passable => {}
+            BlockBehavior::VelocityModifier(modifier) => {
+                entity.velocity.0 *= modifier;
+            }
+            BlockBehavior::Climbable => {
+                entity.velocity.0.y = entity.velocity.0.y.max(-CLIMB_SPEED);
+            }
+        }
+    }
+}
+
+/// The block a position is in.
+fn block_pos(position: DVec3) -> BlockPos {
+    BlockPos::new(
+        position.x.floor() as i32,
+        position.y.floor() as i32,
+        position.z.floor() as i32,
+    )
+}
+
+/// Helper function to help with creating the ranges used for aabb broadphase.
+fn create_range(start: i32, step: i32, steps: i32, center: i32) -> std::ops::RangeInclusive<i32> {
+    if step > 0 {
+        start - step * (steps + 1)..=center + step * (steps + 2)
+    } else {
+        center + step * (steps + 2)..=start - step * (steps + 1)
+    }
+}
+
+/// Move the entity for `delta_seconds` while resolving the collisions with blocks.
+///
+/// When the entity hits a block, it is moved up to the block and the velocity into the block is removed
+/// (or reflected, see [`Restitution`]). The rest of the movement continues along the surface of the block.
+fn resolve_block_collisions(
+    entity: &mut PhysicsQueryItem,
+    block_collision_config: &BlockCollisionConfig,
+    entity_hitbox: Aabb,
+    delta_seconds: f32,
+    layer: &ChunkLayer,
+    collision_writer: &mut EventWriter<EntityBlockCollisionEvent>,
+) {
+    let start = entity.position.0;
+    // The part of `delta_seconds` that the entity still has to move.
+    let mut remaining = 1.0;
+
+    for _ in 0..MAX_SLIDES {
+        // The hitbox has to follow the entity while it slides along the blocks.
+        let entity_hitbox = entity_hitbox.translate(entity.position.0 - start);
+        let velocity_delta = entity.velocity.0 * delta_seconds * remaining;
+        let (vx, vy, vz) = (velocity_delta.x, velocity_delta.y, velocity_delta.z);
+
+        let (step_x, step_y, step_z) = (
+            if vx > 0.0 { 1 } else { -1 },
+            if vy > 0.0 { 1 } else { -1 },
+            if vz > 0.0 { 1 } else { -1 },
+        );
+
+        let (steps_x, steps_y, steps_z) = (
+            (entity_hitbox.width_x() / 2.0) as i32,
+            (entity_hitbox.width_y() / 2.0) as i32,
+            (entity_hitbox.width_z() / 2.0) as i32,
+        );
+
+        let (x, y, z) = (
+            entity.position.0.x as i32,
+            entity.position.0.y as i32,
+            entity.position.0.z as i32,
+        );
+
+        let (cx, cy, cz) = (
+            (entity.position.0.x + velocity_delta.x as f64) as i32,
+            (entity.position.0.y + velocity_delta.y as f64) as i32,
+            (entity.position.0.z + velocity_delta.z as f64) as i32,
+        );
+
+        let mut potential_collisions = Vec::new();
+
+        let x_range = create_range(x, step_x, steps_x, cx);
+        for i in x_range.step_by(step_x.unsigned_abs() as usize) {
+            let y_range = create_range(y, step_y, steps_y, cy);
+
+            for j in y_range.step_by(step_y.unsigned_abs() as usize) {
+                let z_range = create_range(z, step_z, steps_z, cz);
+
+                for k in z_range.step_by(step_z.unsigned_abs() as usize) {
+                    let block_pos = BlockPos { x: i, y: j, z: k };
+                    let block = layer.block(block_pos);
+
+                    let Some(block) = block else {
+                        continue;
+                    };
+
+                    if block.state.is_air() {
+                        continue;
+                    }
+
+                    if block_collision_config.block_behavior(block.state) != BlockBehavior::Solid {
+                        continue;
+                    }
 
-                                potential_collisions.push((block_pos, collision));
-                            }
+                    for collider in block.state.collision_shapes() {
+                        let block_aabb =
+                            collider.translate(DVec3::new(i as f64, j as f64, k as f64));
+
+                        let Some(collision) =
+                            swept_aabb_collide(&entity_hitbox, &velocity_delta, &block_aabb)
+                        else {
+                            continue;
+                        };
+
+                        if collision.face_direction.x.is_none()
+                            && collision.face_direction.y.is_none()
+                            && collision.face_direction.z.is_none()
+                        {
+                            continue;
                         }
+
+                        potential_collisions.push((block_pos, collision));
                     }
                 }
+            }
+        }
 
-                if potential_collisions.is_empty() {
-                    break;
-                }
+        if potential_collisions.is_empty() {
+            entity.position.0 += velocity_delta.as_dvec3();
+            return;
+        }
 
-                let (block_pos, mut collision) = potential_collisions
-                    .into_iter()
-                    .min_by(|a, b| a.1.entry_time.partial_cmp(&b.1.entry_time).unwrap())
-                    .unwrap();
+        let (block_pos, mut collision) = potential_collisions
+            .into_iter()
+            .min_by(|a, b| a.1.entry_time.partial_cmp(&b.1.entry_time).unwrap())
+            .unwrap();
 
-                collision.entry_time -= 0.01;
+        collision.entry_time = (collision.entry_time - 0.01).max(0.0);
 
-                let mut collision_bitmap = 0;
+        // Move the entity up to the block, the rest of the movement will be along the surface.
+        entity.position.0 += velocity_delta.as_dvec3() * collision.entry_time;
+        remaining *= 1.0 - collision.entry_time as f32;
 
-                if let Some(normal_x) = collision.face_direction.x {
-                    entity.velocity.0.x = 0.0;
-                    entity.position.0.x += vx as f64 * collision.entry_time;
-                    let direction = if normal_x {
-                        Direction::East
-                    } else {
-                        Direction::West
-                    };
-                    collision_bitmap |= 1 << direction as u8;
-                }
+        let mut collision_bitmap = 0;
 
-                if let Some(normal_y) = collision.face_direction.y {
-                    entity.velocity.0.y = 0.0;
-                    entity.position.0.y += vy as f64 * collision.entry_time;
-                    let direction = if normal_y {
-                        Direction::Up
-                    } else {
-                        Direction::Down
-                    };
-                    collision_bitmap |= 1 << direction as u8;
-                }
+        if let Some(normal_x) = collision.face_direction.x {
+            entity.velocity.0.x = entity
+                .restitution
+                .map_or(0.0, |r| Restitution::bounce(entity.velocity.0.x, r.0.x));
+            let direction = if normal_x {
+                Direction::East
+            } else {
+                Direction::West
+            };
+            collision_bitmap |= 1 << direction as u8;
+        }
 
-                if let Some(normal_z) = collision.face_direction.z {
-                    entity.velocity.0.z = 0.0;
-                    entity.position.0.z += vz as f64 * collision.entry_time;
-                    let direction = if normal_z {
-                        Direction::South
-                    } else {
-                        Direction::North
-                    };
-                    collision_bitmap |= 1 << direction as u8;
-                }
+        if let Some(normal_y) = collision.face_direction.y {
+            entity.velocity.0.y = entity
+                .restitution
+                .map_or(0.0, |r| Restitution::bounce(entity.velocity.0.y, r.0.y));
+            let direction = if normal_y {
+                Direction::Up
+            } else {
+                Direction::Down
+            };
+            collision_bitmap |= 1 << direction as u8;
+        }
 
-                let event = EntityBlockCollisionEvent {
-                    entity: entity.entity,
-                    block_pos,
-                    block_face_bitmap: collision_bitmap,
-                };
+        if let Some(normal_z) = collision.face_direction.z {
+            entity.velocity.0.z = entity
+                .restitution
+                .map_or(0.0, |r| Restitution::bounce(entity.velocity.0.z, r.0.z));
+            let direction = if normal_z {
+                Direction::South
+            } else {
+                Direction::North
+            };
+            collision_bitmap |= 1 << direction as u8;
+        }
 
-                if let Some(stop_on_block_collision) = entity.stop_on_block_collision {
-                    if stop_on_block_collision.should_stop_bitmap(collision_bitmap) {
-                        entity.velocity.0 = Vec3::ZERO;
-                    }
-                }
+        if let Some(friction) = entity.friction {
+            let factor = (1.0 - friction.0 * delta_seconds).max(0.0);
 
-                entity_block_collision_writer.send(event);
+            if collision.face_direction.x.is_none() {
+                entity.velocity.0.x *= factor;
             }
-        }
 
-        entity.position.0 += (entity.velocity.0 * time.delta_seconds()).as_dvec3();
+            if collision.face_direction.y.is_none() {
+                entity.velocity.0.y *= factor;
+            }
 
-        // TODO: entity collision
+            if collision.face_direction.z.is_none() {
+                entity.velocity.0.z *= factor;
+            }
+        }
 
-        if let Some(entity_collision_config) = entity.entity_collision_config {
-            let aabb = entity_collision_config
-                .entity_collider_hitbox
-                .unwrap_or(entity.hitbox.get());
+        let event = EntityBlockCollisionEvent {
+            entity: entity.entity,
+            block_pos,
+            block_face_bitmap: collision_bitmap,
+        };
 
-            for other in bvh[ENTITY_ENTITY_BVH_IDX].get_in_range(aabb) {
-                if other.entity == entity.entity {
-                    continue;
-                }
-
-                entity_entity_collision_writer.send(EntityEntityCollisionEvent {
-                    entity1: entity.entity,
-                    entity2: other.entity,
-                });
+        if let Some(stop_on_block_collision) = entity.stop_on_block_collision {
+            if stop_on_block_collision.should_stop_bitmap(collision_bitmap) {
+                entity.velocity.0 = Vec3::ZERO;
             }
         }
-    });
+
+        collision_writer.send(event);
+    }
 }
 
 #[allow(clippy::type_complexity)]
 fn rebuild_bvh(
-    query: Query<PhysicsQuery, Or<(With<EntityCollisionConfig>, With<BlockCollisionConfig>)>>,
+    // Spectators do not collide with anything.
+    query: Query<
+        PhysicsQuery,
+        (
+            Or<(With<EntityCollisionConfig>, With<BlockCollisionConfig>)>,
+            Without<Spectating>,
+        ),
+    >,
     mut bvh: ResMut<BvhResource>,
 ) {
     if query.is_empty() {
@@ -341,6 +780,9 @@ fn rebuild_bvh(
     let mut entity_block_colls = vec![];
 
     for entity in query.iter() {
+        let collision_layer = entity.collision_layer.copied().unwrap_or_default();
+        let collision_mask = entity.collision_mask.copied().unwrap_or_default();
+
         if let Some(entity_collision_config) = entity.entity_collision_config {
             let aabb = match entity_collision_config.entity_collider_hitbox {
                 Some(hitbox) => hitbox.translate(entity.position.0),
@@ -350,6 +792,8 @@ fn rebuild_bvh(
             entity_entity_colls.push(EntityBvhEntry {
                 entity: entity.entity,
                 hitbox: aabb,
+                layer: collision_layer.0,
+                mask: collision_mask.0,
             });
         }
 
@@ -362,14 +806,16 @@ fn rebuild_bvh(
             entity_block_colls.push(EntityBvhEntry {
                 entity: entity.entity,
                 hitbox: aabb,
+                layer: collision_layer.0,
+                mask: collision_mask.0,
             });
         }
     }
 
     bvh.get_mut(ENTITY_ENTITY_BVH_IDX)
         .unwrap()
-        .build(entity_entity_colls);
+        .update(entity_entity_colls);
     bvh.get_mut(ENTITY_BLOCK_BVH_IDX)
         .unwrap()
-        .build(entity_block_colls);
+        .update(entity_block_colls);
 }
@@ -0,0 +1,269 @@
+//! Following other entities (like a leash) and riding entities.
+
+use bevy_time::Time;
+use valence::{
+    ecs::entity::EntityHashMap,
+    entity::{EntityId, Velocity},
+    prelude::*,
+    protocol::{packets::play::EntityPassengersSetS2c, VarInt, WritePacket},
+};
+
+/// The entity will be pulled towards the target if it is further away than the distance.
+///
+/// The pull is a spring force that is applied to the [`Velocity`] of the entity.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Follow {
+    pub target: Entity,
+    /// The distance the entity tries to keep to the target.
+    pub distance: f64,
+    /// How strong the entity is pulled towards the target (per second).
+    pub stiffness: f32,
+    /// How much of the velocity is removed while the entity is pulled (per second).
+    pub damping: f32,
+    /// If the entity is further away than this distance, it will be teleported to the target.
+    pub teleport_distance: Option<f64>,
+}
+
+impl Follow {
+    pub fn new(target: Entity, distance: f64) -> Self {
+        Self {
+            target,
+            distance,
+            stiffness: 8.0,
+            damping: 2.0,
+            teleport_distance: None,
+        }
+    }
+
+    pub fn with_teleport_distance(mut self, distance: f64) -> Self {
+        self.teleport_distance = Some(distance);
+        self
+    }
+}
+
+/// The entity is riding another entity.
+///
+/// Insert this component to mount an entity and remove it to dismount.
+/// The position of the passenger is set to the position of the vehicle plus the offset
+/// (clients are moved by the client itself).
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Riding {
+    pub vehicle: Entity,
+    pub offset: DVec3,
+}
+
+impl Riding {
+    pub fn new(vehicle: Entity) -> Self {
+        Self {
+            vehicle,
+            offset: DVec3::ZERO,
+        }
+    }
+
+    pub fn with_offset(mut self, offset: DVec3) -> Self {
+        self.offset = offset;
+        self
+    }
+}
+
+/// The passengers of an entity, this is updated from the [`Riding`] components and should not be changed manually.
+#[derive(Component, Clone, Debug, Default)]
+pub struct Passengers(pub Vec<Entity>);
+
+/// An event that is emitted after an entity started riding another entity.
+#[derive(Event, Debug)]
+pub struct MountEvent {
+    pub passenger: Entity,
+    pub vehicle: Entity,
+}
+
+/// An event that is emitted after an entity stopped riding another entity
+/// (the [`Riding`] component was removed, the vehicle was despawned or a client sneaked).
+#[derive(Event, Debug)]
+pub struct DismountEvent {
+    pub passenger: Entity,
+    pub vehicle: Entity,
+}
+
+pub(crate) fn follow_system(
+    time: Res<Time>,
+    mut query: Query<(&Follow, &mut Position, &mut Velocity), Without<Client>>,
+    targets: Query<&Position, Without<Follow>>,
+) {
+    let dt = time.delta_seconds();
+
+    for (follow, mut position, mut velocity) in query.iter_mut() {
+        let Ok(target) = targets.get(follow.target) else {
+            continue;
+        };
+
+        let offset = target.0 - position.0;
+        let distance = offset.length();
+
+        if follow
+            .teleport_distance
+            .is_some_and(|teleport_distance| distance > teleport_distance)
+        {
+            position.0 = target.0;
+            velocity.0 = Vec3::ZERO;
+            continue;
+        }
+
+        if distance <= follow.distance {
+            continue;
+        }
+
+        let stretch = (offset.normalize() * (distance - follow.distance)).as_vec3();
+        let force = stretch * follow.stiffness - velocity.0 * follow.damping;
+
+        velocity.0 += force * dt;
+    }
+}
+
+/// Dismount clients that start sneaking.
+pub(crate) fn dismount_on_sneak(
+    mut commands: Commands,
+    mut events: EventReader<SneakEvent>,
+    riding: Query<(), With<Riding>>,
+) {
+    for &SneakEvent { client, state } in events.read() {
+        if state == SneakState::Start && riding.contains(client) {
+            commands.entity(client).remove::<Riding>();
+        }
+    }
+}
+
+/// Keep the [`Passengers`] in sync with the [`Riding`] components and send them to the clients.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn sync_passengers(
+    mut commands: Commands,
+    riding: Query<(Entity, &Riding)>,
+    mut vehicles: Query<(Entity, &mut Passengers)>,
+    entities: Query<(&EntityId, &EntityLayerId), Without<Despawned>>,
+    mut clients: Query<(Entity, &mut Client, &VisibleEntityLayers)>,
+    new_clients: Query<(), Added<Client>>,
+    mut mount_writer: EventWriter<MountEvent>,
+    mut dismount_writer: EventWriter<DismountEvent>,
+) {
+    let mut passengers = EntityHashMap::<Vec<Entity>>::default();
+
+    for (passenger, riding) in riding.iter() {
+        if !entities.contains(riding.vehicle) || !entities.contains(passenger) {
+            // The vehicle (or the passenger) was despawned.
+            commands.entity(passenger).remove::<Riding>();
+            continue;
+        }
+
+        passengers
+            .entry(riding.vehicle)
+            .or_default()
+            .push(passenger);
+    }
+
+    let mut changed = vec![];
+
+    for (vehicle, mut current) in vehicles.iter_mut() {
+        let new = passengers.get(&vehicle).cloned().unwrap_or_default();
+
+        if current.0 == new {
+            continue;
+        }
+
+        for &passenger in current
+            .0
+            .iter()
+            .filter(|passenger| !new.contains(passenger))
+        {
+            dismount_writer.send(DismountEvent { passenger, vehicle });
+        }
+
+        for &passenger in new
+            .iter()
+            .filter(|passenger| !current.0.contains(passenger))
+        {
+            mount_writer.send(MountEvent { passenger, vehicle });
+        }
+
+        if new.is_empty() {
+            commands.entity(vehicle).remove::<Passengers>();
+        }
+
+        current.0 = new;
+        changed.push(vehicle);
+    }
+
+    // Vehicles that did not have any passengers before.
+    for (&vehicle, new) in passengers.iter() {
+        if vehicles.contains(vehicle) {
+            continue;
+        }
+
+        for &passenger in new.iter() {
+            mount_writer.send(MountEvent { passenger, vehicle });
+        }
+
+        commands.entity(vehicle).insert(Passengers(new.clone()));
+        changed.push(vehicle);
+    }
+
+    let packet = |vehicle: Entity| {
+        let (vehicle_id, layer) = entities.get(vehicle).ok()?;
+
+        let passenger_ids = passengers
+            .get(&vehicle)
+            .into_iter()
+            .flatten()
+            .filter_map(|&passenger| entities.get(passenger).ok())
+            .map(|(id, _)| VarInt(id.get()))
+            .collect();
+
+        Some((
+            layer.0,
+            EntityPassengersSetS2c {
+                entity_id: VarInt(vehicle_id.get()),
+                passengers: passenger_ids,
+            },
+        ))
+    };
+
+    let changed_packets = changed.into_iter().filter_map(packet).collect::<Vec<_>>();
+
+    // New clients need the passengers of all vehicles.
+    let all_packets = if new_clients.is_empty() {
+        vec![]
+    } else {
+        passengers.keys().copied().filter_map(packet).collect()
+    };
+
+    for (entity, mut client, visible_layers) in clients.iter_mut() {
+        let packets = if new_clients.contains(entity) {
+            &all_packets
+        } else {
+            &changed_packets
+        };
+
+        for (layer, packet) in packets.iter() {
+            if visible_layers.0.contains(layer) {
+                client.write_packet(packet);
+            }
+        }
+    }
+}
+
+/// Move the passengers with their vehicle.
+pub(crate) fn move_passengers(
+    mut passengers: Query<(&Riding, &mut Position, Option<&mut Velocity>), Without<Client>>,
+    vehicles: Query<&Position, (With<Passengers>, Without<Riding>)>,
+) {
+    for (riding, mut position, velocity) in passengers.iter_mut() {
+        let Ok(vehicle) = vehicles.get(riding.vehicle) else {
+            continue;
+        };
+
+        position.0 = vehicle.0 + riding.offset;
+
+        if let Some(mut velocity) = velocity {
+            velocity.0 = Vec3::ZERO;
+        }
+    }
+}
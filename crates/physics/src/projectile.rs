@@ -0,0 +1,339 @@
+use valence::{
+    ecs::world::Command,
+    entity::{entity::NoGravity, EntityKind, Velocity},
+    prelude::*,
+};
+
+use crate::{
+    settings::PhysicsSettings, Acceleration, BlockCollisionConfig, CollisionLayer, CollisionMask,
+    Drag, EntityCollisionConfig, Restitution, SpeedLimit, StopOnBlockCollision,
+};
+
+/// The eye height of a player.
+const PLAYER_EYE_HEIGHT: f64 = 1.62;
+
+/// Attached to entities spawned with a [`ProjectileBuilder`].
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Projectile {
+    /// The entity that shot the projectile.
+    pub owner: Option<Entity>,
+}
+
+enum ProjectileOrigin {
+    /// Shot from the eyes of an entity in the direction it is looking.
+    EntityEyes {
+        entity: Entity,
+        eye_height: f64,
+    },
+    Position {
+        position: DVec3,
+        direction: Vec3,
+    },
+}
+
+/// A helper to spawn projectiles with all components the physics need.
+///
+/// ```ignore
+/// ProjectileBuilder::new(SnowballEntityBundle::default())
+///     .from_entity_eyes(player)
+///     .speed(30.0)
+///     .inaccuracy(0.01)
+///     .spawn(&mut commands);
+/// ```
+///
+/// The [`Acceleration`], [`Drag`], [`SpeedLimit`] and [`Restitution`] are taken from the [`PhysicsSettings`] of the entity type.
+pub struct ProjectileBuilder<B: Bundle> {
+    bundle: B,
+    origin: ProjectileOrigin,
+    owner: Option<Entity>,
+    speed: f32,
+    gravity: bool,
+    inaccuracy: f32,
+    forward_offset: f64,
+    entity_collisions: bool,
+    collision_mask: CollisionMask,
+    block_collisions: bool,
+    stop_on_block_collision: bool,
+}
+
+impl<B: Bundle> ProjectileBuilder<B> {
+    /// Create a projectile from an entity bundle (e.g. `SnowballEntityBundle::default()`).
+    ///
+    /// The position, look, velocity and (if shot from an entity) the layer of the bundle will be overwritten.
+    pub fn new(bundle: B) -> Self {
+        Self {
+            bundle,
+            origin: ProjectileOrigin::Position {
+                position: DVec3::ZERO,
+                direction: Vec3::Z,
+            },
+            owner: None,
+            speed: 20.0,
+            gravity: true,
+            inaccuracy: 0.0,
+            forward_offset: 1.0,
+            entity_collisions: true,
+            collision_mask: CollisionMask::ALL,
+            block_collisions: true,
+            stop_on_block_collision: false,
+        }
+    }
+
+    /// Shoot the projectile from the eyes of an entity in the direction it is looking.
+    ///
+    /// The entity will also be the owner of the projectile.
+    pub fn from_entity_eyes(mut self, entity: Entity) -> Self {
+        self.origin = ProjectileOrigin::EntityEyes {
+            entity,
+            eye_height: PLAYER_EYE_HEIGHT,
+        };
+        self.owner = Some(entity);
+        self
+    }
+
+    /// Change the eye height used by [`Self::from_entity_eyes`] (the default is the eye height of a player).
+    pub fn eye_height(mut self, height: f64) -> Self {
+        if let ProjectileOrigin::EntityEyes { eye_height, .. } = &mut self.origin {
+            *eye_height = height;
+        }
+        self
+    }
+
+    /// Shoot the projectile from a position in a direction.
+    pub fn from_position(mut self, position: DVec3, direction: Vec3) -> Self {
+        self.origin = ProjectileOrigin::Position {
+            position,
+            direction,
+        };
+        self
+    }
+
+    pub fn owner(mut self, owner: Entity) -> Self {
+        self.owner = Some(owner);
+        self
+    }
+
+    /// The speed in blocks per second.
+    pub fn speed(mut self, speed: f32) -> Self {
+        self.speed = speed;
+        self
+    }
+
+    /// If `false`, the projectile will fly in a straight line.
+    pub fn gravity(mut self, gravity: bool) -> Self {
+        self.gravity = gravity;
+        self
+    }
+
+    /// The random spread of the direction.
+    pub fn inaccuracy(mut self, inaccuracy: f32) -> Self {
+        self.inaccuracy = inaccuracy;
+        self
+    }
+
+    /// How far in front of the origin the projectile will be spawned.
+    pub fn forward_offset(mut self, offset: f64) -> Self {
+        self.forward_offset = offset;
+        self
+    }
+
+    /// Add an [`EntityCollisionConfig`] (enabled by default).
+    pub fn entity_collisions(mut self, enabled: bool) -> Self {
+        self.entity_collisions = enabled;
+        self
+    }
+
+    /// The collision groups the projectile can hit (see [`CollisionMask`]).
+    ///
+    /// The projectile itself is in [`CollisionLayer::PROJECTILE`].
+    pub fn collision_mask(mut self, mask: CollisionMask) -> Self {
+        self.collision_mask = mask;
+        self
+    }
+
+    /// Add a [`BlockCollisionConfig`] (enabled by default).
+    pub fn block_collisions(mut self, enabled: bool) -> Self {
+        self.block_collisions = enabled;
+        self
+    }
+
+    /// Stop the projectile when it hits any block.
+    pub fn stop_on_block_collision(mut self, enabled: bool) -> Self {
+        self.stop_on_block_collision = enabled;
+        self
+    }
+
+    /// Spawn the projectile.
+    ///
+    /// The origin is resolved when the commands are applied, if the entity the projectile is shot from
+    /// does not exist anymore, the projectile will be despawned.
+    pub fn spawn(self, commands: &mut Commands) -> Entity {
+        let Self {
+            bundle,
+            origin,
+            owner,
+            speed,
+            gravity,
+            inaccuracy,
+            forward_offset,
+            entity_collisions,
+            collision_mask,
+            block_collisions,
+            stop_on_block_collision,
+        } = self;
+
+        let projectile = commands.spawn(bundle).id();
+
+        commands.add(SetupProjectile {
+            projectile,
+            origin,
+            owner,
+            speed,
+            gravity,
+            inaccuracy,
+            forward_offset,
+            entity_collisions,
+            collision_mask,
+            block_collisions,
+            stop_on_block_collision,
+        });
+
+        projectile
+    }
+}
+
+struct SetupProjectile {
+    projectile: Entity,
+    origin: ProjectileOrigin,
+    owner: Option<Entity>,
+    speed: f32,
+    gravity: bool,
+    inaccuracy: f32,
+    forward_offset: f64,
+    entity_collisions: bool,
+    collision_mask: CollisionMask,
+    block_collisions: bool,
+    stop_on_block_collision: bool,
+}
+
+impl Command for SetupProjectile {
+    fn apply(self, world: &mut World) {
+        let (position, direction, layer) = match self.origin {
+            ProjectileOrigin::EntityEyes { entity, eye_height } => {
+                let Some(shooter) = world.get_entity(entity) else {
+                    world.despawn(self.projectile);
+                    return;
+                };
+
+                let (Some(position), Some(look)) =
+                    (shooter.get::<Position>(), shooter.get::<Look>())
+                else {
+                    world.despawn(self.projectile);
+                    return;
+                };
+
+                (
+                    position.0 + DVec3::new(0.0, eye_height, 0.0),
+                    look_direction(look),
+                    shooter.get::<EntityLayerId>().copied(),
+                )
+            }
+            ProjectileOrigin::Position {
+                position,
+                direction,
+            } => (position, direction.normalize_or_zero(), None),
+        };
+
+        let direction = if self.inaccuracy > 0.0 {
+            let spread = Vec3::new(
+                rand::random::<f32>() - 0.5,
+                rand::random::<f32>() - 0.5,
+                rand::random::<f32>() - 0.5,
+            ) * 2.0
+                * self.inaccuracy;
+
+            (direction + spread).normalize_or_zero()
+        } else {
+            direction
+        };
+
+        let Some(mut projectile) = world.get_entity_mut(self.projectile) else {
+            return;
+        };
+
+        if let Some(layer) = layer {
+            projectile.insert(layer);
+        }
+
+        let kind = projectile.get::<EntityKind>().copied();
+        let layer = projectile
+            .get::<EntityLayerId>()
+            .map_or(Entity::PLACEHOLDER, |layer| layer.0);
+
+        projectile.insert((
+            Position(position + direction.as_dvec3() * self.forward_offset),
+            direction_look(direction),
+            Velocity(direction * self.speed),
+            NoGravity(true),
+            Projectile { owner: self.owner },
+        ));
+
+        if self.entity_collisions {
+            projectile.insert((
+                EntityCollisionConfig::default(),
+                CollisionLayer::PROJECTILE,
+                self.collision_mask,
+            ));
+        }
+
+        if self.block_collisions {
+            projectile.insert(BlockCollisionConfig::default());
+        }
+
+        if self.stop_on_block_collision {
+            projectile.insert(StopOnBlockCollision::all());
+        }
+
+        let profile = world
+            .get_resource::<PhysicsSettings>()
+            .zip(kind)
+            .map(|(settings, kind)| settings.profile(kind, layer));
+
+        if let Some(profile) = profile {
+            let mut projectile = world.entity_mut(self.projectile);
+
+            projectile.insert((
+                Drag(Vec3::splat(profile.drag)),
+                SpeedLimit(profile.terminal_velocity),
+            ));
+
+            if self.gravity {
+                projectile.insert(Acceleration(Vec3::new(0.0, -profile.gravity, 0.0)));
+            }
+
+            if profile.restitution != 0.0 {
+                projectile.insert(Restitution::splat(profile.restitution));
+            }
+        }
+    }
+}
+
+/// The direction an entity is looking at.
+pub fn look_direction(look: &Look) -> Vec3 {
+    let yaw = look.yaw.to_radians();
+    let pitch = look.pitch.to_radians();
+
+    Vec3::new(
+        -yaw.sin() * pitch.cos(),
+        -pitch.sin(),
+        yaw.cos() * pitch.cos(),
+    )
+}
+
+/// The look of an entity that is looking in the given direction.
+pub fn direction_look(direction: Vec3) -> Look {
+    Look {
+        yaw: (-direction.x).atan2(direction.z).to_degrees(),
+        pitch: (-direction.y).asin().to_degrees(),
+    }
+}
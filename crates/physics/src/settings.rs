@@ -0,0 +1,169 @@
+use std::collections::HashMap;
+
+use valence::{entity::EntityKind, prelude::*};
+
+use crate::{Acceleration, Drag, Restitution, SpeedLimit};
+
+/// The physics constants of an entity type.
+///
+/// All values are per second (the vanilla values are per tick).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PhysicsProfile {
+    /// The gravity in blocks per second squared.
+    pub gravity: f32,
+    /// The air drag (see [`Drag`]).
+    pub drag: f32,
+    /// The maximum speed in blocks per second.
+    pub terminal_velocity: f32,
+    /// How much of the velocity is kept when bouncing off a block (0.0 - 1.0).
+    ///
+    /// If this is not `0.0`, a [`Restitution`] component will be added.
+    pub restitution: f32,
+}
+
+impl PhysicsProfile {
+    pub const fn new(gravity: f32, drag: f32) -> Self {
+        Self {
+            gravity,
+            drag,
+            terminal_velocity: 100.0,
+            restitution: 0.0,
+        }
+    }
+
+    pub const fn with_restitution(mut self, restitution: f32) -> Self {
+        self.restitution = restitution;
+        self
+    }
+}
+
+/// Physics constants that only apply to a single layer.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LayerPhysics {
+    pub gravity_multiplier: f32,
+    pub drag_multiplier: f32,
+    /// Overrides the terminal velocity of all entities on the layer.
+    pub terminal_velocity: Option<f32>,
+}
+
+impl Default for LayerPhysics {
+    fn default() -> Self {
+        Self {
+            gravity_multiplier: 1.0,
+            drag_multiplier: 1.0,
+            terminal_velocity: None,
+        }
+    }
+}
+
+/// The physics constants that are used for entities with the [`DefaultPhysics`] component.
+#[derive(Resource)]
+pub struct PhysicsSettings {
+    /// The profile used for entity types without a profile.
+    pub default_profile: PhysicsProfile,
+    pub profiles: HashMap<EntityKind, PhysicsProfile>,
+    pub layers: HashMap<Entity, LayerPhysics>,
+}
+
+impl Default for PhysicsSettings {
+    fn default() -> Self {
+        // TODO: set based on tick rate
+        let thrown = PhysicsProfile::new(0.03 * 400.0, 0.2);
+        let arrow = PhysicsProfile::new(0.05 * 400.0, 0.2);
+
+        Self {
+            default_profile: PhysicsProfile::new(0.08 * 400.0, 0.4),
+            profiles: HashMap::from([
+                (EntityKind::SNOWBALL, thrown),
+                (EntityKind::EGG, thrown),
+                (EntityKind::ENDER_PEARL, thrown),
+                (EntityKind::POTION, PhysicsProfile::new(0.05 * 400.0, 0.2)),
+                (EntityKind::ARROW, arrow),
+                (EntityKind::SPECTRAL_ARROW, arrow),
+                (EntityKind::TRIDENT, arrow),
+                (EntityKind::ITEM, PhysicsProfile::new(0.04 * 400.0, 0.4)),
+            ]),
+            layers: HashMap::new(),
+        }
+    }
+}
+
+impl PhysicsSettings {
+    /// The physics constants for an entity type on a layer.
+    pub fn profile(&self, kind: EntityKind, layer: Entity) -> PhysicsProfile {
+        let mut profile = self
+            .profiles
+            .get(&kind)
+            .copied()
+            .unwrap_or(self.default_profile);
+
+        if let Some(layer) = self.layers.get(&layer) {
+            profile.gravity *= layer.gravity_multiplier;
+            profile.drag *= layer.drag_multiplier;
+
+            if let Some(terminal_velocity) = layer.terminal_velocity {
+                profile.terminal_velocity = terminal_velocity;
+            }
+        }
+
+        profile
+    }
+
+    /// The physics components for an entity type on a layer.
+    pub fn components(&self, kind: EntityKind, layer: Entity) -> (Acceleration, Drag, SpeedLimit) {
+        let profile = self.profile(kind, layer);
+
+        (
+            Acceleration(Vec3::new(0.0, -profile.gravity, 0.0)),
+            Drag(Vec3::splat(profile.drag)),
+            SpeedLimit(profile.terminal_velocity),
+        )
+    }
+}
+
+/// Entities with this component will get their [`Acceleration`], [`Drag`], [`SpeedLimit`]
+/// and [`Restitution`] from the [`PhysicsSettings`] (components that are already present are not replaced).
+#[derive(Component, Default)]
+pub struct DefaultPhysics;
+
+#[allow(clippy::type_complexity)]
+pub(crate) fn apply_default_physics(
+    mut commands: Commands,
+    settings: Res<PhysicsSettings>,
+    query: Query<
+        (
+            Entity,
+            &EntityKind,
+            &EntityLayerId,
+            Has<Acceleration>,
+            Has<Drag>,
+            Has<SpeedLimit>,
+            Has<Restitution>,
+        ),
+        Added<DefaultPhysics>,
+    >,
+) {
+    for (entity, kind, layer, has_acceleration, has_drag, has_speed_limit, has_restitution) in
+        query.iter()
+    {
+        let (acceleration, drag, speed_limit) = settings.components(*kind, layer.0);
+        let restitution = settings.profile(*kind, layer.0).restitution;
+        let mut entity = commands.entity(entity);
+
+        if !has_acceleration {
+            entity.insert(acceleration);
+        }
+
+        if !has_drag {
+            entity.insert(drag);
+        }
+
+        if !has_speed_limit {
+            entity.insert(speed_limit);
+        }
+
+        if !has_restitution && restitution != 0.0 {
+            entity.insert(Restitution::splat(restitution));
+        }
+    }
+}
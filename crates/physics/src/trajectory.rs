@@ -0,0 +1,154 @@
+//! Steer projectiles (e.g. for guided abilities) without changing the physics integration.
+
+use std::time::Duration;
+
+use bevy_time::Time;
+use valence::{entity::Velocity, prelude::*};
+
+use crate::Acceleration;
+
+/// A custom trajectory, see [`Trajectory::Custom`].
+///
+/// The parameters are: `position`, `velocity`, `elapsed` (time since the controller was added).
+/// Returns the acceleration that is applied in addition to the [`Acceleration`] of the entity.
+pub type TrajectoryFn = Box<dyn Fn(DVec3, Vec3, Duration) -> Vec3 + Send + Sync>;
+
+/// How a [`TrajectoryController`] steers the entity.
+pub enum Trajectory {
+    /// Steer towards the center of the hitbox of an entity, the speed of the entity is kept.
+    ///
+    /// The steering stops if the target does not exist anymore.
+    Homing {
+        target: Entity,
+        /// The maximum steering acceleration (in blocks per second squared).
+        strength: f32,
+    },
+    /// Fly in an arc that reaches the target point after the flight time (gravity is taken into account).
+    ///
+    /// The entity is not controlled anymore after the flight time.
+    Arc {
+        target: DVec3,
+        flight_time: Duration,
+    },
+    /// Fly straight until `return_after`, then home back to the owner.
+    Boomerang {
+        owner: Entity,
+        return_after: Duration,
+        /// The maximum steering acceleration (in blocks per second squared) on the way back.
+        strength: f32,
+    },
+    Custom(TrajectoryFn),
+}
+
+/// Adjusts the velocity of an entity every physics step according to the [`Trajectory`].
+///
+/// ```ignore
+/// ProjectileBuilder::new((
+///     SnowballEntityBundle::default(),
+///     TrajectoryController::new(Trajectory::Homing { target, strength: 40.0 }),
+/// ))
+/// .from_entity_eyes(player)
+/// .spawn(&mut commands);
+/// ```
+#[derive(Component)]
+pub struct TrajectoryController {
+    pub trajectory: Trajectory,
+    elapsed: Duration,
+}
+
+impl TrajectoryController {
+    pub fn new(trajectory: Trajectory) -> Self {
+        Self {
+            trajectory,
+            elapsed: Duration::ZERO,
+        }
+    }
+
+    /// Use a closure as the trajectory, see [`TrajectoryFn`].
+    pub fn custom(f: impl Fn(DVec3, Vec3, Duration) -> Vec3 + Send + Sync + 'static) -> Self {
+        Self::new(Trajectory::Custom(Box::new(f)))
+    }
+
+    /// The time since the controller was added.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+}
+
+/// The acceleration that turns the velocity towards the target without changing the speed.
+fn steer_towards(position: DVec3, velocity: Vec3, target: DVec3, strength: f32) -> Vec3 {
+    let direction = (target - position).normalize_or_zero().as_vec3();
+    let desired = direction * velocity.length();
+
+    (desired - velocity).clamp_length_max(strength)
+}
+
+pub(crate) fn apply_trajectory_controllers(
+    time: Res<Time>,
+    mut controlled: Query<(
+        &mut TrajectoryController,
+        &Position,
+        &mut Velocity,
+        Option<&Acceleration>,
+    )>,
+    targets: Query<(&Position, Option<&Hitbox>)>,
+) {
+    let delta = time.delta();
+    let delta_seconds = time.delta_seconds();
+
+    if delta_seconds <= 0.0 {
+        return;
+    }
+
+    let target_center = |entity: Entity| {
+        targets
+            .get(entity)
+            .ok()
+            .map(|(position, hitbox)| match hitbox {
+                Some(hitbox) => {
+                    let hitbox = hitbox.get();
+                    (hitbox.min() + hitbox.max()) / 2.0
+                }
+                None => position.0,
+            })
+    };
+
+    for (mut controller, position, mut velocity, acceleration) in controlled.iter_mut() {
+        let elapsed = controller.elapsed;
+        controller.elapsed += delta;
+
+        let steering = match &controller.trajectory {
+            Trajectory::Homing { target, strength } => target_center(*target)
+                .map(|target| steer_towards(position.0, velocity.0, target, *strength)),
+            Trajectory::Arc {
+                target,
+                flight_time,
+            } => {
+                let Some(remaining) = flight_time.checked_sub(elapsed).filter(|t| !t.is_zero())
+                else {
+                    continue;
+                };
+
+                // The velocity that reaches the target in the remaining time with the current acceleration.
+                let t = remaining.as_secs_f32();
+                let gravity = acceleration.map_or(Vec3::ZERO, |acceleration| acceleration.0);
+                let required = (*target - position.0).as_vec3() / t - gravity * t / 2.0;
+
+                Some((required - velocity.0) / delta_seconds)
+            }
+            Trajectory::Boomerang {
+                owner,
+                return_after,
+                strength,
+            } => (elapsed >= *return_after)
+                .then(|| target_center(*owner))
+                .flatten()
+                .map(|owner| steer_towards(position.0, velocity.0, owner, *strength)),
+            Trajectory::Custom(f) => Some(f(position.0, velocity.0, elapsed)),
+        };
+
+        if let Some(steering) = steering {
+            velocity.0 += steering * delta_seconds;
+        }
+    }
+}
@@ -19,6 +19,19 @@ pub struct CollisionNormals {
     pub z: Option<bool>,
 }
 
+impl CollisionNormals {
+    /// The normals as a vector, axes without a collision are `0.0`.
+    pub fn normal(&self) -> Vec3 {
+        let axis = |normal: Option<bool>| match normal {
+            Some(true) => 1.0,
+            Some(false) => -1.0,
+            None => 0.0,
+        };
+
+        Vec3::new(axis(self.x), axis(self.y), axis(self.z))
+    }
+}
+
 pub struct CollisionResult {
     pub entry_time: f64,
     /// The normals of the
@@ -0,0 +1,215 @@
+//! Trigger zones that detect entities entering and leaving an area (e.g. capture points, shops or kill zones).
+//!
+//! Only entities in the entity BVH (entities with an [`EntityCollisionConfig`](crate::EntityCollisionConfig)) are tracked.
+
+use bvh::bvh_resource::{BvhResource, ENTITY_ENTITY_BVH_IDX};
+use valence::{ecs::entity::EntityHashSet, math::Aabb, prelude::*};
+
+use crate::CollisionLayer;
+
+/// The shape of a [`TriggerZone`].
+///
+/// If the zone entity has a [`Position`], the shape is relative to it (the zone moves with the entity),
+/// otherwise the shape is in world coordinates.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ZoneShape {
+    Aabb(Aabb),
+    Sphere { center: DVec3, radius: f64 },
+}
+
+impl ZoneShape {
+    /// Move the shape by the given offset.
+    pub fn translate(&self, offset: DVec3) -> Self {
+        match *self {
+            Self::Aabb(aabb) => Self::Aabb(aabb.translate(offset)),
+            Self::Sphere { center, radius } => Self::Sphere {
+                center: center + offset,
+                radius,
+            },
+        }
+    }
+
+    /// The smallest AABB that contains the shape.
+    pub fn bounds(&self) -> Aabb {
+        match *self {
+            Self::Aabb(aabb) => aabb,
+            Self::Sphere { center, radius } => {
+                Aabb::new(center - DVec3::splat(radius), center + DVec3::splat(radius))
+            }
+        }
+    }
+
+    /// If the hitbox is inside of (or intersects) the shape.
+    pub fn intersects(&self, hitbox: Aabb) -> bool {
+        match *self {
+            Self::Aabb(aabb) => aabb.intersects(hitbox),
+            Self::Sphere { center, radius } => {
+                let closest = center.clamp(hitbox.min(), hitbox.max());
+                closest.distance_squared(center) <= radius * radius
+            }
+        }
+    }
+}
+
+/// An area that emits a [`ZoneEnterEvent`] and [`ZoneLeaveEvent`] when entities enter or leave it.
+#[derive(Component, Clone, Debug)]
+pub struct TriggerZone {
+    pub shape: ZoneShape,
+    /// Only entities in one of these collision layers are tracked.
+    pub tracked_layers: u32,
+    inside: EntityHashSet,
+}
+
+impl TriggerZone {
+    pub fn new(shape: ZoneShape) -> Self {
+        Self {
+            shape,
+            tracked_layers: u32::MAX,
+            inside: EntityHashSet::default(),
+        }
+    }
+
+    pub fn aabb(aabb: Aabb) -> Self {
+        Self::new(ZoneShape::Aabb(aabb))
+    }
+
+    pub fn sphere(center: impl Into<DVec3>, radius: f64) -> Self {
+        Self::new(ZoneShape::Sphere {
+            center: center.into(),
+            radius,
+        })
+    }
+
+    /// Only track entities in the collision layer.
+    pub fn with_tracked_layer(mut self, layer: CollisionLayer) -> Self {
+        if self.tracked_layers == u32::MAX {
+            self.tracked_layers = 0;
+        }
+
+        self.tracked_layers |= layer.0;
+        self
+    }
+
+    /// The entities that are currently inside of the zone.
+    pub fn entities(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.inside.iter().copied()
+    }
+
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.inside.contains(&entity)
+    }
+}
+
+/// An event that is emitted when an entity enters a [`TriggerZone`].
+#[derive(Event, Clone, Copy, Debug)]
+pub struct ZoneEnterEvent {
+    pub zone: Entity,
+    pub entity: Entity,
+}
+
+/// An event that is emitted when an entity leaves a [`TriggerZone`] (or is despawned while inside).
+#[derive(Event, Clone, Copy, Debug)]
+pub struct ZoneLeaveEvent {
+    pub zone: Entity,
+    pub entity: Entity,
+}
+
+pub(crate) fn update_trigger_zones(
+    mut zones: Query<(
+        Entity,
+        &mut TriggerZone,
+        Option<&Position>,
+        Option<&EntityLayerId>,
+    )>,
+    layers: Query<&EntityLayerId>,
+    bvh: Res<BvhResource>,
+    mut enter_writer: EventWriter<ZoneEnterEvent>,
+    mut leave_writer: EventWriter<ZoneLeaveEvent>,
+) {
+    for (zone_entity, mut zone, position, zone_layer) in zones.iter_mut() {
+        let shape = match position {
+            Some(position) => zone.shape.translate(position.0),
+            None => zone.shape,
+        };
+
+        // Zones without a layer track entities on all layers.
+        let on_zone_layer = |entity| match zone_layer {
+            Some(zone_layer) => layers
+                .get(entity)
+                .is_ok_and(|layer| layer.0 == zone_layer.0),
+            None => true,
+        };
+
+        let inside = bvh[ENTITY_ENTITY_BVH_IDX]
+            .get_in_range(shape.bounds())
+            .filter(|entry| {
+                entry.entity != zone_entity
+                    && entry.layer & zone.tracked_layers != 0
+                    && shape.intersects(entry.hitbox)
+                    && on_zone_layer(entry.entity)
+            })
+            .map(|entry| entry.entity)
+            .collect::<EntityHashSet>();
+
+        for entity in inside.difference(&zone.inside) {
+            enter_writer.send(ZoneEnterEvent {
+                zone: zone_entity,
+                entity: *entity,
+            });
+        }
+
+        for entity in zone.inside.difference(&inside) {
+            leave_writer.send(ZoneLeaveEvent {
+                zone: zone_entity,
+                entity: *entity,
+            });
+        }
+
+        zone.inside = inside;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hitbox(min: [f64; 3], max: [f64; 3]) -> Aabb {
+        Aabb::new(DVec3::from(min), DVec3::from(max))
+    }
+
+    #[test]
+    fn aabb_zone_intersects_overlapping_hitboxes() {
+        let zone = ZoneShape::Aabb(hitbox([0.0, 0.0, 0.0], [4.0, 4.0, 4.0]));
+
+        assert!(zone.intersects(hitbox([1.0, 1.0, 1.0], [2.0, 2.0, 2.0])));
+        assert!(zone.intersects(hitbox([3.5, 0.0, 3.5], [4.5, 2.0, 4.5])));
+        assert!(!zone.intersects(hitbox([5.0, 0.0, 0.0], [6.0, 2.0, 1.0])));
+    }
+
+    #[test]
+    fn sphere_zone_uses_the_closest_point_of_the_hitbox() {
+        let zone = ZoneShape::Sphere {
+            center: DVec3::ZERO,
+            radius: 2.0,
+        };
+
+        // The center of the hitbox is outside of the sphere, but the hitbox reaches into it.
+        assert!(zone.intersects(hitbox([1.5, -1.0, -0.5], [3.5, 1.0, 0.5])));
+        // The corner of the bounds is outside of the sphere.
+        assert!(!zone.intersects(hitbox([1.5, 1.5, 1.5], [2.0, 2.0, 2.0])));
+        // The hitbox contains the whole sphere.
+        assert!(zone.intersects(hitbox([-5.0, -5.0, -5.0], [5.0, 5.0, 5.0])));
+    }
+
+    #[test]
+    fn translated_zone_moves_with_the_offset() {
+        let zone = ZoneShape::Sphere {
+            center: DVec3::ZERO,
+            radius: 1.0,
+        }
+        .translate(DVec3::new(10.0, 0.0, 0.0));
+
+        assert!(zone.intersects(hitbox([9.5, 0.0, 0.0], [10.5, 1.0, 1.0])));
+        assert!(!zone.intersects(hitbox([0.0, 0.0, 0.0], [1.0, 1.0, 1.0])));
+    }
+}
@@ -0,0 +1,140 @@
+//! Attaches the components that are needed by the other crates (combat, building, chat, fall damage)
+//! to every new player, so they do not have to be inserted manually.
+
+use building::{BuildState, PlayerBuildConfig};
+use chat::ChatAbility;
+use combat::{CombatState, PlayerCombatConfig};
+use fall_damage::{FallingState, FallingStateConfig};
+use utils::damage::TakesDamage;
+use valence::{equipment::EquipmentInventorySync, prelude::*};
+
+/// The components that are attached to every new player, `None` (or `false`) components are not attached.
+///
+/// Components that are inserted by other systems later (e.g. in the `init_clients` system of the server)
+/// replace the components of the template.
+#[derive(Resource, Clone)]
+pub struct PlayerTemplate {
+    /// The config of the [`CombatState`].
+    pub combat: Option<PlayerCombatConfig>,
+    /// The config of the [`BuildState`].
+    pub build: Option<PlayerBuildConfig>,
+    /// Attach a [`ChatAbility`].
+    pub chat: bool,
+    /// The config of the [`FallingState`].
+    pub fall_damage: Option<FallingStateConfig>,
+    pub takes_damage: Option<TakesDamage>,
+    /// Sync the [`Equipment`] with the inventory, so other players see the armor and held items.
+    pub equipment_inventory_sync: bool,
+}
+
+impl Default for PlayerTemplate {
+    fn default() -> Self {
+        Self {
+            combat: Some(PlayerCombatConfig::default()),
+            build: None,
+            chat: true,
+            fall_damage: Some(FallingStateConfig::default()),
+            takes_damage: Some(TakesDamage::default()),
+            equipment_inventory_sync: true,
+        }
+    }
+}
+
+impl PlayerTemplate {
+    /// A template that does not attach any components.
+    pub fn empty() -> Self {
+        Self {
+            combat: None,
+            build: None,
+            chat: false,
+            fall_damage: None,
+            takes_damage: None,
+            equipment_inventory_sync: false,
+        }
+    }
+
+    pub fn with_combat(mut self, config: PlayerCombatConfig) -> Self {
+        self.combat = Some(config);
+        self
+    }
+
+    pub fn with_build(mut self, config: PlayerBuildConfig) -> Self {
+        self.build = Some(config);
+        self
+    }
+
+    pub fn with_chat(mut self, chat: bool) -> Self {
+        self.chat = chat;
+        self
+    }
+
+    pub fn with_fall_damage(mut self, config: FallingStateConfig) -> Self {
+        self.fall_damage = Some(config);
+        self
+    }
+
+    pub fn with_takes_damage(mut self, takes_damage: TakesDamage) -> Self {
+        self.takes_damage = Some(takes_damage);
+        self
+    }
+
+    pub fn with_equipment_inventory_sync(mut self, sync: bool) -> Self {
+        self.equipment_inventory_sync = sync;
+        self
+    }
+
+    /// Attach the components of the template to an entity.
+    pub fn apply(&self, entity: &mut EntityCommands, position: DVec3) {
+        if let Some(config) = &self.combat {
+            entity.insert(CombatState {
+                combat_config: config.clone(),
+                ..Default::default()
+            });
+        }
+
+        if let Some(config) = &self.build {
+            entity.insert(BuildState {
+                build_config: config.clone(),
+                ..Default::default()
+            });
+        }
+
+        if self.chat {
+            entity.insert(ChatAbility::default());
+        }
+
+        if let Some(config) = &self.fall_damage {
+            let mut falling_state = FallingState::new(position);
+            falling_state.falling_state_config = config.clone();
+            entity.insert(falling_state);
+        }
+
+        if let Some(takes_damage) = &self.takes_damage {
+            entity.insert(takes_damage.clone());
+        }
+
+        if self.equipment_inventory_sync {
+            entity.insert(EquipmentInventorySync);
+        }
+    }
+}
+
+/// Attaches the components of the [`PlayerTemplate`] to every new client.
+pub struct PlayerSetupPlugin;
+
+impl Plugin for PlayerSetupPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PlayerTemplate>()
+            .add_systems(PreUpdate, setup_players);
+    }
+}
+
+fn setup_players(
+    mut commands: Commands,
+    template: Res<PlayerTemplate>,
+    clients: Query<(Entity, &Position), Added<Client>>,
+) {
+    for (entity, position) in clients.iter() {
+        template.apply(&mut commands.entity(entity), position.0);
+    }
+}
@@ -0,0 +1,375 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use valence::{
+    digging::DiggingEvent,
+    ecs::{entity::EntityHashMap, system::SystemParam},
+    entity::OldPosition,
+    math::{DVec2, DVec3},
+    prelude::*,
+    protocol::{packets::play::BlockUpdateS2c, WritePacket},
+};
+
+/// The flags that can be set on a region.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum RegionFlag {
+    /// Placing and breaking blocks.
+    Build,
+    /// Players attacking other players.
+    Pvp,
+    /// Players entering the region.
+    Entry,
+    /// Entities taking damage inside the region.
+    ///
+    /// [`RegionBypass`] is checked against the attacker, not the victim.
+    Damage,
+}
+
+/// The shape of a region.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum RegionShape {
+    /// An axis aligned box, both corners are inclusive.
+    Cuboid {
+        #[serde(with = "block_pos")]
+        min: BlockPos,
+        #[serde(with = "block_pos")]
+        max: BlockPos,
+    },
+    /// A polygon on the x/z plane that is extruded from `min_y` to `max_y` (inclusive).
+    Polygon {
+        /// The corners of the polygon as `(x, z)` coordinates.
+        #[serde(with = "points")]
+        points: Vec<DVec2>,
+        min_y: f64,
+        max_y: f64,
+    },
+}
+
+impl RegionShape {
+    /// Check if the shape contains the given position.
+    pub fn contains(&self, pos: DVec3) -> bool {
+        match self {
+            RegionShape::Cuboid { min, max } => {
+                pos.x >= min.x as f64
+                    && pos.x < max.x as f64 + 1.0
+                    && pos.y >= min.y as f64
+                    && pos.y < max.y as f64 + 1.0
+                    && pos.z >= min.z as f64
+                    && pos.z < max.z as f64 + 1.0
+            }
+            RegionShape::Polygon {
+                points,
+                min_y,
+                max_y,
+            } => {
+                if pos.y < *min_y || pos.y > *max_y + 1.0 {
+                    return false;
+                }
+
+                // Ray casting on the x/z plane.
+                let mut inside = false;
+                let mut j = points.len().wrapping_sub(1);
+                for i in 0..points.len() {
+                    let (a, b) = (points[i], points[j]);
+                    if (a.y > pos.z) != (b.y > pos.z)
+                        && pos.x < (b.x - a.x) * (pos.z - a.y) / (b.y - a.y) + a.x
+                    {
+                        inside = !inside;
+                    }
+                    j = i;
+                }
+
+                inside
+            }
+        }
+    }
+}
+
+/// A protected region.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Region {
+    pub name: String,
+    pub shape: RegionShape,
+    /// If multiple regions overlap, the flags of the region with the highest priority are used.
+    pub priority: i32,
+    /// `true` allows, `false` denies the action, flags that are not set are inherited
+    /// from overlapping regions with a lower priority (or allowed if no region sets them).
+    pub flags: HashMap<RegionFlag, bool>,
+}
+
+impl Region {
+    pub fn new(name: impl Into<String>, shape: RegionShape) -> Self {
+        Self {
+            name: name.into(),
+            shape,
+            priority: 0,
+            flags: HashMap::new(),
+        }
+    }
+
+    pub fn with_priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    pub fn with_flag(mut self, flag: RegionFlag, allow: bool) -> Self {
+        self.flags.insert(flag, allow);
+        self
+    }
+}
+
+/// Serializes a [`BlockPos`] as `[x, y, z]`.
+mod block_pos {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use valence::BlockPos;
+
+    pub fn serialize<S: Serializer>(pos: &BlockPos, serializer: S) -> Result<S::Ok, S::Error> {
+        [pos.x, pos.y, pos.z].serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<BlockPos, D::Error> {
+        let [x, y, z] = <[i32; 3]>::deserialize(deserializer)?;
+        Ok(BlockPos::new(x, y, z))
+    }
+}
+
+/// Serializes the points of a polygon as `[x, z]`.
+mod points {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use valence::math::DVec2;
+
+    pub fn serialize<S: Serializer>(points: &[DVec2], serializer: S) -> Result<S::Ok, S::Error> {
+        points
+            .iter()
+            .map(|point| [point.x, point.y])
+            .collect::<Vec<_>>()
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<DVec2>, D::Error> {
+        Ok(Vec::<[f64; 2]>::deserialize(deserializer)?
+            .into_iter()
+            .map(|[x, z]| DVec2::new(x, z))
+            .collect())
+    }
+}
+
+/// The id of a region.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RegionId(u64);
+
+/// Stores all regions per layer.
+#[derive(Resource, Default)]
+pub struct Regions {
+    layers: EntityHashMap<Vec<(RegionId, Region)>>,
+    next_id: u64,
+}
+
+impl Regions {
+    /// Add a region to a layer.
+    pub fn add_region(&mut self, layer: Entity, region: Region) -> RegionId {
+        let id = RegionId(self.next_id);
+        self.next_id += 1;
+
+        let regions = self.layers.entry(layer).or_default();
+        regions.push((id, region));
+        // Keep the regions sorted so the highest priority is checked first.
+        regions.sort_by_key(|(_, region)| std::cmp::Reverse(region.priority));
+
+        id
+    }
+
+    /// Remove a region, returns the region if it existed.
+    pub fn remove_region(&mut self, id: RegionId) -> Option<Region> {
+        for regions in self.layers.values_mut() {
+            if let Some(idx) = regions.iter().position(|(region_id, _)| *region_id == id) {
+                return Some(regions.remove(idx).1);
+            }
+        }
+
+        None
+    }
+
+    pub fn get(&self, id: RegionId) -> Option<&Region> {
+        self.layers
+            .values()
+            .flatten()
+            .find(|(region_id, _)| *region_id == id)
+            .map(|(_, region)| region)
+    }
+
+    pub fn get_mut(&mut self, id: RegionId) -> Option<&mut Region> {
+        self.layers
+            .values_mut()
+            .flatten()
+            .find(|(region_id, _)| *region_id == id)
+            .map(|(_, region)| region)
+    }
+
+    /// All regions on the layer that contain the position (highest priority first).
+    pub fn regions_at(
+        &self,
+        layer: Entity,
+        pos: DVec3,
+    ) -> impl Iterator<Item = (RegionId, &Region)> + '_ {
+        self.layers
+            .get(&layer)
+            .into_iter()
+            .flatten()
+            .filter(move |(_, region)| region.shape.contains(pos))
+            .map(|(id, region)| (*id, region))
+    }
+
+    /// The value of a flag at a position and the region that set it.
+    ///
+    /// Returns `None` if no region at the position sets the flag.
+    pub fn flag_at(&self, layer: Entity, pos: DVec3, flag: RegionFlag) -> Option<(RegionId, bool)> {
+        self.regions_at(layer, pos)
+            .find_map(|(id, region)| region.flags.get(&flag).map(|allow| (id, *allow)))
+    }
+}
+
+/// Entities with this component ignore all region flags (e.g. admins).
+#[derive(Component)]
+pub struct RegionBypass;
+
+/// An event that will be fired if an action was denied by a region flag.
+#[derive(Event, Debug)]
+pub struct RegionFlagDeniedEvent {
+    /// The entity that tried to perform the action.
+    pub entity: Entity,
+    pub region: RegionId,
+    pub flag: RegionFlag,
+    pub position: DVec3,
+}
+
+/// A system param that can be used to check region flags from any system.
+///
+/// This also works if the [`RegionsPlugin`] was not added, in that case every action is allowed.
+#[derive(SystemParam)]
+pub struct RegionFlagCheck<'w, 's> {
+    regions: Option<Res<'w, Regions>>,
+    bypass: Query<'w, 's, (), With<RegionBypass>>,
+    denied_events: Option<ResMut<'w, Events<RegionFlagDeniedEvent>>>,
+}
+
+impl RegionFlagCheck<'_, '_> {
+    /// Check if the entity is allowed to perform the action at the position.
+    ///
+    /// A [`RegionFlagDeniedEvent`] will be fired if the action is denied.
+    pub fn check(&mut self, entity: Entity, layer: Entity, pos: DVec3, flag: RegionFlag) -> bool {
+        let Some(regions) = &self.regions else {
+            return true;
+        };
+
+        if self.bypass.contains(entity) {
+            return true;
+        }
+
+        match regions.flag_at(layer, pos, flag) {
+            Some((region, false)) => {
+                if let Some(events) = &mut self.denied_events {
+                    events.send(RegionFlagDeniedEvent {
+                        entity,
+                        region,
+                        flag,
+                        position: pos,
+                    });
+                }
+
+                false
+            }
+            _ => true,
+        }
+    }
+
+    /// Check the flag without firing an event.
+    pub fn is_allowed(&self, entity: Entity, layer: Entity, pos: DVec3, flag: RegionFlag) -> bool {
+        self.bypass.contains(entity) || self.is_allowed_at(layer, pos, flag)
+    }
+
+    /// Check the flag of the regions at the position, without a bypass or an event
+    /// (e.g. for actions that are not caused by an entity).
+    pub fn is_allowed_at(&self, layer: Entity, pos: DVec3, flag: RegionFlag) -> bool {
+        let Some(regions) = &self.regions else {
+            return true;
+        };
+
+        regions
+            .flag_at(layer, pos, flag)
+            .map_or(true, |(_, allow)| allow)
+    }
+}
+
+/// Adds the [`Regions`] resource and enforces the [`RegionFlag::Entry`] flag.
+///
+/// Digging in regions without [`RegionFlag::Build`] is reverted on the client,
+/// systems that break blocks should check the flag with [`RegionFlagCheck`] themselves.
+pub struct RegionsPlugin;
+
+impl Plugin for RegionsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<RegionFlagDeniedEvent>()
+            .init_resource::<Regions>()
+            .add_systems(Update, (deny_digging, deny_entry));
+    }
+}
+
+/// Valence does not break blocks on its own, but the client already predicted the break.
+/// If breaking is denied, the real block is sent to the client again.
+fn deny_digging(
+    mut events: EventReader<DiggingEvent>,
+    mut clients: Query<(&mut Client, &EntityLayerId)>,
+    layers: Query<&ChunkLayer>,
+    mut check: RegionFlagCheck,
+) {
+    for event in events.read() {
+        let Ok((mut client, layer_id)) = clients.get_mut(event.client) else {
+            continue;
+        };
+
+        let pos = DVec3::new(
+            event.position.x as f64 + 0.5,
+            event.position.y as f64 + 0.5,
+            event.position.z as f64 + 0.5,
+        );
+
+        if check.check(event.client, layer_id.0, pos, RegionFlag::Build) {
+            continue;
+        }
+
+        let Some(block) = layers
+            .get(layer_id.0)
+            .ok()
+            .and_then(|layer| layer.block(event.position))
+        else {
+            continue;
+        };
+
+        client.write_packet(&BlockUpdateS2c {
+            position: event.position,
+            block_id: block.state,
+        });
+    }
+}
+
+/// Teleports players back if they enter a region they are not allowed to enter.
+fn deny_entry(
+    mut clients: Query<(Entity, &mut Position, &OldPosition, &EntityLayerId), With<Client>>,
+    mut check: RegionFlagCheck,
+) {
+    for (entity, mut position, old_position, layer_id) in clients.iter_mut() {
+        if position.0 == old_position.get() {
+            continue;
+        }
+
+        if !check.is_allowed(entity, layer_id.0, old_position.get(), RegionFlag::Entry) {
+            // The player was already inside (e.g. teleported there), let them leave.
+            continue;
+        }
+
+        if !check.check(entity, layer_id.0, position.0, RegionFlag::Entry) {
+            position.0 = old_position.get();
+        }
+    }
+}
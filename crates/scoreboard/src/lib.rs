@@ -0,0 +1,281 @@
+use std::borrow::Cow;
+
+use valence::{
+    ecs::system::SystemParam,
+    prelude::*,
+    protocol::{
+        packets::play::{
+            scoreboard_display_s2c::ScoreboardPosition,
+            scoreboard_objective_update_s2c::{ObjectiveMode, ObjectiveRenderType},
+            scoreboard_player_update_s2c::ScoreboardPlayerUpdateAction,
+            team_s2c::{CollisionRule, Mode, NameTagVisibility, TeamColor, TeamFlags},
+            ScoreboardDisplayS2c, ScoreboardObjectiveUpdateS2c, ScoreboardPlayerUpdateS2c, TeamS2c,
+        },
+        VarInt, WritePacket,
+    },
+    text::IntoText,
+};
+
+/// The name of the objective that is used for the sidebar.
+const OBJECTIVE_NAME: &str = "sidebar";
+/// The maximum amount of lines the client can show in the sidebar.
+pub const MAX_LINES: usize = 15;
+
+/// A sidebar scoreboard that is only shown to the client it is attached to.
+///
+/// Every line has a short score holder name, the text of the line is the prefix of a team with that holder.
+/// Only the lines that changed are sent to the client.
+#[derive(Component)]
+pub struct Sidebar {
+    title: Text,
+    lines: Vec<Text>,
+    title_changed: bool,
+    /// The lines that were sent to the client.
+    sent_lines: Vec<Text>,
+    sent: bool,
+}
+
+impl Sidebar {
+    pub fn new(title: impl IntoText<'static>) -> Self {
+        Self {
+            title: title.into_cow_text().into_owned(),
+            lines: vec![],
+            title_changed: false,
+            sent_lines: vec![],
+            sent: false,
+        }
+    }
+
+    pub fn with_lines(mut self, lines: impl IntoIterator<Item = Text>) -> Self {
+        self.set_lines(lines);
+        self
+    }
+
+    pub fn title(&self) -> &Text {
+        &self.title
+    }
+
+    pub fn set_title(&mut self, title: impl IntoText<'static>) {
+        self.title = title.into_cow_text().into_owned();
+        self.title_changed = true;
+    }
+
+    pub fn lines(&self) -> &[Text] {
+        &self.lines
+    }
+
+    /// Set the line at the index (starting at the top), missing lines above it will be filled with empty lines.
+    ///
+    /// Lines after [`MAX_LINES`] are ignored.
+    pub fn set_line(&mut self, index: usize, line: impl IntoText<'static>) {
+        if index >= MAX_LINES {
+            return;
+        }
+
+        if self.lines.len() <= index {
+            self.lines.resize(index + 1, Text::default());
+        }
+
+        self.lines[index] = line.into_cow_text().into_owned();
+    }
+
+    pub fn remove_line(&mut self, index: usize) {
+        if index < self.lines.len() {
+            self.lines.remove(index);
+        }
+    }
+
+    pub fn set_lines(&mut self, lines: impl IntoIterator<Item = Text>) {
+        self.lines = lines.into_iter().take(MAX_LINES).collect();
+    }
+
+    pub fn clear(&mut self) {
+        self.lines.clear();
+    }
+}
+
+/// A helper to change the sidebar of players.
+#[derive(SystemParam)]
+pub struct Sidebars<'w, 's> {
+    query: Query<'w, 's, &'static mut Sidebar>,
+}
+
+impl Sidebars<'_, '_> {
+    /// Set a line of the sidebar of a player (if the player has a [`Sidebar`]).
+    pub fn set_line(&mut self, player: Entity, index: usize, line: impl IntoText<'static>) {
+        if let Ok(mut sidebar) = self.query.get_mut(player) {
+            sidebar.set_line(index, line);
+        }
+    }
+
+    pub fn set_title(&mut self, player: Entity, title: impl IntoText<'static>) {
+        if let Ok(mut sidebar) = self.query.get_mut(player) {
+            sidebar.set_title(title);
+        }
+    }
+
+    pub fn get_mut(&mut self, player: Entity) -> Option<Mut<'_, Sidebar>> {
+        self.query.get_mut(player).ok()
+    }
+}
+
+pub struct ScoreboardPlugin;
+
+impl Plugin for ScoreboardPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(PostUpdate, sync_sidebars)
+            .observe(remove_sidebar);
+    }
+}
+
+/// The score holder name of a line.
+///
+/// Holder names are limited to 40 characters, so they only consist of (invisible) color codes.
+fn line_holder(index: usize) -> String {
+    format!("§{index:x}§r")
+}
+
+/// The name of the team that shows the text of a line.
+fn line_team(index: usize) -> String {
+    format!("sidebar_{index}")
+}
+
+/// Create the team of a line (if a holder is given) or update its text.
+fn line_team_packet<'a>(
+    team_name: &'a str,
+    line: &'a Text,
+    holder: Option<&'a str>,
+) -> TeamS2c<'a> {
+    let team_display_name = Cow::Owned(Text::default());
+    let team_prefix = Cow::Borrowed(line);
+    let team_suffix = Cow::Owned(Text::default());
+
+    let mode = match holder {
+        Some(holder) => Mode::CreateTeam {
+            team_display_name,
+            friendly_flags: TeamFlags::new(),
+            name_tag_visibility: NameTagVisibility::Always,
+            collision_rule: CollisionRule::Always,
+            team_color: TeamColor::White,
+            team_prefix,
+            team_suffix,
+            entities: vec![holder],
+        },
+        None => Mode::UpdateTeamInfo {
+            team_display_name,
+            friendly_flags: TeamFlags::new(),
+            name_tag_visibility: NameTagVisibility::Always,
+            collision_rule: CollisionRule::Always,
+            team_color: TeamColor::White,
+            team_prefix,
+            team_suffix,
+        },
+    };
+
+    TeamS2c { team_name, mode }
+}
+
+fn sync_sidebars(mut query: Query<(&mut Client, &mut Sidebar), Changed<Sidebar>>) {
+    for (mut client, mut sidebar) in query.iter_mut() {
+        let sidebar = &mut *sidebar;
+
+        if !sidebar.sent {
+            client.write_packet(&ScoreboardObjectiveUpdateS2c {
+                objective_name: OBJECTIVE_NAME,
+                mode: ObjectiveMode::Create {
+                    objective_display_name: sidebar.title.clone(),
+                    render_type: ObjectiveRenderType::Integer,
+                },
+            });
+
+            client.write_packet(&ScoreboardDisplayS2c {
+                position: ScoreboardPosition::Sidebar,
+                score_name: OBJECTIVE_NAME,
+            });
+
+            sidebar.sent = true;
+            sidebar.title_changed = false;
+        } else if sidebar.title_changed {
+            client.write_packet(&ScoreboardObjectiveUpdateS2c {
+                objective_name: OBJECTIVE_NAME,
+                mode: ObjectiveMode::Update {
+                    objective_display_name: sidebar.title.clone(),
+                    render_type: ObjectiveRenderType::Integer,
+                },
+            });
+
+            sidebar.title_changed = false;
+        }
+
+        let lines = &sidebar.lines;
+        let sent_lines = &sidebar.sent_lines;
+
+        // The scores decide the order of the lines, so all scores change if the amount of lines changes.
+        let resend_all = lines.len() != sent_lines.len();
+
+        for index in 0..lines.len().max(sent_lines.len()) {
+            let holder = line_holder(index);
+            let team_name = line_team(index);
+
+            let Some(line) = lines.get(index) else {
+                client.write_packet(&ScoreboardPlayerUpdateS2c {
+                    entity_name: &holder,
+                    action: ScoreboardPlayerUpdateAction::Remove {
+                        objective_name: OBJECTIVE_NAME,
+                    },
+                });
+                client.write_packet(&TeamS2c {
+                    team_name: &team_name,
+                    mode: Mode::RemoveTeam,
+                });
+                continue;
+            };
+
+            match sent_lines.get(index) {
+                None => client.write_packet(&line_team_packet(&team_name, line, Some(&holder))),
+                Some(sent) if sent != line => {
+                    client.write_packet(&line_team_packet(&team_name, line, None))
+                }
+                Some(_) => {}
+            }
+
+            if resend_all {
+                client.write_packet(&ScoreboardPlayerUpdateS2c {
+                    entity_name: &holder,
+                    action: ScoreboardPlayerUpdateAction::Update {
+                        objective_name: OBJECTIVE_NAME,
+                        objective_score: VarInt((lines.len() - index) as i32),
+                    },
+                });
+            }
+        }
+
+        sidebar.sent_lines.clone_from(&sidebar.lines);
+    }
+}
+
+/// The teams of the lines are removed with the sidebar, so they can be created again by a new sidebar.
+fn remove_sidebar(
+    trigger: Trigger<OnRemove, Sidebar>,
+    mut clients: Query<(&mut Client, &Sidebar)>,
+) {
+    let Ok((mut client, sidebar)) = clients.get_mut(trigger.entity()) else {
+        return;
+    };
+
+    if !sidebar.sent {
+        return;
+    }
+
+    client.write_packet(&ScoreboardObjectiveUpdateS2c {
+        objective_name: OBJECTIVE_NAME,
+        mode: ObjectiveMode::Remove,
+    });
+
+    for index in 0..sidebar.sent_lines.len() {
+        client.write_packet(&TeamS2c {
+            team_name: &line_team(index),
+            mode: Mode::RemoveTeam,
+        });
+    }
+}
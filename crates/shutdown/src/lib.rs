@@ -0,0 +1,193 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use valence::{
+    app::AppExit,
+    ecs::schedule::{ScheduleLabel, SystemSet},
+    prelude::*,
+};
+
+/// The schedule that runs once when the server shuts down.
+///
+/// Other crates can register their shutdown logic into the [`ShutdownSet`]s of this schedule.
+#[derive(ScheduleLabel, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Shutdown;
+
+/// The ordered stages of the [`Shutdown`] schedule.
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ShutdownSet {
+    /// Players are kicked or transferred.
+    DisconnectPlayers,
+    /// Persistence stores (stats, balances, audit logs, ...) should be flushed.
+    FlushStores,
+    /// Modified chunks should be saved.
+    SaveChunks,
+}
+
+/// Send this event to start the shutdown of the server.
+#[derive(Event)]
+pub struct ShutdownRequestEvent {
+    /// Overrides the [`ShutdownConfig::grace_period`].
+    pub grace_period: Option<Duration>,
+}
+
+/// An event that will be fired once the shutdown was requested (before the grace period).
+#[derive(Event)]
+pub struct ShutdownStartedEvent;
+
+/// What should happen to the players once the grace period is over.
+pub enum ShutdownPlayerAction {
+    /// Kick the player with the given reason.
+    Kick(Text),
+    /// Handle the player with a custom function (e.g. transfer them to another server).
+    ///
+    /// The parameters are: `commands`, `player_entity`.
+    Custom(fn(&mut Commands, Entity)),
+}
+
+#[derive(Resource)]
+pub struct ShutdownConfig {
+    /// The message that will be broadcast to all players when the shutdown starts.
+    pub message: Option<Text>,
+    /// The time between the shutdown request and the actual shutdown.
+    pub grace_period: Duration,
+    /// What should happen to the players.
+    pub player_action: ShutdownPlayerAction,
+    /// Start the shutdown when the process receives SIGINT (Ctrl+C).
+    pub handle_ctrl_c: bool,
+}
+
+impl Default for ShutdownConfig {
+    fn default() -> Self {
+        Self {
+            message: Some("The server is shutting down".color(Color::RED)),
+            grace_period: Duration::from_secs(3),
+            player_action: ShutdownPlayerAction::Kick("The server was shut down".into_text()),
+            handle_ctrl_c: true,
+        }
+    }
+}
+
+/// The current state of the shutdown.
+#[derive(Resource, Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShutdownState {
+    #[default]
+    Running,
+    /// The shutdown was requested, the [`Shutdown`] schedule will run once the deadline is reached.
+    Announced { deadline: Instant },
+    /// The [`Shutdown`] schedule ran, the app will exit in the next tick
+    /// (so the disconnect packets can still be sent).
+    Finished,
+}
+
+/// Set by the Ctrl+C handler.
+#[derive(Resource, Default)]
+struct CtrlCSignal(Arc<AtomicBool>);
+
+pub struct ShutdownPlugin;
+
+impl Plugin for ShutdownPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ShutdownRequestEvent>()
+            .add_event::<ShutdownStartedEvent>()
+            .init_resource::<ShutdownConfig>()
+            .init_resource::<ShutdownState>()
+            .init_resource::<CtrlCSignal>()
+            .init_schedule(Shutdown)
+            .configure_sets(
+                Shutdown,
+                (
+                    ShutdownSet::DisconnectPlayers,
+                    ShutdownSet::FlushStores,
+                    ShutdownSet::SaveChunks,
+                )
+                    .chain(),
+            )
+            .add_systems(Startup, install_ctrl_c_handler)
+            .add_systems(Update, (start_shutdown, run_shutdown).chain())
+            .add_systems(
+                Shutdown,
+                disconnect_players.in_set(ShutdownSet::DisconnectPlayers),
+            );
+    }
+}
+
+fn install_ctrl_c_handler(config: Res<ShutdownConfig>, signal: Res<CtrlCSignal>) {
+    if !config.handle_ctrl_c {
+        return;
+    }
+
+    let signal = signal.0.clone();
+    if let Err(err) = ctrlc::set_handler(move || signal.store(true, Ordering::SeqCst)) {
+        tracing::warn!("failed to install the Ctrl+C handler: {err}");
+    }
+}
+
+fn start_shutdown(
+    mut requests: EventReader<ShutdownRequestEvent>,
+    mut started_writer: EventWriter<ShutdownStartedEvent>,
+    mut state: ResMut<ShutdownState>,
+    mut clients: Query<&mut Client>,
+    config: Res<ShutdownConfig>,
+    signal: Res<CtrlCSignal>,
+) {
+    let ctrl_c = signal.0.swap(false, Ordering::SeqCst);
+
+    let grace_period = match requests.read().last() {
+        Some(request) => request.grace_period.unwrap_or(config.grace_period),
+        None if ctrl_c => config.grace_period,
+        None => return,
+    };
+
+    if *state != ShutdownState::Running {
+        return;
+    }
+
+    *state = ShutdownState::Announced {
+        deadline: Instant::now() + grace_period,
+    };
+
+    if let Some(message) = &config.message {
+        for mut client in clients.iter_mut() {
+            client.send_chat_message(message.clone());
+        }
+    }
+
+    tracing::info!("shutting down in {grace_period:?}");
+    started_writer.send(ShutdownStartedEvent);
+}
+
+fn run_shutdown(world: &mut World) {
+    match *world.resource::<ShutdownState>() {
+        ShutdownState::Running => {}
+        ShutdownState::Announced { deadline } => {
+            if Instant::now() < deadline {
+                return;
+            }
+
+            world.run_schedule(Shutdown);
+            *world.resource_mut::<ShutdownState>() = ShutdownState::Finished;
+        }
+        ShutdownState::Finished => {
+            world.send_event(AppExit::Success);
+        }
+    }
+}
+
+fn disconnect_players(
+    mut commands: Commands,
+    mut clients: Query<(Entity, &mut Client)>,
+    config: Res<ShutdownConfig>,
+) {
+    for (entity, mut client) in clients.iter_mut() {
+        match &config.player_action {
+            ShutdownPlayerAction::Kick(reason) => client.kick(reason.clone()),
+            ShutdownPlayerAction::Custom(action) => action(&mut commands, entity),
+        }
+    }
+}
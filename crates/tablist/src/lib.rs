@@ -0,0 +1,346 @@
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet},
+};
+
+use valence::{
+    ecs::entity::{EntityHashMap, EntityHashSet},
+    keepalive::Ping,
+    player_list::{DisplayName, Listed, PlayerListEntryBundle, PlayerListSet},
+    prelude::*,
+    protocol::{
+        packets::play::{
+            player_list_s2c::{PlayerListActions, PlayerListEntry},
+            team_s2c::{CollisionRule, Mode, NameTagVisibility, TeamColor, TeamFlags},
+            PlayerListHeaderS2c, PlayerListS2c, TeamS2c,
+        },
+        WritePacket,
+    },
+    text::IntoText,
+    uuid::Uuid,
+};
+use visibility::VisibilityRules;
+
+/// The header and footer of the tab list of a single player.
+#[derive(Component, Clone, Debug, Default)]
+pub struct TabListHeaderFooter {
+    pub header: Text,
+    pub footer: Text,
+}
+
+/// The name of a player (or fake entry) in the tab list.
+#[derive(Component, Clone, Debug)]
+pub struct TabListName(pub Text);
+
+/// Change the ping that other players see in the tab list.
+///
+/// The real [`Ping`] of the player is not changed (so it can still be used e.g. for lag compensation).
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TabListPing {
+    /// No ping bars are shown.
+    Hidden,
+    /// Show a fixed ping (in milliseconds).
+    Fixed(i32),
+}
+
+impl TabListPing {
+    fn latency(&self) -> i32 {
+        match self {
+            Self::Hidden => -1,
+            Self::Fixed(ping) => *ping,
+        }
+    }
+}
+
+/// The position of a player (or fake entry) in the tab list, lower values are shown first.
+///
+/// Entries with the same order are sorted by their name.
+/// Entries without this component are shown after all entries with this component.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TabListOrder(pub u16);
+
+impl TabListOrder {
+    /// The name of the team that is used to sort the entries.
+    fn team_name(&self) -> String {
+        format!("tab_{:05}", self.0)
+    }
+}
+
+/// Marker component for entries in the tab list that are not players.
+#[derive(Component)]
+pub struct FakeTabListEntry;
+
+/// Spawn an entry in the tab list that is not a player (e.g. for lobby information).
+pub fn spawn_fake_entry(
+    commands: &mut Commands,
+    text: impl IntoText<'static>,
+    order: TabListOrder,
+) -> Entity {
+    let uuid = Uuid::from_u128(rand::random());
+    let username = format!("~{}", &uuid.simple().to_string()[..15]);
+
+    commands
+        .spawn((
+            PlayerListEntryBundle {
+                uuid: UniqueId(uuid),
+                username: Username(username),
+                display_name: DisplayName(Some(text.into_cow_text().into_owned())),
+                listed: Listed(true),
+                ..Default::default()
+            },
+            order,
+            FakeTabListEntry,
+        ))
+        .id()
+}
+
+/// The teams that are used to sort the tab list.
+#[derive(Resource, Default)]
+struct TabListTeams {
+    members: BTreeMap<TabListOrder, BTreeSet<String>>,
+    /// The order every entry currently has.
+    entries: EntityHashMap<(TabListOrder, String)>,
+}
+
+/// The entries that are currently unlisted for a client because of the [`VisibilityRules`].
+#[derive(Resource, Default)]
+struct HiddenTabListEntries(EntityHashMap<EntityHashSet>);
+
+pub struct TabListPlugin;
+
+impl Plugin for TabListPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TabListTeams>()
+            .init_resource::<HiddenTabListEntries>()
+            .add_systems(
+                PostUpdate,
+                (
+                    sync_header_footer,
+                    sync_names,
+                    // Valence sends the latency in `PlayerListSet`, it is overwritten afterwards.
+                    sync_ping.after(PlayerListSet),
+                    (update_order, send_teams_to_new_clients).chain(),
+                    sync_hidden_entries.after(PlayerListSet),
+                ),
+            );
+    }
+}
+
+fn sync_header_footer(
+    mut query: Query<(&mut Client, &TabListHeaderFooter), Changed<TabListHeaderFooter>>,
+) {
+    for (mut client, header_footer) in query.iter_mut() {
+        client.write_packet(&PlayerListHeaderS2c {
+            header: Cow::Borrowed(&header_footer.header),
+            footer: Cow::Borrowed(&header_footer.footer),
+        });
+    }
+}
+
+fn sync_names(
+    mut commands: Commands,
+    mut query: Query<(Entity, &TabListName, Option<&mut DisplayName>), Changed<TabListName>>,
+) {
+    for (entity, name, display_name) in query.iter_mut() {
+        match display_name {
+            Some(mut display_name) => display_name.0 = Some(name.0.clone()),
+            None => {
+                commands
+                    .entity(entity)
+                    .insert(DisplayName(Some(name.0.clone())));
+            }
+        }
+    }
+}
+
+/// Overwrite the latency valence sends whenever the ping changes.
+#[allow(clippy::type_complexity)]
+fn sync_ping(
+    entries: Query<(&UniqueId, &TabListPing), Or<(Changed<TabListPing>, Changed<Ping>)>>,
+    mut clients: Query<&mut Client>,
+) {
+    let updates = entries
+        .iter()
+        .map(|(uuid, ping)| PlayerListEntry {
+            player_uuid: uuid.0,
+            ping: ping.latency(),
+            ..Default::default()
+        })
+        .collect::<Vec<_>>();
+
+    if updates.is_empty() {
+        return;
+    }
+
+    let packet = PlayerListS2c {
+        actions: PlayerListActions::new().with_update_latency(true),
+        entries: Cow::Borrowed(&updates),
+    };
+
+    for mut client in clients.iter_mut() {
+        client.write_packet(&packet);
+    }
+}
+
+fn create_team_packet<'a>(team_name: &'a str, members: Vec<&'a str>) -> TeamS2c<'a> {
+    TeamS2c {
+        team_name,
+        mode: Mode::CreateTeam {
+            team_display_name: Cow::Owned(Text::default()),
+            friendly_flags: TeamFlags::new(),
+            name_tag_visibility: NameTagVisibility::Always,
+            collision_rule: CollisionRule::Always,
+            team_color: TeamColor::White,
+            team_prefix: Cow::Owned(Text::default()),
+            team_suffix: Cow::Owned(Text::default()),
+            entities: members,
+        },
+    }
+}
+
+enum TeamUpdate {
+    Create,
+    Add,
+    Remove,
+}
+
+/// Move entries into the team of their order.
+fn update_order(
+    mut teams: ResMut<TabListTeams>,
+    changed: Query<(Entity, &TabListOrder, &Username), Changed<TabListOrder>>,
+    mut removed: RemovedComponents<TabListOrder>,
+    mut clients: Query<&mut Client>,
+) {
+    let teams = &mut *teams;
+    let mut updates = vec![];
+
+    let removed = removed.read().map(|entity| (entity, None));
+    let changed = changed
+        .iter()
+        .map(|(entity, order, username)| (entity, Some((*order, username.0.clone()))));
+
+    for (entity, new) in removed.chain(changed).collect::<Vec<_>>() {
+        if let Some((old_order, name)) = teams.entries.remove(&entity) {
+            if let Some(members) = teams.members.get_mut(&old_order) {
+                members.remove(&name);
+            }
+
+            updates.push((old_order, TeamUpdate::Remove, name));
+        }
+
+        let Some((order, name)) = new else {
+            continue;
+        };
+
+        let update = if teams.members.contains_key(&order) {
+            TeamUpdate::Add
+        } else {
+            TeamUpdate::Create
+        };
+
+        teams.members.entry(order).or_default().insert(name.clone());
+        teams.entries.insert(entity, (order, name.clone()));
+        updates.push((order, update, name));
+    }
+
+    for (order, update, name) in updates {
+        let team_name = order.team_name();
+
+        let packet = match update {
+            TeamUpdate::Create => create_team_packet(&team_name, vec![&name]),
+            TeamUpdate::Add => TeamS2c {
+                team_name: &team_name,
+                mode: Mode::AddEntities {
+                    entities: vec![&name],
+                },
+            },
+            TeamUpdate::Remove => TeamS2c {
+                team_name: &team_name,
+                mode: Mode::RemoveEntities {
+                    entities: vec![&name],
+                },
+            },
+        };
+
+        // New clients will receive all teams in `send_teams_to_new_clients`.
+        for mut client in clients.iter_mut().filter(|client| !client.is_added()) {
+            client.write_packet(&packet);
+        }
+    }
+}
+
+/// Clients that joined need to know all teams.
+fn send_teams_to_new_clients(
+    teams: Res<TabListTeams>,
+    mut clients: Query<&mut Client, Added<Client>>,
+) {
+    for mut client in clients.iter_mut() {
+        for (order, members) in teams.members.iter() {
+            let team_name = order.team_name();
+            let members = members.iter().map(String::as_str).collect();
+
+            client.write_packet(&create_team_packet(&team_name, members));
+        }
+    }
+}
+
+/// Unlist the entries that are hidden from a client by the [`VisibilityRules`] (e.g. vanished players).
+///
+/// Valence lists entries again for everyone when their [`Listed`] component changes,
+/// so these entries are hidden again afterwards.
+fn sync_hidden_entries(
+    rules: Option<Res<VisibilityRules>>,
+    mut hidden: ResMut<HiddenTabListEntries>,
+    entries: Query<(Entity, &UniqueId, Ref<Listed>)>,
+    mut clients: Query<(Entity, &mut Client)>,
+    mut removed: RemovedComponents<Client>,
+) {
+    for entity in removed.read() {
+        hidden.0.remove(&entity);
+    }
+
+    let Some(rules) = rules else {
+        return;
+    };
+
+    let entries_changed = entries.iter().any(|(_, _, listed)| listed.is_changed());
+
+    for (viewer, mut client) in clients.iter_mut() {
+        if !rules.is_changed() && !entries_changed && !client.is_added() {
+            continue;
+        }
+
+        let hidden_entries = hidden.0.entry(viewer).or_default();
+        let mut updates = vec![];
+
+        for (target, uuid, listed) in entries.iter() {
+            // New clients and changed entries were just listed by valence.
+            let was_hidden =
+                hidden_entries.contains(&target) && !listed.is_changed() && !client.is_added();
+            let hide = listed.0 && rules.is_hidden_in_tab_list(viewer, target);
+
+            if hide {
+                hidden_entries.insert(target);
+            } else {
+                hidden_entries.remove(&target);
+            }
+
+            if hide != was_hidden && (hide || listed.0) {
+                updates.push(PlayerListEntry {
+                    player_uuid: uuid.0,
+                    listed: !hide,
+                    ..Default::default()
+                });
+            }
+        }
+
+        hidden_entries.retain(|entity| entries.contains(*entity));
+
+        if !updates.is_empty() {
+            client.write_packet(&PlayerListS2c {
+                actions: PlayerListActions::new().with_update_listed(true),
+                entries: Cow::Owned(updates),
+            });
+        }
+    }
+}
@@ -0,0 +1,187 @@
+//! Floating (multi-line) text at a location, e.g. for leaderboards, shop labels and damage indicators.
+
+use std::time::{Duration, Instant};
+
+use valence::{
+    ecs::entity::EntityHashSet,
+    entity::{
+        display::Billboard,
+        text_display::{self, TextDisplayEntityBundle},
+    },
+    prelude::*,
+};
+
+/// A floating text, the text is rendered by a text display entity.
+#[derive(Component, Clone, Debug, Default)]
+pub struct Hologram {
+    lines: Vec<Text>,
+    /// The hologram will be despawned after this time.
+    despawn_at: Option<Instant>,
+}
+
+impl Hologram {
+    pub fn new(lines: impl IntoIterator<Item = Text>) -> Self {
+        Self {
+            lines: lines.into_iter().collect(),
+            despawn_at: None,
+        }
+    }
+
+    /// Despawn the hologram after the given time (e.g. for damage indicators).
+    pub fn with_lifetime(mut self, lifetime: Duration) -> Self {
+        self.despawn_at = Some(Instant::now() + lifetime);
+        self
+    }
+
+    pub fn lines(&self) -> &[Text] {
+        &self.lines
+    }
+
+    /// Set a line, missing lines above it will be filled with empty lines.
+    pub fn set_line(&mut self, index: usize, line: Text) {
+        if self.lines.len() <= index {
+            self.lines.resize(index + 1, Text::default());
+        }
+
+        self.lines[index] = line;
+    }
+
+    pub fn set_lines(&mut self, lines: impl IntoIterator<Item = Text>) {
+        self.lines = lines.into_iter().collect();
+    }
+
+    fn text(&self) -> Text {
+        let mut text = Text::default();
+
+        for (idx, line) in self.lines.iter().enumerate() {
+            if idx > 0 {
+                text = text + "\n";
+            }
+
+            text = text + line.clone();
+        }
+
+        text
+    }
+}
+
+/// Only the clients in this set can see the hologram.
+///
+/// Holograms with this component are moved to their own entity layer,
+/// which is added to the [`VisibleEntityLayers`] of the viewers.
+#[derive(Component, Clone, Debug, Default)]
+pub struct HologramViewers(pub EntityHashSet);
+
+/// The entity layer of a hologram with [`HologramViewers`].
+#[derive(Component)]
+struct HologramLayer(Entity);
+
+/// Marker for the entity layers that were created for holograms.
+#[derive(Component)]
+struct HologramLayerOf(Entity);
+
+/// Spawn a hologram, the lines can be changed later through the [`Hologram`] component.
+pub fn spawn_hologram(
+    commands: &mut Commands,
+    layer: Entity,
+    position: DVec3,
+    hologram: Hologram,
+) -> Entity {
+    commands
+        .spawn((
+            TextDisplayEntityBundle {
+                position: Position(position),
+                layer: EntityLayerId(layer),
+                text_display_text: text_display::Text(hologram.text()),
+                // Always face the player.
+                display_billboard: Billboard(3),
+                ..Default::default()
+            },
+            hologram,
+        ))
+        .id()
+}
+
+pub(crate) fn update_holograms(
+    mut commands: Commands,
+    mut query: Query<(Entity, Ref<Hologram>, &mut text_display::Text)>,
+) {
+    for (entity, hologram, mut text) in query.iter_mut() {
+        if hologram
+            .despawn_at
+            .is_some_and(|despawn_at| despawn_at <= Instant::now())
+        {
+            commands.entity(entity).insert(Despawned);
+            continue;
+        }
+
+        if hologram.is_changed() && !hologram.is_added() {
+            text.0 = hologram.text();
+        }
+    }
+}
+
+/// Move holograms with [`HologramViewers`] to their own layer.
+pub(crate) fn create_hologram_layers(
+    mut commands: Commands,
+    server: Res<Server>,
+    mut holograms: Query<
+        (Entity, &mut EntityLayerId),
+        (
+            With<Hologram>,
+            Added<HologramViewers>,
+            Without<HologramLayer>,
+        ),
+    >,
+) {
+    for (entity, mut layer) in holograms.iter_mut() {
+        let hologram_layer = commands
+            .spawn((EntityLayer::new(&server), HologramLayerOf(entity)))
+            .id();
+
+        layer.0 = hologram_layer;
+        commands
+            .entity(entity)
+            .insert(HologramLayer(hologram_layer));
+    }
+}
+
+#[allow(clippy::type_complexity)]
+pub(crate) fn update_hologram_viewers(
+    holograms: Query<
+        (&HologramViewers, &HologramLayer),
+        Or<(Changed<HologramViewers>, Added<HologramLayer>)>,
+    >,
+    all_holograms: Query<(&HologramViewers, &HologramLayer)>,
+    mut clients: Query<(Entity, Ref<Client>, &mut VisibleEntityLayers)>,
+) {
+    for (client, client_ref, mut visible_layers) in clients.iter_mut() {
+        // New clients need to be checked against all holograms.
+        let holograms = if client_ref.is_added() {
+            all_holograms.iter().collect::<Vec<_>>()
+        } else {
+            holograms.iter().collect::<Vec<_>>()
+        };
+
+        for (viewers, layer) in holograms {
+            if viewers.0.contains(&client) {
+                visible_layers.0.insert(layer.0);
+            } else {
+                visible_layers.0.remove(&layer.0);
+            }
+        }
+    }
+}
+
+/// Despawn the layers of holograms that were despawned.
+pub(crate) fn despawn_hologram_layers(
+    mut commands: Commands,
+    layers: Query<(Entity, &HologramLayerOf)>,
+    holograms: Query<(), With<Hologram>>,
+) {
+    for (layer, hologram) in layers.iter() {
+        if holograms.get(hologram.0).is_err() {
+            commands.entity(layer).insert(Despawned);
+        }
+    }
+}
@@ -0,0 +1,25 @@
+pub mod holograms;
+pub mod messages;
+
+use valence::prelude::*;
+
+pub struct UiPlugin;
+
+impl Plugin for UiPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                messages::add_message_queues,
+                messages::show_messages,
+                holograms::update_holograms,
+                (
+                    holograms::create_hologram_layers,
+                    holograms::update_hologram_viewers,
+                    holograms::despawn_hologram_layers,
+                )
+                    .chain(),
+            ),
+        );
+    }
+}
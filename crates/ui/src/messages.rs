@@ -0,0 +1,215 @@
+//! Timed action bar messages and titles.
+//!
+//! Messages with a lower priority will not replace the message that is currently shown,
+//! instead they are shown once all messages with a higher priority are finished.
+
+use std::time::{Duration, Instant};
+
+use valence::{prelude::*, text::IntoText, title::SetTitle};
+
+/// The client hides the action bar after a few seconds, so it has to be resent.
+const ACTION_BAR_RESEND: Duration = Duration::from_secs(1);
+
+/// A message in the action bar (above the hotbar).
+#[derive(Clone, Debug)]
+pub struct ActionBarMessage {
+    pub text: Text,
+    pub duration: Duration,
+    pub priority: i32,
+}
+
+impl ActionBarMessage {
+    pub fn new(text: impl IntoText<'static>, duration: Duration) -> Self {
+        Self {
+            text: text.into_cow_text().into_owned(),
+            duration,
+            priority: 0,
+        }
+    }
+
+    pub fn with_priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+}
+
+/// A title (and subtitle) in the middle of the screen.
+#[derive(Clone, Debug)]
+pub struct TitleMessage {
+    pub title: Text,
+    pub subtitle: Option<Text>,
+    pub fade_in: Duration,
+    pub stay: Duration,
+    pub fade_out: Duration,
+    pub priority: i32,
+}
+
+impl TitleMessage {
+    pub fn new(title: impl IntoText<'static>) -> Self {
+        Self {
+            title: title.into_cow_text().into_owned(),
+            subtitle: None,
+            // The vanilla default times.
+            fade_in: Duration::from_millis(500),
+            stay: Duration::from_millis(3500),
+            fade_out: Duration::from_secs(1),
+            priority: 0,
+        }
+    }
+
+    pub fn with_subtitle(mut self, subtitle: impl IntoText<'static>) -> Self {
+        self.subtitle = Some(subtitle.into_cow_text().into_owned());
+        self
+    }
+
+    pub fn with_times(mut self, fade_in: Duration, stay: Duration, fade_out: Duration) -> Self {
+        self.fade_in = fade_in;
+        self.stay = stay;
+        self.fade_out = fade_out;
+        self
+    }
+
+    pub fn with_priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    fn duration(&self) -> Duration {
+        self.fade_in + self.stay + self.fade_out
+    }
+}
+
+/// The queued action bar messages and titles of a player.
+///
+/// This is added to all clients by the [`UiPlugin`](crate::UiPlugin).
+#[derive(Component, Default)]
+pub struct MessageQueue {
+    action_bars: Vec<ActionBarMessage>,
+    titles: Vec<TitleMessage>,
+    current_action_bar: Option<Shown<ActionBarMessage>>,
+    current_title: Option<Shown<TitleMessage>>,
+}
+
+struct Shown<T> {
+    message: T,
+    started: Instant,
+    last_sent: Option<Instant>,
+}
+
+impl<T> Shown<T> {
+    fn new(message: T) -> Self {
+        Self {
+            message,
+            started: Instant::now(),
+            last_sent: None,
+        }
+    }
+}
+
+/// Insert the message after all messages with the same or a higher priority.
+fn enqueue<T>(queue: &mut Vec<T>, message: T, priority: impl Fn(&T) -> i32) {
+    let idx = queue.partition_point(|queued| priority(queued) >= priority(&message));
+    queue.insert(idx, message);
+}
+
+impl MessageQueue {
+    /// Show an action bar message, if a message with a higher priority is shown, the message will be queued.
+    pub fn show_action_bar(&mut self, message: ActionBarMessage) {
+        match &self.current_action_bar {
+            Some(current) if current.message.priority > message.priority => {
+                enqueue(&mut self.action_bars, message, |message| message.priority);
+            }
+            _ => self.current_action_bar = Some(Shown::new(message)),
+        }
+    }
+
+    /// Show a title, if a title with a higher priority is shown, the title will be queued.
+    pub fn show_title(&mut self, message: TitleMessage) {
+        match &self.current_title {
+            Some(current) if current.message.priority > message.priority => {
+                enqueue(&mut self.titles, message, |message| message.priority);
+            }
+            _ => self.current_title = Some(Shown::new(message)),
+        }
+    }
+
+    /// Remove all queued messages (the current messages will still be shown until they are finished).
+    pub fn clear(&mut self) {
+        self.action_bars.clear();
+        self.titles.clear();
+    }
+}
+
+pub(crate) fn add_message_queues(
+    mut commands: Commands,
+    clients: Query<Entity, (Added<Client>, Without<MessageQueue>)>,
+) {
+    for entity in clients.iter() {
+        commands.entity(entity).insert(MessageQueue::default());
+    }
+}
+
+fn ticks(duration: Duration) -> i32 {
+    // TODO: set based on tick rate
+    (duration.as_secs_f32() * 20.0).round() as i32
+}
+
+pub(crate) fn show_messages(mut query: Query<(&mut Client, &mut MessageQueue)>) {
+    for (mut client, mut queue) in query.iter_mut() {
+        let queue = &mut *queue;
+
+        if queue
+            .current_action_bar
+            .as_ref()
+            .is_some_and(|current| current.started.elapsed() >= current.message.duration)
+        {
+            queue.current_action_bar = None;
+        }
+
+        if queue.current_action_bar.is_none() && !queue.action_bars.is_empty() {
+            queue.current_action_bar = Some(Shown::new(queue.action_bars.remove(0)));
+        }
+
+        if let Some(current) = &mut queue.current_action_bar {
+            let resend = match current.last_sent {
+                Some(last_sent) => last_sent.elapsed() >= ACTION_BAR_RESEND,
+                None => true,
+            };
+
+            if resend {
+                client.set_action_bar(current.message.text.clone());
+                current.last_sent = Some(Instant::now());
+            }
+        }
+
+        if queue
+            .current_title
+            .as_ref()
+            .is_some_and(|current| current.started.elapsed() >= current.message.duration())
+        {
+            queue.current_title = None;
+        }
+
+        if queue.current_title.is_none() && !queue.titles.is_empty() {
+            queue.current_title = Some(Shown::new(queue.titles.remove(0)));
+        }
+
+        if let Some(current) = &mut queue.current_title {
+            if current.last_sent.is_none() {
+                let message = &current.message;
+
+                client.set_title_times(
+                    ticks(message.fade_in),
+                    ticks(message.stay),
+                    ticks(message.fade_out),
+                );
+
+                // The subtitle of the previous title would still be shown.
+                client.set_subtitle(message.subtitle.clone().unwrap_or_default());
+
+                client.set_title(message.title.clone());
+                current.last_sent = Some(Instant::now());
+            }
+        }
+    }
+}
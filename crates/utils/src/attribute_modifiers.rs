@@ -0,0 +1,150 @@
+//! The `AttributeModifiers` NBT tag of items.
+//!
+//! https://minecraft.wiki/w/Attribute#Modifiers
+
+use valence::{
+    nbt::{value::ValueRef, Value},
+    ItemStack,
+};
+
+pub const ATTACK_DAMAGE: &str = "generic.attack_damage";
+pub const ATTACK_SPEED: &str = "generic.attack_speed";
+pub const ARMOR: &str = "generic.armor";
+pub const ARMOR_TOUGHNESS: &str = "generic.armor_toughness";
+pub const KNOCKBACK_RESISTANCE: &str = "generic.knockback_resistance";
+
+/// The base attack damage of a player.
+pub const PLAYER_ATTACK_DAMAGE: f64 = 1.0;
+/// The base attack speed of a player.
+pub const PLAYER_ATTACK_SPEED: f64 = 4.0;
+
+/// The slots an item can be in when its modifiers are applied.
+const ARMOR_SLOTS: [&str; 4] = ["head", "chest", "legs", "feet"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttributeOperation {
+    /// The amount is added to the value.
+    Add,
+    /// The amount is multiplied with the base value and added to the value.
+    MultiplyBase,
+    /// The value is multiplied with `1 + amount`.
+    Multiply,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AttributeModifier {
+    /// The name of the attribute without the namespace (e.g. `"generic.attack_damage"`).
+    pub attribute: String,
+    pub amount: f64,
+    pub operation: AttributeOperation,
+    /// The slot the item has to be in for the modifier to apply, `None` is every slot.
+    pub slot: Option<String>,
+}
+
+impl AttributeModifier {
+    /// The modifier applies to items in the main hand.
+    pub fn applies_to_main_hand(&self) -> bool {
+        match self.slot.as_deref() {
+            Some(slot) => slot == "mainhand",
+            None => true,
+        }
+    }
+
+    /// The modifier applies to items in an armor slot.
+    pub fn applies_to_armor(&self) -> bool {
+        match self.slot.as_deref() {
+            Some(slot) => ARMOR_SLOTS.contains(&slot),
+            None => true,
+        }
+    }
+}
+
+pub trait ItemStackAttributeModifiersExt {
+    /// The modifiers in the `AttributeModifiers` NBT tag.
+    ///
+    /// This is `None` if the item does not have the tag, in that case the default values of the item kind are used.
+    fn attribute_modifiers(&self) -> Option<Vec<AttributeModifier>>;
+}
+
+impl ItemStackAttributeModifiersExt for ItemStack {
+    fn attribute_modifiers(&self) -> Option<Vec<AttributeModifier>> {
+        let Some(Value::List(modifiers)) = self.nbt.as_ref()?.get("AttributeModifiers") else {
+            return None;
+        };
+
+        let mut result = vec![];
+
+        for modifier in modifiers {
+            let ValueRef::Compound(modifier) = modifier else {
+                continue;
+            };
+
+            let Some(Value::String(attribute)) = modifier.get("AttributeName") else {
+                continue;
+            };
+
+            let amount = match modifier.get("Amount") {
+                Some(Value::Double(amount)) => *amount,
+                Some(Value::Float(amount)) => *amount as f64,
+                Some(Value::Int(amount)) => *amount as f64,
+                _ => continue,
+            };
+
+            let operation = match modifier.get("Operation") {
+                Some(Value::Int(0)) | None => AttributeOperation::Add,
+                Some(Value::Int(1)) => AttributeOperation::MultiplyBase,
+                Some(Value::Int(2)) => AttributeOperation::Multiply,
+                _ => continue,
+            };
+
+            let slot = match modifier.get("Slot") {
+                Some(Value::String(slot)) => Some(slot.clone()),
+                _ => None,
+            };
+
+            result.push(AttributeModifier {
+                attribute: attribute
+                    .strip_prefix("minecraft:")
+                    .unwrap_or(attribute)
+                    .to_string(),
+                amount,
+                operation,
+                slot,
+            });
+        }
+
+        Some(result)
+    }
+}
+
+/// Apply the modifiers of an attribute to a base value (in the same order as vanilla).
+pub fn apply_modifiers<'a>(
+    base: f64,
+    attribute: &str,
+    modifiers: impl IntoIterator<Item = &'a AttributeModifier>,
+) -> f64 {
+    let modifiers = modifiers
+        .into_iter()
+        .filter(|modifier| modifier.attribute == attribute)
+        .collect::<Vec<_>>();
+
+    let operation = |operation| {
+        modifiers
+            .iter()
+            .filter(move |modifier| modifier.operation == operation)
+            .map(|modifier| modifier.amount)
+    };
+
+    let base = base + operation(AttributeOperation::Add).sum::<f64>();
+    let mut value = base;
+
+    for amount in operation(AttributeOperation::MultiplyBase) {
+        value += base * amount;
+    }
+
+    for amount in operation(AttributeOperation::Multiply) {
+        value *= 1.0 + amount;
+    }
+
+    value
+}
@@ -0,0 +1,150 @@
+//! Cooldowns for items and abilities, item cooldowns are shown on the client (the grey overlay on the item).
+
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use valence::{
+    prelude::*,
+    protocol::{packets::play::CooldownUpdateS2c, VarInt, WritePacket},
+};
+
+/// What a cooldown is for.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum CooldownKey {
+    /// The cooldown of an item kind, this is shown on the client.
+    Item(ItemKind),
+    /// Any other cooldown (e.g. an ability).
+    Custom(String),
+}
+
+impl From<ItemKind> for CooldownKey {
+    fn from(item: ItemKind) -> Self {
+        Self::Item(item)
+    }
+}
+
+impl From<&str> for CooldownKey {
+    fn from(key: &str) -> Self {
+        Self::Custom(key.to_string())
+    }
+}
+
+impl From<String> for CooldownKey {
+    fn from(key: String) -> Self {
+        Self::Custom(key)
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Cooldown {
+    started: Instant,
+    duration: Duration,
+}
+
+/// The cooldowns of an entity.
+///
+/// ```ignore
+/// if cooldowns.is_ready(ItemKind::EnderPearl) {
+///     cooldowns.start(ItemKind::EnderPearl, Duration::from_secs(1));
+/// }
+/// ```
+#[derive(Component, Clone, Debug, Default)]
+pub struct Cooldowns {
+    cooldowns: HashMap<CooldownKey, Cooldown>,
+    /// The item cooldowns that have to be sent to the client.
+    pending: Vec<(ItemKind, Duration)>,
+}
+
+impl Cooldowns {
+    /// Start (or restart) a cooldown.
+    pub fn start(&mut self, key: impl Into<CooldownKey>, duration: Duration) {
+        let key = key.into();
+
+        if let CooldownKey::Item(item) = key {
+            self.pending.push((item, duration));
+        }
+
+        self.cooldowns.insert(
+            key,
+            Cooldown {
+                started: Instant::now(),
+                duration,
+            },
+        );
+    }
+
+    /// Start a cooldown that is shown on an item (e.g. an ability that is used with an item).
+    pub fn start_shown_on(
+        &mut self,
+        key: impl Into<CooldownKey>,
+        duration: Duration,
+        item: ItemKind,
+    ) {
+        let key = key.into();
+
+        // Item cooldowns are already sent by `start`.
+        if key != CooldownKey::Item(item) {
+            self.pending.push((item, duration));
+        }
+
+        self.start(key, duration);
+    }
+
+    /// The time until the cooldown is over.
+    pub fn remaining(&self, key: impl Into<CooldownKey>) -> Duration {
+        self.cooldowns
+            .get(&key.into())
+            .map(|cooldown| cooldown.duration.saturating_sub(cooldown.started.elapsed()))
+            .unwrap_or_default()
+    }
+
+    pub fn is_ready(&self, key: impl Into<CooldownKey>) -> bool {
+        self.remaining(key).is_zero()
+    }
+
+    /// Stop a cooldown.
+    pub fn reset(&mut self, key: impl Into<CooldownKey>) {
+        let key = key.into();
+
+        if let CooldownKey::Item(item) = key {
+            self.pending.push((item, Duration::ZERO));
+        }
+
+        self.cooldowns.remove(&key);
+    }
+}
+
+pub struct CooldownPlugin;
+
+impl Plugin for CooldownPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(PostUpdate, sync_cooldowns);
+    }
+}
+
+/// Send new item cooldowns to the clients and remove cooldowns that are over.
+fn sync_cooldowns(mut query: Query<(&mut Cooldowns, Option<&mut Client>), Changed<Cooldowns>>) {
+    for (mut cooldowns, client) in query.iter_mut() {
+        // Bypass the change detection, otherwise every entity would be changed again in the next tick.
+        let cooldowns = cooldowns.bypass_change_detection();
+        let pending = std::mem::take(&mut cooldowns.pending);
+
+        if let Some(mut client) = client {
+            for (item, duration) in pending {
+                // TODO: set based on tick rate
+                let ticks = (duration.as_secs_f32() * 20.0).round() as i32;
+
+                client.write_packet(&CooldownUpdateS2c {
+                    item_id: VarInt(item.to_raw() as i32),
+                    cooldown_ticks: VarInt(ticks),
+                });
+            }
+        }
+
+        cooldowns
+            .cooldowns
+            .retain(|_, cooldown| cooldown.started.elapsed() < cooldown.duration);
+    }
+}
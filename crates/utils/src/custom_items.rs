@@ -0,0 +1,95 @@
+//! Custom items that override the values of their item kind (e.g. a stick that deals 12 damage).
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use valence::{nbt::Value, prelude::*};
+
+use crate::enchantments::Enchantment;
+
+/// The NBT tag that contains the id of a custom item.
+pub const CUSTOM_ITEM_TAG: &str = "custom_item";
+
+/// The values of a custom item, values that are `None` are taken from the item kind.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CustomItem {
+    pub attack_damage: Option<f32>,
+    pub attack_speed: Option<f32>,
+    pub armor_points: Option<f32>,
+    pub armor_toughness: Option<f32>,
+    pub knockback_resistance: Option<f32>,
+    /// The distance (in blocks) players can attack entities with this item (e.g. longer for spears).
+    pub reach: Option<f32>,
+    /// Overrides the formulas that are used for the enchantments of this item.
+    ///
+    /// The names reference formulas of the crate that uses the enchantment (e.g. the combat formulas).
+    pub enchantment_formulas: HashMap<Enchantment, String>,
+}
+
+/// All custom items.
+///
+/// An item stack is a custom item if it has the id of the item in the [`CUSTOM_ITEM_TAG`] NBT tag,
+/// or if its display name is in [`CustomItems::names`].
+#[derive(Resource, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CustomItems {
+    /// The custom items by their id.
+    pub items: HashMap<String, CustomItem>,
+    /// The ids of custom items by the display name of the item stack.
+    ///
+    /// The display name is the raw `display.Name` NBT tag (a JSON text component, e.g. `{"text":"Legendary Blade"}`).
+    pub names: HashMap<String, String>,
+}
+
+impl CustomItems {
+    pub fn with(mut self, id: impl Into<String>, item: CustomItem) -> Self {
+        self.items.insert(id.into(), item);
+        self
+    }
+
+    /// The custom item of an item stack.
+    pub fn get(&self, stack: &ItemStack) -> Option<&CustomItem> {
+        if let Some(id) = stack.custom_item_id() {
+            return self.items.get(id);
+        }
+
+        let name = stack.display_name()?;
+        self.items.get(self.names.get(name)?)
+    }
+}
+
+pub trait ItemStackCustomItemExt {
+    /// The id in the [`CUSTOM_ITEM_TAG`] NBT tag.
+    fn custom_item_id(&self) -> Option<&str>;
+    /// Set the [`CUSTOM_ITEM_TAG`] NBT tag.
+    fn set_custom_item_id(&mut self, id: impl Into<String>);
+    /// The raw `display.Name` NBT tag.
+    fn display_name(&self) -> Option<&str>;
+}
+
+impl ItemStackCustomItemExt for ItemStack {
+    fn custom_item_id(&self) -> Option<&str> {
+        match self.nbt.as_ref()?.get(CUSTOM_ITEM_TAG)? {
+            Value::String(id) => Some(id),
+            _ => None,
+        }
+    }
+
+    fn set_custom_item_id(&mut self, id: impl Into<String>) {
+        self.nbt
+            .get_or_insert_with(Default::default)
+            .insert(CUSTOM_ITEM_TAG, id.into());
+    }
+
+    fn display_name(&self) -> Option<&str> {
+        let Value::Compound(display) = self.nbt.as_ref()?.get("display")? else {
+            return None;
+        };
+
+        match display.get("Name")? {
+            Value::String(name) => Some(name),
+            _ => None,
+        }
+    }
+}
@@ -1,19 +1,169 @@
 use std::time::Duration;
 
 use bevy_time::{Time, Timer, TimerMode};
+#[cfg(feature = "regions")]
+use regions::RegionFlag;
+use serde::{Deserialize, Serialize};
 use valence::{
-    entity::{entity::Flags, living::Health, EntityId},
+    block::{PropName, PropValue},
+    ecs::entity::EntityHashMap,
+    entity::{entity::Flags, living::Health, EntityId, EntityKind},
     prelude::*,
-    protocol::{packets::play::EntityDamageS2c, sound::SoundCategory, Sound, VarInt, WritePacket},
-    Layer,
+    protocol::{sound::SoundCategory, Sound},
 };
 
+use crate::{
+    effects::HurtSource,
+    knockback::KnockbackPlugin,
+    pvp::{remove_pvp_rules, PvpCheck, PvpDeniedEvent, PvpRules},
+    sound::{SoundBuilder, Sounds},
+    spawn::SpawnProtection,
+    spectator::Spectating,
+    weather::{is_exposed_to_sky, Weather},
+};
+
+/// The cause of a [`DamageEvent`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DamageType {
+    #[default]
+    Generic,
+    Melee,
+    Projectile,
+    Fall,
+    Fire,
+    /// Poison can not kill, the entity is left with at least 1 health.
+    Poison,
+    Wither,
+    Explosion,
+    Lightning,
+    WorldBorder,
+    /// Not sent by any system in this crate, servers with an air supply can use it.
+    Drowning,
+}
+
+impl DamageType {
+    /// If damage of this type can kill an entity.
+    pub fn is_lethal(self) -> bool {
+        self != Self::Poison
+    }
+
+    fn bit(self) -> u32 {
+        1 << self as u32
+    }
+}
+
+/// The damage types an entity is immune to (e.g. creative players, staff or bosses).
+///
+/// An entity that is immune to [`DamageType::Fire`] can still be set on fire (like with fire resistance),
+/// use [`FireImmune`] to prevent that.
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Immunities(u32);
+
+impl Immunities {
+    /// Immune to no damage type.
+    pub fn none() -> Self {
+        Self(0)
+    }
+
+    /// Immune to every damage type.
+    pub fn all() -> Self {
+        Self(u32::MAX)
+    }
+
+    pub fn with(mut self, damage_type: DamageType) -> Self {
+        self.insert(damage_type);
+        self
+    }
+
+    pub fn insert(&mut self, damage_type: DamageType) {
+        self.0 |= damage_type.bit();
+    }
+
+    pub fn remove(&mut self, damage_type: DamageType) {
+        self.0 &= !damage_type.bit();
+    }
+
+    pub fn contains(&self, damage_type: DamageType) -> bool {
+        self.0 & damage_type.bit() != 0
+    }
+}
+
+impl FromIterator<DamageType> for Immunities {
+    fn from_iter<T: IntoIterator<Item = DamageType>>(iter: T) -> Self {
+        iter.into_iter().fold(Self::none(), Self::with)
+    }
+}
+
+/// Scales the damage an entity takes from the environment and from NPCs,
+/// e.g. for difficulty levels or handicaps. Damage dealt by players is not scaled.
+///
+/// Entities without this component use the [`DefaultDifficultyProfile`] (if the resource exists).
+#[derive(Component, Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DifficultyProfile {
+    /// The multiplier for damage without an attacker (fall, fire, drowning, ...).
+    pub environment_damage_multiplier: f32,
+    /// The multiplier for damage dealt by entities that are not players.
+    pub npc_damage_multiplier: f32,
+}
+
+impl DifficultyProfile {
+    pub const EASY: Self = Self {
+        environment_damage_multiplier: 0.5,
+        npc_damage_multiplier: 0.5,
+    };
+    pub const NORMAL: Self = Self {
+        environment_damage_multiplier: 1.0,
+        npc_damage_multiplier: 1.0,
+    };
+    pub const HARD: Self = Self {
+        environment_damage_multiplier: 1.0,
+        npc_damage_multiplier: 1.5,
+    };
+
+    /// The multiplier for damage of an attacker, `None` is damage from the environment.
+    pub fn multiplier(&self, attacker: Option<DamageSource>) -> f32 {
+        match attacker {
+            None => self.environment_damage_multiplier,
+            Some(DamageSource::Npc) => self.npc_damage_multiplier,
+            Some(DamageSource::Player) => 1.0,
+        }
+    }
+}
+
+impl Default for DifficultyProfile {
+    fn default() -> Self {
+        Self::NORMAL
+    }
+}
+
+/// The kind of attacker, used by the [`DifficultyProfile`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DamageSource {
+    Player,
+    Npc,
+}
+
+/// The [`DifficultyProfile`] of entities without their own profile.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct DefaultDifficultyProfile(pub DifficultyProfile);
+
 /// An event that will be fired if an entity takes damage.
-#[derive(Event)]
+#[derive(Event, Clone)]
 pub struct DamageEvent {
     pub victim: Entity,
     pub attacker: Option<Entity>,
     pub damage: f32,
+    pub damage_type: DamageType,
+    /// The item the attacker used (e.g. the sword or the bow).
+    pub weapon: Option<ItemStack>,
+    /// The kind of the projectile that hit the victim.
+    pub projectile: Option<EntityKind>,
+    /// The entity that directly dealt the damage if it is not the attacker (e.g. the thrown trident).
+    pub direct_source: Option<Entity>,
+    /// The damage was dealt to all entities in an area (e.g. with the `AreaDamage` of the physics crate).
+    pub area_of_effect: bool,
 }
 
 #[derive(Event)]
@@ -24,19 +174,70 @@ pub struct StartBurningEvent {
     pub damage_per_second: f32,
 }
 
-/// Marker component for entities that are on fire.
-#[derive(Component)]
-struct OnFire;
+/// An event that will be fired if an entity stops burning.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct BurningStoppedEvent {
+    pub entity: Entity,
+    pub reason: BurnStopReason,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BurnStopReason {
+    /// The burn duration is over.
+    Expired,
+    /// The fire was put out with the [`ExtinguishEvent`].
+    Extinguished,
+    /// The entity entered water.
+    Water,
+    /// The entity entered powder snow.
+    PowderSnow,
+    /// The entity is standing in the rain (see [`Weather`]).
+    Rain,
+}
+
+/// An event that will be fired if an entity that is immune to fire (see [`FireImmune`]) was set on fire.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct BurnPreventedEvent {
+    pub victim: Entity,
+    pub attacker: Option<Entity>,
+}
+
+/// Entities with this component can not be set on fire and do not take burn damage.
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct FireImmune;
+
+/// Send this event to put out the fire of an entity.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct ExtinguishEvent {
+    pub entity: Entity,
+}
+
+/// What happens if an entity that is already burning is set on fire again.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BurnStacking {
+    /// The new duration is added to the remaining duration.
+    ExtendDuration,
+    /// The longer duration and the higher damage are used (like vanilla).
+    #[default]
+    Max,
+    /// The new fire replaces the old one.
+    Refresh,
+    /// The damage of both fires is added, the longer duration is used.
+    StackDamage,
+}
 
 /// An event that will be fired if an entity dies.
-#[derive(Event)]
+#[derive(Event, Clone)]
 pub struct DeathEvent {
     pub victim: Entity,
     pub attacker: Option<Entity>,
+    /// The damage that killed the victim.
+    pub killing_blow: DamageEvent,
 }
 
 /// This component will be added to entities that register damage with the [`DamageEvent`]
-#[derive(Component)]
+#[derive(Component, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct TakesDamage {
     /// If the hurt animation should be shown when the player is hit (the player will turn red for a others).
     pub show_hurt: bool,
@@ -57,29 +258,158 @@ pub struct TakesDamage {
     pub burn_duration_multiplier: f32,
     /// Burn damage multiplier.
     pub burn_damage_multiplier: f32,
+    /// What happens if the entity is set on fire while it is burning.
+    pub burn_stacking: BurnStacking,
 }
 
-#[derive(Component)]
-struct BurnTimer {
-    pub second_timer: Timer,
-    pub full_timer: Timer,
-    seconds_left: u32,
-    attacker: Option<Entity>,
-    damage_per_second: f32,
+/// A source of damage over time (e.g. fire, poison or wither), see [`PeriodicDamage`].
+#[derive(Clone, Debug)]
+pub struct DamageOverTime {
+    pub damage_type: DamageType,
+    /// The damage that is dealt every interval.
+    pub damage: f32,
+    /// The entity that caused the damage.
+    pub source: Option<Entity>,
+    remaining: Duration,
+    timer: Timer,
 }
 
-impl BurnTimer {
-    pub fn new(duration: Duration, attacker: Option<Entity>, damage_per_second: f32) -> Self {
+impl DamageOverTime {
+    pub fn new(
+        damage_type: DamageType,
+        damage: f32,
+        interval: Duration,
+        duration: Duration,
+    ) -> Self {
         Self {
-            second_timer: Timer::new(Duration::from_secs(1), TimerMode::Repeating),
-            full_timer: Timer::new(duration, TimerMode::Once),
-            seconds_left: duration.as_secs() as u32,
-            attacker,
+            damage_type,
+            damage,
+            source: None,
+            remaining: duration,
+            timer: Timer::new(interval, TimerMode::Repeating),
+        }
+    }
+
+    /// Fire damage that is dealt every second.
+    pub fn fire(damage_per_second: f32, duration: Duration) -> Self {
+        Self::new(
+            DamageType::Fire,
             damage_per_second,
+            Duration::from_secs(1),
+            duration,
+        )
+    }
+
+    /// Poison damage (1.25 seconds interval like vanilla poison I), this can not kill.
+    pub fn poison(damage: f32, duration: Duration) -> Self {
+        Self::new(
+            DamageType::Poison,
+            damage,
+            Duration::from_millis(1250),
+            duration,
+        )
+    }
+
+    /// Wither damage (2 seconds interval like vanilla wither I).
+    pub fn wither(damage: f32, duration: Duration) -> Self {
+        Self::new(DamageType::Wither, damage, Duration::from_secs(2), duration)
+    }
+
+    pub fn with_source(mut self, source: Entity) -> Self {
+        self.source = Some(source);
+        self
+    }
+
+    /// The time until the damage stops.
+    pub fn remaining(&self) -> Duration {
+        self.remaining
+    }
+
+    /// The time between two damage ticks.
+    pub fn interval(&self) -> Duration {
+        self.timer.duration()
+    }
+
+    /// Combine with another instance of the same type, the timer keeps running so the
+    /// entity can not be damaged faster.
+    fn stack(&mut self, other: DamageOverTime, stacking: BurnStacking) {
+        match stacking {
+            BurnStacking::ExtendDuration => {
+                self.remaining += other.remaining;
+                self.damage = self.damage.max(other.damage);
+            }
+            BurnStacking::Max => {
+                self.remaining = self.remaining.max(other.remaining);
+                self.damage = self.damage.max(other.damage);
+            }
+            BurnStacking::Refresh => {
+                self.remaining = other.remaining;
+                self.damage = other.damage;
+            }
+            BurnStacking::StackDamage => {
+                self.remaining = self.remaining.max(other.remaining);
+                self.damage += other.damage;
+            }
+        }
+
+        if other.source.is_some() {
+            self.source = other.source;
         }
     }
 }
 
+/// All damage over time an entity is currently taking, multiple instances (even of the same type) are
+/// ticked independently.
+///
+/// Fire is added with the [`StartBurningEvent`] and is always a single instance of [`DamageType::Fire`].
+#[derive(Component, Clone, Debug, Default)]
+pub struct PeriodicDamage {
+    instances: Vec<DamageOverTime>,
+}
+
+impl PeriodicDamage {
+    pub fn add(&mut self, instance: DamageOverTime) {
+        self.instances.push(instance);
+    }
+
+    /// The first instance of the damage type.
+    pub fn get(&self, damage_type: DamageType) -> Option<&DamageOverTime> {
+        self.instances
+            .iter()
+            .find(|instance| instance.damage_type == damage_type)
+    }
+
+    fn get_mut(&mut self, damage_type: DamageType) -> Option<&mut DamageOverTime> {
+        self.instances
+            .iter_mut()
+            .find(|instance| instance.damage_type == damage_type)
+    }
+
+    pub fn contains(&self, damage_type: DamageType) -> bool {
+        self.get(damage_type).is_some()
+    }
+
+    /// Remove all instances of the damage type, returns true if there was one.
+    pub fn remove(&mut self, damage_type: DamageType) -> bool {
+        let len = self.instances.len();
+        self.instances
+            .retain(|instance| instance.damage_type != damage_type);
+        self.instances.len() != len
+    }
+
+    pub fn clear(&mut self) {
+        self.instances.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.instances.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &DamageOverTime> {
+        self.instances.iter()
+    }
+}
+
 impl Default for TakesDamage {
     fn default() -> Self {
         Self {
@@ -91,6 +421,7 @@ impl Default for TakesDamage {
             show_burning: true,
             burn_duration_multiplier: 1.0,
             burn_damage_multiplier: 1.0,
+            burn_stacking: BurnStacking::default(),
         }
     }
 }
@@ -99,112 +430,459 @@ pub struct DamagePlugin;
 
 impl Plugin for DamagePlugin {
     fn build(&self, app: &mut App) {
+        // Used by the `AreaDamage` of the physics crate.
+        if !app.is_plugin_added::<KnockbackPlugin>() {
+            app.add_plugins(KnockbackPlugin);
+        }
+
         app.add_event::<DamageEvent>()
             .add_event::<DeathEvent>()
             .add_event::<StartBurningEvent>()
-            .add_systems(Update, (damage_system, burn_system));
+            .add_event::<BurningStoppedEvent>()
+            .add_event::<ExtinguishEvent>()
+            .add_event::<BurnPreventedEvent>()
+            .add_event::<PvpDeniedEvent>()
+            .init_resource::<PvpRules>()
+            .add_systems(
+                Update,
+                (
+                    damage_system,
+                    (burn_system, periodic_damage_system).chain(),
+                    remove_pvp_rules,
+                ),
+            );
     }
 }
 
+#[allow(clippy::type_complexity, clippy::too_many_arguments)]
 fn damage_system(
     mut events: EventReader<DamageEvent>,
     mut event_writer: EventWriter<DeathEvent>,
-    mut query: Query<(&mut Health, &TakesDamage, &Position, &EntityId)>,
-    mut layer: Query<&mut ChunkLayer>,
+    mut query: Query<(
+        &mut Health,
+        &TakesDamage,
+        &Position,
+        &EntityId,
+        &EntityLayerId,
+        Option<&SpawnProtection>,
+        Option<&Immunities>,
+        Has<Spectating>,
+        Option<&DifficultyProfile>,
+        Option<&Look>,
+    )>,
+    attackers: Query<(&EntityId, &Position)>,
+    players: Query<(), With<Client>>,
+    default_difficulty: Option<Res<DefaultDifficultyProfile>>,
+    mut layers: Query<&mut ChunkLayer>,
+    mut pvp_check: PvpCheck,
+    mut sounds: Sounds,
 ) {
     for events in events.read() {
-        for (mut health, takes_damage, position, entity_id) in query.iter_mut() {
-            if health.0 <= 0.0 {
+        if let Ok((
+            mut health,
+            takes_damage,
+            position,
+            entity_id,
+            layer_id,
+            spawn_protection,
+            immunities,
+            spectating,
+            difficulty,
+            look,
+        )) = query.get_mut(events.victim)
+        {
+            if health.0 <= 0.0 || spectating {
+                continue;
+            }
+
+            if immunities.is_some_and(|immunities| immunities.contains(events.damage_type)) {
                 continue;
             }
 
-            let entity_id: VarInt = entity_id.get().into();
+            if spawn_protection.is_some_and(|protection| protection.is_active()) {
+                continue;
+            }
 
-            let damage = events.damage * takes_damage.damage_multiplier;
-            health.0 -= damage;
+            // Only attackers can bypass the flag, damage without an attacker (e.g. burning or falling)
+            // is denied silently, so it does not fire a denied event every tick.
+            #[cfg(feature = "regions")]
+            {
+                let region_check = pvp_check.region_check();
+                let damage_allowed = match events.attacker {
+                    Some(attacker) => {
+                        region_check.check(attacker, layer_id.0, position.0, RegionFlag::Damage)
+                    }
+                    None => region_check.is_allowed_at(layer_id.0, position.0, RegionFlag::Damage),
+                };
+
+                if !damage_allowed {
+                    continue;
+                }
+            }
 
-            let mut layer = layer.single_mut();
+            if let Some(attacker) = events.attacker.filter(|attacker| {
+                *attacker != events.victim
+                    && players.contains(*attacker)
+                    && players.contains(events.victim)
+            }) {
+                if !pvp_check.check(attacker, events.victim) {
+                    continue;
+                }
+            }
+
+            let mut damage = events.damage * takes_damage.damage_multiplier;
+
+            if let Some(difficulty) = difficulty
+                .copied()
+                .or(default_difficulty.as_deref().map(|default| default.0))
+            {
+                let source = events.attacker.map(|attacker| {
+                    if players.contains(attacker) {
+                        DamageSource::Player
+                    } else {
+                        DamageSource::Npc
+                    }
+                });
+
+                damage *= difficulty.multiplier(source);
+            }
+
+            if !events.damage_type.is_lethal() {
+                damage = damage.min(health.0 - 1.0);
+
+                if damage <= 0.0 {
+                    continue;
+                }
+            }
+
+            let Ok(mut layer) = layers.get_mut(layer_id.0) else {
+                continue;
+            };
+
+            health.0 -= damage;
 
             if takes_damage.show_hurt {
-                layer
-                    .view_writer(position.0)
-                    .write_packet(&EntityDamageS2c {
+                let source = events
+                    .attacker
+                    .and_then(|attacker| attackers.get(attacker).ok())
+                    .map(|(entity_id, position)| HurtSource {
                         entity_id,
-                        source_type_id: 1.into(),
-                        source_cause_id: 0.into(),
-                        source_direct_id: 0.into(),
-                        source_pos: Some(position.0),
+                        position: position.0,
+                        direct_entity_id: events
+                            .direct_source
+                            .and_then(|direct| attackers.get(direct).ok())
+                            .map(|(direct_id, _)| direct_id),
                     });
+
+                crate::effects::play_hurt_animation(
+                    &mut layer,
+                    entity_id,
+                    position.0,
+                    look.map_or(0.0, |look| look.yaw),
+                    source,
+                );
             }
 
             if health.0 <= 0.0 {
                 if takes_damage.play_sound {
-                    layer.play_sound(
-                        Sound::EntityPlayerDeath,
-                        SoundCategory::Player,
-                        position.0,
-                        1.0,
-                        1.0,
-                    );
+                    SoundBuilder::new(Sound::EntityPlayerDeath, position.0)
+                        .category(SoundCategory::Player)
+                        .play(&mut sounds, layer_id.0);
                 }
 
                 if !takes_damage.suppress_death_event {
                     event_writer.send(DeathEvent {
                         victim: events.victim,
                         attacker: events.attacker,
+                        killing_blow: events.clone(),
                     });
                 }
 
                 health.0 = takes_damage.set_hp_after_death;
             } else if takes_damage.play_sound {
-                layer.play_sound(
-                    Sound::EntityPlayerHurt,
-                    SoundCategory::Player,
-                    position.0,
-                    1.0,
-                    1.0,
-                );
+                SoundBuilder::new(Sound::EntityPlayerHurt, position.0)
+                    .category(SoundCategory::Player)
+                    .play(&mut sounds, layer_id.0);
             }
         }
     }
 }
 
+#[allow(clippy::type_complexity)]
 fn burn_system(
     mut commands: Commands,
     mut events: EventReader<StartBurningEvent>,
-    mut query: Query<(Entity, &TakesDamage, Option<&mut BurnTimer>, &mut Flags)>,
-    mut damage_writer: EventWriter<DamageEvent>,
-    time: Res<Time>,
+    mut extinguish_events: EventReader<ExtinguishEvent>,
+    mut query: Query<(
+        Entity,
+        &TakesDamage,
+        Option<&mut PeriodicDamage>,
+        &mut Flags,
+        &Hitbox,
+        &EntityLayerId,
+        Has<FireImmune>,
+    )>,
+    layers: Query<&ChunkLayer>,
+    weather: Option<Res<Weather>>,
+    mut sounds: Sounds,
+    mut stopped_writer: EventWriter<BurningStoppedEvent>,
+    mut prevented_writer: EventWriter<BurnPreventedEvent>,
 ) {
-    for (victim, takes_damage, burn_timer, mut flags) in query.iter_mut() {
-        if let Some(mut burn_timer) = burn_timer {
-            if !burn_timer.full_timer.tick(time.delta()).finished() {
-                if burn_timer.second_timer.tick(time.delta()).finished() {
-                    burn_timer.seconds_left -= 1;
-                    damage_writer.send(DamageEvent {
-                        victim,
-                        attacker: burn_timer.attacker,
-                        damage: burn_timer.damage_per_second * takes_damage.burn_damage_multiplier,
-                    });
-                }
-            } else {
-                commands.entity(victim).remove::<OnFire>();
-                commands.entity(victim).remove::<BurnTimer>();
-                flags.set_on_fire(false);
-            }
+    let raining = weather.is_some_and(|weather| weather.raining);
+
+    let mut extinguished = extinguish_events
+        .read()
+        .map(|event| (event.entity, BurnStopReason::Extinguished))
+        .collect::<EntityHashMap<_>>();
+
+    for (entity, _, periodic_damage, _, hitbox, layer, _) in query.iter() {
+        let burning = periodic_damage.is_some_and(|damage| damage.contains(DamageType::Fire));
+
+        if !burning || extinguished.contains_key(&entity) {
+            continue;
+        }
+
+        let Ok(chunk_layer) = layers.get(layer.0) else {
+            continue;
+        };
+
+        let hitbox = hitbox.get();
+        let center = (hitbox.min() + hitbox.max()) / 2.0;
+        let head = BlockPos::new(
+            center.x.floor() as i32,
+            hitbox.max().y.floor() as i32,
+            center.z.floor() as i32,
+        );
+
+        let reason = if crate::intersects_block(&hitbox, chunk_layer, |state| {
+            state.to_kind() == BlockKind::Water
+                || state.get(PropName::Waterlogged) == Some(PropValue::True)
+        }) {
+            BurnStopReason::Water
+        } else if crate::intersects_block(&hitbox, chunk_layer, |state| {
+            state.to_kind() == BlockKind::PowderSnow
+        }) {
+            BurnStopReason::PowderSnow
+        } else if raining && is_exposed_to_sky(chunk_layer, head) {
+            BurnStopReason::Rain
+        } else {
+            continue;
+        };
+
+        extinguished.insert(entity, reason);
+
+        SoundBuilder::new(Sound::EntityGenericExtinguishFire, center)
+            .category(SoundCategory::Neutral)
+            .volume(0.7)
+            .play(&mut sounds, layer.0);
+    }
+
+    for (&entity, &reason) in &extinguished {
+        let Ok((_, _, Some(mut periodic_damage), mut flags, _, _, _)) = query.get_mut(entity)
+        else {
+            continue;
+        };
+
+        if periodic_damage.remove(DamageType::Fire) {
+            flags.set_on_fire(false);
+            stopped_writer.send(BurningStoppedEvent { entity, reason });
         }
     }
 
+    // Entities without the component that are set on fire multiple times in the same tick.
+    let mut new_fires = EntityHashMap::<DamageOverTime>::default();
+
     for event in events.read() {
-        for (victim, takes_damage, _, mut flags) in query.iter_mut() {
-            let duration = event
+        let Ok((victim, takes_damage, periodic_damage, mut flags, _, _, fire_immune)) =
+            query.get_mut(event.victim)
+        else {
+            continue;
+        };
+
+        if fire_immune {
+            prevented_writer.send(BurnPreventedEvent {
+                victim,
+                attacker: event.attacker,
+            });
+            continue;
+        }
+
+        let mut fire = DamageOverTime::fire(
+            event.damage_per_second,
+            event
                 .duration
-                .mul_f32(takes_damage.burn_duration_multiplier);
-            let burn_timer = BurnTimer::new(duration, event.attacker, event.damage_per_second);
-            commands.entity(victim).insert(burn_timer);
-            commands.entity(victim).insert(OnFire);
+                .mul_f32(takes_damage.burn_duration_multiplier),
+        );
+        fire.source = event.attacker;
+
+        match periodic_damage {
+            Some(mut periodic_damage) => match periodic_damage.get_mut(DamageType::Fire) {
+                Some(current) => current.stack(fire, takes_damage.burn_stacking),
+                None => periodic_damage.add(fire),
+            },
+            None => match new_fires.get_mut(&victim) {
+                Some(current) => current.stack(fire, takes_damage.burn_stacking),
+                None => {
+                    new_fires.insert(victim, fire);
+                }
+            },
+        }
 
+        if takes_damage.show_burning {
             flags.set_on_fire(true);
         }
     }
+
+    for (entity, fire) in new_fires {
+        let mut periodic_damage = PeriodicDamage::default();
+        periodic_damage.add(fire);
+
+        commands.entity(entity).insert(periodic_damage);
+    }
+}
+
+/// Tick all [`PeriodicDamage`] and deal the damage.
+fn periodic_damage_system(
+    mut query: Query<(
+        Entity,
+        &mut PeriodicDamage,
+        &TakesDamage,
+        &mut Flags,
+        Has<FireImmune>,
+    )>,
+    mut damage_writer: EventWriter<DamageEvent>,
+    mut stopped_writer: EventWriter<BurningStoppedEvent>,
+    time: Res<Time>,
+) {
+    for (victim, mut periodic_damage, takes_damage, mut flags, fire_immune) in query.iter_mut() {
+        if periodic_damage.is_empty() {
+            continue;
+        }
+
+        let was_burning = periodic_damage.contains(DamageType::Fire);
+
+        for instance in periodic_damage.instances.iter_mut() {
+            instance.remaining = instance.remaining.saturating_sub(time.delta());
+
+            if !instance.timer.tick(time.delta()).just_finished() {
+                continue;
+            }
+
+            let damage = match instance.damage_type {
+                DamageType::Fire if fire_immune => continue,
+                DamageType::Fire => instance.damage * takes_damage.burn_damage_multiplier,
+                _ => instance.damage,
+            };
+
+            damage_writer.send(DamageEvent {
+                victim,
+                attacker: instance.source,
+                damage,
+                damage_type: instance.damage_type,
+                weapon: None,
+                projectile: None,
+                direct_source: None,
+                area_of_effect: false,
+            });
+        }
+
+        periodic_damage
+            .instances
+            .retain(|instance| !instance.remaining.is_zero());
+
+        if was_burning && !periodic_damage.contains(DamageType::Fire) {
+            flags.set_on_fire(false);
+
+            stopped_writer.send(BurningStoppedEvent {
+                entity: victim,
+                reason: BurnStopReason::Expired,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fire(damage: f32, secs: u64) -> DamageOverTime {
+        DamageOverTime::fire(damage, Duration::from_secs(secs))
+    }
+
+    #[test]
+    fn stack_extend_duration() {
+        let mut instance = fire(1.0, 3);
+        instance.stack(fire(2.0, 4), BurnStacking::ExtendDuration);
+
+        assert_eq!(instance.remaining(), Duration::from_secs(7));
+        assert_eq!(instance.damage, 2.0);
+    }
+
+    #[test]
+    fn stack_max() {
+        let mut instance = fire(3.0, 5);
+        instance.stack(fire(2.0, 4), BurnStacking::Max);
+
+        assert_eq!(instance.remaining(), Duration::from_secs(5));
+        assert_eq!(instance.damage, 3.0);
+    }
+
+    #[test]
+    fn stack_refresh() {
+        let mut instance = fire(3.0, 5);
+        instance.stack(fire(2.0, 4), BurnStacking::Refresh);
+
+        assert_eq!(instance.remaining(), Duration::from_secs(4));
+        assert_eq!(instance.damage, 2.0);
+    }
+
+    #[test]
+    fn stack_damage() {
+        let mut instance = fire(1.0, 3);
+        instance.stack(fire(2.0, 4), BurnStacking::StackDamage);
+
+        assert_eq!(instance.remaining(), Duration::from_secs(4));
+        assert_eq!(instance.damage, 3.0);
+    }
+
+    #[test]
+    fn stack_keeps_the_source_if_the_new_instance_has_none() {
+        let source = Entity::from_raw(1);
+        let mut instance = fire(1.0, 3).with_source(source);
+        instance.stack(fire(1.0, 3), BurnStacking::Max);
+
+        assert_eq!(instance.source, Some(source));
+    }
+
+    #[test]
+    fn immunities_insert_and_remove() {
+        let mut immunities = Immunities::none().with(DamageType::Fire);
+
+        assert!(immunities.contains(DamageType::Fire));
+        assert!(!immunities.contains(DamageType::Fall));
+
+        immunities.insert(DamageType::Fall);
+        immunities.remove(DamageType::Fire);
+
+        assert!(!immunities.contains(DamageType::Fire));
+        assert!(immunities.contains(DamageType::Fall));
+    }
+
+    #[test]
+    fn immunities_all_and_from_iter() {
+        assert!(Immunities::all().contains(DamageType::Drowning));
+        assert!(!Immunities::none().contains(DamageType::Generic));
+
+        let immunities = [DamageType::Poison, DamageType::Wither]
+            .into_iter()
+            .collect::<Immunities>();
+
+        assert_eq!(
+            immunities,
+            Immunities::none()
+                .with(DamageType::Poison)
+                .with(DamageType::Wither)
+        );
+        assert!(!immunities.contains(DamageType::Melee));
+    }
 }
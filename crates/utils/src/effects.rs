@@ -0,0 +1,226 @@
+//! Helpers to give visual feedback for entities (entity status animations and particles),
+//! and the [`ParticleEmitter`] for particle trails.
+//!
+//! All packets are only sent to the clients that can see the entity.
+
+use bevy_time::Time;
+use valence::{
+    entity::{EntityId, EntityStatus, EntityStatuses},
+    math::Aabb,
+    prelude::*,
+    protocol::{
+        packets::play::{DamageTiltS2c, EntityDamageS2c},
+        Particle, VarInt, WritePacket,
+    },
+    Layer,
+};
+
+/// Trigger entity status animations.
+pub trait EntityStatusesExt {
+    /// The death animation (and sound) of a living entity.
+    fn play_death_animation(&mut self);
+    /// The animation of a totem of undying saving the entity.
+    fn play_totem_animation(&mut self);
+    /// The sound of a shield blocking an attack.
+    fn play_shield_block(&mut self);
+    /// The sound of a shield breaking.
+    fn play_shield_break(&mut self);
+    /// The smoke particles that are shown after a living entity died.
+    fn play_death_particles(&mut self);
+}
+
+impl EntityStatusesExt for EntityStatuses {
+    fn play_death_animation(&mut self) {
+        self.trigger(EntityStatus::PlayDeathSoundOrAddProjectileHitParticles);
+    }
+
+    fn play_totem_animation(&mut self) {
+        self.trigger(EntityStatus::UseTotemOfUndying);
+    }
+
+    fn play_shield_block(&mut self) {
+        self.trigger(EntityStatus::BlockWithShield);
+    }
+
+    fn play_shield_break(&mut self) {
+        self.trigger(EntityStatus::BreakShield);
+    }
+
+    fn play_death_particles(&mut self) {
+        self.trigger(EntityStatus::AddDeathParticles);
+    }
+}
+
+/// The attacker of a [`play_hurt_animation`].
+#[derive(Clone, Copy, Debug)]
+pub struct HurtSource<'a> {
+    pub entity_id: &'a EntityId,
+    pub position: DVec3,
+    /// The entity that directly dealt the damage (e.g. a projectile), `None` if it is the attacker.
+    pub direct_entity_id: Option<&'a EntityId>,
+}
+
+/// Show the hurt animation (the entity flashes red) of an entity.
+///
+/// If the `source` is known, the camera of a hurt player tilts away from it (`yaw` is the yaw of the hurt entity).
+pub fn play_hurt_animation(
+    layer: &mut ChunkLayer,
+    entity_id: &EntityId,
+    position: DVec3,
+    yaw: f32,
+    source: Option<HurtSource>,
+) {
+    // The ids are sent + 1, 0 means there is no source entity.
+    let cause_id = source.map_or(0, |source| source.entity_id.get() + 1);
+    let direct_id = source
+        .and_then(|source| source.direct_entity_id)
+        .map_or(cause_id, |direct| direct.get() + 1);
+    let mut writer = layer.view_writer(position);
+
+    writer.write_packet(&EntityDamageS2c {
+        entity_id: VarInt(entity_id.get()),
+        source_type_id: 1.into(),
+        source_cause_id: cause_id.into(),
+        source_direct_id: direct_id.into(),
+        source_pos: Some(source.map_or(position, |source| source.position)),
+    });
+
+    if let Some(source) = source {
+        let delta = source.position - position;
+
+        writer.write_packet(&DamageTiltS2c {
+            entity_id: VarInt(entity_id.get()),
+            yaw: delta.z.atan2(delta.x).to_degrees() as f32 - yaw,
+        });
+    }
+}
+
+/// Spawn particles randomly spread over the hitbox of an entity.
+pub fn spawn_particles_around(
+    layer: &mut ChunkLayer,
+    particle: &Particle,
+    hitbox: Aabb,
+    count: i32,
+) {
+    let center = (hitbox.min() + hitbox.max()) / 2.0;
+    // The offset is the standard deviation of the particle positions.
+    let spread = ((hitbox.max() - hitbox.min()) / 4.0).as_vec3();
+
+    layer.play_particle(particle, false, center, spread, 0.0, count);
+}
+
+/// Spawn the particles of a critical hit on an entity.
+pub fn spawn_critical_hit_particles(layer: &mut ChunkLayer, hitbox: Aabb) {
+    spawn_particles_around(layer, &Particle::Crit, hitbox, 16);
+}
+
+/// Spawn the particles of a hit with an enchanted weapon on an entity.
+pub fn spawn_enchanted_hit_particles(layer: &mut ChunkLayer, hitbox: Aabb) {
+    spawn_particles_around(layer, &Particle::EnchantedHit, hitbox, 16);
+}
+
+/// Spawn the particle of a sweep attack in front of the attacker.
+pub fn spawn_sweep_particles(layer: &mut ChunkLayer, attacker_position: DVec3, direction: Vec3) {
+    let position = attacker_position + DVec3::new(direction.x as f64, 0.9, direction.z as f64);
+
+    layer.play_particle(&Particle::SweepAttack, false, position, Vec3::ZERO, 0.0, 1);
+}
+
+/// Spawns particles along the path of an entity (e.g. a trail behind arrows, fireballs or spells).
+#[derive(Component, Clone, Debug)]
+pub struct ParticleEmitter {
+    pub particle: Particle,
+    /// The particles that are spawned per second.
+    pub rate: f32,
+    /// The offset of the particles from the position of the entity.
+    pub offset: Vec3,
+    /// The random spread of the particles (the standard deviation of the positions).
+    pub spread: Vec3,
+    /// The speed of the particles (what this does depends on the particle).
+    pub speed: f32,
+    /// The position of the entity when the last particles were spawned.
+    last_position: Option<DVec3>,
+    /// Particles that were not spawned yet, because the rate is not a multiple of the tick rate.
+    pending: f32,
+}
+
+impl ParticleEmitter {
+    pub fn new(particle: Particle, rate: f32) -> Self {
+        Self {
+            particle,
+            rate,
+            offset: Vec3::ZERO,
+            spread: Vec3::ZERO,
+            speed: 0.0,
+            last_position: None,
+            pending: 0.0,
+        }
+    }
+
+    pub fn with_offset(mut self, offset: impl Into<Vec3>) -> Self {
+        self.offset = offset.into();
+        self
+    }
+
+    pub fn with_spread(mut self, spread: impl Into<Vec3>) -> Self {
+        self.spread = spread.into();
+        self
+    }
+
+    pub fn with_speed(mut self, speed: f32) -> Self {
+        self.speed = speed;
+        self
+    }
+}
+
+pub struct ParticleEmitterPlugin;
+
+impl Plugin for ParticleEmitterPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(PostUpdate, emit_particles);
+    }
+}
+
+/// Spawn the particles of this tick evenly spread between the last and the current position,
+/// so fast entities leave a continuous trail.
+fn emit_particles(
+    time: Res<Time>,
+    mut emitters: Query<(&mut ParticleEmitter, &Position, &EntityLayerId)>,
+    mut layers: Query<&mut ChunkLayer>,
+) {
+    let dt = time.delta_seconds();
+
+    for (mut emitter, position, layer_id) in emitters.iter_mut() {
+        let last_position = emitter
+            .last_position
+            .replace(position.0)
+            .unwrap_or(position.0);
+
+        emitter.pending += emitter.rate * dt;
+        let count = emitter.pending.floor();
+        emitter.pending -= count;
+
+        if count < 1.0 {
+            continue;
+        }
+
+        let Ok(mut layer) = layers.get_mut(layer_id.0) else {
+            continue;
+        };
+
+        let offset = emitter.offset.as_dvec3();
+
+        for i in 0..count as u32 {
+            let point = last_position.lerp(position.0, (i + 1) as f64 / count as f64) + offset;
+
+            layer.play_particle(
+                &emitter.particle,
+                false,
+                point,
+                emitter.spread,
+                emitter.speed,
+                1,
+            );
+        }
+    }
+}
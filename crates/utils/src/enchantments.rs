@@ -1,7 +1,8 @@
 use std::collections::HashMap;
 
+use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
 use valence::{
-    nbt::{value::ValueRef, Value},
+    nbt::{compound, value::ValueRef, Compound, List, Value},
     ItemStack,
 };
 
@@ -90,6 +91,76 @@ impl Enchantment {
         }
     }
 
+    /// The maximum level of the enchantment in vanilla.
+    pub fn max_level(&self) -> u32 {
+        match self {
+            Enchantment::AquaAffinity
+            | Enchantment::CurseOfBinding
+            | Enchantment::Channeling
+            | Enchantment::Flame
+            | Enchantment::Infinity
+            | Enchantment::Multishot
+            | Enchantment::SilkTouch => 1,
+            Enchantment::FrostWalker
+            | Enchantment::FireAspect
+            | Enchantment::Knockback
+            | Enchantment::Punch => 2,
+            Enchantment::DepthStrider
+            | Enchantment::Respiration
+            | Enchantment::SoulSpeed
+            | Enchantment::Thorns
+            | Enchantment::SwiftSneak
+            | Enchantment::Looting
+            | Enchantment::SweepingEdge
+            | Enchantment::Loyalty
+            | Enchantment::Riptide
+            | Enchantment::QuickCharge
+            | Enchantment::Fortune
+            | Enchantment::LuckOftheSea
+            | Enchantment::Lure => 3,
+            Enchantment::BlastProtection
+            | Enchantment::FeatherFalling
+            | Enchantment::FireProtection
+            | Enchantment::ProjectileProtection
+            | Enchantment::Protection
+            | Enchantment::Piercing => 4,
+            Enchantment::BaneOfArthropods
+            | Enchantment::Efficiency
+            | Enchantment::Impaling
+            | Enchantment::Sharpness
+            | Enchantment::Smite
+            | Enchantment::Power => 5,
+        }
+    }
+
+    /// The two enchantments can not be on the same item.
+    pub fn conflicts_with(&self, other: &Enchantment) -> bool {
+        use Enchantment::*;
+
+        if self == other {
+            return false;
+        }
+
+        let groups: [&[Enchantment]; 7] = [
+            &[
+                Protection,
+                BlastProtection,
+                FireProtection,
+                ProjectileProtection,
+            ],
+            &[Sharpness, Smite, BaneOfArthropods],
+            &[DepthStrider, FrostWalker],
+            &[Fortune, SilkTouch],
+            &[Loyalty, Riptide],
+            &[Channeling, Riptide],
+            &[Multishot, Piercing],
+        ];
+
+        groups
+            .iter()
+            .any(|group| group.contains(self) && group.contains(other))
+    }
+
     pub fn from_id(id: &str) -> Option<Self> {
         match id {
             "aqua_affinity" | "minecraft:aqua_affinity" => Some(Enchantment::AquaAffinity),
@@ -137,8 +208,31 @@ impl Enchantment {
     }
 }
 
+/// Enchantments are (de)serialized by their id (e.g. `"sharpness"`).
+impl Serialize for Enchantment {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.id())
+    }
+}
+
+impl<'de> Deserialize<'de> for Enchantment {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let id = String::deserialize(deserializer)?;
+        Enchantment::from_id(&id)
+            .ok_or_else(|| D::Error::custom(format!("unknown enchantment `{id}`")))
+    }
+}
+
 pub trait ItemStackEnchantmentsExt {
     fn enchantments(&self) -> HashMap<Enchantment, u32>;
+    /// Replace the enchantments of the item.
+    fn set_enchantments(&mut self, enchantments: &HashMap<Enchantment, u32>);
+    /// Add an enchantment or change its level.
+    fn set_enchantment(&mut self, enchantment: Enchantment, level: u32);
+    /// Remove an enchantment, returns the level it had.
+    fn remove_enchantment(&mut self, enchantment: Enchantment) -> Option<u32>;
+    /// Add enchantments, enchantments the item already has are overwritten.
+    fn add_enchantments(&mut self, enchantments: &HashMap<Enchantment, u32>);
 }
 
 impl ItemStackEnchantmentsExt for ItemStack {
@@ -148,11 +242,17 @@ impl ItemStackEnchantmentsExt for ItemStack {
             if let Some(Value::List(enchants)) = nbt.get("Enchantments") {
                 for enchant in enchants {
                     if let ValueRef::Compound(enchant) = enchant {
-                        if let (Some(Value::String(id)), Some(Value::Long(level))) =
-                            (enchant.get("id"), enchant.get("lvl"))
-                        {
+                        // Vanilla stores the level as a short.
+                        let level = match enchant.get("lvl") {
+                            Some(Value::Short(level)) => *level as i64,
+                            Some(Value::Int(level)) => *level as i64,
+                            Some(Value::Long(level)) => *level,
+                            _ => continue,
+                        };
+
+                        if let Some(Value::String(id)) = enchant.get("id") {
                             if let Some(enchantment) = Enchantment::from_id(id) {
-                                enchantments.insert(enchantment, *level as u32);
+                                enchantments.insert(enchantment, level.max(0) as u32);
                             }
                         }
                     }
@@ -162,4 +262,80 @@ impl ItemStackEnchantmentsExt for ItemStack {
 
         enchantments
     }
+
+    fn set_enchantments(&mut self, enchantments: &HashMap<Enchantment, u32>) {
+        let list = enchantments
+            .iter()
+            .map(|(enchantment, level)| {
+                compound! {
+                    "id" => format!("minecraft:{}", enchantment.id()),
+                    "lvl" => *level as i64,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        self.nbt
+            .get_or_insert_with(Compound::new)
+            .insert("Enchantments", List::Compound(list));
+    }
+
+    fn set_enchantment(&mut self, enchantment: Enchantment, level: u32) {
+        let mut enchantments = self.enchantments();
+        enchantments.insert(enchantment, level);
+        self.set_enchantments(&enchantments);
+    }
+
+    fn remove_enchantment(&mut self, enchantment: Enchantment) -> Option<u32> {
+        let mut enchantments = self.enchantments();
+        let level = enchantments.remove(&enchantment)?;
+
+        if enchantments.is_empty() {
+            if let Some(nbt) = &mut self.nbt {
+                nbt.remove("Enchantments");
+            }
+        } else {
+            self.set_enchantments(&enchantments);
+        }
+
+        Some(level)
+    }
+
+    fn add_enchantments(&mut self, enchantments: &HashMap<Enchantment, u32>) {
+        let mut current = self.enchantments();
+        current.extend(enchantments);
+        self.set_enchantments(&current);
+    }
+}
+
+/// Combine the enchantments of two items like an anvil.
+///
+/// Enchantments of the sacrifice that conflict with the enchantments of the target are dropped.
+/// If both items have the same enchantment with the same level, the level is increased by one (up to the max level),
+/// otherwise the higher level is kept.
+pub fn combine_enchantments(
+    target: &HashMap<Enchantment, u32>,
+    sacrifice: &HashMap<Enchantment, u32>,
+) -> HashMap<Enchantment, u32> {
+    let mut result = target.clone();
+
+    for (&enchantment, &level) in sacrifice {
+        if target
+            .keys()
+            .any(|existing| existing.conflicts_with(&enchantment))
+        {
+            continue;
+        }
+
+        let level = match target.get(&enchantment) {
+            Some(&existing) if existing == level => {
+                (level + 1).min(enchantment.max_level()).max(level)
+            }
+            Some(&existing) => existing.max(level),
+            None => level,
+        };
+
+        result.insert(enchantment, level);
+    }
+
+    result
 }
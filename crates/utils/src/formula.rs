@@ -0,0 +1,118 @@
+use std::{collections::HashMap, error::Error, fmt};
+
+/// Formulas (or other callbacks) that are registered by name,
+/// so they can be referenced by serializable configs.
+///
+/// `F` can be a function pointer or a boxed closure (e.g. `Box<dyn Fn(f32) -> f32 + Send + Sync>`).
+pub struct FormulaRegistry<F> {
+    formulas: HashMap<String, F>,
+}
+
+impl<F> Default for FormulaRegistry<F> {
+    fn default() -> Self {
+        Self {
+            formulas: HashMap::new(),
+        }
+    }
+}
+
+impl<F> FormulaRegistry<F> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with(mut self, name: impl Into<String>, formula: F) -> Self {
+        self.register(name, formula);
+        self
+    }
+
+    /// Register a formula, returns the formula that was previously registered with this name.
+    pub fn register(&mut self, name: impl Into<String>, formula: F) -> Option<F> {
+        self.formulas.insert(name.into(), formula)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&F> {
+        self.formulas.get(name)
+    }
+
+    /// Like [`FormulaRegistry::get`], but returns an error if the formula does not exist.
+    pub fn resolve(&self, name: &str) -> Result<&F, UnknownFormula> {
+        self.get(name)
+            .ok_or_else(|| UnknownFormula(name.to_string()))
+    }
+
+    /// Like [`FormulaRegistry::get`], but takes an optional name and logs unknown formulas.
+    ///
+    /// Names should be checked when the config is loaded (see [`FormulaRegistry::validate`]),
+    /// this is only the fallback for configs that were not.
+    pub fn resolve_or_warn(&self, name: Option<&str>) -> Option<&F> {
+        let name = name?;
+        let formula = self.get(name);
+
+        if formula.is_none() {
+            tracing::warn!("unknown formula `{name}`");
+        }
+
+        formula
+    }
+
+    /// Returns an error if the name is set, but the formula does not exist.
+    pub fn validate(&self, name: Option<&str>) -> Result<(), UnknownFormula> {
+        match name {
+            Some(name) => self.resolve(name).map(|_| ()),
+            None => Ok(()),
+        }
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.formulas.contains_key(name)
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> + '_ {
+        self.formulas.keys().map(String::as_str)
+    }
+}
+
+/// A config referenced a formula that was not registered.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownFormula(pub String);
+
+impl fmt::Display for UnknownFormula {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown formula `{}`", self.0)
+    }
+}
+
+impl Error for UnknownFormula {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registry() -> FormulaRegistry<fn(f32) -> f32> {
+        FormulaRegistry::<fn(f32) -> f32>::new().with("double", |x| x * 2.0)
+    }
+
+    #[test]
+    fn resolve_known_and_unknown_formulas() {
+        let registry = registry();
+
+        assert_eq!(registry.resolve("double").map(|f| f(2.0)), Ok(4.0));
+        assert_eq!(
+            registry.resolve("triple").map(|f| f(2.0)),
+            Err(UnknownFormula("triple".to_string()))
+        );
+    }
+
+    #[test]
+    fn validate_only_fails_for_unknown_names() {
+        let registry = registry();
+
+        assert_eq!(registry.validate(None), Ok(()));
+        assert_eq!(registry.validate(Some("double")), Ok(()));
+        assert_eq!(
+            registry.validate(Some("triple")),
+            Err(UnknownFormula("triple".to_string()))
+        );
+    }
+}
@@ -0,0 +1,214 @@
+//! Show the health of (non player) entities, e.g. NPCs and bosses.
+
+use valence::{
+    entity::{
+        display::Billboard,
+        entity::{CustomName, CustomNameVisible},
+        living::Health,
+        text_display::{self, TextDisplayEntityBundle},
+    },
+    prelude::*,
+    text::IntoText,
+};
+
+/// Shows the health of an entity, the display is updated every time the [`Health`] changes.
+#[derive(Component, Clone, Debug)]
+pub struct HealthDisplay {
+    pub mode: HealthDisplayMode,
+    pub format: HealthFormat,
+    /// The health of the entity when it is fully healed.
+    pub max_health: f32,
+}
+
+impl HealthDisplay {
+    pub fn new(mode: HealthDisplayMode, max_health: f32) -> Self {
+        Self {
+            mode,
+            format: HealthFormat::Hearts,
+            max_health,
+        }
+    }
+
+    pub fn with_format(mut self, format: HealthFormat) -> Self {
+        self.format = format;
+        self
+    }
+}
+
+/// Where the health of an entity is shown.
+///
+/// Boss bars are shown with the `HealthBossBar` of the bossbar crate.
+#[derive(Clone, Debug)]
+pub enum HealthDisplayMode {
+    /// The health replaces the custom name of the entity.
+    Name,
+    /// A floating text above the entity.
+    Hologram {
+        /// The height of the text above the hitbox of the entity.
+        offset: f64,
+    },
+}
+
+/// How the health is formatted as a text.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HealthFormat {
+    /// `❤❤❤❤❤` (one heart for every two health).
+    Hearts,
+    /// `15.5 / 20 ❤`
+    Numeric,
+}
+
+impl HealthFormat {
+    pub fn format(&self, health: f32, max_health: f32) -> Text {
+        let health = health.max(0.0);
+
+        match self {
+            Self::Hearts => {
+                let max_hearts = (max_health / 2.0).ceil() as usize;
+                let hearts = ((health / 2.0).ceil() as usize).min(max_hearts);
+
+                "❤".repeat(hearts).color(Color::RED)
+                    + "❤".repeat(max_hearts - hearts).color(Color::DARK_GRAY)
+            }
+            Self::Numeric => format!("{health:.1} / {max_health:.1} ❤").color(Color::RED),
+        }
+    }
+}
+
+/// A text display that shows the health of an entity.
+#[derive(Component)]
+struct HealthDisplayOf(Entity);
+
+/// The text display of an entity with a [`HealthDisplay`].
+#[derive(Component)]
+struct HealthDisplayEntity(Entity);
+
+pub struct HealthDisplayPlugin;
+
+impl Plugin for HealthDisplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                spawn_health_displays,
+                update_health_displays,
+                move_holograms,
+                despawn_health_displays,
+            )
+                .chain(),
+        );
+    }
+}
+
+fn spawn_health_displays(
+    mut commands: Commands,
+    query: Query<(Entity, &HealthDisplay, &Health, &EntityLayerId), Added<HealthDisplay>>,
+) {
+    for (entity, display, health, layer) in query.iter() {
+        let text = display.format.format(health.0, display.max_health);
+
+        let display_entity = match &display.mode {
+            HealthDisplayMode::Name => {
+                commands
+                    .entity(entity)
+                    .insert((CustomName(Some(text)), CustomNameVisible(true)));
+                continue;
+            }
+            HealthDisplayMode::Hologram { .. } => commands
+                .spawn((
+                    TextDisplayEntityBundle {
+                        text_display_text: text_display::Text(text),
+                        display_billboard: Billboard(3),
+                        layer: *layer,
+                        ..Default::default()
+                    },
+                    HealthDisplayOf(entity),
+                ))
+                .id(),
+        };
+
+        commands
+            .entity(entity)
+            .insert(HealthDisplayEntity(display_entity));
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn update_health_displays(
+    mut query: Query<
+        (
+            &HealthDisplay,
+            &Health,
+            Option<&mut CustomName>,
+            Option<&HealthDisplayEntity>,
+        ),
+        Changed<Health>,
+    >,
+    mut holograms: Query<&mut text_display::Text, With<HealthDisplayOf>>,
+) {
+    for (display, health, custom_name, display_entity) in query.iter_mut() {
+        let text = display.format.format(health.0, display.max_health);
+
+        match &display.mode {
+            HealthDisplayMode::Name => {
+                if let Some(mut custom_name) = custom_name {
+                    custom_name.0 = Some(text);
+                }
+            }
+            HealthDisplayMode::Hologram { .. } => {
+                if let Some(mut hologram) =
+                    display_entity.and_then(|display| holograms.get_mut(display.0).ok())
+                {
+                    hologram.0 = text;
+                }
+            }
+        }
+    }
+}
+
+/// Holograms follow the entity they belong to.
+fn move_holograms(
+    owners: Query<(&HealthDisplay, &Hitbox, &EntityLayerId)>,
+    mut holograms: Query<
+        (&HealthDisplayOf, &mut Position, &mut EntityLayerId),
+        Without<HealthDisplay>,
+    >,
+) {
+    for (owner, mut position, mut layer) in holograms.iter_mut() {
+        let Ok((display, hitbox, owner_layer)) = owners.get(owner.0) else {
+            continue;
+        };
+
+        let HealthDisplayMode::Hologram { offset } = display.mode else {
+            continue;
+        };
+
+        let hitbox = hitbox.get();
+        let top = DVec3::new(
+            (hitbox.min().x + hitbox.max().x) / 2.0,
+            hitbox.max().y + offset,
+            (hitbox.min().z + hitbox.max().z) / 2.0,
+        );
+
+        if position.0 != top {
+            position.0 = top;
+        }
+
+        if layer.0 != owner_layer.0 {
+            layer.0 = owner_layer.0;
+        }
+    }
+}
+
+/// Despawn holograms of entities that were despawned (or had their [`HealthDisplay`] removed).
+fn despawn_health_displays(
+    mut commands: Commands,
+    displays: Query<(Entity, &HealthDisplayOf)>,
+    owners: Query<(), With<HealthDisplay>>,
+) {
+    for (entity, owner) in displays.iter() {
+        if owners.get(owner.0).is_err() {
+            commands.entity(entity).insert(Despawned);
+        }
+    }
+}
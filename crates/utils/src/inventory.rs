@@ -0,0 +1,72 @@
+//! Add picked up items to player inventories the way vanilla does.
+
+use valence::{
+    inventory::player_inventory::PlayerInventory,
+    prelude::{Inventory, ItemStack},
+};
+
+pub trait InventoryExt {
+    /// The first empty slot of the hotbar or the main inventory (the hotbar is filled first).
+    fn first_empty_pickup_slot(&self) -> Option<u16>;
+    /// Check if the item fits into the inventory, see [`InventoryExt::add_item`].
+    fn can_add_item(&self, item: &ItemStack, preferred_slot: Option<u16>) -> bool;
+    /// Add the item like a vanilla item pickup, returns `false` if the inventory is full.
+    ///
+    /// The `preferred_slot` is used if it is empty (e.g. the slot a trident was thrown from),
+    /// otherwise the item is added to a stack of the same item or to the first empty slot.
+    /// The hotbar is filled before the main inventory.
+    fn add_item(&mut self, item: &ItemStack, preferred_slot: Option<u16>) -> bool;
+}
+
+impl InventoryExt for Inventory {
+    fn first_empty_pickup_slot(&self) -> Option<u16> {
+        pickup_slots().find(|slot| self.slot(*slot).is_empty())
+    }
+
+    fn can_add_item(&self, item: &ItemStack, preferred_slot: Option<u16>) -> bool {
+        pickup_slot(self, item, preferred_slot).is_some()
+    }
+
+    fn add_item(&mut self, item: &ItemStack, preferred_slot: Option<u16>) -> bool {
+        let Some(slot) = pickup_slot(self, item, preferred_slot) else {
+            return false;
+        };
+
+        let stack = self.slot(slot);
+
+        if stack.is_empty() {
+            self.set_slot(slot, item.clone());
+        } else {
+            let count = stack.count + item.count;
+            self.set_slot_amount(slot, count);
+        }
+
+        true
+    }
+}
+
+fn pickup_slots() -> impl Iterator<Item = u16> {
+    PlayerInventory::SLOTS_HOTBAR.chain(PlayerInventory::SLOTS_MAIN)
+}
+
+/// The slot the item will be added to, see [`InventoryExt::add_item`].
+fn pickup_slot(
+    inventory: &Inventory,
+    item: &ItemStack,
+    preferred_slot: Option<u16>,
+) -> Option<u16> {
+    if let Some(slot) = preferred_slot.filter(|slot| inventory.slot(*slot).is_empty()) {
+        return Some(slot);
+    }
+
+    let stack_slot = pickup_slots().find(|slot| {
+        let stack = inventory.slot(*slot);
+
+        !stack.is_empty()
+            && stack.item == item.item
+            && stack.nbt == item.nbt
+            && stack.count as i16 + item.count as i16 <= item.item.max_stack() as i16
+    });
+
+    stack_slot.or_else(|| inventory.first_empty_pickup_slot())
+}
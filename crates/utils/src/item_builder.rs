@@ -0,0 +1,172 @@
+//! A builder for item stacks with custom NBT (name, lore, enchantments, ...).
+
+use std::collections::HashMap;
+
+use valence::{
+    nbt::{Compound, List, Value},
+    prelude::*,
+    text::IntoText,
+};
+
+use crate::enchantments::{Enchantment, ItemStackEnchantmentsExt};
+
+/// The flags of the `HideFlags` NBT tag.
+///
+/// https://minecraft.wiki/w/Tutorials/Command_NBT_tags#Items
+pub mod hide_flags {
+    pub const ENCHANTMENTS: i32 = 1;
+    pub const ATTRIBUTE_MODIFIERS: i32 = 2;
+    pub const UNBREAKABLE: i32 = 4;
+    pub const CAN_DESTROY: i32 = 8;
+    pub const CAN_PLACE_ON: i32 = 16;
+    pub const OTHER: i32 = 32;
+    pub const DYED: i32 = 64;
+    pub const ARMOR_TRIM: i32 = 128;
+    pub const ALL: i32 = 255;
+}
+
+/// A builder for item stacks.
+///
+/// ```ignore
+/// let sword = ItemBuilder::new(ItemKind::DiamondSword)
+///     .name("Legendary Blade".color(Color::GOLD))
+///     .lore(["A very sharp sword"])
+///     .enchant(Enchantment::Sharpness, 5)
+///     .unbreakable()
+///     .hide_flags()
+///     .build();
+/// ```
+#[derive(Clone, Debug)]
+pub struct ItemBuilder {
+    item: ItemKind,
+    count: i8,
+    name: Option<Text>,
+    lore: Vec<Text>,
+    enchantments: HashMap<Enchantment, u32>,
+    unbreakable: bool,
+    hide_flags: i32,
+    custom_model_data: Option<i32>,
+    nbt: Compound,
+}
+
+impl ItemBuilder {
+    pub fn new(item: ItemKind) -> Self {
+        Self {
+            item,
+            count: 1,
+            name: None,
+            lore: vec![],
+            enchantments: HashMap::new(),
+            unbreakable: false,
+            hide_flags: 0,
+            custom_model_data: None,
+            nbt: Compound::new(),
+        }
+    }
+
+    pub fn count(mut self, count: i8) -> Self {
+        self.count = count;
+        self
+    }
+
+    /// The custom name of the item (custom names are italic by default).
+    pub fn name(mut self, name: impl IntoText<'static>) -> Self {
+        self.name = Some(name.into_cow_text().into_owned());
+        self
+    }
+
+    /// Replace the lore (the lines below the name) of the item.
+    pub fn lore<T: IntoText<'static>>(mut self, lines: impl IntoIterator<Item = T>) -> Self {
+        self.lore = lines
+            .into_iter()
+            .map(|line| line.into_cow_text().into_owned())
+            .collect();
+        self
+    }
+
+    /// Add a line to the lore of the item.
+    pub fn lore_line(mut self, line: impl IntoText<'static>) -> Self {
+        self.lore.push(line.into_cow_text().into_owned());
+        self
+    }
+
+    pub fn enchant(mut self, enchantment: Enchantment, level: u32) -> Self {
+        self.enchantments.insert(enchantment, level);
+        self
+    }
+
+    pub fn enchantments(mut self, enchantments: &HashMap<Enchantment, u32>) -> Self {
+        self.enchantments.extend(enchantments);
+        self
+    }
+
+    pub fn unbreakable(mut self) -> Self {
+        self.unbreakable = true;
+        self
+    }
+
+    /// Hide all tooltips (enchantments, attribute modifiers, ...).
+    pub fn hide_flags(mut self) -> Self {
+        self.hide_flags = hide_flags::ALL;
+        self
+    }
+
+    /// Hide some tooltips, the flags are combined from the constants in [`hide_flags`].
+    pub fn hide(mut self, flags: i32) -> Self {
+        self.hide_flags |= flags;
+        self
+    }
+
+    pub fn custom_model_data(mut self, data: i32) -> Self {
+        self.custom_model_data = Some(data);
+        self
+    }
+
+    /// Set any other NBT tag.
+    pub fn nbt(mut self, key: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.nbt.insert(key, value);
+        self
+    }
+
+    pub fn build(self) -> ItemStack {
+        let mut nbt = self.nbt;
+
+        if self.name.is_some() || !self.lore.is_empty() {
+            let mut display = Compound::new();
+
+            // Names and lore lines are json text components.
+            if let Some(name) = self.name {
+                display.insert("Name", name.to_string());
+            }
+
+            if !self.lore.is_empty() {
+                display.insert(
+                    "Lore",
+                    List::String(self.lore.iter().map(Text::to_string).collect()),
+                );
+            }
+
+            nbt.insert("display", display);
+        }
+
+        if self.unbreakable {
+            nbt.insert("Unbreakable", Value::Byte(1));
+        }
+
+        if self.hide_flags != 0 {
+            nbt.insert("HideFlags", Value::Int(self.hide_flags));
+        }
+
+        if let Some(data) = self.custom_model_data {
+            nbt.insert("CustomModelData", Value::Int(data));
+        }
+
+        let mut stack = ItemStack::new(self.item, self.count, (!nbt.is_empty()).then_some(nbt));
+
+        if !self.enchantments.is_empty() {
+            stack.set_enchantments(&self.enchantments);
+        }
+
+        stack
+    }
+}
@@ -1,42 +1,161 @@
-use valence::{prelude::Equipment, ItemKind};
+use serde::{Deserialize, Serialize};
+use valence::{prelude::Equipment, ItemKind, ItemStack};
+
+use crate::{
+    attribute_modifiers::{self, ItemStackAttributeModifiersExt},
+    custom_items::CustomItems,
+};
+
+/// The distance (in blocks) players can attack entities with any item in vanilla (survival).
+pub const DEFAULT_REACH: f32 = 3.0;
 
 pub trait EquipmentExt {
     /// The armor points of the equipment.
-    fn armor_points(&self) -> f32;
+    fn armor_points(&self, custom_items: &CustomItems) -> f32;
     /// The armor toughness of the equipment.
-    fn armor_toughness(&self) -> f32;
+    fn armor_toughness(&self, custom_items: &CustomItems) -> f32;
     /// The knockback resistance of the equipment.
     ///
     /// This is a value between 0.0 and 1.0.
     ///
     /// https://minecraft.wiki/w/Knockback_(mechanic)#Natural_knockback_resistance
-    fn knockback_resistance(&self) -> f32;
+    fn knockback_resistance(&self, custom_items: &CustomItems) -> f32;
 }
 
 impl EquipmentExt for Equipment {
-    fn armor_points(&self) -> f32 {
-        self.head().item.armor_points()
-            + self.chest().item.armor_points()
-            + self.legs().item.armor_points()
-            + self.feet().item.armor_points()
+    fn armor_points(&self, custom_items: &CustomItems) -> f32 {
+        self.head().armor_points(custom_items)
+            + self.chest().armor_points(custom_items)
+            + self.legs().armor_points(custom_items)
+            + self.feet().armor_points(custom_items)
     }
 
-    fn armor_toughness(&self) -> f32 {
-        self.head().item.armor_toughness()
-            + self.chest().item.armor_toughness()
-            + self.legs().item.armor_toughness()
-            + self.feet().item.armor_toughness()
+    fn armor_toughness(&self, custom_items: &CustomItems) -> f32 {
+        self.head().armor_toughness(custom_items)
+            + self.chest().armor_toughness(custom_items)
+            + self.legs().armor_toughness(custom_items)
+            + self.feet().armor_toughness(custom_items)
     }
 
-    fn knockback_resistance(&self) -> f32 {
-        self.head().item.knockback_resistance()
-            + self.chest().item.knockback_resistance()
-            + self.legs().item.knockback_resistance()
-            + self.feet().item.knockback_resistance()
+    fn knockback_resistance(&self, custom_items: &CustomItems) -> f32 {
+        self.head().knockback_resistance(custom_items)
+            + self.chest().knockback_resistance(custom_items)
+            + self.legs().knockback_resistance(custom_items)
+            + self.feet().knockback_resistance(custom_items)
+    }
+}
+
+/// The values of an item stack.
+///
+/// The values of [`CustomItems`] are used first, then the `AttributeModifiers` NBT tag
+/// (see [`ItemStackAttributeModifiersExt`]) and then the values of the item kind.
+pub trait ItemStackExt {
+    /// The armor points of the item stack.
+    fn armor_points(&self, custom_items: &CustomItems) -> f32;
+    /// The armor toughness of the item stack.
+    fn armor_toughness(&self, custom_items: &CustomItems) -> f32;
+    /// The attack damage of the item stack.
+    fn attack_damage(&self, combat_system: &CombatSystem, custom_items: &CustomItems) -> f32;
+    /// The attack speed of the item stack, these values are only for [`CombatSystem::New`].
+    fn attack_speed(&self, custom_items: &CustomItems) -> f32;
+    /// The knockback resistance of the item stack.
+    fn knockback_resistance(&self, custom_items: &CustomItems) -> f32;
+    /// The distance (in blocks) players can attack entities with the item stack.
+    fn reach(&self, custom_items: &CustomItems) -> f32;
+}
+
+impl ItemStackExt for ItemStack {
+    fn armor_points(&self, custom_items: &CustomItems) -> f32 {
+        custom_items
+            .get(self)
+            .and_then(|item| item.armor_points)
+            .or_else(|| armor_modifier(self, attribute_modifiers::ARMOR))
+            .unwrap_or_else(|| self.item.armor_points())
+    }
+
+    fn armor_toughness(&self, custom_items: &CustomItems) -> f32 {
+        custom_items
+            .get(self)
+            .and_then(|item| item.armor_toughness)
+            .or_else(|| armor_modifier(self, attribute_modifiers::ARMOR_TOUGHNESS))
+            .unwrap_or_else(|| self.item.armor_toughness())
+    }
+
+    fn attack_damage(&self, combat_system: &CombatSystem, custom_items: &CustomItems) -> f32 {
+        custom_items
+            .get(self)
+            .and_then(|item| item.attack_damage)
+            .or_else(|| {
+                main_hand_modifier(
+                    self,
+                    attribute_modifiers::ATTACK_DAMAGE,
+                    attribute_modifiers::PLAYER_ATTACK_DAMAGE,
+                )
+            })
+            .unwrap_or_else(|| self.item.attack_damage(combat_system))
+    }
+
+    fn attack_speed(&self, custom_items: &CustomItems) -> f32 {
+        custom_items
+            .get(self)
+            .and_then(|item| item.attack_speed)
+            .or_else(|| {
+                main_hand_modifier(
+                    self,
+                    attribute_modifiers::ATTACK_SPEED,
+                    attribute_modifiers::PLAYER_ATTACK_SPEED,
+                )
+            })
+            .unwrap_or_else(|| self.item.attack_speed())
+    }
+
+    fn knockback_resistance(&self, custom_items: &CustomItems) -> f32 {
+        custom_items
+            .get(self)
+            .and_then(|item| item.knockback_resistance)
+            .or_else(|| armor_modifier(self, attribute_modifiers::KNOCKBACK_RESISTANCE))
+            .unwrap_or_else(|| self.item.knockback_resistance())
+    }
+
+    fn reach(&self, custom_items: &CustomItems) -> f32 {
+        custom_items
+            .get(self)
+            .and_then(|item| item.reach)
+            .unwrap_or_else(|| self.item.reach())
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// The value of an attribute of an item in the main hand if the item has attribute modifiers.
+fn main_hand_modifier(stack: &ItemStack, attribute: &str, base: f64) -> Option<f32> {
+    let modifiers = stack.attribute_modifiers()?;
+
+    let value = attribute_modifiers::apply_modifiers(
+        base,
+        attribute,
+        modifiers
+            .iter()
+            .filter(|modifier| modifier.applies_to_main_hand()),
+    );
+
+    Some(value as f32)
+}
+
+/// The value of an attribute of an armor item if the item has attribute modifiers.
+fn armor_modifier(stack: &ItemStack, attribute: &str) -> Option<f32> {
+    let modifiers = stack.attribute_modifiers()?;
+
+    let value = attribute_modifiers::apply_modifiers(
+        0.0,
+        attribute,
+        modifiers
+            .iter()
+            .filter(|modifier| modifier.applies_to_armor()),
+    );
+
+    Some(value as f32)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum CombatSystem {
     Old,
     New,
@@ -53,6 +172,8 @@ pub trait ItemKindExt {
     fn attack_speed(&self) -> f32;
     /// The knockback resistance of the item.
     fn knockback_resistance(&self) -> f32;
+    /// The distance (in blocks) players can attack entities with the item.
+    fn reach(&self) -> f32;
 }
 
 impl ItemKindExt for ItemKind {
@@ -244,4 +365,8 @@ impl ItemKindExt for ItemKind {
             _ => 0.0,
         }
     }
+
+    fn reach(&self) -> f32 {
+        DEFAULT_REACH
+    }
 }
@@ -0,0 +1,101 @@
+//! Knockback for clients and other entities.
+//!
+//! The velocity of clients can only be set with a packet (the server does not know their velocity),
+//! while other entities are moved with their [`Velocity`] component (by the physics crate).
+//! All knockback an entity receives in a tick is combined before it is applied.
+
+use valence::{client::FlushPacketsSet, ecs::entity::EntityHashMap, entity::Velocity, prelude::*};
+
+/// How the knockback is combined with the current velocity of the entity.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum KnockbackMode {
+    /// The knockback is added to the current velocity.
+    ///
+    /// For clients this is the same as [`KnockbackMode::Replace`], because their velocity is not known.
+    #[default]
+    Add,
+    /// The current velocity is replaced by the knockback.
+    Replace,
+}
+
+/// Knockback resistance from effects (e.g. abilities or kits, 0.0 - 1.0).
+///
+/// The combat crate combines it with the resistance of the armor and the `generic.knockback_resistance` attribute.
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq)]
+pub struct KnockbackResistanceEffect(pub f32);
+
+/// Send this event to knock back an entity (the velocity is in blocks per second).
+#[derive(Event, Clone, Copy, Debug)]
+pub struct ApplyKnockbackEvent {
+    pub entity: Entity,
+    pub velocity: Vec3,
+    pub mode: KnockbackMode,
+}
+
+impl ApplyKnockbackEvent {
+    pub fn add(entity: Entity, velocity: impl Into<Vec3>) -> Self {
+        Self {
+            entity,
+            velocity: velocity.into(),
+            mode: KnockbackMode::Add,
+        }
+    }
+
+    pub fn replace(entity: Entity, velocity: impl Into<Vec3>) -> Self {
+        Self {
+            entity,
+            velocity: velocity.into(),
+            mode: KnockbackMode::Replace,
+        }
+    }
+}
+
+pub struct KnockbackPlugin;
+
+impl Plugin for KnockbackPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ApplyKnockbackEvent>()
+            .add_systems(PostUpdate, apply_knockback.before(FlushPacketsSet));
+    }
+}
+
+/// The knockback of an entity in this tick.
+#[derive(Default)]
+struct CombinedKnockback {
+    velocity: Vec3,
+    replace: bool,
+}
+
+fn apply_knockback(
+    mut events: EventReader<ApplyKnockbackEvent>,
+    mut query: Query<(Option<&mut Client>, Option<&mut Velocity>)>,
+) {
+    let mut combined = EntityHashMap::<CombinedKnockback>::default();
+
+    for event in events.read() {
+        // A broken formula or config value should not send the entity to NaN.
+        if !event.velocity.is_finite() {
+            continue;
+        }
+
+        let knockback = combined.entry(event.entity).or_default();
+        knockback.velocity += event.velocity;
+        knockback.replace |= event.mode == KnockbackMode::Replace;
+    }
+
+    for (entity, knockback) in combined {
+        let Ok((client, velocity)) = query.get_mut(entity) else {
+            continue;
+        };
+
+        if let Some(mut client) = client {
+            client.set_velocity(knockback.velocity);
+        } else if let Some(mut velocity) = velocity {
+            if knockback.replace {
+                velocity.0 = knockback.velocity;
+            } else {
+                velocity.0 += knockback.velocity;
+            }
+        }
+    }
+}
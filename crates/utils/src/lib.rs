@@ -1,7 +1,24 @@
 pub mod aaab;
+pub mod attribute_modifiers;
+pub mod cooldowns;
+pub mod custom_items;
 pub mod damage;
+pub mod effects;
 pub mod enchantments;
+pub mod formula;
+pub mod health_display;
+pub mod inventory;
+pub mod item_builder;
 pub mod item_values;
+pub mod knockback;
+pub mod pvp;
+pub mod rate_limit;
+pub mod serialization;
+pub mod sound;
+pub mod spawn;
+pub mod spectator;
+pub mod weather;
+pub mod worldborder;
 
 pub use item_values::ItemKindExt;
 use valence::{math::Aabb, prelude::*};
@@ -24,17 +41,65 @@ pub fn aabb_full_block_intersections(aabb: &Aabb) -> Vec<BlockPos> {
     blocks
 }
 
-/// Returns true if the AABB is on a block
+/// Returns true if the AABB stands on the collision shape of a block.
 pub fn is_on_block(hitbox: &Aabb, layer: &ChunkLayer) -> bool {
-    let hitbox = Aabb::new(hitbox.min() + DVec3::new(0.0, -0.001, 0.0), hitbox.max());
+    let feet = Aabb::new(
+        DVec3::new(hitbox.min().x, hitbox.min().y - 0.001, hitbox.min().z),
+        DVec3::new(hitbox.max().x, hitbox.min().y, hitbox.max().z),
+    );
 
-    let blocks_below = aabb_full_block_intersections(&hitbox);
+    aabb_full_block_intersections(&feet).iter().any(|pos| {
+        let Some(block) = layer.block(*pos) else {
+            return false;
+        };
 
-    blocks_below.iter().any(|b| {
-        if let Some(block) = layer.block(*b) {
-            !block.state.is_air()
-        } else {
-            false
-        }
+        let offset = DVec3::new(pos.x as f64, pos.y as f64, pos.z as f64);
+
+        block
+            .state
+            .collision_shapes()
+            .any(|shape| shape.translate(offset).intersects(feet))
+    })
+}
+
+/// The distance between the points that are checked by [`line_of_sight`].
+const LINE_OF_SIGHT_STEP: f64 = 0.2;
+
+/// Returns true if there is no collision shape of a block between the two positions.
+pub fn line_of_sight(layer: &ChunkLayer, from: DVec3, to: DVec3) -> bool {
+    let distance = from.distance(to);
+    let steps = (distance / LINE_OF_SIGHT_STEP).ceil() as usize;
+
+    (0..=steps).all(|step| {
+        let point = from.lerp(to, step as f64 / steps.max(1) as f64);
+        let pos = BlockPos::new(
+            point.x.floor() as i32,
+            point.y.floor() as i32,
+            point.z.floor() as i32,
+        );
+
+        let Some(block) = layer.block(pos) else {
+            return true;
+        };
+
+        let offset = DVec3::new(pos.x as f64, pos.y as f64, pos.z as f64);
+
+        !block
+            .state
+            .collision_shapes()
+            .any(|shape| shape.translate(offset).contains_point(point))
+    })
+}
+
+/// Returns true if the AABB intersects a block that matches the predicate.
+pub fn intersects_block(
+    hitbox: &Aabb,
+    layer: &ChunkLayer,
+    predicate: impl Fn(BlockState) -> bool,
+) -> bool {
+    aabb_full_block_intersections(hitbox).iter().any(|pos| {
+        layer
+            .block(*pos)
+            .is_some_and(|block| predicate(block.state))
     })
 }
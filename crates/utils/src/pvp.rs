@@ -0,0 +1,213 @@
+//! Toggle PvP globally, per world, per region (with the `Pvp` region flag and the `regions` feature) or per player pair (e.g. for duels).
+
+use std::collections::HashMap;
+
+#[cfg(feature = "regions")]
+use regions::{RegionFlag, RegionFlagCheck};
+use valence::{
+    ecs::{entity::EntityHashMap, system::SystemParam},
+    prelude::*,
+};
+
+/// The rules that decide if players can damage each other.
+///
+/// The priority is: pair > region > world > global,
+/// so players of a duel can fight even if PvP is disabled everywhere else.
+#[derive(Resource, Debug, Clone)]
+pub struct PvpRules {
+    /// PvP is allowed in worlds without their own rule.
+    pub enabled: bool,
+    worlds: EntityHashMap<bool>,
+    pairs: HashMap<(Entity, Entity), bool>,
+    /// The only opponent of a player (see [`PvpRules::scope_to_pair`]).
+    scoped: EntityHashMap<Entity>,
+}
+
+impl Default for PvpRules {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            worlds: EntityHashMap::default(),
+            pairs: HashMap::new(),
+            scoped: EntityHashMap::default(),
+        }
+    }
+}
+
+impl PvpRules {
+    /// Override the global rule in a world (layer), `None` removes the override.
+    pub fn set_world(&mut self, layer: Entity, enabled: Option<bool>) {
+        match enabled {
+            Some(enabled) => self.worlds.insert(layer, enabled),
+            None => self.worlds.remove(&layer),
+        };
+    }
+
+    /// Override all other rules between two players, `None` removes the override.
+    pub fn set_pair(&mut self, a: Entity, b: Entity, enabled: Option<bool>) {
+        match enabled {
+            Some(enabled) => self.pairs.insert(pair(a, b), enabled),
+            None => self.pairs.remove(&pair(a, b)),
+        };
+    }
+
+    pub fn world(&self, layer: Entity) -> Option<bool> {
+        self.worlds.get(&layer).copied()
+    }
+
+    pub fn pair(&self, a: Entity, b: Entity) -> Option<bool> {
+        self.pairs.get(&pair(a, b)).copied()
+    }
+
+    /// Allow PvP between the two players (regardless of the other rules),
+    /// but deny PvP between them and everyone else (e.g. during a duel).
+    pub fn scope_to_pair(&mut self, a: Entity, b: Entity) {
+        self.set_pair(a, b, Some(true));
+        self.scoped.insert(a, b);
+        self.scoped.insert(b, a);
+    }
+
+    /// Remove the scope of [`PvpRules::scope_to_pair`] from the player and their opponent.
+    pub fn remove_scope(&mut self, player: Entity) {
+        if let Some(opponent) = self.scoped.remove(&player) {
+            self.scoped.remove(&opponent);
+            self.set_pair(player, opponent, None);
+        }
+    }
+
+    /// The only opponent the player can fight (see [`PvpRules::scope_to_pair`]).
+    pub fn scoped_opponent(&self, player: Entity) -> Option<Entity> {
+        self.scoped.get(&player).copied()
+    }
+
+    /// Remove all rules of a player or world.
+    pub fn remove(&mut self, entity: Entity) {
+        self.remove_scope(entity);
+        self.worlds.remove(&entity);
+        self.pairs.retain(|(a, b), _| *a != entity && *b != entity);
+    }
+}
+
+fn pair(a: Entity, b: Entity) -> (Entity, Entity) {
+    if a < b {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
+
+/// The rule that denied PvP.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PvpDeniedReason {
+    Global,
+    World,
+    /// The attacker or the victim is inside a region that denies PvP.
+    Region,
+    /// A rule between the two players (see [`PvpRules::set_pair`] and [`PvpRules::scope_to_pair`]).
+    Pair,
+}
+
+/// An event that is emitted when a player attacks another player, but PvP is denied.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct PvpDeniedEvent {
+    pub attacker: Entity,
+    pub victim: Entity,
+    pub reason: PvpDeniedReason,
+}
+
+/// Checks the [`PvpRules`] and (with the `regions` feature) the `Pvp` flag of the regions.
+#[derive(SystemParam)]
+pub struct PvpCheck<'w, 's> {
+    rules: Option<Res<'w, PvpRules>>,
+    players: Query<'w, 's, (&'static EntityLayerId, &'static Position)>,
+    denied_events: Option<ResMut<'w, Events<PvpDeniedEvent>>>,
+    #[cfg(feature = "regions")]
+    region_check: RegionFlagCheck<'w, 's>,
+}
+
+impl<'w, 's> PvpCheck<'w, 's> {
+    /// Check if the attacker can damage the victim.
+    ///
+    /// A [`PvpDeniedEvent`] will be fired if it is denied.
+    pub fn check(&mut self, attacker: Entity, victim: Entity) -> bool {
+        let Some(reason) = self.denied_reason(attacker, victim) else {
+            return true;
+        };
+
+        if let Some(events) = &mut self.denied_events {
+            events.send(PvpDeniedEvent {
+                attacker,
+                victim,
+                reason,
+            });
+        }
+
+        false
+    }
+
+    /// The region check of the pvp check, systems with a [`PvpCheck`] can not have their own [`RegionFlagCheck`].
+    #[cfg(feature = "regions")]
+    pub fn region_check(&mut self) -> &mut RegionFlagCheck<'w, 's> {
+        &mut self.region_check
+    }
+
+    #[cfg_attr(not(feature = "regions"), allow(unused_variables))]
+    fn denied_reason(&mut self, attacker: Entity, victim: Entity) -> Option<PvpDeniedReason> {
+        let Ok([(attacker_layer, attacker_position), (victim_layer, victim_position)]) =
+            self.players.get_many([attacker, victim])
+        else {
+            return None;
+        };
+
+        if let Some(rules) = &self.rules {
+            match rules.pair(attacker, victim) {
+                Some(true) => return None,
+                Some(false) => return Some(PvpDeniedReason::Pair),
+                None => {}
+            }
+
+            let out_of_scope = |player: Entity, other: Entity| {
+                rules
+                    .scoped_opponent(player)
+                    .is_some_and(|opponent| opponent != other)
+            };
+
+            if out_of_scope(attacker, victim) || out_of_scope(victim, attacker) {
+                return Some(PvpDeniedReason::Pair);
+            }
+        }
+
+        // Pvp is denied if either the attacker or the victim is inside a region without pvp.
+        #[cfg(feature = "regions")]
+        if !(self.region_check.check(
+            attacker,
+            attacker_layer.0,
+            attacker_position.0,
+            RegionFlag::Pvp,
+        ) && self.region_check.check(
+            attacker,
+            victim_layer.0,
+            victim_position.0,
+            RegionFlag::Pvp,
+        )) {
+            return Some(PvpDeniedReason::Region);
+        }
+
+        let rules = self.rules.as_ref()?;
+
+        match rules.world(victim_layer.0) {
+            Some(false) => Some(PvpDeniedReason::World),
+            Some(true) => None,
+            None => (!rules.enabled).then_some(PvpDeniedReason::Global),
+        }
+    }
+}
+
+pub(crate) fn remove_pvp_rules(
+    mut rules: ResMut<PvpRules>,
+    mut removed: RemovedComponents<Client>,
+) {
+    for entity in removed.read() {
+        rules.remove(entity);
+    }
+}
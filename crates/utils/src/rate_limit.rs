@@ -0,0 +1,201 @@
+//! Per player rate limits (token buckets) for actions that can be spammed with macros or modified clients.
+//!
+//! The rate limits are only checked if the [`RateLimitConfig`] resource exists.
+
+use std::{collections::HashMap, time::Instant};
+
+use serde::{Deserialize, Serialize};
+use valence::{ecs::system::SystemParam, prelude::*};
+
+/// The actions that can be rate limited.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum RateLimitKind {
+    Attack,
+    BlockPlace,
+    Chat,
+}
+
+/// A token bucket, every action takes one token.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct TokenBucketConfig {
+    /// The maximum number of tokens (the number of actions that can be done at once).
+    pub capacity: f32,
+    /// The tokens that are added every second.
+    pub refill_per_second: f32,
+}
+
+impl TokenBucketConfig {
+    pub fn new(capacity: f32, refill_per_second: f32) -> Self {
+        Self {
+            capacity,
+            refill_per_second,
+        }
+    }
+}
+
+/// The rate limits of the actions, actions without a bucket are not limited.
+#[derive(Resource, Clone, Debug, Serialize, Deserialize)]
+pub struct RateLimitConfig {
+    pub buckets: HashMap<RateLimitKind, TokenBucketConfig>,
+}
+
+impl RateLimitConfig {
+    pub fn with(mut self, kind: RateLimitKind, bucket: TokenBucketConfig) -> Self {
+        self.buckets.insert(kind, bucket);
+        self
+    }
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            buckets: HashMap::from([
+                (RateLimitKind::Attack, TokenBucketConfig::new(20.0, 20.0)),
+                (
+                    RateLimitKind::BlockPlace,
+                    TokenBucketConfig::new(20.0, 20.0),
+                ),
+                (RateLimitKind::Chat, TokenBucketConfig::new(5.0, 1.0)),
+            ]),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct TokenBucket {
+    tokens: f32,
+    last_refill: Instant,
+}
+
+/// The token buckets of a player, this is added to every client.
+#[derive(Component, Clone, Debug, Default)]
+pub struct RateLimiter {
+    buckets: HashMap<RateLimitKind, TokenBucket>,
+}
+
+impl RateLimiter {
+    /// Take a token from the bucket, returns false if the bucket is empty.
+    fn try_take(&mut self, kind: RateLimitKind, config: TokenBucketConfig) -> bool {
+        let now = Instant::now();
+        let bucket = self.buckets.entry(kind).or_insert(TokenBucket {
+            tokens: config.capacity,
+            last_refill: now,
+        });
+
+        let elapsed = now.duration_since(bucket.last_refill).as_secs_f32();
+        bucket.tokens = (bucket.tokens + elapsed * config.refill_per_second).min(config.capacity);
+        bucket.last_refill = now;
+
+        if bucket.tokens < 1.0 {
+            return false;
+        }
+
+        bucket.tokens -= 1.0;
+        true
+    }
+}
+
+/// An event that is emitted when a player exceeded a rate limit (e.g. to notify staff).
+#[derive(Event, Clone, Copy, Debug)]
+pub struct RateLimitExceededEvent {
+    pub player: Entity,
+    pub kind: RateLimitKind,
+}
+
+/// A system param to check the rate limits of players.
+#[derive(SystemParam)]
+pub struct RateLimits<'w, 's> {
+    config: Option<Res<'w, RateLimitConfig>>,
+    limiters: Query<'w, 's, &'static mut RateLimiter>,
+    exceeded_writer: EventWriter<'w, RateLimitExceededEvent>,
+}
+
+impl RateLimits<'_, '_> {
+    /// Check the rate limit of the action, returns false if the action should be denied.
+    ///
+    /// Entities without a [`RateLimiter`] (e.g. NPCs) are never limited.
+    pub fn check(&mut self, player: Entity, kind: RateLimitKind) -> bool {
+        let Some(bucket) = self
+            .config
+            .as_ref()
+            .and_then(|config| config.buckets.get(&kind).copied())
+        else {
+            return true;
+        };
+
+        let Ok(mut limiter) = self.limiters.get_mut(player) else {
+            return true;
+        };
+
+        if limiter.try_take(kind, bucket) {
+            return true;
+        }
+
+        self.exceeded_writer
+            .send(RateLimitExceededEvent { player, kind });
+        false
+    }
+}
+
+pub struct RateLimitPlugin;
+
+impl Plugin for RateLimitPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<RateLimitExceededEvent>()
+            .add_systems(PreUpdate, init_rate_limiters);
+    }
+}
+
+fn init_rate_limiters(mut commands: Commands, clients: Query<Entity, Added<Client>>) {
+    for entity in clients.iter() {
+        commands.entity(entity).insert(RateLimiter::default());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn bucket_refills_over_time() {
+        let config = TokenBucketConfig::new(2.0, 1.0);
+        let mut limiter = RateLimiter::default();
+
+        assert!(limiter.try_take(RateLimitKind::Chat, config));
+        assert!(limiter.try_take(RateLimitKind::Chat, config));
+        assert!(!limiter.try_take(RateLimitKind::Chat, config));
+
+        // Pretend one second has passed since the last refill.
+        let bucket = limiter.buckets.get_mut(&RateLimitKind::Chat).unwrap();
+        bucket.last_refill -= Duration::from_secs(1);
+
+        assert!(limiter.try_take(RateLimitKind::Chat, config));
+        assert!(!limiter.try_take(RateLimitKind::Chat, config));
+    }
+
+    #[test]
+    fn bucket_does_not_refill_over_capacity() {
+        let config = TokenBucketConfig::new(1.0, 1.0);
+        let mut limiter = RateLimiter::default();
+
+        assert!(limiter.try_take(RateLimitKind::Attack, config));
+
+        let bucket = limiter.buckets.get_mut(&RateLimitKind::Attack).unwrap();
+        bucket.last_refill -= Duration::from_secs(10);
+
+        assert!(limiter.try_take(RateLimitKind::Attack, config));
+        assert!(!limiter.try_take(RateLimitKind::Attack, config));
+    }
+
+    #[test]
+    fn buckets_are_separate_per_kind() {
+        let config = TokenBucketConfig::new(1.0, 0.0);
+        let mut limiter = RateLimiter::default();
+
+        assert!(limiter.try_take(RateLimitKind::Attack, config));
+        assert!(!limiter.try_take(RateLimitKind::Attack, config));
+        assert!(limiter.try_take(RateLimitKind::BlockPlace, config));
+    }
+}
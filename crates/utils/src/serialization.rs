@@ -0,0 +1,123 @@
+//! Helpers for (de)serializing valence types with `#[serde(with = "...")]`.
+
+use valence::{BlockKind, ItemKind};
+
+fn parse_block<E: serde::de::Error>(name: &str) -> Result<BlockKind, E> {
+    BlockKind::from_str(name.trim_start_matches("minecraft:"))
+        .ok_or_else(|| E::custom(format!("unknown block `{name}`")))
+}
+
+fn parse_item<E: serde::de::Error>(name: &str) -> Result<ItemKind, E> {
+    ItemKind::from_str(name.trim_start_matches("minecraft:"))
+        .ok_or_else(|| E::custom(format!("unknown item `{name}`")))
+}
+
+/// (De)serializes an [`ItemKind`] by its name (e.g. `"diamond_sword"`).
+pub mod item_kind {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use valence::ItemKind;
+
+    pub fn serialize<S: Serializer>(item: &ItemKind, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(item.to_str())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<ItemKind, D::Error> {
+        super::parse_item(&String::deserialize(deserializer)?)
+    }
+}
+
+/// (De)serializes a set of [`ItemKind`]s by their names.
+pub mod item_kind_set {
+    use std::collections::HashSet;
+
+    use serde::{Deserialize, Deserializer, Serializer};
+    use valence::ItemKind;
+
+    pub fn serialize<S: Serializer>(
+        items: &HashSet<ItemKind>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(items.iter().map(|item| item.to_str()))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<HashSet<ItemKind>, D::Error> {
+        Vec::<String>::deserialize(deserializer)?
+            .iter()
+            .map(|name| super::parse_item(name))
+            .collect()
+    }
+}
+
+/// (De)serializes a map with [`ItemKind`] keys by their names.
+pub mod item_kind_map {
+    use std::collections::HashMap;
+
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use valence::ItemKind;
+
+    pub fn serialize<S: Serializer, V: Serialize>(
+        items: &HashMap<ItemKind, V>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(items.iter().map(|(item, value)| (item.to_str(), value)))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>, V: Deserialize<'de>>(
+        deserializer: D,
+    ) -> Result<HashMap<ItemKind, V>, D::Error> {
+        HashMap::<String, V>::deserialize(deserializer)?
+            .into_iter()
+            .map(|(name, value)| Ok((super::parse_item(&name)?, value)))
+            .collect()
+    }
+}
+
+/// (De)serializes a set of [`BlockKind`]s by their names.
+pub mod block_kind_set {
+    use std::collections::HashSet;
+
+    use serde::{Deserialize, Deserializer, Serializer};
+    use valence::BlockKind;
+
+    pub fn serialize<S: Serializer>(
+        blocks: &HashSet<BlockKind>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(blocks.iter().map(|block| block.to_str()))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<HashSet<BlockKind>, D::Error> {
+        Vec::<String>::deserialize(deserializer)?
+            .iter()
+            .map(|name| super::parse_block(name))
+            .collect()
+    }
+}
+
+/// (De)serializes a map with [`BlockKind`] keys by their names.
+pub mod block_kind_map {
+    use std::collections::HashMap;
+
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use valence::BlockKind;
+
+    pub fn serialize<S: Serializer, V: Serialize>(
+        blocks: &HashMap<BlockKind, V>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(blocks.iter().map(|(block, value)| (block.to_str(), value)))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>, V: Deserialize<'de>>(
+        deserializer: D,
+    ) -> Result<HashMap<BlockKind, V>, D::Error> {
+        HashMap::<String, V>::deserialize(deserializer)?
+            .into_iter()
+            .map(|(name, value)| Ok((super::parse_block(&name)?, value)))
+            .collect()
+    }
+}
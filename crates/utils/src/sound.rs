@@ -0,0 +1,152 @@
+//! Play sounds to the players in range of a position.
+//!
+//! ```ignore
+//! fn play(mut sounds: Sounds) {
+//!     SoundBuilder::new(Sound::EntityPlayerAttackSweep, position)
+//!         .category(SoundCategory::Player)
+//!         // The client of the attacker already played the sound.
+//!         .exclude(attacker)
+//!         .play(&mut sounds, layer);
+//! }
+//! ```
+
+use valence::{
+    client::VisibleChunkLayer,
+    ecs::system::SystemParam,
+    prelude::*,
+    protocol::{
+        packets::play::PlaySoundS2c,
+        sound::{Sound, SoundCategory},
+        WritePacket,
+    },
+};
+
+/// The distance at which a sound with a volume of `1.0` can be heard (the vanilla value).
+pub const SOUND_RANGE: f64 = 16.0;
+
+/// A sound that is played at a position.
+#[derive(Clone, Debug)]
+pub struct SoundBuilder {
+    sound: Sound,
+    position: DVec3,
+    category: SoundCategory,
+    volume: f32,
+    pitch: f32,
+    seed: Option<i64>,
+    range: Option<f64>,
+    exclude: Vec<Entity>,
+}
+
+impl SoundBuilder {
+    pub fn new(sound: Sound, position: impl Into<DVec3>) -> Self {
+        Self {
+            sound,
+            position: position.into(),
+            category: SoundCategory::Master,
+            volume: 1.0,
+            pitch: 1.0,
+            seed: None,
+            range: None,
+            exclude: vec![],
+        }
+    }
+
+    pub fn category(mut self, category: SoundCategory) -> Self {
+        self.category = category;
+        self
+    }
+
+    pub fn volume(mut self, volume: f32) -> Self {
+        self.volume = volume;
+        self
+    }
+
+    pub fn pitch(mut self, pitch: f32) -> Self {
+        self.pitch = pitch;
+        self
+    }
+
+    /// The seed the client uses to pick one of the variants of the sound (random if not set).
+    pub fn seed(mut self, seed: i64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Only players in this range hear the sound.
+    ///
+    /// By default this is the range in which the client can hear the sound ([`SOUND_RANGE`] times the volume).
+    pub fn range(mut self, range: f64) -> Self {
+        self.range = Some(range);
+        self
+    }
+
+    /// Do not play the sound to this player (e.g. because their client already played it).
+    pub fn exclude(mut self, player: Entity) -> Self {
+        self.exclude.push(player);
+        self
+    }
+
+    /// Play the sound to the players in range on the layer.
+    pub fn play(&self, sounds: &mut Sounds, layer: Entity) {
+        sounds.play(self, layer);
+    }
+
+    /// The distance at which the sound can be heard.
+    pub fn effective_range(&self) -> f64 {
+        self.range
+            .unwrap_or(SOUND_RANGE * (self.volume as f64).max(1.0))
+    }
+}
+
+/// A system param to play sounds, see [`SoundBuilder`].
+#[derive(SystemParam)]
+pub struct Sounds<'w, 's> {
+    clients: Query<
+        'w,
+        's,
+        (
+            Entity,
+            &'static mut Client,
+            &'static Position,
+            &'static VisibleChunkLayer,
+        ),
+    >,
+}
+
+impl Sounds<'_, '_> {
+    /// Play a sound to the players in range on the layer.
+    pub fn play(&mut self, sound: &SoundBuilder, layer: Entity) {
+        let range = sound.effective_range();
+        let seed = sound.seed.unwrap_or_else(rand::random);
+
+        for (entity, mut client, position, visible_layer) in self.clients.iter_mut() {
+            if visible_layer.0 != layer
+                || sound.exclude.contains(&entity)
+                || position.0.distance_squared(sound.position) > range * range
+            {
+                continue;
+            }
+
+            write_sound(&mut client, sound, seed);
+        }
+    }
+
+    /// Play a sound to a single player.
+    pub fn play_to(&mut self, sound: &SoundBuilder, player: Entity) {
+        if let Ok((_, mut client, _, _)) = self.clients.get_mut(player) {
+            write_sound(&mut client, sound, sound.seed.unwrap_or_else(rand::random));
+        }
+    }
+}
+
+fn write_sound(client: &mut Client, sound: &SoundBuilder, seed: i64) {
+    client.write_packet(&PlaySoundS2c {
+        id: sound.sound.to_id(),
+        category: sound.category,
+        // The position is sent in fixed point (1/8 of a block).
+        position: (sound.position * 8.0).as_ivec3(),
+        volume: sound.volume,
+        pitch: sound.pitch,
+        seed,
+    });
+}
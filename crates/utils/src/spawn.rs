@@ -0,0 +1,226 @@
+//! Spawn points and spawn protection.
+
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use rand::seq::SliceRandom;
+use valence::prelude::*;
+
+/// A position where players can spawn.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SpawnPoint {
+    pub position: DVec3,
+    pub look: Look,
+}
+
+impl SpawnPoint {
+    pub fn new(position: impl Into<DVec3>) -> Self {
+        Self {
+            position: position.into(),
+            look: Look::default(),
+        }
+    }
+
+    pub fn with_look(mut self, yaw: f32, pitch: f32) -> Self {
+        self.look = Look::new(yaw, pitch);
+        self
+    }
+}
+
+/// How a spawn point is selected from a list of spawn points.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SpawnStrategy {
+    /// Always the first spawn point.
+    First,
+    #[default]
+    Random,
+    /// The spawn points are used one after another.
+    RoundRobin,
+    /// The spawn point with the largest distance to the closest other player.
+    FarthestFromPlayers,
+}
+
+/// A list of spawn points and how they are selected.
+#[derive(Clone, Debug, Default)]
+pub struct SpawnGroup {
+    pub points: Vec<SpawnPoint>,
+    pub strategy: SpawnStrategy,
+    /// The next spawn point for [`SpawnStrategy::RoundRobin`].
+    next: usize,
+}
+
+impl SpawnGroup {
+    pub fn new(points: impl IntoIterator<Item = SpawnPoint>, strategy: SpawnStrategy) -> Self {
+        Self {
+            points: points.into_iter().collect(),
+            strategy,
+            next: 0,
+        }
+    }
+
+    /// Select a spawn point, `players` are the positions of the other players.
+    pub fn select(&mut self, players: &[DVec3]) -> Option<SpawnPoint> {
+        if self.points.is_empty() {
+            return None;
+        }
+
+        match self.strategy {
+            SpawnStrategy::First => self.points.first().copied(),
+            SpawnStrategy::Random => self.points.choose(&mut rand::thread_rng()).copied(),
+            SpawnStrategy::RoundRobin => {
+                let point = self.points[self.next % self.points.len()];
+                self.next = (self.next + 1) % self.points.len();
+                Some(point)
+            }
+            SpawnStrategy::FarthestFromPlayers => {
+                let closest_player = |point: &SpawnPoint| {
+                    players
+                        .iter()
+                        .map(|player| player.distance_squared(point.position))
+                        .fold(f64::INFINITY, f64::min)
+                };
+
+                self.points
+                    .iter()
+                    .max_by(|a, b| closest_player(a).total_cmp(&closest_player(b)))
+                    .copied()
+            }
+        }
+    }
+}
+
+/// The spawn points of players.
+///
+/// A spawn point is selected in this order: the spawn point of the player, the spawn points of their team,
+/// the default spawn points.
+#[derive(Resource, Clone, Debug, Default)]
+pub struct SpawnPoints {
+    pub default: SpawnGroup,
+    /// The spawn points by team.
+    pub teams: HashMap<u16, SpawnGroup>,
+    /// Spawn points of single players (e.g. a checkpoint).
+    pub players: HashMap<Entity, SpawnPoint>,
+    /// How long players are protected after they (re)spawned with the [`SpawnPlayerEvent`].
+    ///
+    /// If this is zero, no [`SpawnProtection`] is added.
+    pub protection: Duration,
+}
+
+impl SpawnPoints {
+    /// Select a spawn point for a player, `players` are the positions of the other players.
+    pub fn select(
+        &mut self,
+        player: Entity,
+        team: Option<u16>,
+        players: &[DVec3],
+    ) -> Option<SpawnPoint> {
+        if let Some(point) = self.players.get(&player) {
+            return Some(*point);
+        }
+
+        if let Some(group) = team.and_then(|team| self.teams.get_mut(&team)) {
+            if let Some(point) = group.select(players) {
+                return Some(point);
+            }
+        }
+
+        self.default.select(players)
+    }
+}
+
+/// Players with this component can not take damage and can not be attacked.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct SpawnProtection {
+    until: Instant,
+    /// Remove the protection if the player attacks another entity.
+    pub break_on_attack: bool,
+}
+
+impl SpawnProtection {
+    pub fn new(duration: Duration) -> Self {
+        Self {
+            until: Instant::now() + duration,
+            break_on_attack: true,
+        }
+    }
+
+    pub fn with_break_on_attack(mut self, break_on_attack: bool) -> Self {
+        self.break_on_attack = break_on_attack;
+        self
+    }
+
+    pub fn is_active(&self) -> bool {
+        Instant::now() < self.until
+    }
+
+    /// The time until the protection ends.
+    pub fn remaining(&self) -> Duration {
+        self.until.saturating_duration_since(Instant::now())
+    }
+}
+
+/// Send this event to move a player to a spawn point from [`SpawnPoints`].
+#[derive(Event, Clone, Copy, Debug)]
+pub struct SpawnPlayerEvent {
+    pub player: Entity,
+    /// The team of the player, this selects the spawn points of the team.
+    pub team: Option<u16>,
+}
+
+pub struct SpawnPlugin;
+
+impl Plugin for SpawnPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SpawnPoints>()
+            .add_event::<SpawnPlayerEvent>()
+            .add_systems(Update, (spawn_players, remove_spawn_protection));
+    }
+}
+
+fn spawn_players(
+    mut commands: Commands,
+    mut events: EventReader<SpawnPlayerEvent>,
+    mut spawn_points: ResMut<SpawnPoints>,
+    mut players: Query<(Entity, &mut Position, &mut Look, &EntityLayerId), With<Client>>,
+) {
+    for event in events.read() {
+        let Ok((_, _, _, layer)) = players.get(event.player) else {
+            continue;
+        };
+
+        let layer = layer.0;
+        let others = players
+            .iter()
+            .filter(|(entity, _, _, other_layer)| *entity != event.player && other_layer.0 == layer)
+            .map(|(_, position, _, _)| position.0)
+            .collect::<Vec<_>>();
+
+        let Some(point) = spawn_points.select(event.player, event.team, &others) else {
+            tracing::warn!("no spawn point for player {:?}", event.player);
+            continue;
+        };
+
+        let Ok((_, mut position, mut look, _)) = players.get_mut(event.player) else {
+            continue;
+        };
+
+        position.0 = point.position;
+        *look = point.look;
+
+        if !spawn_points.protection.is_zero() {
+            commands
+                .entity(event.player)
+                .insert(SpawnProtection::new(spawn_points.protection));
+        }
+    }
+}
+
+fn remove_spawn_protection(mut commands: Commands, query: Query<(Entity, &SpawnProtection)>) {
+    for (entity, protection) in query.iter() {
+        if !protection.is_active() {
+            commands.entity(entity).remove::<SpawnProtection>();
+        }
+    }
+}
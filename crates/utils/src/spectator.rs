@@ -0,0 +1,89 @@
+//! Spectators can not attack or be attacked, do not take damage and are not part of the collision BVH.
+
+use valence::{
+    prelude::*,
+    protocol::{packets::play::SetCameraEntityS2c, VarInt, WritePacket},
+};
+
+/// Marks an entity as a spectator.
+///
+/// This does not change the [`GameMode`], set it to [`GameMode::Spectator`] as well
+/// if the player should be able to fly through blocks.
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Spectating {
+    /// The entity the camera of the spectator is attached to (first person view),
+    /// `None` for a free camera.
+    ///
+    /// The camera is only updated if the [`SpectatorPlugin`] is added.
+    pub camera: Option<Entity>,
+}
+
+impl Spectating {
+    /// A spectator with a free camera.
+    pub fn free() -> Self {
+        Self { camera: None }
+    }
+
+    /// A spectator that sees through the eyes of another entity.
+    pub fn attached(target: Entity) -> Self {
+        Self {
+            camera: Some(target),
+        }
+    }
+}
+
+/// Attaches the cameras of the spectators to their [`Spectating::camera`] entity.
+pub struct SpectatorPlugin;
+
+impl Plugin for SpectatorPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            PostUpdate,
+            (detach_from_despawned_targets, update_cameras, reset_cameras).chain(),
+        );
+    }
+}
+
+/// The camera of spectators whose target is gone is detached.
+fn detach_from_despawned_targets(
+    mut spectators: Query<&mut Spectating>,
+    targets: Query<(), (With<EntityId>, Without<Despawned>)>,
+) {
+    for mut spectating in spectators.iter_mut() {
+        if spectating
+            .camera
+            .is_some_and(|target| !targets.contains(target))
+        {
+            spectating.camera = None;
+        }
+    }
+}
+
+fn update_cameras(
+    mut spectators: Query<(&mut Client, &EntityId, &Spectating), Changed<Spectating>>,
+    entity_ids: Query<&EntityId>,
+) {
+    for (mut client, own_id, spectating) in spectators.iter_mut() {
+        let camera_id = spectating
+            .camera
+            .and_then(|target| entity_ids.get(target).ok())
+            .unwrap_or(own_id);
+
+        client.write_packet(&SetCameraEntityS2c {
+            entity_id: VarInt(camera_id.get()),
+        });
+    }
+}
+
+fn reset_cameras(
+    mut removed: RemovedComponents<Spectating>,
+    mut clients: Query<(&mut Client, &EntityId)>,
+) {
+    for entity in removed.read() {
+        if let Ok((mut client, own_id)) = clients.get_mut(entity) {
+            client.write_packet(&SetCameraEntityS2c {
+                entity_id: VarInt(own_id.get()),
+            });
+        }
+    }
+}
@@ -0,0 +1,23 @@
+//! The weather of the server.
+
+use valence::prelude::*;
+
+/// The weather used for riptide, channeling and rain extinguishing fire.
+///
+/// Valence does not keep track of the weather, so this has to be kept in sync by the user.
+#[derive(Resource, Default, Debug, Clone, Copy)]
+pub struct Weather {
+    pub raining: bool,
+    pub thundering: bool,
+}
+
+/// Returns true if there is no block above the position (rain can reach it).
+pub fn is_exposed_to_sky(layer: &ChunkLayer, position: BlockPos) -> bool {
+    let top = layer.min_y() + layer.height() as i32;
+
+    !(position.y..top).any(|y| {
+        layer
+            .block([position.x, y, position.z])
+            .is_some_and(|block| !block.state.is_air())
+    })
+}
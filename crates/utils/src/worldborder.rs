@@ -0,0 +1,291 @@
+//! A world border per layer that can shrink and expand over time.
+//!
+//! Entities outside of the border are pushed back and damaged.
+
+use std::time::{Duration, Instant};
+
+use bevy_time::{Time, Timer, TimerMode};
+use valence::{
+    client::VisibleChunkLayer,
+    prelude::*,
+    protocol::{
+        packets::play::{
+            WorldBorderCenterChangedS2c, WorldBorderInitializeS2c, WorldBorderInterpolateSizeS2c,
+        },
+        VarInt, VarLong, WritePacket,
+    },
+};
+
+use crate::{
+    damage::{DamageEvent, DamageType, TakesDamage},
+    knockback::{ApplyKnockbackEvent, KnockbackPlugin},
+};
+
+/// The portal teleport boundary that is sent to the client (the vanilla value).
+const PORTAL_TELEPORT_BOUNDARY: i32 = 29_999_984;
+
+/// The world border of a layer, this component has to be added to the layer entity.
+#[derive(Component, Clone, Debug)]
+pub struct WorldBorder {
+    center: DVec2,
+    old_diameter: f64,
+    new_diameter: f64,
+    /// When the current size change started.
+    lerp_start: Instant,
+    lerp_duration: Duration,
+    /// Entities can be this many blocks outside of the border before they are damaged.
+    pub damage_buffer: f64,
+    /// The damage per second and block outside of the [`WorldBorder::damage_buffer`].
+    pub damage_per_block: f32,
+    /// The velocity (in blocks per second) entities outside of the border are pushed back with.
+    pub knockback: f32,
+    /// The distance at which the client shows the warning.
+    pub warning_blocks: i32,
+    /// The time before the border reaches the player at which the client shows the warning (in seconds).
+    pub warning_time: i32,
+    damage_timer: Timer,
+    dirty: bool,
+}
+
+impl WorldBorder {
+    pub fn new(center: impl Into<DVec2>, diameter: f64) -> Self {
+        Self {
+            center: center.into(),
+            old_diameter: diameter,
+            new_diameter: diameter,
+            lerp_start: Instant::now(),
+            lerp_duration: Duration::ZERO,
+            damage_buffer: 5.0,
+            damage_per_block: 0.2,
+            knockback: 0.0,
+            warning_blocks: 5,
+            warning_time: 15,
+            damage_timer: Timer::new(Duration::from_secs(1), TimerMode::Repeating),
+            dirty: true,
+        }
+    }
+
+    pub fn with_damage(mut self, damage_buffer: f64, damage_per_block: f32) -> Self {
+        self.damage_buffer = damage_buffer;
+        self.damage_per_block = damage_per_block;
+        self
+    }
+
+    pub fn with_knockback(mut self, knockback: f32) -> Self {
+        self.knockback = knockback;
+        self
+    }
+
+    pub fn with_warning(mut self, warning_blocks: i32, warning_time: i32) -> Self {
+        self.warning_blocks = warning_blocks;
+        self.warning_time = warning_time;
+        self
+    }
+
+    pub fn center(&self) -> DVec2 {
+        self.center
+    }
+
+    pub fn set_center(&mut self, center: impl Into<DVec2>) {
+        self.center = center.into();
+        self.dirty = true;
+    }
+
+    /// The current diameter of the border.
+    pub fn diameter(&self) -> f64 {
+        if self.lerp_duration.is_zero() {
+            return self.new_diameter;
+        }
+
+        let progress =
+            (self.lerp_start.elapsed().as_secs_f64() / self.lerp_duration.as_secs_f64()).min(1.0);
+
+        self.old_diameter + (self.new_diameter - self.old_diameter) * progress
+    }
+
+    /// The diameter the border is shrinking or expanding to.
+    pub fn target_diameter(&self) -> f64 {
+        self.new_diameter
+    }
+
+    /// The time until the border reached its target diameter.
+    pub fn remaining_time(&self) -> Duration {
+        self.lerp_duration.saturating_sub(self.lerp_start.elapsed())
+    }
+
+    pub fn set_diameter(&mut self, diameter: f64) {
+        self.lerp_diameter(diameter, Duration::ZERO);
+    }
+
+    /// Shrink or expand the border to the diameter over the given time.
+    pub fn lerp_diameter(&mut self, diameter: f64, duration: Duration) {
+        self.old_diameter = self.diameter();
+        self.new_diameter = diameter;
+        self.lerp_start = Instant::now();
+        self.lerp_duration = duration;
+        self.dirty = true;
+    }
+
+    /// How far the position is outside of the border (negative if inside).
+    pub fn distance_outside(&self, position: DVec3) -> f64 {
+        let radius = self.diameter() / 2.0;
+
+        let dx = (position.x - self.center.x).abs() - radius;
+        let dz = (position.z - self.center.y).abs() - radius;
+
+        dx.max(dz)
+    }
+
+    pub fn contains(&self, position: DVec3) -> bool {
+        self.distance_outside(position) <= 0.0
+    }
+
+    fn initialize_packet(&self) -> WorldBorderInitializeS2c {
+        WorldBorderInitializeS2c {
+            x: self.center.x,
+            z: self.center.y,
+            old_diameter: self.diameter(),
+            new_diameter: self.new_diameter,
+            duration_millis: VarLong(self.remaining_time().as_millis() as i64),
+            portal_teleport_boundary: VarInt(PORTAL_TELEPORT_BOUNDARY),
+            warning_blocks: VarInt(self.warning_blocks),
+            warning_time: VarInt(self.warning_time),
+        }
+    }
+}
+
+/// Marks entities that are outside of the world border of their layer.
+#[derive(Component)]
+pub struct OutsideBorder;
+
+/// An event that is emitted when an entity leaves or enters the world border of its layer.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct BorderCrossedEvent {
+    pub entity: Entity,
+    pub layer: Entity,
+    /// The entity is now outside of the border.
+    pub outside: bool,
+}
+
+pub struct WorldBorderPlugin;
+
+impl Plugin for WorldBorderPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<KnockbackPlugin>() {
+            app.add_plugins(KnockbackPlugin);
+        }
+
+        app.add_event::<BorderCrossedEvent>().add_systems(
+            Update,
+            (sync_world_borders, initialize_world_borders, border_effects),
+        );
+    }
+}
+
+/// Send changed world borders to the clients on the layer.
+fn sync_world_borders(mut layers: Query<(&mut WorldBorder, &mut ChunkLayer)>) {
+    for (mut border, mut layer) in layers.iter_mut() {
+        if !border.dirty {
+            continue;
+        }
+
+        border.dirty = false;
+
+        layer.write_packet(&WorldBorderCenterChangedS2c {
+            x_pos: border.center.x,
+            z_pos: border.center.y,
+        });
+
+        layer.write_packet(&WorldBorderInterpolateSizeS2c {
+            old_diameter: border.diameter(),
+            new_diameter: border.new_diameter,
+            duration_millis: VarLong(border.remaining_time().as_millis() as i64),
+        });
+    }
+}
+
+/// Send the world border to clients that joined the layer.
+fn initialize_world_borders(
+    mut clients: Query<(&mut Client, &VisibleChunkLayer), Changed<VisibleChunkLayer>>,
+    layers: Query<&WorldBorder>,
+) {
+    for (mut client, layer) in clients.iter_mut() {
+        if let Ok(border) = layers.get(layer.0) {
+            client.write_packet(&border.initialize_packet());
+        }
+    }
+}
+
+/// Push back and damage entities that are outside of the border.
+#[allow(clippy::type_complexity)]
+fn border_effects(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut layers: Query<&mut WorldBorder>,
+    entities: Query<
+        (
+            Entity,
+            &Position,
+            &EntityLayerId,
+            Has<TakesDamage>,
+            Has<OutsideBorder>,
+        ),
+        Or<(With<Client>, With<TakesDamage>)>,
+    >,
+    mut damage_writer: EventWriter<DamageEvent>,
+    mut knockback_writer: EventWriter<ApplyKnockbackEvent>,
+    mut crossed_writer: EventWriter<BorderCrossedEvent>,
+) {
+    for mut border in layers.iter_mut() {
+        border.damage_timer.tick(time.delta());
+    }
+
+    for (entity, position, layer, takes_damage, was_outside) in entities.iter() {
+        let Ok(border) = layers.get(layer.0) else {
+            continue;
+        };
+
+        let distance = border.distance_outside(position.0);
+        let outside = distance > 0.0;
+
+        if outside != was_outside {
+            if outside {
+                commands.entity(entity).insert(OutsideBorder);
+            } else {
+                commands.entity(entity).remove::<OutsideBorder>();
+            }
+
+            crossed_writer.send(BorderCrossedEvent {
+                entity,
+                layer: layer.0,
+                outside,
+            });
+        }
+
+        if !outside {
+            continue;
+        }
+
+        if border.knockback > 0.0 {
+            let to_center = DVec3::new(border.center.x, position.0.y, border.center.y) - position.0;
+            let knockback = to_center.normalize_or_zero().as_vec3() * border.knockback;
+
+            knockback_writer.send(ApplyKnockbackEvent::replace(entity, knockback));
+        }
+
+        let damage = (distance - border.damage_buffer) as f32 * border.damage_per_block;
+
+        if takes_damage && damage > 0.0 && border.damage_timer.just_finished() {
+            damage_writer.send(DamageEvent {
+                victim: entity,
+                attacker: None,
+                damage,
+                damage_type: DamageType::WorldBorder,
+                weapon: None,
+                projectile: None,
+                direct_source: None,
+                area_of_effect: false,
+            });
+        }
+    }
+}
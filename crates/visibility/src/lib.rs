@@ -0,0 +1,372 @@
+use std::{borrow::Cow, collections::HashMap};
+
+use valence::{
+    client::{FlushPacketsSet, OldView, UpdateClientsSet, View},
+    ecs::{
+        entity::{EntityHashMap, EntityHashSet},
+        query::QueryData,
+    },
+    entity::{query::EntityInitQuery, EntityKind, OldEntityLayerId, OldPosition},
+    prelude::*,
+    protocol::{packets::play::EntitiesDestroyS2c, VarInt, WritePacket},
+};
+
+/// The category of an entity, used to look up the culling distance in the [`CullingConfig`].
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EntityCategory {
+    Player,
+    Mob,
+    /// Dropped items and experience orbs.
+    Item,
+    Projectile,
+    /// Entities that are only used for visuals (markers, displays, area effect clouds).
+    Decoration,
+    /// A category defined by the server.
+    Custom(u16),
+}
+
+impl EntityCategory {
+    /// Derive the category from the entity kind.
+    pub fn from_kind(kind: EntityKind) -> Self {
+        match kind {
+            EntityKind::PLAYER => Self::Player,
+            EntityKind::ITEM | EntityKind::EXPERIENCE_ORB => Self::Item,
+            EntityKind::ARROW
+            | EntityKind::SPECTRAL_ARROW
+            | EntityKind::TRIDENT
+            | EntityKind::SNOWBALL
+            | EntityKind::EGG
+            | EntityKind::ENDER_PEARL
+            | EntityKind::POTION
+            | EntityKind::FIREBALL
+            | EntityKind::SMALL_FIREBALL
+            | EntityKind::FIREWORK_ROCKET => Self::Projectile,
+            EntityKind::AREA_EFFECT_CLOUD
+            | EntityKind::MARKER
+            | EntityKind::TEXT_DISPLAY
+            | EntityKind::ITEM_DISPLAY
+            | EntityKind::BLOCK_DISPLAY => Self::Decoration,
+            _ => Self::Mob,
+        }
+    }
+}
+
+/// Configures how far away entities of each category are visible to clients.
+#[derive(Resource)]
+pub struct CullingConfig {
+    /// The maximum distance (in blocks) at which entities of a category are shown to a client.
+    ///
+    /// Categories that are not in the map will never be culled.
+    pub max_distances: HashMap<EntityCategory, f64>,
+    /// An entity that was culled will only be shown again once it is this much closer than the maximum distance.
+    /// This prevents entities at the border from being spawned and despawned every tick.
+    pub hysteresis: f64,
+    /// Automatically insert an [`EntityCategory`] (derived from the [`EntityKind`]) into new entities.
+    pub auto_categorize: bool,
+}
+
+impl Default for CullingConfig {
+    fn default() -> Self {
+        Self {
+            max_distances: HashMap::from([
+                (EntityCategory::Item, 24.0),
+                (EntityCategory::Decoration, 32.0),
+                (EntityCategory::Projectile, 64.0),
+            ]),
+            hysteresis: 2.0,
+            auto_categorize: true,
+        }
+    }
+}
+
+/// Rules that hide entities from specific players (e.g. vanished staff or a "hide players" toggle in a lobby).
+///
+/// Hidden entities are despawned for the viewer just like culled entities (see [`CulledEntities`]).
+#[derive(Resource, Default)]
+pub struct VisibilityRules {
+    /// Maps a viewer to the entities that are hidden from them.
+    hidden: EntityHashMap<EntityHashSet>,
+    /// Entities that are hidden from every viewer without a bypass.
+    vanished: EntityHashSet,
+    /// Viewers that can see vanished entities (e.g. staff).
+    bypass: EntityHashSet,
+    /// Viewers that do not see other players.
+    players_hidden: EntityHashSet,
+}
+
+impl VisibilityRules {
+    /// Hide the target from the viewer.
+    pub fn hide(&mut self, viewer: Entity, target: Entity) {
+        self.hidden.entry(viewer).or_default().insert(target);
+    }
+
+    /// Show a target that was hidden with [`VisibilityRules::hide`] again.
+    pub fn show(&mut self, viewer: Entity, target: Entity) {
+        if let Some(hidden) = self.hidden.get_mut(&viewer) {
+            hidden.remove(&target);
+
+            if hidden.is_empty() {
+                self.hidden.remove(&viewer);
+            }
+        }
+    }
+
+    /// Hide the target from all viewers without a bypass.
+    pub fn vanish(&mut self, target: Entity) {
+        self.vanished.insert(target);
+    }
+
+    pub fn unvanish(&mut self, target: Entity) {
+        self.vanished.remove(&target);
+    }
+
+    pub fn is_vanished(&self, target: Entity) -> bool {
+        self.vanished.contains(&target)
+    }
+
+    /// Allow the viewer to see vanished entities.
+    pub fn set_bypass(&mut self, viewer: Entity, bypass: bool) {
+        if bypass {
+            self.bypass.insert(viewer);
+        } else {
+            self.bypass.remove(&viewer);
+        }
+    }
+
+    /// Hide all other players from the viewer (they are still listed in the tab list).
+    pub fn set_players_hidden(&mut self, viewer: Entity, hidden: bool) {
+        if hidden {
+            self.players_hidden.insert(viewer);
+        } else {
+            self.players_hidden.remove(&viewer);
+        }
+    }
+
+    pub fn players_hidden(&self, viewer: Entity) -> bool {
+        self.players_hidden.contains(&viewer)
+    }
+
+    /// Returns `true` if the target is hidden from the viewer.
+    pub fn is_hidden(&self, viewer: Entity, target: Entity, target_is_player: bool) -> bool {
+        self.is_hidden_in_tab_list(viewer, target)
+            || (target_is_player && viewer != target && self.players_hidden.contains(&viewer))
+    }
+
+    /// Returns `true` if the target should not be listed in the tab list of the viewer.
+    ///
+    /// Unlike [`VisibilityRules::is_hidden`], this ignores [`VisibilityRules::set_players_hidden`].
+    pub fn is_hidden_in_tab_list(&self, viewer: Entity, target: Entity) -> bool {
+        if viewer == target {
+            return false;
+        }
+
+        self.hidden
+            .get(&viewer)
+            .is_some_and(|hidden| hidden.contains(&target))
+            || (self.vanished.contains(&target) && !self.bypass.contains(&viewer))
+    }
+
+    /// Remove all rules of an entity (as a viewer and as a target).
+    pub fn remove(&mut self, entity: Entity) {
+        self.hidden.remove(&entity);
+        self.hidden.retain(|_, hidden| {
+            hidden.remove(&entity);
+            !hidden.is_empty()
+        });
+        self.vanished.remove(&entity);
+        self.bypass.remove(&entity);
+        self.players_hidden.remove(&entity);
+    }
+}
+
+/// Stores the entities that are currently hidden from a client,
+/// because they are too far away or hidden by the [`VisibilityRules`].
+///
+/// This will be added to clients automatically.
+#[derive(Component, Default, Clone)]
+pub struct CulledEntities(EntityHashSet);
+
+impl CulledEntities {
+    /// Returns `true` if the entity is currently hidden from the client.
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.0.contains(&entity)
+    }
+}
+
+pub struct VisibilityPlugin;
+
+impl Plugin for VisibilityPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CullingConfig>()
+            .init_resource::<VisibilityRules>()
+            .add_systems(
+                PostUpdate,
+                (
+                    (categorize_entities, init_culled_entities, remove_rules),
+                    cull_entities
+                        .after(UpdateClientsSet)
+                        .before(FlushPacketsSet),
+                )
+                    .chain(),
+            );
+    }
+}
+
+fn categorize_entities(
+    mut commands: Commands,
+    config: Res<CullingConfig>,
+    entities: Query<(Entity, &EntityKind), (Added<EntityKind>, Without<EntityCategory>)>,
+) {
+    if !config.auto_categorize {
+        return;
+    }
+
+    for (entity, kind) in entities.iter() {
+        commands
+            .entity(entity)
+            .insert(EntityCategory::from_kind(*kind));
+    }
+}
+
+fn init_culled_entities(
+    mut commands: Commands,
+    clients: Query<Entity, (Added<Client>, Without<CulledEntities>)>,
+) {
+    for client in clients.iter() {
+        commands.entity(client).insert(CulledEntities::default());
+    }
+}
+
+/// The rules of despawned entities are removed, so they do not apply to new entities with the same id.
+fn remove_rules(mut rules: ResMut<VisibilityRules>, mut removed: RemovedComponents<EntityLayerId>) {
+    for entity in removed.read() {
+        rules.remove(entity);
+    }
+}
+
+#[derive(QueryData)]
+struct CullableQuery {
+    entity: Entity,
+    kind: &'static EntityKind,
+    category: Option<&'static EntityCategory>,
+    layer: &'static EntityLayerId,
+    old_layer: &'static OldEntityLayerId,
+    position: &'static Position,
+    old_position: &'static OldPosition,
+    init: EntityInitQuery,
+}
+
+impl CullableQueryItem<'_> {
+    /// Returns `true` if valence spawns the entity again for clients that already see it,
+    /// because it entered a new chunk or layer.
+    fn respawned(&self) -> bool {
+        self.layer.0 != self.old_layer.get()
+            || ChunkPos::from(self.position.0) != ChunkPos::from(self.old_position.get())
+    }
+}
+
+#[derive(QueryData)]
+#[query_data(mutable)]
+struct CullingClientQuery {
+    entity: Entity,
+    client: &'static mut Client,
+    position: &'static Position,
+    view: View,
+    old_view: OldView,
+    visible_layers: Ref<'static, VisibleEntityLayers>,
+    culled: &'static mut CulledEntities,
+}
+
+/// Runs after valence updated the view of the clients, so the spawn packets it sent are destroyed again.
+fn cull_entities(
+    config: Res<CullingConfig>,
+    rules: Res<VisibilityRules>,
+    mut clients: Query<CullingClientQuery>,
+    entity_layers: Query<&EntityLayer>,
+    entities: Query<CullableQuery>,
+) {
+    for CullingClientQueryItem {
+        entity: client_entity,
+        mut client,
+        position,
+        view,
+        old_view,
+        visible_layers,
+        mut culled,
+    } in clients.iter_mut()
+    {
+        let view = view.get();
+
+        // Valence spawns all entities in the view again when the view moved or the visible layers changed.
+        let view_changed = view != old_view.get() || visible_layers.is_changed();
+
+        // Entities that left the view or the layers the client can see are despawned by valence,
+        // they are spawned again (and culled again if needed) when they come back.
+        culled.0.retain(|entity| {
+            entities.get(*entity).is_ok_and(|e| {
+                visible_layers.0.contains(&e.layer.0) && view.contains(ChunkPos::from(e.position.0))
+            })
+        });
+
+        let mut destroy = vec![];
+
+        // Only the entities in the view of the client are spawned for it.
+        let nearby = visible_layers
+            .0
+            .iter()
+            .filter_map(|layer| entity_layers.get(*layer).ok())
+            .flat_map(|layer| view.iter().flat_map(move |pos| layer.entities_at(pos)));
+
+        for entity in entities.iter_many(nearby) {
+            if entity.entity == client_entity {
+                continue;
+            }
+
+            let is_culled = culled.0.contains(&entity.entity);
+            let distance = entity.position.0.distance(position.0);
+
+            let too_far = entity
+                .category
+                .and_then(|category| config.max_distances.get(category))
+                .is_some_and(|max_distance| {
+                    if is_culled {
+                        distance >= max_distance - config.hysteresis
+                    } else {
+                        distance > *max_distance
+                    }
+                });
+
+            let hide = too_far
+                || rules.is_hidden(
+                    client_entity,
+                    entity.entity,
+                    *entity.kind == EntityKind::PLAYER,
+                );
+
+            match (is_culled, hide) {
+                (true, false) => {
+                    culled.0.remove(&entity.entity);
+                    entity
+                        .init
+                        .write_init_packets(entity.position.0, &mut *client);
+                }
+                (true, true) => {
+                    if view_changed || entity.respawned() {
+                        destroy.push(VarInt(entity.init.entity_id.get()));
+                    }
+                }
+                (false, true) => {
+                    culled.0.insert(entity.entity);
+                    destroy.push(VarInt(entity.init.entity_id.get()));
+                }
+                (false, false) => {}
+            }
+        }
+
+        if !destroy.is_empty() {
+            client.write_packet(&EntitiesDestroyS2c {
+                entity_ids: Cow::Borrowed(&destroy),
+            });
+        }
+    }
+}
@@ -44,6 +44,10 @@ fn setup(
             required_prefix: None,
             chat_cooldown: Some(Duration::from_secs_f32(0.5)),
             global_prefix: None,
+            format_message: format_player_message,
+            message_filter: Some(Box::new(censor_bad_words)),
+            proximity: None,
+            ..Default::default()
         },
     );
 
@@ -54,7 +58,23 @@ fn setup(
             hide_msg_for_sender: false,
             required_prefix: Some("@t".to_string()),
             chat_cooldown: None,
-            global_prefix: Some("[§cTeam§r] ".to_string()),
+            global_prefix: Some("[".into_text() + "Team".color(Color::RED) + "] "),
+            format_message: format_player_message,
+            message_filter: None,
+            proximity: None,
+            ..Default::default()
+        },
+    );
+
+    // Local chat
+    chat_channels.add_channel(
+        2,
+        ChatChannelConfig {
+            required_prefix: Some("@l".to_string()),
+            global_prefix: Some("[".into_text() + "Local".color(Color::YELLOW) + "] "),
+            format_message: format_player_message,
+            proximity: Some(ProximityConfig::with_radius(16.0)),
+            ..Default::default()
         },
     );
 
@@ -73,7 +93,6 @@ fn init_clients(
             &mut VisibleChunkLayer,
             &mut VisibleEntityLayers,
             &mut GameMode,
-            &Username,
         ),
         Added<Client>,
     >,
@@ -86,7 +105,6 @@ fn init_clients(
         mut visible_chunk_layer,
         mut visible_entity_layers,
         mut game_mode,
-        username,
     ) in &mut clients
     {
         let layer = layers.single();
@@ -104,7 +122,7 @@ fn init_clients(
             player_ent,
             PlayerChatChannelConfig {
                 permission: ChatChannelPermission::ReadWrite,
-                prefix: Some(format!("{}> ", username.0)),
+                prefix: None,
             },
         );
 
@@ -113,8 +131,36 @@ fn init_clients(
             player_ent,
             PlayerChatChannelConfig {
                 permission: ChatChannelPermission::ReadWrite,
-                prefix: Some(format!("{}> ", username.0)),
+                prefix: None,
+            },
+        );
+
+        chat_channels.add_player_to_channel(
+            2,
+            player_ent,
+            PlayerChatChannelConfig {
+                permission: ChatChannelPermission::ReadWrite,
+                prefix: None,
             },
         );
     }
 }
+
+fn format_player_message(sender_name: &Username, message: &str) -> Text {
+    sender_name.0.clone().color(Color::GRAY) + "> " + message.to_string()
+}
+
+fn censor_bad_words(message: &str) -> FilterResult {
+    const BAD_WORDS: [&str; 2] = ["creeper", "griefer"];
+
+    if BAD_WORDS.iter().any(|word| message.contains(word)) {
+        let mut censored = message.to_string();
+        for word in BAD_WORDS {
+            censored = censored.replace(word, &"*".repeat(word.len()));
+        }
+
+        FilterResult::Censor(censored)
+    } else {
+        FilterResult::Allow
+    }
+}
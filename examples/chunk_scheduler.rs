@@ -0,0 +1,117 @@
+use chunk_scheduler::{ChunkSchedulerPlugin, TransferEvent};
+use valence::prelude::*;
+
+const SPAWN_Y: i32 = 64;
+
+/// The two layers players can be transferred between.
+#[derive(Resource)]
+struct Layers([Entity; 2]);
+
+pub fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_plugins(ChunkSchedulerPlugin)
+        .add_systems(Startup, setup)
+        .add_systems(
+            Update,
+            (init_clients, despawn_disconnected_clients, on_player_sneak),
+        )
+        .run();
+}
+
+fn setup(
+    mut commands: Commands,
+    server: Res<Server>,
+    dimensions: Res<DimensionTypeRegistry>,
+    biomes: Res<BiomeRegistry>,
+) {
+    let mut layers = [Entity::PLACEHOLDER; 2];
+
+    for (i, block) in [BlockState::GRASS_BLOCK, BlockState::STONE]
+        .into_iter()
+        .enumerate()
+    {
+        let mut layer = LayerBundle::new(ident!("overworld"), &dimensions, &biomes, &server);
+
+        for z in -16..16 {
+            for x in -16..16 {
+                layer.chunk.insert_chunk([x, z], UnloadedChunk::new());
+            }
+        }
+
+        for z in -256..256 {
+            for x in -256..256 {
+                layer.chunk.set_block([x, SPAWN_Y, z], block);
+            }
+        }
+
+        layers[i] = commands.spawn(layer).id();
+    }
+
+    commands.insert_resource(Layers(layers));
+}
+
+#[allow(clippy::type_complexity)]
+fn init_clients(
+    mut clients: Query<
+        (
+            &mut Client,
+            &mut Position,
+            &mut EntityLayerId,
+            &mut VisibleChunkLayer,
+            &mut VisibleEntityLayers,
+            &mut GameMode,
+        ),
+        Added<Client>,
+    >,
+    layers: Res<Layers>,
+) {
+    for (
+        mut client,
+        mut pos,
+        mut layer_id,
+        mut visible_chunk_layer,
+        mut visible_entity_layers,
+        mut game_mode,
+    ) in &mut clients
+    {
+        let layer = layers.0[0];
+
+        pos.0 = [0.0, f64::from(SPAWN_Y) + 1.0, 0.0].into();
+        layer_id.0 = layer;
+        visible_chunk_layer.0 = layer;
+        visible_entity_layers.0.insert(layer);
+        *game_mode = GameMode::Creative;
+
+        client.send_chat_message("Sneak to switch to the other layer");
+    }
+}
+
+fn on_player_sneak(
+    clients: Query<&EntityLayerId, With<Client>>,
+    layers: Res<Layers>,
+    mut events: EventReader<SneakEvent>,
+    mut transfer_writer: EventWriter<TransferEvent>,
+) {
+    for event in events.read() {
+        if event.state != SneakState::Start {
+            continue;
+        }
+
+        let Ok(layer_id) = clients.get(event.client) else {
+            continue;
+        };
+
+        let target = if layer_id.0 == layers.0[0] {
+            layers.0[1]
+        } else {
+            layers.0[0]
+        };
+
+        transfer_writer.send(TransferEvent {
+            client: event.client,
+            layer: Some(target),
+            position: [0.0, f64::from(SPAWN_Y) + 1.0, 0.0].into(),
+        });
+    }
+}
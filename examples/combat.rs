@@ -3,13 +3,13 @@ use bevy_time::TimePlugin;
 use combat::{CombatPlugin, CombatState};
 use fall_damage::{FallDamagePlugin, FallingState};
 use physics::{Acceleration, BlockCollisionConfig, PhysicsPlugin, StopOnBlockCollision};
+use player_setup::{PlayerSetupPlugin, PlayerTemplate};
 use utils::{
     damage::{DamageEvent, DamagePlugin, TakesDamage},
     item_values::CombatSystem,
 };
 use valence::{
     entity::{zombie::ZombieEntityBundle, EntityStatuses},
-    equipment::EquipmentInventorySync,
     prelude::*,
 };
 const SPAWN_Y: i32 = 64;
@@ -23,6 +23,8 @@ pub fn main() {
         .add_plugins(PhysicsPlugin)
         .add_plugins(DamagePlugin)
         .add_plugins(CombatPlugin)
+        .add_plugins(PlayerSetupPlugin)
+        .insert_resource(PlayerTemplate::default().with_chat(false))
         .add_systems(
             Update,
             (
@@ -82,10 +84,8 @@ fn setup(
 
 #[allow(clippy::type_complexity)]
 fn init_clients(
-    mut commands: Commands,
     mut clients: Query<
         (
-            Entity,
             &mut Client,
             &mut Position,
             &mut EntityLayerId,
@@ -99,7 +99,6 @@ fn init_clients(
     layers: Query<Entity, (With<ChunkLayer>, With<EntityLayer>)>,
 ) {
     for (
-        player_ent,
         mut client,
         mut pos,
         mut layer_id,
@@ -117,12 +116,6 @@ fn init_clients(
         visible_entity_layers.0.insert(layer);
         *game_mode = GameMode::Survival;
 
-        commands
-            .entity(player_ent)
-            .insert(CombatState::default())
-            .insert(FallingState::new(pos.0))
-            .insert(EquipmentInventorySync);
-
         inventory.set_slot(36, ItemStack::new(ItemKind::DiamondSword, 1, None));
         inventory.set_slot(37, ItemStack::new(ItemKind::DiamondPickaxe, 1, None));
         inventory.set_slot(38, ItemStack::new(ItemKind::DiamondAxe, 1, None));
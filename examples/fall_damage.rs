@@ -1,7 +1,8 @@
 // #![cfg(feature = "chat")]
 
 use bevy_time::TimePlugin;
-use fall_damage::{FallDamagePlugin, FallingState};
+use fall_damage::{FallDamagePlugin, FallingStateConfig};
+use player_setup::{PlayerSetupPlugin, PlayerTemplate};
 use utils::damage::{DamagePlugin, TakesDamage};
 use valence::prelude::*;
 
@@ -14,6 +15,15 @@ pub fn main() {
         .add_plugins(TimePlugin)
         .add_plugins(DamagePlugin)
         .add_plugins(FallDamagePlugin)
+        .add_plugins(PlayerSetupPlugin)
+        .insert_resource(
+            PlayerTemplate::empty()
+                .with_fall_damage(FallingStateConfig::default())
+                .with_takes_damage(TakesDamage {
+                    set_hp_after_death: 20.0,
+                    ..Default::default()
+                }),
+        )
         .add_systems(Update, (init_clients, despawn_disconnected_clients))
         .run();
 }
@@ -51,10 +61,8 @@ fn setup(
 
 #[allow(clippy::type_complexity)]
 fn init_clients(
-    mut commands: Commands,
     mut clients: Query<
         (
-            Entity,
             &mut Position,
             &mut EntityLayerId,
             &mut VisibleChunkLayer,
@@ -66,7 +74,6 @@ fn init_clients(
     layers: Query<Entity, (With<ChunkLayer>, With<EntityLayer>)>,
 ) {
     for (
-        player_ent,
         mut pos,
         mut layer_id,
         mut visible_chunk_layer,
@@ -81,13 +88,5 @@ fn init_clients(
         visible_chunk_layer.0 = layer;
         visible_entity_layers.0.insert(layer);
         *game_mode = GameMode::Survival;
-
-        commands
-            .entity(player_ent)
-            .insert(TakesDamage {
-                set_hp_after_death: 20.0,
-                ..Default::default()
-            })
-            .insert(FallingState::new(pos.0));
     }
 }
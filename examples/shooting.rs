@@ -1,12 +1,11 @@
 use bevy_time::TimePlugin;
+use physics::projectile::ProjectileBuilder;
 use physics::{
-    Acceleration, BlockCollisionConfig, Drag, EntityBlockCollisionEvent, EntityCollisionConfig,
-    EntityEntityCollisionEvent, PhysicsPlugin, SpeedLimit,
+    EntityBlockCollisionEvent, EntityCollisionConfig, EntityEntityCollisionEvent, PhysicsPlugin,
 };
-use valence::entity::entity::NoGravity;
+use utils::sound::{SoundBuilder, Sounds};
 use valence::entity::pig::PigEntityBundle;
 use valence::entity::snowball::SnowballEntityBundle;
-use valence::entity::Velocity;
 use valence::interact_item::InteractItemEvent;
 use valence::inventory::player_inventory::PlayerInventory;
 use valence::prelude::*;
@@ -111,47 +110,24 @@ fn init_clients(
 
 fn on_player_right_click(
     mut commands: Commands,
-    mut query: Query<(&mut Client, &Position, &Look, &EntityLayerId)>,
+    query: Query<(&Position, &EntityLayerId)>,
     mut events: EventReader<InteractItemEvent>,
+    mut sounds: Sounds,
 ) {
     for event in events.read() {
-        let Ok((mut client, pos, look, layer_id)) = query.get_mut(event.client) else {
+        let Ok((pos, layer)) = query.get(event.client) else {
             continue;
         };
 
-        let yaw = look.yaw.to_radians();
-        let pitch = look.pitch.to_radians();
+        SoundBuilder::new(Sound::EntityArrowShoot, pos.0)
+            .category(SoundCategory::Neutral)
+            .play(&mut sounds, layer.0);
 
-        let direction = Vec3::new(
-            -yaw.sin() * pitch.cos(),
-            -pitch.sin(),
-            yaw.cos() * pitch.cos(),
-        );
-
-        client.play_sound(
-            Sound::EntityArrowShoot,
-            SoundCategory::Neutral,
-            pos.0,
-            1.0,
-            1.0,
-        );
-
-        commands
-            .spawn(SnowballEntityBundle {
-                position: Position(
-                    pos.0 + DVec3::new(0.0, 1.0, 0.0) + (direction * 2.0).as_dvec3(),
-                ),
-                velocity: Velocity(direction * 20.0),
-                entity_no_gravity: NoGravity(true),
-                layer: *layer_id,
-
-                ..Default::default()
-            })
-            .insert(Acceleration(Vec3::new(0.0, -20.0, 0.0)))
-            .insert(Drag(Vec3::new(0.99 / 20.0, 0.99 / 20.0, 0.99 / 20.0)))
-            .insert(SpeedLimit(100.0))
-            .insert(EntityCollisionConfig::default())
-            .insert(BlockCollisionConfig::default());
+        ProjectileBuilder::new(SnowballEntityBundle::default())
+            .from_entity_eyes(event.client)
+            .forward_offset(2.0)
+            .speed(20.0)
+            .spawn(&mut commands);
     }
 }
 
@@ -166,24 +142,23 @@ fn on_entity_block_collision(
 
 fn on_entity_entity_collision(
     mut commands: Commands,
-    mut players: Query<(&mut Client, &Position)>,
-    target: Query<&TargetMarker>,
+    // Both need mutable access to the clients.
+    mut players: ParamSet<(Query<&mut Client>, Sounds)>,
+    target: Query<(&Position, &EntityLayerId), With<TargetMarker>>,
     mut events: EventReader<EntityEntityCollisionEvent>,
 ) {
     for event in events.read() {
-        if target.get(event.entity2).is_ok() {
+        if let Ok((pos, layer)) = target.get(event.entity2) {
             commands.entity(event.entity1).insert(Despawned);
 
-            for (mut client, pos) in players.iter_mut() {
+            for mut client in players.p0().iter_mut() {
                 client.send_chat_message("Hit!");
-                client.play_sound(
-                    Sound::EntityPigDeath,
-                    SoundCategory::Neutral,
-                    pos.0,
-                    1.0,
-                    1.0,
-                );
             }
+
+            // The sound is played at the target, not at the position of each player.
+            SoundBuilder::new(Sound::EntityPigDeath, pos.0)
+                .category(SoundCategory::Neutral)
+                .play(&mut players.p1(), layer.0);
         }
     }
 }
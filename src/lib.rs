@@ -1,12 +1,54 @@
+#[cfg(feature = "abilities")]
+pub use abilities;
+#[cfg(feature = "anticheat")]
+pub use anticheat;
+#[cfg(feature = "async_bridge")]
+pub use async_bridge;
+#[cfg(feature = "bossbar")]
+pub use bossbar;
 #[cfg(feature = "bvh")]
 pub use bvh;
 #[cfg(feature = "chat")]
 pub use chat;
+#[cfg(feature = "chunk_scheduler")]
+pub use chunk_scheduler;
 #[cfg(feature = "combat")]
 pub use combat;
+#[cfg(feature = "combat_log")]
+pub use combat_log;
+#[cfg(feature = "config")]
+pub use config;
+#[cfg(feature = "consumables")]
+pub use consumables;
 #[cfg(feature = "fall_damage")]
 pub use fall_damage;
+#[cfg(feature = "kits")]
+pub use kits;
+#[cfg(feature = "loot")]
+pub use loot;
+#[cfg(feature = "menus")]
+pub use menus;
+#[cfg(feature = "minigame")]
+pub use minigame;
+#[cfg(feature = "npcs")]
+pub use npcs;
+#[cfg(feature = "persistence")]
+pub use persistence;
 #[cfg(feature = "physics")]
 pub use physics;
+#[cfg(feature = "player_setup")]
+pub use player_setup;
+#[cfg(feature = "regions")]
+pub use regions;
+#[cfg(feature = "scoreboard")]
+pub use scoreboard;
+#[cfg(feature = "shutdown")]
+pub use shutdown;
+#[cfg(feature = "tablist")]
+pub use tablist;
+#[cfg(feature = "ui")]
+pub use ui;
 #[cfg(feature = "utils")]
 pub use utils;
+#[cfg(feature = "visibility")]
+pub use visibility;